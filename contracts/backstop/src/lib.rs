@@ -0,0 +1,491 @@
+#![no_std]
+
+//! # Stellend Backstop Contract
+//!
+//! An insurance fund for the lending pool. Stakers deposit the backstop token
+//! (typically USDC), earn a share of the protocol fees the pool sends here,
+//! and are first in line to absorb bad debt.
+//!
+//! ## How It Works
+//!
+//! - Deposits mint shares against the fund's assets, like a vault
+//! - Protocol fees sent by the pool raise the value of every share
+//! - When the pool writes off a borrower's bad debt it calls
+//!   `cover_bad_debt`, which pays what the fund can from its assets and
+//!   lowers the value of every share; suppliers only lose what is left over
+//! - Withdrawals need a cooldown: stakers call `request_withdrawal`, wait
+//!   for the cooldown, then `withdraw` within the claim window that follows.
+//!   Queued shares keep absorbing losses and a matured request expires, so
+//!   stakers cannot keep one ready to front-run a write-off they see coming
+//!
+//! If bad debt ever drains the fund completely, new deposits are refused
+//! because they would be diluted by the worthless shares still outstanding.
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env,
+};
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding};
+
+use apogee_common::{BUMP_AMOUNT, LIFETIME_THRESHOLD};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Default withdrawal cooldown (7 days)
+const DEFAULT_COOLDOWN: u64 = 7 * 24 * 60 * 60;
+
+/// Default time a matured withdrawal can be claimed in (2 days)
+const DEFAULT_WITHDRAW_WINDOW: u64 = 2 * 24 * 60 * 60;
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the backstop
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Admin allowed to change the cooldown
+    Admin,
+    /// Lending pool allowed to draw on the fund
+    Pool,
+    /// Token staked and paid out
+    Token,
+    /// Seconds between a withdrawal request and the withdrawal
+    Cooldown,
+    /// Seconds after the cooldown during which a request can be claimed
+    WithdrawWindow,
+    /// Total shares outstanding
+    TotalShares,
+    /// Tokens backing the shares
+    TotalAssets,
+    /// Shares held by a staker
+    Shares(Address),
+    /// Pending withdrawal of a staker
+    Withdrawal(Address),
+}
+
+/// A queued withdrawal
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WithdrawalRequest {
+    /// Shares to redeem
+    pub shares: i128,
+    /// Timestamp from which the shares can be withdrawn
+    pub unlock_at: u64,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the backstop
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum BackstopError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Amount must be positive
+    InvalidAmount = 3,
+    /// Staker does not hold enough shares
+    InsufficientShares = 4,
+    /// Staker has no withdrawal queued
+    NoWithdrawalRequest = 5,
+    /// Withdrawal cooldown has not elapsed
+    CooldownActive = 6,
+    /// Fund has been drained by bad debt; deposits are closed
+    FundDepleted = 7,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 8,
+    /// Withdrawal request was not claimed within the window after its cooldown
+    Expired = 9,
+}
+
+impl From<FixedPointError> for BackstopError {
+    fn from(_: FixedPointError) -> Self {
+        BackstopError::MathOverflow
+    }
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct Backstop;
+
+#[contractimpl]
+impl Backstop {
+    /// Initialize the backstop
+    ///
+    /// # Arguments
+    /// * `admin` - Address allowed to change the cooldown
+    /// * `pool` - Lending pool allowed to draw on the fund
+    /// * `token` - Token staked and used to cover bad debt
+    pub fn initialize(env: Env, admin: Address, pool: Address, token: Address) -> Result<(), BackstopError> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(BackstopError::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().set(&DataKey::Token, &token);
+        env.storage().instance().set(&DataKey::Cooldown, &DEFAULT_COOLDOWN);
+
+        Ok(())
+    }
+
+    /// Stake tokens in the fund
+    ///
+    /// # Arguments
+    /// * `user` - Staker (must authorize)
+    /// * `amount` - Tokens to stake
+    ///
+    /// # Returns
+    /// Shares minted
+    pub fn deposit(env: Env, user: Address, amount: i128) -> Result<i128, BackstopError> {
+        user.require_auth();
+
+        if amount <= 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+
+        let total_shares = Self::get_total_shares(env.clone());
+        let total_assets = Self::get_total_assets(env.clone());
+        let shares = if total_shares == 0 {
+            amount
+        } else if total_assets == 0 {
+            return Err(BackstopError::FundDepleted);
+        } else {
+            mul_div(amount, total_shares, total_assets, Rounding::Down)?
+        };
+        if shares == 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+
+        token::Client::new(&env, &Self::get_token(env.clone())?).transfer(&user, &env.current_contract_address(), &amount);
+
+        env.storage().instance().set(&DataKey::TotalShares, &(total_shares + shares));
+        env.storage().instance().set(&DataKey::TotalAssets, &(total_assets + amount));
+        Self::set_shares(&env, &user, Self::get_shares(env.clone(), user.clone()) + shares);
+
+        env.events().publish((symbol_short!("deposit"), user), (amount, shares));
+
+        Ok(shares)
+    }
+
+    /// Queue shares for withdrawal once the cooldown has passed
+    ///
+    /// Replaces any earlier request and restarts the cooldown.
+    ///
+    /// # Arguments
+    /// * `user` - Staker (must authorize)
+    /// * `shares` - Shares to redeem
+    pub fn request_withdrawal(env: Env, user: Address, shares: i128) -> Result<WithdrawalRequest, BackstopError> {
+        user.require_auth();
+
+        if shares <= 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+        if shares > Self::get_shares(env.clone(), user.clone()) {
+            return Err(BackstopError::InsufficientShares);
+        }
+
+        let request = WithdrawalRequest {
+            shares,
+            unlock_at: env.ledger().timestamp() + Self::get_cooldown(env.clone()),
+        };
+        let key = DataKey::Withdrawal(user.clone());
+        env.storage().persistent().set(&key, &request);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        env.events().publish((symbol_short!("wd_req"), user), request.clone());
+
+        Ok(request)
+    }
+
+    /// Redeem queued shares after their cooldown
+    ///
+    /// Shares are redeemed at the current value, after any losses absorbed
+    /// while they were queued. The request must be claimed within the
+    /// withdraw window after `unlock_at`; after that a new request is needed.
+    ///
+    /// # Arguments
+    /// * `user` - Staker (must authorize)
+    ///
+    /// # Returns
+    /// Tokens paid out
+    pub fn withdraw(env: Env, user: Address) -> Result<i128, BackstopError> {
+        user.require_auth();
+
+        let request = Self::get_withdrawal(env.clone(), user.clone()).ok_or(BackstopError::NoWithdrawalRequest)?;
+        let now = env.ledger().timestamp();
+        if now < request.unlock_at {
+            return Err(BackstopError::CooldownActive);
+        }
+        if now > request.unlock_at + Self::get_withdraw_window(env.clone()) {
+            return Err(BackstopError::Expired);
+        }
+
+        let user_shares = Self::get_shares(env.clone(), user.clone());
+        if request.shares > user_shares {
+            return Err(BackstopError::InsufficientShares);
+        }
+
+        let total_shares = Self::get_total_shares(env.clone());
+        let total_assets = Self::get_total_assets(env.clone());
+        let amount = mul_div(request.shares, total_assets, total_shares, Rounding::Down)?;
+
+        env.storage().persistent().remove(&DataKey::Withdrawal(user.clone()));
+        Self::set_shares(&env, &user, user_shares - request.shares);
+        env.storage().instance().set(&DataKey::TotalShares, &(total_shares - request.shares));
+        env.storage().instance().set(&DataKey::TotalAssets, &(total_assets - amount));
+
+        if amount > 0 {
+            token::Client::new(&env, &Self::get_token(env.clone())?).transfer(&env.current_contract_address(), &user, &amount);
+        }
+
+        env.events().publish((symbol_short!("withdraw"), user), (amount, request.shares));
+
+        Ok(amount)
+    }
+
+    /// Credit protocol fees the pool has transferred to the fund (pool only)
+    ///
+    /// # Arguments
+    /// * `amount` - Tokens already sent to this contract
+    pub fn distribute(env: Env, amount: i128) -> Result<(), BackstopError> {
+        Self::get_pool(env.clone())?.require_auth();
+
+        if amount <= 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+
+        let total_assets = Self::get_total_assets(env.clone()) + amount;
+        env.storage().instance().set(&DataKey::TotalAssets, &total_assets);
+
+        env.events().publish((symbol_short!("distrib"),), (amount, total_assets));
+
+        Ok(())
+    }
+
+    /// Pay bad debt out of the fund (pool only)
+    ///
+    /// Sends up to `amount` to the pool, limited by the fund's assets.
+    ///
+    /// # Returns
+    /// Tokens sent to the pool
+    pub fn cover_bad_debt(env: Env, amount: i128) -> Result<i128, BackstopError> {
+        let pool = Self::get_pool(env.clone())?;
+        pool.require_auth();
+
+        if amount <= 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+
+        let total_assets = Self::get_total_assets(env.clone());
+        let covered = amount.min(total_assets);
+        if covered > 0 {
+            env.storage().instance().set(&DataKey::TotalAssets, &(total_assets - covered));
+            token::Client::new(&env, &Self::get_token(env.clone())?).transfer(&env.current_contract_address(), &pool, &covered);
+        }
+
+        env.events().publish((symbol_short!("bad_debt"),), (amount, covered));
+
+        Ok(covered)
+    }
+
+    /// Set the withdrawal cooldown in seconds (admin only)
+    pub fn set_cooldown(env: Env, cooldown: u64) -> Result<(), BackstopError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        env.storage().instance().set(&DataKey::Cooldown, &cooldown);
+        env.events().publish((symbol_short!("cooldown"),), cooldown);
+
+        Ok(())
+    }
+
+    /// Get the withdrawal cooldown in seconds
+    pub fn get_cooldown(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::Cooldown).unwrap_or(DEFAULT_COOLDOWN)
+    }
+
+    /// Set how long a matured withdrawal can be claimed, in seconds (admin only)
+    pub fn set_withdraw_window(env: Env, window: u64) -> Result<(), BackstopError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        if window == 0 {
+            return Err(BackstopError::InvalidAmount);
+        }
+        env.storage().instance().set(&DataKey::WithdrawWindow, &window);
+        env.events().publish((symbol_short!("wd_window"),), window);
+
+        Ok(())
+    }
+
+    /// Get how long a matured withdrawal can be claimed, in seconds
+    pub fn get_withdraw_window(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::WithdrawWindow)
+            .unwrap_or(DEFAULT_WITHDRAW_WINDOW)
+    }
+
+    /// Get a staker's shares
+    pub fn get_shares(env: Env, user: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::Shares(user)).unwrap_or(0)
+    }
+
+    /// Get the tokens a staker's shares are currently worth
+    pub fn get_user_assets(env: Env, user: Address) -> Result<i128, BackstopError> {
+        let total_shares = Self::get_total_shares(env.clone());
+        if total_shares == 0 {
+            return Ok(0);
+        }
+        Ok(mul_div(
+            Self::get_shares(env.clone(), user),
+            Self::get_total_assets(env),
+            total_shares,
+            Rounding::Down,
+        )?)
+    }
+
+    /// Get a staker's pending withdrawal, if any
+    pub fn get_withdrawal(env: Env, user: Address) -> Option<WithdrawalRequest> {
+        env.storage().persistent().get(&DataKey::Withdrawal(user))
+    }
+
+    /// Get the total shares outstanding
+    pub fn get_total_shares(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::TotalShares).unwrap_or(0)
+    }
+
+    /// Get the tokens backing all shares
+    pub fn get_total_assets(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::TotalAssets).unwrap_or(0)
+    }
+
+    /// Get the admin address
+    pub fn get_admin(env: Env) -> Result<Address, BackstopError> {
+        env.storage().instance().get(&DataKey::Admin).ok_or(BackstopError::NotInitialized)
+    }
+
+    /// Get the lending pool address
+    pub fn get_pool(env: Env) -> Result<Address, BackstopError> {
+        env.storage().instance().get(&DataKey::Pool).ok_or(BackstopError::NotInitialized)
+    }
+
+    /// Get the staked token address
+    pub fn get_token(env: Env) -> Result<Address, BackstopError> {
+        env.storage().instance().get(&DataKey::Token).ok_or(BackstopError::NotInitialized)
+    }
+
+    /// Internal: Store a staker's shares and extend their TTL
+    fn set_shares(env: &Env, user: &Address, shares: i128) {
+        let key = DataKey::Shares(user.clone());
+        env.storage().persistent().set(&key, &shares);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::TokenClient,
+    };
+    use apogee_testutils::{create_token, fund};
+
+    fn setup() -> (Env, BackstopClient<'static>, TokenClient<'static>, Address) {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let pool = Address::generate(&env);
+        let (token, _) = create_token(&env, &admin);
+
+        let contract_id = env.register_contract(None, Backstop);
+        let client = BackstopClient::new(&env, &contract_id);
+        client.initialize(&admin, &pool, &token.address);
+
+        (env, client, token, pool)
+    }
+
+    #[test]
+    fn test_fees_and_bad_debt_move_share_value() {
+        let (env, client, token, pool) = setup();
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        fund(&env, &token.address, &[&alice, &bob, &pool], 1_000);
+
+        assert_eq!(client.deposit(&alice, &1_000), 1_000);
+
+        // Fees raise the value of existing shares
+        token.transfer(&pool, &client.address, &500);
+        client.distribute(&500);
+        assert_eq!(client.get_user_assets(&alice), 1_500);
+        assert_eq!(client.deposit(&bob, &600), 400);
+
+        // Bad debt is paid out of the fund, capped at its assets
+        assert_eq!(client.cover_bad_debt(&1_050), 1_050);
+        assert_eq!(token.balance(&pool), 1_550);
+        assert_eq!(client.get_user_assets(&alice), 750);
+        assert_eq!(client.get_user_assets(&bob), 300);
+
+        assert_eq!(client.cover_bad_debt(&5_000), 1_050);
+        assert_eq!(client.get_total_assets(), 0);
+        assert_eq!(client.try_deposit(&bob, &100), Err(Ok(BackstopError::FundDepleted)));
+    }
+
+    #[test]
+    fn test_withdrawal_cooldown() {
+        let (env, client, token, _pool) = setup();
+        let alice = Address::generate(&env);
+        fund(&env, &token.address, &[&alice], 1_000);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.deposit(&alice, &1_000);
+        assert_eq!(client.try_withdraw(&alice), Err(Ok(BackstopError::NoWithdrawalRequest)));
+        assert_eq!(
+            client.try_request_withdrawal(&alice, &1_001),
+            Err(Ok(BackstopError::InsufficientShares))
+        );
+
+        let request = client.request_withdrawal(&alice, &400);
+        assert_eq!(request.unlock_at, 1_000 + DEFAULT_COOLDOWN);
+        assert_eq!(client.try_withdraw(&alice), Err(Ok(BackstopError::CooldownActive)));
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000 + DEFAULT_COOLDOWN);
+        assert_eq!(client.withdraw(&alice), 400);
+        assert_eq!(token.balance(&alice), 400);
+        assert_eq!(client.get_shares(&alice), 600);
+        assert_eq!(client.get_withdrawal(&alice), None);
+    }
+
+    #[test]
+    fn test_matured_withdrawal_expires() {
+        let (env, client, token, _pool) = setup();
+        let alice = Address::generate(&env);
+        fund(&env, &token.address, &[&alice], 1_000);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.deposit(&alice, &1_000);
+        let request = client.request_withdrawal(&alice, &1_000);
+
+        // A request kept past its claim window cannot be used later
+        let expires_at = request.unlock_at + DEFAULT_WITHDRAW_WINDOW;
+        env.ledger().with_mut(|li| li.timestamp = expires_at + 1);
+        assert_eq!(client.try_withdraw(&alice), Err(Ok(BackstopError::Expired)));
+
+        // Re-queueing restarts the cooldown
+        let request = client.request_withdrawal(&alice, &1_000);
+        assert_eq!(client.try_withdraw(&alice), Err(Ok(BackstopError::CooldownActive)));
+        env.ledger().with_mut(|li| li.timestamp = request.unlock_at + DEFAULT_WITHDRAW_WINDOW);
+        assert_eq!(client.withdraw(&alice), 1_000);
+    }
+}
@@ -0,0 +1,418 @@
+#![no_std]
+
+//! # Stellend Blend Adapter Contract
+//!
+//! Exposes the lending pool through the subset of the Blend pool interface
+//! that wallets and dashboards use: `submit` with typed requests,
+//! `get_positions` and `get_reserve_list`. Tooling built for Blend can then
+//! read and move Apogee positions unchanged.
+//!
+//! ## How It Works
+//!
+//! - Reserves are identified by token address and indexed in the pool's
+//!   listing order, as Blend indexes its reserve list
+//! - `submit` translates the requests into one pool `multicall`, so they run
+//!   atomically under the user's single authorization
+//! - Amounts are in the pool's units (7 decimals). Withdrawals are requested
+//!   in underlying, as in Blend, and converted to pool shares; asking for
+//!   more than the balance withdraws all of it
+//!
+//! Differences from Blend: the user must also be the spender and the
+//! recipient, auction requests are not supported, and collateral positions
+//! are reported in underlying rather than in bTokens.
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, Address, Env, Map, Symbol,
+    Vec,
+};
+use stellend_fixed_point::{mul_div, Rounding};
+
+use apogee_common::{ReserveConfig, BUMP_AMOUNT, EXCHANGE_RATE_SCALE, LIFETIME_THRESHOLD};
+
+// ============================================================================
+// BLEND TYPES
+// ============================================================================
+
+/// Blend request types accepted by `submit`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum RequestType {
+    Supply = 0,
+    Withdraw = 1,
+    SupplyCollateral = 2,
+    WithdrawCollateral = 3,
+    Borrow = 4,
+    Repay = 5,
+}
+
+/// A Blend pool request
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Request {
+    /// A `RequestType`
+    pub request_type: u32,
+    /// Reserve token
+    pub address: Address,
+    pub amount: i128,
+}
+
+/// A user's balances per reserve index, as Blend reports them
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Positions {
+    /// Debt, including accrued interest
+    pub liabilities: Map<u32, i128>,
+    /// Collateral, in underlying
+    pub collateral: Map<u32, i128>,
+    /// Supplied pool shares
+    pub supply: Map<u32, i128>,
+}
+
+// ============================================================================
+// POOL INTERFACE
+// ============================================================================
+
+/// One step of the pool's `multicall`; mirrors the pool's `Operation`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Operation {
+    Supply(Symbol, i128),
+    Withdraw(Symbol, i128),
+    DepositCollateral(Symbol, i128),
+    WithdrawCollateral(Symbol, i128),
+    Borrow(Symbol, i128),
+    Repay(Symbol, i128),
+}
+
+/// Lending pool functions used by the adapter
+#[contractclient(name = "PoolClient")]
+pub trait Pool {
+    fn multicall(env: Env, user: Address, ops: Vec<Operation>) -> Vec<i128>;
+    fn list_reserves(env: Env) -> Vec<Symbol>;
+    fn get_reserve(env: Env, asset: Symbol) -> ReserveConfig;
+    fn get_user_shares(env: Env, user: Address, asset: Symbol) -> i128;
+    fn get_user_collateral(env: Env, user: Address, asset: Symbol) -> i128;
+    fn get_user_borrow_with_interest(env: Env, user: Address, asset: Symbol) -> i128;
+    fn get_exchange_rate_with_interest(env: Env, asset: Symbol) -> i128;
+}
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the adapter
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Lending pool backing the adapter
+    Pool,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the adapter
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AdapterError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Token is not a reserve of the pool
+    UnknownReserve = 3,
+    /// Request type, or a spender or recipient other than the user, is not supported
+    UnsupportedRequest = 4,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 5,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct BlendAdapter;
+
+#[contractimpl]
+impl BlendAdapter {
+    /// Initialize the adapter with the lending pool
+    pub fn initialize(env: Env, pool: Address) -> Result<(), AdapterError> {
+        if env.storage().instance().has(&DataKey::Pool) {
+            return Err(AdapterError::AlreadyInitialized);
+        }
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    /// Run `requests` on `from`'s position in one atomic pool call
+    ///
+    /// # Arguments
+    /// * `from` - Owner of the position (must authorize)
+    /// * `spender` - Pays supplies and repayments; must be `from`
+    /// * `to` - Receives withdrawals and borrows; must be `from`
+    /// * `requests` - Blend requests, run in order
+    ///
+    /// # Returns
+    /// `from`'s positions afterwards
+    pub fn submit(
+        env: Env,
+        from: Address,
+        spender: Address,
+        to: Address,
+        requests: Vec<Request>,
+    ) -> Result<Positions, AdapterError> {
+        from.require_auth();
+
+        if spender != from || to != from {
+            return Err(AdapterError::UnsupportedRequest);
+        }
+
+        let pool = Self::pool(&env)?;
+        let reserves = Self::reserves(&env, &pool);
+        let mut ops = Vec::new(&env);
+        for request in requests.iter() {
+            let asset = Self::find_reserve(&reserves, &request.address)?;
+            let amount = request.amount;
+            let op = match request.request_type {
+                t if t == RequestType::Supply as u32 => Operation::Supply(asset, amount),
+                t if t == RequestType::Withdraw as u32 => {
+                    let rate = pool.get_exchange_rate_with_interest(&asset);
+                    let shares = mul_div(amount, EXCHANGE_RATE_SCALE, rate, Rounding::Up)
+                        .map_err(|_| AdapterError::MathOverflow)?;
+                    if shares >= pool.get_user_shares(&from, &asset) {
+                        Operation::Withdraw(asset, i128::MAX)
+                    } else {
+                        Operation::Withdraw(asset, shares)
+                    }
+                }
+                t if t == RequestType::SupplyCollateral as u32 => Operation::DepositCollateral(asset, amount),
+                t if t == RequestType::WithdrawCollateral as u32 => {
+                    if amount >= pool.get_user_collateral(&from, &asset) {
+                        Operation::WithdrawCollateral(asset, i128::MAX)
+                    } else {
+                        Operation::WithdrawCollateral(asset, amount)
+                    }
+                }
+                t if t == RequestType::Borrow as u32 => Operation::Borrow(asset, amount),
+                t if t == RequestType::Repay as u32 => Operation::Repay(asset, amount),
+                _ => return Err(AdapterError::UnsupportedRequest),
+            };
+            ops.push_back(op);
+        }
+        pool.multicall(&from, &ops);
+
+        Ok(Self::positions(&env, &pool, &reserves, &from))
+    }
+
+    /// Get a user's positions, keyed by reserve index
+    pub fn get_positions(env: Env, address: Address) -> Result<Positions, AdapterError> {
+        let pool = Self::pool(&env)?;
+        let reserves = Self::reserves(&env, &pool);
+        Ok(Self::positions(&env, &pool, &reserves, &address))
+    }
+
+    /// Get the reserve tokens, in reserve index order
+    pub fn get_reserve_list(env: Env) -> Result<Vec<Address>, AdapterError> {
+        let pool = Self::pool(&env)?;
+        let mut tokens = Vec::new(&env);
+        for (token, _) in Self::reserves(&env, &pool).iter() {
+            tokens.push_back(token);
+        }
+        Ok(tokens)
+    }
+
+    /// Get the lending pool
+    pub fn get_pool(env: Env) -> Result<Address, AdapterError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Pool)
+            .ok_or(AdapterError::NotInitialized)
+    }
+
+    /// Internal: Pool client
+    fn pool(env: &Env) -> Result<PoolClient<'_>, AdapterError> {
+        Ok(PoolClient::new(env, &Self::get_pool(env.clone())?))
+    }
+
+    /// Internal: Token and symbol of each reserve, in listing order
+    fn reserves(env: &Env, pool: &PoolClient) -> Vec<(Address, Symbol)> {
+        let mut reserves = Vec::new(env);
+        for asset in pool.list_reserves().iter() {
+            reserves.push_back((pool.get_reserve(&asset).token, asset));
+        }
+        reserves
+    }
+
+    /// Internal: Symbol of the reserve for `token`
+    fn find_reserve(reserves: &Vec<(Address, Symbol)>, token: &Address) -> Result<Symbol, AdapterError> {
+        reserves
+            .iter()
+            .find(|(reserve, _)| reserve == token)
+            .map(|(_, asset)| asset)
+            .ok_or(AdapterError::UnknownReserve)
+    }
+
+    /// Internal: A user's nonzero balances per reserve index
+    fn positions(env: &Env, pool: &PoolClient, reserves: &Vec<(Address, Symbol)>, user: &Address) -> Positions {
+        let mut positions = Positions {
+            liabilities: Map::new(env),
+            collateral: Map::new(env),
+            supply: Map::new(env),
+        };
+        for (index, (_, asset)) in reserves.iter().enumerate() {
+            let index = index as u32;
+            let debt = pool.get_user_borrow_with_interest(user, &asset);
+            if debt > 0 {
+                positions.liabilities.set(index, debt);
+            }
+            let collateral = pool.get_user_collateral(user, &asset);
+            if collateral > 0 {
+                positions.collateral.set(index, collateral);
+            }
+            let shares = pool.get_user_shares(user, &asset);
+            if shares > 0 {
+                positions.supply.set(index, shares);
+            }
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{symbol_short, testutils::Address as _, vec};
+
+    /// Pool with two reserves that records the operations it is sent and
+    /// credits collateral and debt from them
+    #[contract]
+    struct MockPool;
+
+    #[contractimpl]
+    impl MockPool {
+        pub fn setup(env: Env, xlm: Address, usdc: Address) {
+            env.storage().instance().set(&symbol_short!("XLM"), &xlm);
+            env.storage().instance().set(&symbol_short!("USDC"), &usdc);
+        }
+
+        pub fn multicall(env: Env, user: Address, ops: Vec<Operation>) -> Vec<i128> {
+            user.require_auth();
+            let mut results = Vec::new(&env);
+            for op in ops.iter() {
+                match op.clone() {
+                    Operation::DepositCollateral(asset, amount) => {
+                        let key = (symbol_short!("coll"), asset);
+                        let held: i128 = env.storage().instance().get(&key).unwrap_or(0);
+                        env.storage().instance().set(&key, &(held + amount));
+                    }
+                    Operation::Borrow(asset, amount) => {
+                        env.storage().instance().set(&(symbol_short!("debt"), asset), &amount);
+                    }
+                    _ => {}
+                }
+                results.push_back(0);
+            }
+            env.storage().instance().set(&symbol_short!("ops"), &ops);
+            results
+        }
+
+        pub fn last_ops(env: Env) -> Vec<Operation> {
+            env.storage().instance().get(&symbol_short!("ops")).unwrap()
+        }
+
+        pub fn list_reserves(env: Env) -> Vec<Symbol> {
+            vec![&env, symbol_short!("XLM"), symbol_short!("USDC")]
+        }
+
+        pub fn get_reserve(env: Env, asset: Symbol) -> ReserveConfig {
+            ReserveConfig {
+                token: env.storage().instance().get(&asset).unwrap(),
+                decimals: 7,
+                ltv: 0,
+                liquidation_threshold: 0,
+                collateral_enabled: true,
+                borrow_enabled: true,
+                interest_rate_model: env.current_contract_address(),
+            }
+        }
+
+        pub fn get_user_shares(_env: Env, _user: Address, _asset: Symbol) -> i128 {
+            1_000
+        }
+
+        pub fn get_user_collateral(env: Env, _user: Address, asset: Symbol) -> i128 {
+            env.storage().instance().get(&(symbol_short!("coll"), asset)).unwrap_or(0)
+        }
+
+        pub fn get_user_borrow_with_interest(env: Env, _user: Address, asset: Symbol) -> i128 {
+            env.storage().instance().get(&(symbol_short!("debt"), asset)).unwrap_or(0)
+        }
+
+        pub fn get_exchange_rate_with_interest(_env: Env, _asset: Symbol) -> i128 {
+            2 * EXCHANGE_RATE_SCALE
+        }
+    }
+
+    #[test]
+    fn test_submit_translates_blend_requests() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let user = Address::generate(&env);
+        let (xlm, usdc) = (Address::generate(&env), Address::generate(&env));
+
+        let pool_id = env.register_contract(None, MockPool);
+        let pool = MockPoolClient::new(&env, &pool_id);
+        pool.setup(&xlm, &usdc);
+        let adapter = BlendAdapterClient::new(&env, &env.register_contract(None, BlendAdapter));
+        adapter.initialize(&pool_id);
+        assert_eq!(adapter.get_reserve_list(), vec![&env, xlm.clone(), usdc.clone()]);
+
+        let request = |request_type: RequestType, address: &Address, amount: i128| Request {
+            request_type: request_type as u32,
+            address: address.clone(),
+            amount,
+        };
+        let positions = adapter.submit(
+            &user,
+            &user,
+            &user,
+            &vec![
+                &env,
+                request(RequestType::SupplyCollateral, &xlm, 5_000),
+                request(RequestType::Borrow, &usdc, 700),
+                request(RequestType::Withdraw, &usdc, 1_001),
+                request(RequestType::Withdraw, &usdc, 5_000),
+            ],
+        );
+
+        // Withdrawals become shares at the exchange rate, or all of them
+        assert_eq!(
+            pool.last_ops(),
+            vec![
+                &env,
+                Operation::DepositCollateral(symbol_short!("XLM"), 5_000),
+                Operation::Borrow(symbol_short!("USDC"), 700),
+                Operation::Withdraw(symbol_short!("USDC"), 501),
+                Operation::Withdraw(symbol_short!("USDC"), i128::MAX),
+            ]
+        );
+        assert_eq!(positions.collateral.get(0), Some(5_000));
+        assert_eq!(positions.liabilities.get(1), Some(700));
+        assert_eq!(positions.liabilities.get(0), None);
+        assert_eq!(adapter.get_positions(&user), positions);
+
+        let other = Address::generate(&env);
+        assert_eq!(
+            adapter.try_submit(&user, &user, &other, &vec![&env, request(RequestType::Borrow, &usdc, 1)]),
+            Err(Ok(AdapterError::UnsupportedRequest))
+        );
+        assert_eq!(
+            adapter.try_submit(&user, &user, &user, &vec![&env, request(RequestType::Supply, &other, 1)]),
+            Err(Ok(AdapterError::UnknownReserve))
+        );
+    }
+}
@@ -0,0 +1,433 @@
+#![no_std]
+
+//! # Stellend Emissions Contract
+//!
+//! Streams a reward token to the lending pool's suppliers and borrowers
+//! (liquidity mining).
+//!
+//! ## How It Works
+//!
+//! - Each reserve has two reward streams, one for suppliers and one for
+//!   borrowers, each emitting a configurable amount of reward token per second
+//! - Rewards are split by scaled balance: sToken shares for suppliers and
+//!   debt divided by the borrow index for borrowers
+//! - Every stream keeps a reward index (rewards per unit of balance); a user
+//!   earns `balance * (index - user_index)` since their last update
+//! - The pool calls `handle_balance_change` whenever a balance changes, so
+//!   the split is always based on up-to-date balances
+//! - Users collect everything they earned with `claim_rewards`
+//!
+//! The contract pays rewards from its own balance, so the admin must fund it
+//! with the reward token.
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+    Vec,
+};
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding, WAD};
+
+pub use apogee_common::RewardSide;
+use apogee_common::{BUMP_AMOUNT, LIFETIME_THRESHOLD};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Precision of reward indexes (1e18)
+const INDEX_SCALE: i128 = WAD;
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// A reward stream: one side of one reserve
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardStream {
+    pub asset: Symbol,
+    pub side: RewardSide,
+}
+
+/// Accounting state of a reward stream
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StreamState {
+    /// Reward tokens emitted per second
+    pub emission_rate: i128,
+    /// Rewards per unit of balance accumulated so far (scaled by 1e18)
+    pub index: i128,
+    /// Timestamp the index was last brought up to date
+    pub last_update: u64,
+    /// Sum of all users' scaled balances
+    pub total_balance: i128,
+}
+
+/// Storage keys for the emissions controller
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Admin allowed to set emission rates
+    Admin,
+    /// Lending pool reporting balance changes
+    Pool,
+    /// Token paid out as rewards
+    RewardToken,
+    /// Streams that have had an emission rate set
+    Streams,
+    /// Accounting state per stream
+    Stream(RewardStream),
+    /// User's scaled balance in a stream
+    UserBalance(Address, RewardStream),
+    /// Stream index at the user's last update
+    UserIndex(Address, RewardStream),
+    /// Rewards earned by a user and not yet claimed
+    Accrued(Address),
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the emissions controller
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum EmissionsError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Emission rate must not be negative
+    InvalidRate = 3,
+    /// Balance must not be negative
+    InvalidBalance = 4,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 5,
+}
+
+impl From<FixedPointError> for EmissionsError {
+    fn from(_: FixedPointError) -> Self {
+        EmissionsError::MathOverflow
+    }
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct Emissions;
+
+#[contractimpl]
+impl Emissions {
+    /// Initialize the emissions controller
+    ///
+    /// # Arguments
+    /// * `admin` - Address allowed to set emission rates
+    /// * `pool` - Lending pool that reports balance changes
+    /// * `reward_token` - Token paid out as rewards
+    pub fn initialize(env: Env, admin: Address, pool: Address, reward_token: Address) -> Result<(), EmissionsError> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(EmissionsError::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().set(&DataKey::RewardToken, &reward_token);
+
+        Ok(())
+    }
+
+    /// Set the reward tokens emitted per second to one side of a reserve (admin only)
+    ///
+    /// Rewards up to now are accrued at the previous rate first. A rate of 0
+    /// stops the stream.
+    ///
+    /// # Arguments
+    /// * `asset` - Reserve asset symbol in the pool
+    /// * `side` - Suppliers or borrowers
+    /// * `emission_rate` - Reward tokens per second
+    pub fn set_emission_rate(env: Env, asset: Symbol, side: RewardSide, emission_rate: i128) -> Result<(), EmissionsError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        if emission_rate < 0 {
+            return Err(EmissionsError::InvalidRate);
+        }
+
+        let stream = RewardStream { asset: asset.clone(), side };
+        let mut state = Self::accrue_stream(&env, &stream)?;
+        state.emission_rate = emission_rate;
+        env.storage().instance().set(&DataKey::Stream(stream.clone()), &state);
+
+        let mut streams = Self::get_streams(env.clone());
+        if !streams.contains(&stream) {
+            streams.push_back(stream);
+            env.storage().instance().set(&DataKey::Streams, &streams);
+        }
+
+        env.events().publish((symbol_short!("emis_rate"), asset), (side, emission_rate));
+
+        Ok(())
+    }
+
+    /// Record a user's new scaled balance (pool only)
+    ///
+    /// Called by the pool after every supply, withdraw, share transfer,
+    /// borrow, repay and liquidation. Rewards earned on the old balance are
+    /// accrued before it is replaced.
+    ///
+    /// # Arguments
+    /// * `asset` - Reserve asset symbol in the pool
+    /// * `side` - Suppliers or borrowers
+    /// * `user` - User whose balance changed
+    /// * `balance` - User's scaled balance after the change
+    pub fn handle_balance_change(
+        env: Env,
+        asset: Symbol,
+        side: RewardSide,
+        user: Address,
+        balance: i128,
+    ) -> Result<(), EmissionsError> {
+        Self::get_pool(env.clone())?.require_auth();
+
+        if balance < 0 {
+            return Err(EmissionsError::InvalidBalance);
+        }
+
+        let stream = RewardStream { asset, side };
+        let mut state = Self::accrue_stream(&env, &stream)?;
+        let old_balance = Self::accrue_user(&env, &user, &stream, &state)?;
+
+        state.total_balance = state.total_balance - old_balance + balance;
+        env.storage().instance().set(&DataKey::Stream(stream.clone()), &state);
+
+        let key = DataKey::UserBalance(user, stream);
+        env.storage().persistent().set(&key, &balance);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        Ok(())
+    }
+
+    /// Claim all rewards a user has earned across every stream
+    ///
+    /// # Arguments
+    /// * `user` - User claiming (must authorize)
+    ///
+    /// # Returns
+    /// Reward tokens transferred to the user
+    pub fn claim_rewards(env: Env, user: Address) -> Result<i128, EmissionsError> {
+        user.require_auth();
+
+        for stream in Self::get_streams(env.clone()).iter() {
+            let state = Self::accrue_stream(&env, &stream)?;
+            env.storage().instance().set(&DataKey::Stream(stream.clone()), &state);
+            Self::accrue_user(&env, &user, &stream, &state)?;
+        }
+
+        let amount = Self::get_accrued(&env, &user);
+        if amount > 0 {
+            env.storage().persistent().remove(&DataKey::Accrued(user.clone()));
+
+            let reward_token = Self::get_reward_token(env.clone())?;
+            token::Client::new(&env, &reward_token).transfer(&env.current_contract_address(), &user, &amount);
+        }
+
+        env.events().publish((symbol_short!("claim"), user), amount);
+
+        Ok(amount)
+    }
+
+    /// Get the rewards a user could claim right now
+    pub fn get_claimable_rewards(env: Env, user: Address) -> Result<i128, EmissionsError> {
+        let mut amount = Self::get_accrued(&env, &user);
+        for stream in Self::get_streams(env.clone()).iter() {
+            let state = Self::accrue_stream(&env, &stream)?;
+            amount += Self::pending_rewards(&env, &user, &stream, &state)?;
+        }
+        Ok(amount)
+    }
+
+    /// Get the accounting state of one side of a reserve
+    pub fn get_stream(env: Env, asset: Symbol, side: RewardSide) -> StreamState {
+        Self::load_stream(&env, &RewardStream { asset, side })
+    }
+
+    /// Get the streams that have had an emission rate set
+    pub fn get_streams(env: Env) -> Vec<RewardStream> {
+        env.storage().instance().get(&DataKey::Streams).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get a user's scaled balance recorded for one side of a reserve
+    pub fn get_user_balance(env: Env, user: Address, asset: Symbol, side: RewardSide) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UserBalance(user, RewardStream { asset, side }))
+            .unwrap_or(0)
+    }
+
+    /// Get the admin address
+    pub fn get_admin(env: Env) -> Result<Address, EmissionsError> {
+        env.storage().instance().get(&DataKey::Admin).ok_or(EmissionsError::NotInitialized)
+    }
+
+    /// Get the lending pool address
+    pub fn get_pool(env: Env) -> Result<Address, EmissionsError> {
+        env.storage().instance().get(&DataKey::Pool).ok_or(EmissionsError::NotInitialized)
+    }
+
+    /// Get the reward token address
+    pub fn get_reward_token(env: Env) -> Result<Address, EmissionsError> {
+        env.storage().instance().get(&DataKey::RewardToken).ok_or(EmissionsError::NotInitialized)
+    }
+
+    // ========================================================================
+    // INTERNAL
+    // ========================================================================
+
+    /// Internal: Load a stream's state (empty if never touched)
+    fn load_stream(env: &Env, stream: &RewardStream) -> StreamState {
+        env.storage()
+            .instance()
+            .get(&DataKey::Stream(stream.clone()))
+            .unwrap_or(StreamState {
+                emission_rate: 0,
+                index: 0,
+                last_update: env.ledger().timestamp(),
+                total_balance: 0,
+            })
+    }
+
+    /// Internal: A stream's state with its index brought up to now (not stored)
+    ///
+    /// Nothing is emitted while the stream has no balance.
+    fn accrue_stream(env: &Env, stream: &RewardStream) -> Result<StreamState, EmissionsError> {
+        let mut state = Self::load_stream(env, stream);
+        let now = env.ledger().timestamp();
+        let elapsed = now.saturating_sub(state.last_update) as i128;
+
+        if elapsed > 0 && state.emission_rate > 0 && state.total_balance > 0 {
+            let emitted = state.emission_rate.checked_mul(elapsed).ok_or(EmissionsError::MathOverflow)?;
+            state.index += mul_div(emitted, INDEX_SCALE, state.total_balance, Rounding::Down)?;
+        }
+        state.last_update = now;
+
+        Ok(state)
+    }
+
+    /// Internal: Rewards a user earned in a stream since their last update
+    fn pending_rewards(env: &Env, user: &Address, stream: &RewardStream, state: &StreamState) -> Result<i128, EmissionsError> {
+        let balance = Self::get_user_balance(env.clone(), user.clone(), stream.asset.clone(), stream.side);
+        let user_index: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserIndex(user.clone(), stream.clone()))
+            .unwrap_or(0);
+
+        Ok(mul_div(balance, state.index - user_index, INDEX_SCALE, Rounding::Down)?)
+    }
+
+    /// Internal: Move a user's pending rewards in a stream to their accrued total
+    ///
+    /// Returns the user's balance in the stream.
+    fn accrue_user(env: &Env, user: &Address, stream: &RewardStream, state: &StreamState) -> Result<i128, EmissionsError> {
+        let pending = Self::pending_rewards(env, user, stream, state)?;
+        if pending > 0 {
+            let key = DataKey::Accrued(user.clone());
+            env.storage().persistent().set(&key, &(Self::get_accrued(env, user) + pending));
+            env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        }
+
+        let key = DataKey::UserIndex(user.clone(), stream.clone());
+        env.storage().persistent().set(&key, &state.index);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        Ok(Self::get_user_balance(env.clone(), user.clone(), stream.asset.clone(), stream.side))
+    }
+
+    /// Internal: Rewards accrued to a user and not yet claimed
+    fn get_accrued(env: &Env, user: &Address) -> i128 {
+        env.storage().persistent().get(&DataKey::Accrued(user.clone())).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        symbol_short,
+        testutils::{Address as _, Ledger},
+        token::TokenClient,
+    };
+    use apogee_testutils::{create_token, fund};
+
+    fn setup() -> (Env, EmissionsClient<'static>, TokenClient<'static>) {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let pool = Address::generate(&env);
+        let (token, _) = create_token(&env, &admin);
+
+        let contract_id = env.register_contract(None, Emissions);
+        let client = EmissionsClient::new(&env, &contract_id);
+        client.initialize(&admin, &pool, &token.address);
+        fund(&env, &token.address, &[&contract_id], 1_000_000_000_000);
+
+        (env, client, token)
+    }
+
+    #[test]
+    fn test_rewards_split_by_balance() {
+        let (env, client, token) = setup();
+        let usdc = symbol_short!("USDC");
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_emission_rate(&usdc, &RewardSide::Supply, &100);
+        client.handle_balance_change(&usdc, &RewardSide::Supply, &alice, &3_000);
+        client.handle_balance_change(&usdc, &RewardSide::Supply, &bob, &1_000);
+
+        // 100 seconds at 100/s split 3:1
+        env.ledger().with_mut(|li| li.timestamp = 1_100);
+        assert_eq!(client.get_claimable_rewards(&alice), 7_500);
+        assert_eq!(client.get_claimable_rewards(&bob), 2_500);
+
+        // Bob leaves; Alice earns the whole stream afterwards
+        client.handle_balance_change(&usdc, &RewardSide::Supply, &bob, &0);
+        env.ledger().with_mut(|li| li.timestamp = 1_200);
+
+        // Indexes round down, so a unit of dust stays in the contract
+        assert_eq!(client.claim_rewards(&alice), 17_499);
+        assert_eq!(token.balance(&alice), 17_499);
+        assert_eq!(client.claim_rewards(&bob), 2_500);
+        assert_eq!(client.get_claimable_rewards(&alice), 0);
+
+        // Borrow side is a separate stream that has not been configured
+        client.handle_balance_change(&usdc, &RewardSide::Borrow, &bob, &5_000);
+        env.ledger().with_mut(|li| li.timestamp = 1_300);
+        assert_eq!(client.get_claimable_rewards(&bob), 0);
+        assert_eq!(client.get_stream(&usdc, &RewardSide::Borrow).total_balance, 5_000);
+    }
+
+    #[test]
+    fn test_rate_change_accrues_at_old_rate() {
+        let (env, client, _token) = setup();
+        let xlm = symbol_short!("XLM");
+        let alice = Address::generate(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_emission_rate(&xlm, &RewardSide::Borrow, &10);
+        client.handle_balance_change(&xlm, &RewardSide::Borrow, &alice, &500);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_050);
+        client.set_emission_rate(&xlm, &RewardSide::Borrow, &0);
+
+        env.ledger().with_mut(|li| li.timestamp = 2_000);
+        assert_eq!(client.get_claimable_rewards(&alice), 500);
+        assert_eq!(
+            client.try_set_emission_rate(&xlm, &RewardSide::Borrow, &-1),
+            Err(Ok(EmissionsError::InvalidRate))
+        );
+    }
+}
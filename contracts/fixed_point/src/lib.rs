@@ -0,0 +1,275 @@
+#![no_std]
+
+//! # Stellend Fixed-Point Math
+//!
+//! Checked fixed-point helpers shared by the pool, price oracle and interest
+//! rate model.
+//!
+//! All Stellend amounts, prices and rates are `i128` values scaled by a
+//! power of ten (1e7 for percentages and prices, 1e9 for the sToken exchange
+//! rate, 1e27 "ray" for the borrow index). Computing `a * b / c` with raw operators can
+//! overflow on the intermediate product even when the result fits, and
+//! silently truncates. [`mul_div`] computes the product in 256 bits, rounds
+//! explicitly and reports overflow or division by zero as an error instead
+//! of panicking.
+//!
+//! ```text
+//! mul_fixed(a, b, SCALE_7) = a * b / 1e7      // e.g. amount * price
+//! div_fixed(a, b, SCALE_7) = a * 1e7 / b      // e.g. usd_value / price
+//! ```
+
+/// 7-decimal scale used for percentages, rates and prices (1.0 = 10_000_000)
+pub const SCALE_7: i128 = 10_000_000;
+
+/// 9-decimal scale used for the sToken exchange rate
+pub const SCALE_9: i128 = 1_000_000_000;
+
+/// 18-decimal "wad" scale
+pub const WAD: i128 = 1_000_000_000_000_000_000;
+
+/// 27-decimal "ray" scale, used for the borrow index and per-second rates
+pub const RAY: i128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// Errors returned by fixed-point operations
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FixedPointError {
+    /// Result does not fit in an i128
+    Overflow,
+    /// Denominator is zero
+    DivisionByZero,
+}
+
+/// Rounding mode, applied to the magnitude of the result
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    /// Round towards zero (truncate)
+    Down,
+    /// Round away from zero
+    Up,
+}
+
+/// Compute `x * y / denominator` with a 256-bit intermediate product
+///
+/// # Errors
+/// * `DivisionByZero` if `denominator` is zero
+/// * `Overflow` if the result does not fit in an i128
+pub fn mul_div(x: i128, y: i128, denominator: i128, rounding: Rounding) -> Result<i128, FixedPointError> {
+    if denominator == 0 {
+        return Err(FixedPointError::DivisionByZero);
+    }
+
+    let negative = (x < 0) ^ (y < 0) ^ (denominator < 0);
+    let (hi, lo) = wide_mul(x.unsigned_abs(), y.unsigned_abs());
+    let (quotient, remainder) = wide_div(hi, lo, denominator.unsigned_abs())?;
+
+    let magnitude = if rounding == Rounding::Up && remainder != 0 {
+        quotient.checked_add(1).ok_or(FixedPointError::Overflow)?
+    } else {
+        quotient
+    };
+
+    if negative {
+        if magnitude > i128::MIN.unsigned_abs() {
+            return Err(FixedPointError::Overflow);
+        }
+        Ok((magnitude as i128).wrapping_neg())
+    } else {
+        i128::try_from(magnitude).map_err(|_| FixedPointError::Overflow)
+    }
+}
+
+/// Multiply two fixed-point values: `a * b / scale`
+pub fn mul_fixed(a: i128, b: i128, scale: i128, rounding: Rounding) -> Result<i128, FixedPointError> {
+    mul_div(a, b, scale, rounding)
+}
+
+/// Divide two fixed-point values: `a * scale / b`
+pub fn div_fixed(a: i128, b: i128, scale: i128, rounding: Rounding) -> Result<i128, FixedPointError> {
+    mul_div(a, scale, b, rounding)
+}
+
+/// Multiply two ray values: `a * b / RAY`
+pub fn ray_mul(a: i128, b: i128, rounding: Rounding) -> Result<i128, FixedPointError> {
+    mul_div(a, b, RAY, rounding)
+}
+
+/// Divide two ray values: `a * RAY / b`
+pub fn ray_div(a: i128, b: i128, rounding: Rounding) -> Result<i128, FixedPointError> {
+    mul_div(a, RAY, b, rounding)
+}
+
+/// Convert an annual rate to a per-second rate in ray precision
+///
+/// At 1e7 a per-second rate truncates to zero, so it is carried in ray.
+///
+/// # Arguments
+/// * `annual_rate` - Simple annual rate, scaled by `scale`
+/// * `scale` - Scale of `annual_rate`, e.g. `SCALE_7`
+/// * `seconds_per_year` - Length of the rate's year
+pub fn ray_rate_per_second(annual_rate: i128, scale: i128, seconds_per_year: i128) -> Result<i128, FixedPointError> {
+    let per_year = scale.checked_mul(seconds_per_year).ok_or(FixedPointError::Overflow)?;
+    mul_div(annual_rate, RAY, per_year, Rounding::Down)
+}
+
+/// Growth factor of a per-second rate compounded over `elapsed` seconds, in ray
+///
+/// Approximates `(1 + r)^n` by the first terms of its binomial expansion,
+///
+/// ```text
+/// 1 + n·r + n(n-1)/2 · r² + n(n-1)(n-2)/6 · r³
+/// ```
+///
+/// which slightly undercharges: by about 5e-6 for 5% over a full year
+/// without accruing, and negligibly at ledger cadence. Each term is
+/// truncated.
+///
+/// # Arguments
+/// * `rate_per_second` - Per-second rate in ray, e.g. from [`ray_rate_per_second`]
+/// * `elapsed` - Seconds to compound over
+pub fn compound_interest(rate_per_second: i128, elapsed: u64) -> Result<i128, FixedPointError> {
+    let n = elapsed as i128;
+    if n == 0 {
+        return Ok(RAY);
+    }
+
+    let rate_squared = ray_mul(rate_per_second, rate_per_second, Rounding::Down)?;
+    let rate_cubed = ray_mul(rate_squared, rate_per_second, Rounding::Down)?;
+
+    let pairs = n.checked_mul(n - 1).ok_or(FixedPointError::Overflow)?;
+    let first = n.checked_mul(rate_per_second).ok_or(FixedPointError::Overflow)?;
+    let second = pairs.checked_mul(rate_squared).ok_or(FixedPointError::Overflow)? / 2;
+    let third = pairs
+        .checked_mul((n - 2).max(0))
+        .and_then(|n3| n3.checked_mul(rate_cubed))
+        .ok_or(FixedPointError::Overflow)?
+        / 6;
+
+    RAY.checked_add(first)
+        .and_then(|v| v.checked_add(second))
+        .and_then(|v| v.checked_add(third))
+        .ok_or(FixedPointError::Overflow)
+}
+
+/// Full 128x128 -> 256 bit multiplication, returned as (high, low) words
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // Middle column, including the carry out of the low word
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (cross << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+
+    (hi, lo)
+}
+
+/// Divide the 256-bit value (hi, lo) by `d`, returning (quotient, remainder)
+///
+/// Fails with `Overflow` if the quotient does not fit in 128 bits.
+fn wide_div(hi: u128, lo: u128, d: u128) -> Result<(u128, u128), FixedPointError> {
+    if hi == 0 {
+        return Ok((lo / d, lo % d));
+    }
+    if hi >= d {
+        return Err(FixedPointError::Overflow);
+    }
+
+    // Restoring long division over the 128 low bits; `hi < d` so the
+    // quotient fits in 128 bits
+    let mut remainder = hi;
+    let mut quotient: u128 = 0;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= d {
+            remainder = remainder.wrapping_sub(d);
+            quotient |= 1;
+        }
+    }
+
+    Ok((quotient, remainder))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3, Rounding::Down), Ok(33));
+        assert_eq!(mul_div(10, 10, 3, Rounding::Up), Ok(34));
+        assert_eq!(mul_div(9, 10, 3, Rounding::Up), Ok(30));
+        assert_eq!(mul_div(-10, 10, 3, Rounding::Down), Ok(-33));
+        assert_eq!(mul_div(-10, 10, 3, Rounding::Up), Ok(-34));
+        assert_eq!(mul_div(10, -10, -3, Rounding::Down), Ok(33));
+    }
+
+    #[test]
+    fn test_mul_div_large_intermediate() {
+        // The product overflows i128 but the result fits
+        let big = i128::MAX / 3;
+        assert_eq!(mul_div(big, RAY, RAY, Rounding::Down), Ok(big));
+        assert_eq!(mul_div(big, 6, 4, Rounding::Down), Ok(big / 2 * 3));
+        assert_eq!(mul_div(i128::MAX, i128::MAX, i128::MAX, Rounding::Down), Ok(i128::MAX));
+        assert_eq!(mul_div(i128::MIN, 1, 1, Rounding::Down), Ok(i128::MIN));
+    }
+
+    #[test]
+    fn test_mul_div_errors() {
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(FixedPointError::DivisionByZero));
+        assert_eq!(mul_div(i128::MAX, 2, 1, Rounding::Down), Err(FixedPointError::Overflow));
+        assert_eq!(mul_div(i128::MIN, -1, 1, Rounding::Down), Err(FixedPointError::Overflow));
+        assert_eq!(mul_div(i128::MAX, i128::MAX, 1, Rounding::Up), Err(FixedPointError::Overflow));
+    }
+
+    #[test]
+    fn test_fixed_scaling() {
+        // 1000 XLM at $0.30 = $300
+        assert_eq!(mul_fixed(10_000_000_000, 3_000_000, SCALE_7, Rounding::Down), Ok(3_000_000_000));
+        // $300 / $0.30 = 1000 XLM
+        assert_eq!(div_fixed(3_000_000_000, 3_000_000, SCALE_7, Rounding::Down), Ok(10_000_000_000));
+        // 1.5 wad * 2 wad = 3 wad
+        assert_eq!(mul_fixed(WAD * 3 / 2, 2 * WAD, WAD, Rounding::Down), Ok(3 * WAD));
+        // 1 / 3 in ray precision
+        assert_eq!(div_fixed(1, 3, RAY, Rounding::Up), Ok(RAY / 3 + 1));
+        assert_eq!(div_fixed(SCALE_9, 0, SCALE_9, Rounding::Down), Err(FixedPointError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_compound_interest_against_reference() {
+        const SECONDS_PER_YEAR: i128 = 31_557_600;
+        // 5% APR per second
+        let rate = ray_rate_per_second(500_000, SCALE_7, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(rate, 1_584_404_390_701_447_511);
+        assert_eq!(compound_interest(rate, 0), Ok(RAY));
+        assert_eq!(compound_interest(rate, 1), Ok(RAY + rate));
+
+        // Reference values of (1 + r)^n computed to 80 significant digits
+        let cases: [(u64, i128, i128); 3] = [
+            // Five ledgers: exact to the last few ray units
+            (5, 1_000_000_007_922_021_978_610_610_327, 100),
+            // One day: within 1e-12
+            (86_400, 1_000_136_901_909_459_375_190_434_094, RAY / 1_000_000_000_000),
+            // One year untouched: within 1e-5 (e^0.05 = 1.0512711)
+            (31_557_600, 1_051_271_096_334_383_076_191_190_124, RAY / 100_000),
+        ];
+        for (elapsed, reference, tolerance) in cases {
+            let growth = compound_interest(rate, elapsed).unwrap();
+            assert!(growth <= reference, "elapsed = {elapsed}");
+            assert!(reference - growth < tolerance, "elapsed = {elapsed}");
+        }
+
+        // Compounding beats simple interest
+        assert!(compound_interest(rate, 86_400).unwrap() > RAY + rate * 86_400);
+        assert_eq!(ray_mul(RAY * 2, RAY * 3, Rounding::Down), Ok(RAY * 6));
+        assert_eq!(ray_div(RAY * 6, RAY * 3, Rounding::Down), Ok(RAY * 2));
+    }
+}
@@ -60,20 +60,21 @@
 //! | R_max | 100% | Maximum rate at 100% utilization |
 //! | U* | 80% | Optimal/target utilization |
 
-use soroban_sdk::{contract, contractimpl, contracttype, Env};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Vec};
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding, RAY};
+
+pub use apogee_common::Role;
+use apogee_common::{SCALE, SECONDS_PER_YEAR};
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Scaling factor for percentages (1e7 = 10_000_000)
-/// - 100% = 10_000_000
-/// - 1% = 100_000
-/// - 0.01% = 1_000 (1 basis point)
-const SCALE: i128 = 10_000_000;
+/// Premium of the stable borrow rate over the variable rate (2%, scaled by SCALE)
+const STABLE_RATE_PREMIUM: i128 = 200_000;
 
-/// Seconds per year (365.25 days)
-const SECONDS_PER_YEAR: i128 = 31_557_600;
+/// Reserve factor assumed by the supply rate getters that take none (10%)
+const DEFAULT_RESERVE_FACTOR: i128 = 1_000_000;
 
 /// Utilization thresholds (scaled by SCALE)
 const U_85: i128 = 8_500_000;  // 85%
@@ -81,6 +82,35 @@ const U_90: i128 = 9_000_000;  // 90%
 const U_95: i128 = 9_500_000;  // 95%
 const U_99: i128 = 9_900_000;  // 99%
 
+/// Compound an annual rate per second over one year
+///
+/// The per-second rate is carried in ray (1e27) precision, since at 1e7 it
+/// would truncate to zero, and raised to `SECONDS_PER_YEAR` by squaring.
+///
+/// # Arguments
+/// * `annual_rate` - Simple annual rate (scaled by 1e7)
+///
+/// # Returns
+/// Compounded annual rate (scaled by 1e7)
+fn compound_annual_rate(annual_rate: i128) -> Result<i128, FixedPointError> {
+    let rate_per_second = mul_div(annual_rate, RAY, SCALE * SECONDS_PER_YEAR, Rounding::Down)?;
+
+    let mut base = RAY + rate_per_second;
+    let mut exponent = SECONDS_PER_YEAR;
+    let mut result = RAY;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_div(result, base, RAY, Rounding::Down)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul_div(base, base, RAY, Rounding::Down)?;
+        }
+    }
+
+    mul_div(result - RAY, SCALE, RAY, Rounding::Down)
+}
+
 // ============================================================================
 // STORAGE
 // ============================================================================
@@ -104,6 +134,139 @@ pub enum DataKey {
     /// Optimal utilization rate (U*)
     /// Scaled by 1e7, e.g., 80% = 8_000_000
     OptimalUtilization,
+
+    /// Per-asset curve, keyed by token contract address (overrides the defaults above)
+    Config(Address),
+
+    /// Preset the asset's curve was last set from, if any
+    Preset(Address),
+
+    /// Adaptive kink controller for an asset, if enabled
+    Controller(Address),
+
+    /// Running state of an asset's kink controller
+    ControllerState(Address),
+
+    /// Admin; also acts for any role nobody was granted
+    Admin,
+
+    /// Address holding an operational role
+    Role(Role),
+}
+
+/// Full rate model configuration
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RateModelConfig {
+    /// Minimum rate floor (scaled by 1e7)
+    pub rate_min: i128,
+    /// Rate at optimal utilization (scaled by 1e7)
+    pub rate_opt: i128,
+    /// Maximum rate at 100% utilization (scaled by 1e7)
+    pub rate_max: i128,
+    /// Optimal utilization U* (scaled by 1e7)
+    pub optimal_utilization: i128,
+}
+
+/// Adaptive kink controller: steepens an asset's curve above the kink while
+/// utilization stays high, and relaxes it once utilization normalizes
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct KinkController {
+    /// Pool that reports the asset's utilization
+    pub pool: Address,
+    /// Seconds utilization must stay above optimal before the curve steepens
+    pub delay: u64,
+    /// Increase of R_max per second while steepening, and its decrease per
+    /// second while decaying (scaled by 1e7)
+    pub adjust_rate: i128,
+    /// Most R_max may be raised above the configured curve (scaled by 1e7)
+    pub max_boost: i128,
+}
+
+/// Running state of a kink controller
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct ControllerState {
+    /// Current increase of R_max (scaled by 1e7)
+    pub boost: i128,
+    /// When utilization went above optimal; 0 while it is at or below
+    pub above_since: u64,
+    /// Time of the last report
+    pub last_update: u64,
+}
+
+/// Named curves for common kinds of reserve
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RatePreset {
+    /// Stablecoins: cheap up to a high kink, capped at 60%
+    Stable,
+    /// Volatile assets: earlier kink and a steeper top of the curve
+    Volatile,
+    /// Isolated or long-tail assets: low kink, high floor and ceiling
+    Isolated,
+}
+
+impl RatePreset {
+    /// The curve this preset stands for
+    pub fn config(&self) -> RateModelConfig {
+        match self {
+            RatePreset::Stable => RateModelConfig {
+                rate_min: 0,
+                rate_opt: 400_000,      // 4%
+                rate_max: 6_000_000,    // 60%
+                optimal_utilization: 8_000_000,
+            },
+            RatePreset::Volatile => RateModelConfig {
+                rate_min: 0,
+                rate_opt: 700_000,      // 7%
+                rate_max: 15_000_000,   // 150%
+                optimal_utilization: 6_500_000,
+            },
+            RatePreset::Isolated => RateModelConfig {
+                rate_min: 200_000,      // 2%
+                rate_opt: 1_000_000,    // 10%
+                rate_max: 30_000_000,   // 300%
+                optimal_utilization: 5_000_000,
+            },
+        }
+    }
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the interest rate model
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RateModelError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Optimal utilization must be strictly between 0 and 100%
+    InvalidOptimalUtilization = 2,
+    /// Optimal rate is below the minimum rate
+    RateOptBelowMin = 3,
+    /// Maximum rate is below the optimal rate
+    RateMaxBelowOpt = 4,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 5,
+    /// Contract has not been initialized
+    NotInitialized = 6,
+    /// Role cannot be granted or revoked this way
+    InvalidRole = 7,
+    /// Reserve factor must be between 0 and 100%
+    InvalidReserveFactor = 8,
+    /// Controller adjust rate and maximum boost must not be negative
+    InvalidController = 9,
+}
+
+impl From<FixedPointError> for RateModelError {
+    fn from(_: FixedPointError) -> Self {
+        RateModelError::MathOverflow
+    }
 }
 
 // ============================================================================
@@ -126,6 +289,7 @@ impl InterestRateModel {
     /// Initialize the interest rate model with custom parameters
     ///
     /// # Arguments
+    /// * `admin` - Admin allowed to update the parameters later
     /// * `rate_min` - Minimum rate floor (scaled by 1e7)
     /// * `rate_opt` - Rate at optimal utilization (scaled by 1e7)
     /// * `rate_max` - Maximum rate at 100% utilization (scaled by 1e7)
@@ -134,36 +298,23 @@ impl InterestRateModel {
     /// # Example
     /// ```ignore
     /// // R_min=0%, R_opt=4%, R_max=100%, U*=80%
-    /// client.initialize(&0, &400_000, &10_000_000, &8_000_000);
+    /// client.initialize(&admin, &0, &400_000, &10_000_000, &8_000_000);
     /// ```
     pub fn initialize(
         env: Env,
+        admin: Address,
         rate_min: i128,
         rate_opt: i128,
         rate_max: i128,
         optimal_utilization: i128,
-    ) {
+    ) -> Result<(), RateModelError> {
         // Prevent re-initialization
         if env.storage().instance().has(&DataKey::RateMin) {
-            panic!("Already initialized");
-        }
-
-        // Validate parameters
-        if optimal_utilization <= 0 || optimal_utilization >= SCALE {
-            panic!("Invalid optimal utilization: must be between 0 and 100%");
-        }
-        if rate_opt < rate_min {
-            panic!("Rate optimal must be >= rate min");
-        }
-        if rate_max < rate_opt {
-            panic!("Rate max must be >= rate optimal");
+            return Err(RateModelError::AlreadyInitialized);
         }
 
-        // Store parameters
-        env.storage().instance().set(&DataKey::RateMin, &rate_min);
-        env.storage().instance().set(&DataKey::RateOpt, &rate_opt);
-        env.storage().instance().set(&DataKey::RateMax, &rate_max);
-        env.storage().instance().set(&DataKey::OptimalUtilization, &optimal_utilization);
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        Self::store_params(&env, rate_min, rate_opt, rate_max, optimal_utilization)
     }
 
     /// Initialize with default parameters for Stellend MVP
@@ -173,14 +324,15 @@ impl InterestRateModel {
     /// - R_opt: 4% (at 80% utilization)
     /// - R_max: 100% (at 100% utilization)
     /// - U*: 80%
-    pub fn initialize_default(env: Env) {
+    pub fn initialize_default(env: Env, admin: Address) -> Result<(), RateModelError> {
         Self::initialize(
             env,
+            admin,
             0,             // 0% minimum rate
             400_000,       // 4% optimal rate
             10_000_000,    // 100% max rate
             8_000_000,     // 80% optimal utilization
-        );
+        )
     }
 
     // ========================================================================
@@ -205,11 +357,84 @@ impl InterestRateModel {
     ///
     /// # Returns
     /// Annualized borrow rate (scaled by 1e7)
-    pub fn get_borrow_rate(env: Env, utilization: i128) -> i128 {
-        let rate_min = Self::get_rate_min(env.clone());
-        let rate_opt = Self::get_rate_opt(env.clone());
-        let rate_max = Self::get_rate_max(env.clone());
-        let u_optimal = Self::get_optimal_utilization(env);
+    pub fn get_borrow_rate(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        Self::borrow_rate_for(&Self::get_config(env), utilization)
+    }
+
+    /// Get the annualized borrow rate for a specific asset
+    ///
+    /// Uses the asset's own curve if one was set with `set_asset_params`,
+    /// otherwise the default curve.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `utilization` - Current utilization rate (scaled by 1e7)
+    pub fn get_asset_borrow_rate(env: Env, asset: Address, utilization: i128) -> Result<i128, RateModelError> {
+        Self::borrow_rate_for(&Self::get_effective_config(env, asset), utilization)
+    }
+
+    /// Get the annualized supply rate for a specific asset
+    pub fn get_asset_supply_rate(env: Env, asset: Address, utilization: i128) -> Result<i128, RateModelError> {
+        let borrow_rate = Self::get_asset_borrow_rate(env, asset, utilization)?;
+        Self::supply_rate_for(borrow_rate, utilization, DEFAULT_RESERVE_FACTOR)
+    }
+
+    /// Get the borrow and supply rates for a specific asset in one call
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `utilization` - Current utilization rate (scaled by 1e7)
+    /// * `reserve_factor` - Share of interest kept by the protocol (scaled by 1e7)
+    ///
+    /// # Returns
+    /// (borrow rate, supply rate), annualized and scaled by 1e7
+    pub fn get_asset_rates(
+        env: Env,
+        asset: Address,
+        utilization: i128,
+        reserve_factor: i128,
+    ) -> Result<(i128, i128), RateModelError> {
+        let borrow_rate = Self::get_asset_borrow_rate(env, asset, utilization)?;
+        Ok((borrow_rate, Self::supply_rate_for(borrow_rate, utilization, reserve_factor)?))
+    }
+
+    /// Get the stable borrow rate offered at a utilization
+    ///
+    /// Stable rate = variable borrow rate + 2% premium. The pool locks this
+    /// rate for a stable-rate loan at origination.
+    ///
+    /// # Arguments
+    /// * `utilization` - Current utilization rate (scaled by 1e7)
+    ///
+    /// # Returns
+    /// Annualized stable borrow rate (scaled by 1e7)
+    pub fn get_stable_rate(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        Ok(Self::get_borrow_rate(env, utilization)? + STABLE_RATE_PREMIUM)
+    }
+
+    /// Get the stable borrow rate for a specific asset
+    pub fn get_asset_stable_rate(env: Env, asset: Address, utilization: i128) -> Result<i128, RateModelError> {
+        Ok(Self::get_asset_borrow_rate(env, asset, utilization)? + STABLE_RATE_PREMIUM)
+    }
+
+    /// Get the borrow rate a curve would give, without storing it
+    ///
+    /// Lets governance simulate a parameter change before proposing it.
+    ///
+    /// # Arguments
+    /// * `params` - Hypothetical curve parameters
+    /// * `utilization` - Utilization to evaluate at (scaled by 1e7)
+    pub fn preview_rate(_env: Env, params: RateModelConfig, utilization: i128) -> Result<i128, RateModelError> {
+        Self::validate_params(&params)?;
+        Self::borrow_rate_for(&params, utilization)
+    }
+
+    /// Internal: Evaluate the multi-kink curve for `config` at `utilization`
+    fn borrow_rate_for(config: &RateModelConfig, utilization: i128) -> Result<i128, RateModelError> {
+        let rate_min = config.rate_min;
+        let rate_opt = config.rate_opt;
+        let rate_max = config.rate_max;
+        let u_optimal = config.optimal_utilization;
 
         // ΔR = difference between max and optimal rate
         let delta_r = rate_max - rate_opt;
@@ -222,7 +447,7 @@ impl InterestRateModel {
             // Rate = R_opt * (U / U*)
             // At U=0: rate = 0
             // At U=U*: rate = R_opt
-            (rate_opt * utilization) / u_optimal
+            mul_div(rate_opt, utilization, u_optimal, Rounding::Down)?
             
         } else if utilization <= U_85 {
             // ================================================================
@@ -231,7 +456,7 @@ impl InterestRateModel {
             // Adds 5% of ΔR over this range
             let range = U_85 - u_optimal;
             let progress = utilization - u_optimal;
-            let penalty = (delta_r * 50 * progress) / (range * 1000);
+            let penalty = mul_div(delta_r * 50, progress, range * 1000, Rounding::Down)?;
             rate_opt + penalty
             
         } else if utilization <= U_90 {
@@ -242,7 +467,7 @@ impl InterestRateModel {
             let base_penalty = (delta_r * 50) / 1000; // From zone 2
             let range = U_90 - U_85;
             let progress = utilization - U_85;
-            let extra_penalty = (delta_r * 100 * progress) / (range * 1000);
+            let extra_penalty = mul_div(delta_r * 100, progress, range * 1000, Rounding::Down)?;
             rate_opt + base_penalty + extra_penalty
             
         } else if utilization <= U_95 {
@@ -253,7 +478,7 @@ impl InterestRateModel {
             let base_penalty = (delta_r * 150) / 1000; // From zones 2+3
             let range = U_95 - U_90;
             let progress = utilization - U_90;
-            let extra_penalty = (delta_r * 150 * progress) / (range * 1000);
+            let extra_penalty = mul_div(delta_r * 150, progress, range * 1000, Rounding::Down)?;
             rate_opt + base_penalty + extra_penalty
             
         } else if utilization <= U_99 {
@@ -264,7 +489,7 @@ impl InterestRateModel {
             let base_penalty = (delta_r * 300) / 1000; // From zones 2+3+4
             let range = U_99 - U_95;
             let progress = utilization - U_95;
-            let extra_penalty = (delta_r * 200 * progress) / (range * 1000);
+            let extra_penalty = mul_div(delta_r * 200, progress, range * 1000, Rounding::Down)?;
             rate_opt + base_penalty + extra_penalty
             
         } else {
@@ -275,15 +500,15 @@ impl InterestRateModel {
             let base_penalty = (delta_r * 500) / 1000; // From zones 2+3+4+5
             let range = SCALE - U_99;
             let progress = if utilization >= SCALE { range } else { utilization - U_99 };
-            let extra_penalty = (delta_r * 500 * progress) / (range * 1000);
+            let extra_penalty = mul_div(delta_r * 500, progress, range * 1000, Rounding::Down)?;
             rate_opt + base_penalty + extra_penalty
         };
 
         // Apply minimum rate floor
         if raw_rate < rate_min {
-            rate_min
+            Ok(rate_min)
         } else {
-            raw_rate
+            Ok(raw_rate)
         }
     }
 
@@ -294,31 +519,86 @@ impl InterestRateModel {
     ///
     /// # Returns
     /// Rate per second (scaled by 1e7)
-    pub fn get_borrow_rate_per_second(env: Env, utilization: i128) -> i128 {
-        let annual_rate = Self::get_borrow_rate(env, utilization);
-        annual_rate / SECONDS_PER_YEAR
+    pub fn get_borrow_rate_per_second(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        let annual_rate = Self::get_borrow_rate(env, utilization)?;
+        Ok(annual_rate / SECONDS_PER_YEAR)
     }
 
     /// Get the annualized supply rate based on utilization
     ///
-    /// Supply rate = Borrow rate × Utilization × (1 - Reserve Factor)
-    /// For MVP, we assume reserve_factor = 10%
+    /// Supply rate = Borrow rate × Utilization × (1 - Reserve Factor), with
+    /// the default 10% reserve factor; see `get_supply_rate_with_reserve`
     ///
     /// # Arguments
     /// * `utilization` - Current utilization rate (scaled by 1e7)
     ///
     /// # Returns
     /// Annualized supply rate (scaled by 1e7)
-    pub fn get_supply_rate(env: Env, utilization: i128) -> i128 {
-        let borrow_rate = Self::get_borrow_rate(env, utilization);
-        // Supply rate = borrow_rate * utilization * 90% (10% to reserves)
-        (borrow_rate * utilization * 9) / (SCALE * 10)
+    pub fn get_supply_rate(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        Self::get_supply_rate_with_reserve(env, utilization, DEFAULT_RESERVE_FACTOR)
+    }
+
+    /// Get the annualized supply rate for a reserve's own reserve factor
+    ///
+    /// # Arguments
+    /// * `utilization` - Current utilization rate (scaled by 1e7)
+    /// * `reserve_factor` - Share of interest kept by the protocol (scaled by 1e7)
+    ///
+    /// # Returns
+    /// Annualized supply rate (scaled by 1e7)
+    pub fn get_supply_rate_with_reserve(
+        env: Env,
+        utilization: i128,
+        reserve_factor: i128,
+    ) -> Result<i128, RateModelError> {
+        let borrow_rate = Self::get_borrow_rate(env, utilization)?;
+        Self::supply_rate_for(borrow_rate, utilization, reserve_factor)
+    }
+
+    /// Get the borrow and supply rates in one call
+    ///
+    /// Lets the pool read both sides of a market with a single cross-contract
+    /// call.
+    ///
+    /// # Returns
+    /// (borrow rate, supply rate), annualized and scaled by 1e7
+    pub fn get_rates(env: Env, utilization: i128, reserve_factor: i128) -> Result<(i128, i128), RateModelError> {
+        let borrow_rate = Self::get_borrow_rate(env, utilization)?;
+        Ok((borrow_rate, Self::supply_rate_for(borrow_rate, utilization, reserve_factor)?))
+    }
+
+    /// Internal: Supply rate = borrow rate × utilization × (1 - reserve factor)
+    fn supply_rate_for(borrow_rate: i128, utilization: i128, reserve_factor: i128) -> Result<i128, RateModelError> {
+        if !(0..=SCALE).contains(&reserve_factor) {
+            return Err(RateModelError::InvalidReserveFactor);
+        }
+        Ok(mul_div(borrow_rate * utilization, SCALE - reserve_factor, SCALE * SCALE, Rounding::Down)?)
     }
 
     /// Get the supply rate per second
-    pub fn get_supply_rate_per_second(env: Env, utilization: i128) -> i128 {
-        let annual_rate = Self::get_supply_rate(env, utilization);
-        annual_rate / SECONDS_PER_YEAR
+    pub fn get_supply_rate_per_second(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        let annual_rate = Self::get_supply_rate(env, utilization)?;
+        Ok(annual_rate / SECONDS_PER_YEAR)
+    }
+
+    /// Get the borrow APY, compounding the per-second borrow rate over a year
+    ///
+    /// APY = (1 + APR / SECONDS_PER_YEAR) ^ SECONDS_PER_YEAR - 1
+    ///
+    /// # Arguments
+    /// * `utilization` - Current utilization rate (scaled by 1e7)
+    ///
+    /// # Returns
+    /// Annual percentage yield (scaled by 1e7)
+    pub fn get_borrow_apy(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        let annual_rate = Self::get_borrow_rate(env, utilization)?;
+        Ok(compound_annual_rate(annual_rate)?)
+    }
+
+    /// Get the supply APY, compounding the per-second supply rate over a year
+    pub fn get_supply_apy(env: Env, utilization: i128) -> Result<i128, RateModelError> {
+        let annual_rate = Self::get_supply_rate(env, utilization)?;
+        Ok(compound_annual_rate(annual_rate)?)
     }
 
     // ========================================================================
@@ -345,16 +625,342 @@ impl InterestRateModel {
         env.storage().instance().get(&DataKey::OptimalUtilization).unwrap_or(8_000_000)
     }
 
+    /// Get the full rate model configuration
+    pub fn get_config(env: Env) -> RateModelConfig {
+        RateModelConfig {
+            rate_min: Self::get_rate_min(env.clone()),
+            rate_opt: Self::get_rate_opt(env.clone()),
+            rate_max: Self::get_rate_max(env.clone()),
+            optimal_utilization: Self::get_optimal_utilization(env),
+        }
+    }
+
+    /// Get the rate model configuration for an asset
+    ///
+    /// Falls back to the default configuration if the asset has no curve.
+    pub fn get_asset_config(env: Env, asset: Address) -> RateModelConfig {
+        env.storage()
+            .instance()
+            .get(&DataKey::Config(asset))
+            .unwrap_or_else(|| Self::get_config(env))
+    }
+
+    /// Get the curve an asset's rates are currently computed from
+    ///
+    /// The asset's configuration with R_max raised by its kink controller's
+    /// current boost.
+    pub fn get_effective_config(env: Env, asset: Address) -> RateModelConfig {
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+        let state: Option<ControllerState> = env.storage().instance().get(&DataKey::ControllerState(asset));
+        if let Some(state) = state {
+            config.rate_max += state.boost;
+        }
+        config
+    }
+
+    // ========================================================================
+    // ADAPTIVE KINK CONTROLLER
+    // ========================================================================
+
+    /// Enable or reconfigure an asset's kink controller (risk manager only)
+    ///
+    /// The current boost is kept, capped at the new maximum.
+    pub fn set_controller(env: Env, asset: Address, controller: KinkController) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        if controller.adjust_rate < 0 || controller.max_boost < 0 {
+            return Err(RateModelError::InvalidController);
+        }
+        let mut state = Self::get_controller_state(env.clone(), asset.clone());
+        state.boost = state.boost.min(controller.max_boost);
+        env.storage().instance().set(&DataKey::ControllerState(asset.clone()), &state);
+        env.storage().instance().set(&DataKey::Controller(asset.clone()), &controller);
+
+        env.events().publish((symbol_short!("kink_ctl"), asset), controller);
+
+        Ok(())
+    }
+
+    /// Disable an asset's kink controller and drop its boost (risk manager only)
+    pub fn remove_controller(env: Env, asset: Address) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        env.storage().instance().remove(&DataKey::Controller(asset.clone()));
+        env.storage().instance().remove(&DataKey::ControllerState(asset));
+
+        Ok(())
+    }
+
+    /// Get an asset's kink controller
+    pub fn get_controller(env: Env, asset: Address) -> Option<KinkController> {
+        env.storage().instance().get(&DataKey::Controller(asset))
+    }
+
+    /// Get the running state of an asset's kink controller
+    pub fn get_controller_state(env: Env, asset: Address) -> ControllerState {
+        env.storage()
+            .instance()
+            .get(&DataKey::ControllerState(asset))
+            .unwrap_or_default()
+    }
+
+    /// Report the utilization an asset held since the last report (pool only)
+    ///
+    /// Once utilization has stayed above optimal for the controller's delay,
+    /// R_max rises by `adjust_rate` per second up to `max_boost`; at or below
+    /// optimal it falls back at the same speed. A no-op for assets without a
+    /// controller.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `utilization` - Utilization since the last report (scaled by 1e7)
+    pub fn report_utilization(env: Env, asset: Address, utilization: i128) -> Result<(), RateModelError> {
+        let Some(controller) = Self::get_controller(env.clone(), asset.clone()) else {
+            return Ok(());
+        };
+        controller.pool.require_auth();
+
+        let now = env.ledger().timestamp();
+        let mut state = Self::get_controller_state(env.clone(), asset.clone());
+        if state.last_update == 0 {
+            state.last_update = now;
+        }
+        let elapsed = now.saturating_sub(state.last_update) as i128;
+
+        if utilization > Self::get_asset_config(env.clone(), asset.clone()).optimal_utilization {
+            if state.above_since == 0 {
+                state.above_since = state.last_update;
+            }
+            let steepen_from = (state.above_since + controller.delay).max(state.last_update);
+            if now > steepen_from {
+                let increase = controller.adjust_rate * (now - steepen_from) as i128;
+                state.boost = (state.boost + increase).min(controller.max_boost);
+            }
+        } else {
+            state.above_since = 0;
+            state.boost = (state.boost - controller.adjust_rate * elapsed).max(0);
+        }
+        state.last_update = now;
+
+        env.storage().instance().set(&DataKey::ControllerState(asset), &state);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // ADMIN FUNCTIONS
+    // ========================================================================
+
+    /// Get the admin address
+    pub fn get_admin(env: Env) -> Result<Address, RateModelError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(RateModelError::NotInitialized)
+    }
+
+    /// Update the rate curve parameters (risk manager only)
+    ///
+    /// Takes effect on the pool's next interest accrual.
+    ///
+    /// # Arguments
+    /// * `rate_min` - Minimum rate floor (scaled by 1e7)
+    /// * `rate_opt` - Rate at optimal utilization (scaled by 1e7)
+    /// * `rate_max` - Maximum rate at 100% utilization (scaled by 1e7)
+    /// * `optimal_utilization` - Optimal utilization U* (scaled by 1e7)
+    pub fn update_params(
+        env: Env,
+        rate_min: i128,
+        rate_opt: i128,
+        rate_max: i128,
+        optimal_utilization: i128,
+    ) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        Self::store_params(&env, rate_min, rate_opt, rate_max, optimal_utilization)?;
+
+        env.events().publish((symbol_short!("params"),), Self::get_config(env.clone()));
+
+        Ok(())
+    }
+
+    /// Set a dedicated rate curve for one asset (risk manager only)
+    ///
+    /// Lets a multi-asset pool share a single model deployment while giving
+    /// each reserve its own curve.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `config` - Curve parameters for the asset
+    pub fn set_asset_params(env: Env, asset: Address, config: RateModelConfig) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        Self::validate_params(&config)?;
+        Self::store_asset_params(&env, &asset, &config);
+        env.storage().instance().remove(&DataKey::Preset(asset));
+
+        Ok(())
+    }
+
+    /// Set the curves of several assets at once (risk manager only)
+    ///
+    /// Every curve is validated before any is stored, so a governance batch
+    /// either applies in full or not at all.
+    pub fn set_asset_params_batch(env: Env, params: Vec<(Address, RateModelConfig)>) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        for (_, config) in params.iter() {
+            Self::validate_params(&config)?;
+        }
+        for (asset, config) in params.iter() {
+            Self::store_asset_params(&env, &asset, &config);
+            env.storage().instance().remove(&DataKey::Preset(asset));
+        }
+
+        Ok(())
+    }
+
+    /// Set an asset's curve from a named preset (risk manager only)
+    pub fn set_preset(env: Env, asset: Address, preset: RatePreset) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        Self::store_asset_params(&env, &asset, &preset.config());
+        env.storage().instance().set(&DataKey::Preset(asset), &preset);
+
+        Ok(())
+    }
+
+    /// Get the preset an asset's curve was set from
+    ///
+    /// `None` if the asset uses the default curve or custom parameters.
+    pub fn get_asset_preset(env: Env, asset: Address) -> Option<RatePreset> {
+        env.storage().instance().get(&DataKey::Preset(asset))
+    }
+
+    /// Get the curve a preset stands for
+    pub fn get_preset_config(_env: Env, preset: RatePreset) -> RateModelConfig {
+        preset.config()
+    }
+
+    /// Remove an asset's dedicated curve so it uses the default again (risk manager only)
+    pub fn remove_asset_params(env: Env, asset: Address) -> Result<(), RateModelError> {
+        Self::get_role_holder(env.clone(), Role::RiskManager)?.require_auth();
+
+        env.storage().instance().remove(&DataKey::Config(asset.clone()));
+        env.storage().instance().remove(&DataKey::Preset(asset));
+
+        Ok(())
+    }
+
+    /// Internal: Store an asset's curve and announce it
+    fn store_asset_params(env: &Env, asset: &Address, config: &RateModelConfig) {
+        env.storage().instance().set(&DataKey::Config(asset.clone()), config);
+        env.events().publish((symbol_short!("params"), asset.clone()), config.clone());
+    }
+
+    /// Transfer the admin role (admin only)
+    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), RateModelError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+
+        Ok(())
+    }
+
+    /// Grant a role to an address (admin only)
+    ///
+    /// Replaces the previous holder. Granting `Admin` transfers the admin key.
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), RateModelError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        match role {
+            Role::Admin => env.storage().instance().set(&DataKey::Admin, &account),
+            _ => env.storage().instance().set(&DataKey::Role(role), &account),
+        }
+
+        env.events().publish((symbol_short!("role_grnt"), role), account);
+
+        Ok(())
+    }
+
+    /// Revoke a role so it falls back to the admin (admin only)
+    pub fn revoke_role(env: Env, role: Role) -> Result<(), RateModelError> {
+        Self::get_admin(env.clone())?.require_auth();
+
+        if role == Role::Admin {
+            return Err(RateModelError::InvalidRole);
+        }
+        env.storage().instance().remove(&DataKey::Role(role));
+
+        env.events().publish((symbol_short!("role_rvk"), role), ());
+
+        Ok(())
+    }
+
+    /// Get the address currently acting for a role
+    pub fn get_role_holder(env: Env, role: Role) -> Result<Address, RateModelError> {
+        let holder: Option<Address> = match role {
+            Role::Admin => None,
+            _ => env.storage().instance().get(&DataKey::Role(role)),
+        };
+        match holder {
+            Some(holder) => Ok(holder),
+            None => Self::get_admin(env),
+        }
+    }
+
+    /// Check whether `account` currently acts for `role`
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        Self::get_role_holder(env, role) == Ok(account)
+    }
+
+    /// Internal: Validate and store the rate curve parameters
+    fn store_params(
+        env: &Env,
+        rate_min: i128,
+        rate_opt: i128,
+        rate_max: i128,
+        optimal_utilization: i128,
+    ) -> Result<(), RateModelError> {
+        Self::validate_params(&RateModelConfig {
+            rate_min,
+            rate_opt,
+            rate_max,
+            optimal_utilization,
+        })?;
+
+        env.storage().instance().set(&DataKey::RateMin, &rate_min);
+        env.storage().instance().set(&DataKey::RateOpt, &rate_opt);
+        env.storage().instance().set(&DataKey::RateMax, &rate_max);
+        env.storage().instance().set(&DataKey::OptimalUtilization, &optimal_utilization);
+
+        Ok(())
+    }
+
+    /// Internal: Check that a curve is well-formed
+    fn validate_params(config: &RateModelConfig) -> Result<(), RateModelError> {
+        if config.optimal_utilization <= 0 || config.optimal_utilization >= SCALE {
+            return Err(RateModelError::InvalidOptimalUtilization);
+        }
+        if config.rate_opt < config.rate_min {
+            return Err(RateModelError::RateOptBelowMin);
+        }
+        if config.rate_max < config.rate_opt {
+            return Err(RateModelError::RateMaxBelowOpt);
+        }
+        Ok(())
+    }
+
     // ========================================================================
     // UTILITY FUNCTIONS
     // ========================================================================
 
     /// Calculate utilization rate from supply and borrow amounts
-    pub fn calculate_utilization(_env: Env, total_supply: i128, total_borrow: i128) -> i128 {
+    pub fn calculate_utilization(_env: Env, total_supply: i128, total_borrow: i128) -> Result<i128, RateModelError> {
         if total_supply == 0 {
-            return 0;
+            return Ok(0);
         }
-        (total_borrow * SCALE) / total_supply
+        Ok(mul_div(total_borrow, SCALE, total_supply, Rounding::Down)?)
     }
 
     /// Get all current parameters
@@ -397,21 +1003,26 @@ impl InterestRateModel {
 #[cfg(test)]
 mod test {
     use super::*;
-    use soroban_sdk::Env;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        vec, Env,
+    };
 
     #[test]
     fn test_initialize() {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
 
         // R_min=0%, R_opt=4%, R_max=100%, U*=80%
-        client.initialize(&0, &400_000, &10_000_000, &8_000_000);
+        client.initialize(&admin, &0, &400_000, &10_000_000, &8_000_000);
 
         assert_eq!(client.get_rate_min(), 0);
         assert_eq!(client.get_rate_opt(), 400_000);
         assert_eq!(client.get_rate_max(), 10_000_000);
         assert_eq!(client.get_optimal_utilization(), 8_000_000);
+        assert_eq!(client.get_admin(), admin);
     }
 
     #[test]
@@ -420,7 +1031,7 @@ mod test {
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
 
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         assert_eq!(client.get_rate_min(), 0);
         assert_eq!(client.get_rate_opt(), 400_000);      // 4%
@@ -433,7 +1044,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 0% utilization, rate should be 0
         let rate = client.get_borrow_rate(&0);
@@ -445,7 +1056,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 80% utilization (optimal), rate should be R_opt = 4%
         let rate = client.get_borrow_rate(&8_000_000);
@@ -457,7 +1068,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 40% utilization (half of optimal)
         // Rate = R_opt * (40% / 80%) = 4% * 0.5 = 2%
@@ -475,7 +1086,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 85% utilization (end of zone 2)
         // Should be R_opt + 5% of ΔR = 4% + 5% * 96% = 4% + 4.8% = 8.8%
@@ -490,7 +1101,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 90% utilization (end of zone 3)
         // R_opt + (5% + 10%) of ΔR = 4% + 15% * 96% = 4% + 14.4% = 18.4%
@@ -503,7 +1114,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 95% utilization (end of zone 4)
         // R_opt + (5% + 10% + 15%) of ΔR = 4% + 30% * 96% = 4% + 28.8% = 32.8%
@@ -516,7 +1127,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 99% utilization (end of zone 5)
         // R_opt + (5% + 10% + 15% + 20%) of ΔR = 4% + 50% * 96% = 4% + 48% = 52%
@@ -529,7 +1140,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 100% utilization (max)
         // R_opt + 100% of ΔR = 4% + 96% = 100%
@@ -542,12 +1153,48 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // At 80% utilization, borrow rate = 4%
         // Supply rate = 4% * 80% * 90% = 2.88%
         let supply_rate = client.get_supply_rate(&8_000_000);
         assert_eq!(supply_rate, 288_000); // 2.88%
+
+        // With a 20% reserve factor: 4% * 80% * 80% = 2.56%
+        assert_eq!(client.get_supply_rate_with_reserve(&8_000_000, &2_000_000), 256_000);
+        assert_eq!(client.get_rates(&8_000_000, &2_000_000), (400_000, 256_000));
+        assert_eq!(client.get_supply_rate_with_reserve(&8_000_000, &1_000_000), supply_rate);
+        assert_eq!(
+            client.try_get_rates(&8_000_000, &(SCALE + 1)),
+            Err(Ok(RateModelError::InvalidReserveFactor))
+        );
+    }
+
+    #[test]
+    fn test_initialize_errors() {
+        let env = Env::default();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+
+        assert_eq!(
+            client.try_initialize(&admin, &0, &400_000, &10_000_000, &SCALE),
+            Err(Ok(RateModelError::InvalidOptimalUtilization))
+        );
+        assert_eq!(
+            client.try_initialize(&admin, &500_000, &400_000, &10_000_000, &8_000_000),
+            Err(Ok(RateModelError::RateOptBelowMin))
+        );
+        assert_eq!(
+            client.try_initialize(&admin, &0, &400_000, &300_000, &8_000_000),
+            Err(Ok(RateModelError::RateMaxBelowOpt))
+        );
+
+        client.initialize_default(&Address::generate(&env));
+        assert_eq!(
+            client.try_initialize_default(&Address::generate(&env)),
+            Err(Ok(RateModelError::AlreadyInitialized))
+        );
     }
 
     #[test]
@@ -563,6 +1210,11 @@ mod test {
         // 0 supplied = 0% utilization
         let util = client.calculate_utilization(&0, &80);
         assert_eq!(util, 0);
+
+        assert_eq!(
+            client.try_calculate_utilization(&1, &i128::MAX),
+            Err(Ok(RateModelError::MathOverflow))
+        );
     }
 
     #[test]
@@ -570,7 +1222,7 @@ mod test {
         let env = Env::default();
         let contract_id = env.register_contract(None, InterestRateModel);
         let client = InterestRateModelClient::new(&env, &contract_id);
-        client.initialize_default();
+        client.initialize_default(&Address::generate(&env));
 
         // Verify rate always increases with utilization
         let mut prev_rate: i128 = 0;
@@ -581,7 +1233,232 @@ mod test {
             prev_rate = rate;
         }
     }
-}
 
+    #[test]
+    fn test_update_params() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        // R_min=1%, R_opt=6%, R_max=150%, U*=90%
+        client.update_params(&100_000, &600_000, &15_000_000, &9_000_000);
+        assert_eq!(
+            client.get_config(),
+            RateModelConfig {
+                rate_min: 100_000,
+                rate_opt: 600_000,
+                rate_max: 15_000_000,
+                optimal_utilization: 9_000_000,
+            }
+        );
+        assert_eq!(client.get_borrow_rate(&9_000_000), 600_000);
+
+        assert_eq!(
+            client.try_update_params(&0, &400_000, &300_000, &8_000_000),
+            Err(Ok(RateModelError::RateMaxBelowOpt))
+        );
+        assert_eq!(client.get_rate_max(), 15_000_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Auth, InvalidAction)")]
+    fn test_update_params_requires_admin() {
+        let env = Env::default();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        client.update_params(&100_000, &600_000, &15_000_000, &9_000_000);
+    }
+
+    #[test]
+    fn test_per_asset_params() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        let xlm = Address::generate(&env);
+        let usdc = Address::generate(&env);
+        let xlm_config = RateModelConfig {
+            rate_min: 200_000,
+            rate_opt: 1_000_000,
+            rate_max: 20_000_000,
+            optimal_utilization: 5_000_000,
+        };
+        client.set_asset_params(&xlm, &xlm_config);
+
+        // XLM uses its own curve, USDC falls back to the default
+        assert_eq!(client.get_asset_config(&xlm), xlm_config);
+        assert_eq!(client.get_asset_config(&usdc), client.get_config());
+        assert_eq!(client.get_asset_borrow_rate(&xlm, &5_000_000), 1_000_000);
+        assert_eq!(client.get_asset_borrow_rate(&usdc, &8_000_000), 400_000);
+        assert_eq!(
+            client.get_asset_borrow_rate(&usdc, &8_000_000),
+            client.get_borrow_rate(&8_000_000)
+        );
+
+        let bad_config = RateModelConfig { optimal_utilization: 0, ..xlm_config };
+        assert_eq!(
+            client.try_set_asset_params(&xlm, &bad_config),
+            Err(Ok(RateModelError::InvalidOptimalUtilization))
+        );
+
+        client.remove_asset_params(&xlm);
+        assert_eq!(client.get_asset_config(&xlm), client.get_config());
+    }
+
+    #[test]
+    fn test_presets_and_preview() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        let usdc = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.set_preset(&usdc, &RatePreset::Stable);
+        assert_eq!(client.get_asset_config(&usdc), RatePreset::Stable.config());
+        assert_eq!(client.get_asset_preset(&usdc), Some(RatePreset::Stable));
+        assert_eq!(client.get_asset_preset(&xlm), None);
+
+        // Preview evaluates a curve without storing it
+        let isolated = client.get_preset_config(&RatePreset::Isolated);
+        assert_eq!(client.preview_rate(&isolated, &5_000_000), 1_000_000);
+        assert_eq!(client.preview_rate(&isolated, &0), 200_000);
+        assert_eq!(client.get_asset_config(&xlm), client.get_config());
+        let bad_config = RateModelConfig { rate_max: 0, ..isolated.clone() };
+        assert_eq!(
+            client.try_preview_rate(&bad_config, &5_000_000),
+            Err(Ok(RateModelError::RateMaxBelowOpt))
+        );
+
+        // A batch is all or nothing, and custom curves clear the preset
+        let batch = vec![&env, (usdc.clone(), isolated.clone()), (xlm.clone(), bad_config)];
+        assert_eq!(
+            client.try_set_asset_params_batch(&batch),
+            Err(Ok(RateModelError::RateMaxBelowOpt))
+        );
+        assert_eq!(client.get_asset_config(&usdc), RatePreset::Stable.config());
+        client.set_asset_params_batch(&vec![&env, (usdc.clone(), isolated.clone()), (xlm.clone(), isolated)]);
+        assert_eq!(client.get_asset_preset(&usdc), None);
+        assert_eq!(client.get_asset_borrow_rate(&xlm, &5_000_000), 1_000_000);
+    }
 
+    #[test]
+    fn test_kink_controller_steepens_and_decays() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set_timestamp(1_000);
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
 
+        let asset = Address::generate(&env);
+        let pool = Address::generate(&env);
+        // Reports are ignored until a controller is set
+        client.report_utilization(&asset, &9_500_000);
+        assert_eq!(client.get_controller_state(&asset), ControllerState::default());
+
+        client.set_controller(
+            &asset,
+            &KinkController { pool: pool.clone(), delay: 3_600, adjust_rate: 1_000, max_boost: 5_000_000 },
+        );
+        client.report_utilization(&asset, &9_500_000);
+        assert_eq!(env.auths()[0].0, pool);
+
+        // Within the delay the curve is unchanged
+        env.ledger().set_timestamp(1_000 + 3_600);
+        client.report_utilization(&asset, &9_500_000);
+        assert_eq!(client.get_controller_state(&asset).boost, 0);
+
+        // Then R_max rises by 0.01% per second, up to the cap
+        env.ledger().set_timestamp(1_000 + 4_600);
+        client.report_utilization(&asset, &9_500_000);
+        assert_eq!(client.get_controller_state(&asset).boost, 1_000_000);
+        assert_eq!(client.get_effective_config(&asset).rate_max, 11_000_000);
+        assert!(client.get_asset_borrow_rate(&asset, &9_500_000) > client.get_borrow_rate(&9_500_000));
+        env.ledger().set_timestamp(1_000 + 20_000);
+        client.report_utilization(&asset, &9_500_000);
+        assert_eq!(client.get_controller_state(&asset).boost, 5_000_000);
+
+        // Back below optimal, the boost decays at the same speed
+        env.ledger().set_timestamp(1_000 + 22_000);
+        client.report_utilization(&asset, &5_000_000);
+        assert_eq!(client.get_controller_state(&asset).boost, 3_000_000);
+        assert_eq!(client.get_controller_state(&asset).above_since, 0);
+        env.ledger().set_timestamp(1_000 + 30_000);
+        client.report_utilization(&asset, &5_000_000);
+        assert_eq!(client.get_controller_state(&asset).boost, 0);
+
+        client.remove_controller(&asset);
+        assert_eq!(client.get_controller(&asset), None);
+    }
+
+    #[test]
+    fn test_stable_rate() {
+        let env = Env::default();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        // Stable rate is the variable rate plus a 2% premium
+        assert_eq!(client.get_stable_rate(&0), STABLE_RATE_PREMIUM);
+        assert_eq!(client.get_stable_rate(&8_000_000), 400_000 + STABLE_RATE_PREMIUM);
+        assert_eq!(
+            client.get_asset_stable_rate(&Address::generate(&env), &8_000_000),
+            client.get_stable_rate(&8_000_000)
+        );
+    }
+
+    #[test]
+    fn test_apy_compounds_apr() {
+        let env = Env::default();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        client.initialize_default(&Address::generate(&env));
+
+        // 0% utilization: 0% APR, 0% APY
+        assert_eq!(client.get_borrow_apy(&0), 0);
+        assert_eq!(client.get_supply_apy(&0), 0);
+
+        // 80% utilization: 4% APR -> e^0.04 - 1 = 4.0811% APY
+        let apy = client.get_borrow_apy(&8_000_000);
+        assert!(apy > 408_100 && apy < 408_120, "apy = {}", apy);
+
+        // 100% utilization: 100% APR -> e - 1 = 171.83% APY
+        let apy = client.get_borrow_apy(&SCALE);
+        assert!(apy > 17_182_800 && apy < 17_182_830, "apy = {}", apy);
+
+        // Supply APY compounds the supply APR
+        let supply_apr = client.get_supply_rate(&8_000_000);
+        let supply_apy = client.get_supply_apy(&8_000_000);
+        assert!(supply_apy > supply_apr);
+        assert!(supply_apy < client.get_borrow_apy(&8_000_000));
+    }
+
+    #[test]
+    fn test_risk_manager_updates_params() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, InterestRateModel);
+        let client = InterestRateModelClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+        let risk_manager = Address::generate(&env);
+        client.initialize_default(&admin);
+
+        client.grant_role(&Role::RiskManager, &risk_manager);
+        assert!(client.has_role(&Role::RiskManager, &risk_manager));
+
+        client.update_params(&100_000, &600_000, &15_000_000, &9_000_000);
+        assert_eq!(env.auths()[0].0, risk_manager);
+
+        client.revoke_role(&Role::RiskManager);
+        assert_eq!(client.get_role_holder(&Role::RiskManager), admin);
+        assert_eq!(client.try_revoke_role(&Role::Admin), Err(Ok(RateModelError::InvalidRole)));
+    }
+}
@@ -0,0 +1,330 @@
+#![no_std]
+
+//! # Stellend Multisig Contract
+//!
+//! An M-of-N custom account for holding protocol admin keys. Every admin
+//! check in the protocol contracts is a `require_auth` on the stored admin
+//! address, so setting this contract as the admin (at `initialize`, or via
+//! `grant_role(Admin, ..)` / `set_admin`) puts each admin call behind its
+//! signers without any change to the protocol contracts.
+//!
+//! ## How It Works
+//!
+//! - The contract stores the signers' ed25519 public keys and a threshold
+//! - Soroban calls `__check_auth` whenever the contract's authorization is
+//!   required, with the signatures attached to the transaction's auth entry
+//! - Signatures must be ordered by public key, so a signer cannot be
+//!   counted twice, and at least `threshold` of them must verify
+//! - The signer set changes only with the multisig's own authorization,
+//!   through `update_signers`
+//!
+//! A 2-of-3 admin is `initialize([key_a, key_b, key_c], 2)`.
+
+use soroban_sdk::{
+    auth::{Context, CustomAccountInterface},
+    contract, contracterror, contractimpl, contracttype,
+    crypto::Hash,
+    symbol_short, BytesN, Env, Vec,
+};
+
+use apogee_common::{BUMP_AMOUNT, LIFETIME_THRESHOLD};
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the multisig
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Signer public keys
+    Signers,
+    /// Signatures needed to authorize
+    Threshold,
+}
+
+/// One signer's signature over the authorization payload
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Signature {
+    pub public_key: BytesN<32>,
+    pub signature: BytesN<64>,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the multisig
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MultisigError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Threshold must be between 1 and the number of signers, and signers
+    /// must be distinct
+    InvalidSigners = 3,
+    /// Signature from a key that is not a signer
+    UnknownSigner = 4,
+    /// Signatures are not ordered by public key, or a signer signed twice
+    SignaturesNotOrdered = 5,
+    /// Fewer signatures than the threshold
+    ThresholdNotMet = 6,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct Multisig;
+
+#[contractimpl]
+impl Multisig {
+    /// Initialize the signer set
+    ///
+    /// # Arguments
+    /// * `signers` - ed25519 public keys of the signers
+    /// * `threshold` - Signatures needed to authorize
+    pub fn initialize(env: Env, signers: Vec<BytesN<32>>, threshold: u32) -> Result<(), MultisigError> {
+        if env.storage().instance().has(&DataKey::Signers) {
+            return Err(MultisigError::AlreadyInitialized);
+        }
+        Self::set_signers(&env, signers, threshold)
+    }
+
+    /// Replace the signer set (authorized by the multisig itself)
+    pub fn update_signers(env: Env, signers: Vec<BytesN<32>>, threshold: u32) -> Result<(), MultisigError> {
+        env.current_contract_address().require_auth();
+        Self::set_signers(&env, signers, threshold)
+    }
+
+    /// Get the signer public keys
+    pub fn get_signers(env: Env) -> Result<Vec<BytesN<32>>, MultisigError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Signers)
+            .ok_or(MultisigError::NotInitialized)
+    }
+
+    /// Get the number of signatures needed to authorize
+    pub fn get_threshold(env: Env) -> Result<u32, MultisigError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Threshold)
+            .ok_or(MultisigError::NotInitialized)
+    }
+
+    /// Internal: Validate and store a signer set
+    fn set_signers(env: &Env, signers: Vec<BytesN<32>>, threshold: u32) -> Result<(), MultisigError> {
+        if threshold == 0 || threshold > signers.len() {
+            return Err(MultisigError::InvalidSigners);
+        }
+        for (i, signer) in signers.iter().enumerate() {
+            if signers.iter().skip(i + 1).any(|other| other == signer) {
+                return Err(MultisigError::InvalidSigners);
+            }
+        }
+
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        env.storage().instance().set(&DataKey::Threshold, &threshold);
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        env.events().publish((symbol_short!("signers"),), (signers, threshold));
+
+        Ok(())
+    }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for Multisig {
+    type Signature = Vec<Signature>;
+    type Error = MultisigError;
+
+    /// Check that at least `threshold` signers signed the payload
+    ///
+    /// Any contract call the signers approve is authorized; scoping what the
+    /// admin may do is left to the protocol contracts' own roles.
+    #[allow(non_snake_case)]
+    fn __check_auth(
+        env: Env,
+        signature_payload: Hash<32>,
+        signatures: Vec<Signature>,
+        _auth_contexts: Vec<Context>,
+    ) -> Result<(), MultisigError> {
+        let signers = Self::get_signers(env.clone())?;
+        let threshold = Self::get_threshold(env.clone())?;
+        if signatures.len() < threshold {
+            return Err(MultisigError::ThresholdNotMet);
+        }
+
+        let payload = signature_payload.to_bytes().into();
+        let mut previous: Option<BytesN<32>> = None;
+        for signature in signatures.iter() {
+            if let Some(previous) = previous {
+                if previous >= signature.public_key {
+                    return Err(MultisigError::SignaturesNotOrdered);
+                }
+            }
+            if !signers.contains(&signature.public_key) {
+                return Err(MultisigError::UnknownSigner);
+            }
+            // Panics, failing the authorization, if the signature is invalid
+            env.crypto()
+                .ed25519_verify(&signature.public_key, &payload, &signature.signature);
+            previous = Some(signature.public_key);
+        }
+
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::{
+        contract, testutils::BytesN as _, vec, xdr, xdr::WriteXdr, Address, Bytes, IntoVal, Symbol,
+    };
+
+    /// Contract with a single admin-gated setter, standing in for the
+    /// protocol contracts' admin checks
+    #[contract]
+    struct AdminGated;
+
+    #[contractimpl]
+    impl AdminGated {
+        pub fn set_value(env: Env, admin: Address, value: u32) {
+            admin.require_auth();
+            env.storage().instance().set(&symbol_short!("value"), &value);
+        }
+    }
+
+    fn signer(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn sign(env: &Env, key: &SigningKey, payload: &BytesN<32>) -> Signature {
+        Signature {
+            public_key: BytesN::from_array(env, &key.verifying_key().to_bytes()),
+            signature: BytesN::from_array(env, &key.sign(&payload.to_array()).to_bytes()),
+        }
+    }
+
+    /// Signatures ordered by public key, as `__check_auth` expects
+    fn sorted(env: &Env, mut signatures: std::vec::Vec<Signature>) -> Vec<Signature> {
+        signatures.sort_by_key(|s| s.public_key.to_array());
+        let mut sorted = Vec::new(env);
+        for signature in signatures {
+            sorted.push_back(signature);
+        }
+        sorted
+    }
+
+    fn public_keys(env: &Env, keys: &[SigningKey]) -> Vec<BytesN<32>> {
+        let mut public_keys = Vec::new(env);
+        for key in keys {
+            public_keys.push_back(BytesN::from_array(env, &key.verifying_key().to_bytes()));
+        }
+        public_keys
+    }
+
+    #[test]
+    fn test_two_of_three_check_auth() {
+        let env = Env::default();
+        let keys = [signer(1), signer(2), signer(3)];
+        let multisig_id = env.register_contract(None, Multisig);
+        let client = MultisigClient::new(&env, &multisig_id);
+        let public_keys = public_keys(&env, &keys);
+        assert_eq!(
+            client.try_initialize(&public_keys, &4),
+            Err(Ok(MultisigError::InvalidSigners))
+        );
+        client.initialize(&public_keys, &2);
+
+        let payload = BytesN::random(&env);
+        let check = |signatures: Vec<Signature>| {
+            env.try_invoke_contract_check_auth::<MultisigError>(&multisig_id, &payload, signatures.into_val(&env), &vec![&env])
+        };
+
+        // Any two of the three signers authorize
+        assert_eq!(check(sorted(&env, std::vec![sign(&env, &keys[0], &payload), sign(&env, &keys[2], &payload)])), Ok(()));
+        assert_eq!(
+            check(sorted(&env, std::vec![sign(&env, &keys[1], &payload)])),
+            Err(Ok(MultisigError::ThresholdNotMet))
+        );
+
+        // The same signer cannot be counted twice
+        let twice = sign(&env, &keys[1], &payload);
+        assert_eq!(
+            check(vec![&env, twice.clone(), twice]),
+            Err(Ok(MultisigError::SignaturesNotOrdered))
+        );
+
+        // Outsiders do not count, and a bad signature fails outright
+        let outsider = sign(&env, &signer(9), &payload);
+        assert_eq!(
+            check(sorted(&env, std::vec![sign(&env, &keys[0], &payload), outsider])),
+            Err(Ok(MultisigError::UnknownSigner))
+        );
+        let mut forged = sign(&env, &keys[0], &BytesN::random(&env));
+        forged.public_key = BytesN::from_array(&env, &keys[0].verifying_key().to_bytes());
+        assert!(check(sorted(&env, std::vec![forged, sign(&env, &keys[1], &payload)])).is_err());
+    }
+
+    #[test]
+    fn test_multisig_signs_admin_call() {
+        let env = Env::default();
+        let keys = [signer(1), signer(2), signer(3)];
+        let multisig = env.register_contract(None, Multisig);
+        let public_keys = public_keys(&env, &keys);
+        MultisigClient::new(&env, &multisig).initialize(&public_keys, &2);
+        let gated = AdminGatedClient::new(&env, &env.register_contract(None, AdminGated));
+
+        // The auth entry two signers would attach to `set_value(multisig, 7)`
+        let invocation = xdr::SorobanAuthorizedInvocation {
+            function: xdr::SorobanAuthorizedFunction::ContractFn(xdr::InvokeContractArgs {
+                contract_address: (&gated.address).into(),
+                function_name: "set_value".try_into().unwrap(),
+                args: std::vec![(&multisig).into(), xdr::ScVal::U32(7)].try_into().unwrap(),
+            }),
+            sub_invocations: Default::default(),
+        };
+        let (nonce, expiration) = (1, env.ledger().sequence() + 100);
+        let preimage = xdr::HashIdPreimage::SorobanAuthorization(xdr::HashIdPreimageSorobanAuthorization {
+            network_id: xdr::Hash(env.ledger().network_id().to_array()),
+            nonce,
+            signature_expiration_ledger: expiration,
+            invocation: invocation.clone(),
+        });
+        let preimage = Bytes::from_slice(&env, &preimage.to_xdr(xdr::Limits::none()).unwrap());
+        let payload: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let entry = |signatures: Vec<Signature>| xdr::SorobanAuthorizationEntry {
+            credentials: xdr::SorobanCredentials::Address(xdr::SorobanAddressCredentials {
+                address: (&multisig).into(),
+                nonce,
+                signature_expiration_ledger: expiration,
+                signature: (&signatures).into(),
+            }),
+            root_invocation: invocation.clone(),
+        };
+
+        let one = sorted(&env, std::vec![sign(&env, &keys[0], &payload)]);
+        env.set_auths(&[entry(one)]);
+        assert!(gated.try_set_value(&multisig, &7).is_err());
+
+        let two = sorted(&env, std::vec![sign(&env, &keys[0], &payload), sign(&env, &keys[1], &payload)]);
+        env.set_auths(&[entry(two)]);
+        gated.set_value(&multisig, &7);
+        let stored: u32 = env.as_contract(&gated.address, || {
+            env.storage().instance().get(&Symbol::new(&env, "value")).unwrap()
+        });
+        assert_eq!(stored, 7);
+    }
+}
@@ -1,27 +1,66 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, token, Address, Env, Symbol,
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, Val, Vec,
+};
+use stellend_fixed_point::{compound_interest, mul_div, ray_rate_per_second, FixedPointError, Rounding, RAY};
+
+pub use apogee_common::{AccountData, ReserveConfig, RewardSide, Role};
+use apogee_common::{
+    bps_to_scale, convert_amount, native_xlm, utilization_rate, LiquidationEvent, SwapAdapterClient, BPS_SCALE,
+    BUMP_AMOUNT, EXCHANGE_RATE_SCALE, LIFETIME_THRESHOLD, NATIVE_DECIMALS, SCALE, SECONDS_PER_YEAR,
 };
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Scaling factor for percentages and rates (1e7 = 10_000_000)
-/// 100% = 10_000_000, 75% = 7_500_000, 1% = 100_000
-const SCALE: i128 = 10_000_000;
-
 /// Initial exchange rate for sTokens (1:1 with underlying)
 /// Scaled by 1e9 for precision
-const INITIAL_EXCHANGE_RATE: i128 = 1_000_000_000;
+const INITIAL_EXCHANGE_RATE: i128 = EXCHANGE_RATE_SCALE;
+
+/// Initial borrow index (1.0 in ray precision, 1e27)
+const INITIAL_BORROW_INDEX: i128 = RAY;
+
+/// Decimals of every amount the pool stores, prices and takes as an
+/// argument, whatever the reserve token's own `decimals()`. Amounts are
+/// converted to token units only where tokens are transferred
+const CANONICAL_DECIMALS: u32 = 7;
+
+/// User borrow indices below this were stored at the exchange rate scale
+/// (1e9) before the index moved to ray; a 1e9 index would need to grow a
+/// billionfold to reach it
+const LEGACY_BORROW_INDEX_LIMIT: i128 = RAY / EXCHANGE_RATE_SCALE;
 
 /// Liquidation parameters
 /// Close factor: Maximum portion of debt that can be liquidated (50%)
 const CLOSE_FACTOR: i128 = 5_000_000; // 50% (scaled by SCALE)
-/// Liquidation bonus: Extra collateral given to liquidator (5%)
+/// Liquidation bonus: Extra collateral given to liquidator (5%), unless set per asset
 const LIQUIDATION_BONUS: i128 = 500_000; // 5% (scaled by SCALE)
 
+/// Risk parameter bounds enforced on admin setters (scaled by SCALE)
+const MAX_LIQUIDATION_THRESHOLD: i128 = 9_500_000; // 95%
+const MIN_CLOSE_FACTOR: i128 = 500_000; // 5%
+const MAX_RESERVE_FACTOR: i128 = 5_000_000; // 50%
+const MAX_ORIGINATION_FEE: i128 = 500_000; // 5%
+const MAX_FLASH_LOAN_FEE: i128 = 100_000; // 1%
+const MAX_DEPOSIT_WITHDRAW_FEE_BPS: u32 = 100; // 1%
+
+/// Default flash loan fee (0.09%, scaled by SCALE)
+const DEFAULT_FLASH_LOAN_FEE: i128 = 9_000;
+
+/// Utilization at or above which stable rates can be rebalanced up (95%, scaled by SCALE)
+const REBALANCE_UP_UTILIZATION: i128 = 9_500_000;
+/// Margin above the current stable rate at which a stable rate can be rebalanced down (20%, scaled by SCALE)
+const REBALANCE_DOWN_DELTA: i128 = 2_000_000;
+
+/// Default hysteresis for the global borrow limit (5%, scaled by SCALE)
+const DEFAULT_GLOBAL_BORROW_HYSTERESIS: i128 = 500_000;
+
+/// Storage layout version written by this build; `migrate` upgrades older layouts to it
+const STORAGE_VERSION: u32 = 2;
+
 /// Asset symbols
 const XLM: Symbol = symbol_short!("XLM");
 const USDC: Symbol = symbol_short!("USDC");
@@ -51,6 +90,50 @@ pub enum DataKey {
     CollateralEnabled(Symbol),
     /// Whether an asset is enabled for borrowing
     BorrowEnabled(Symbol),
+    /// Whether an asset can be borrowed at a stable rate
+    StableBorrowEnabled(Symbol),
+    /// Listed reserve assets, in listing order
+    Reserves,
+    /// Token decimals per asset, read from the token when it is listed
+    Decimals(Symbol),
+    /// Interest rate model contract per asset (falls back to `InterestRateModel`)
+    ReserveRateModel(Symbol),
+    /// Maximum portion of a borrower's debt repayable in one liquidation (scaled by SCALE)
+    CloseFactor,
+    /// Origination fee charged on new borrows per asset (scaled by SCALE, 0.5% = 50_000)
+    OriginationFee(Symbol),
+    /// Flash loan fee per asset (scaled by SCALE, 0.09% = 9_000)
+    FlashLoanFee(Symbol),
+    /// Amount a delegatee may borrow against a delegator's collateral (delegator, delegatee, asset)
+    BorrowAllowance(Address, Address, Symbol),
+    /// Whether an address is exempt from protocol fees (e.g. protocol-owned contracts)
+    FeeExempt(Address),
+    /// Liquidation bonus at the start of a Dutch auction (scaled by SCALE)
+    MaxAuctionBonus,
+    /// Liquidation bonus once the Dutch auction has fully decayed (scaled by SCALE)
+    MinAuctionBonus,
+    /// Seconds for the auction bonus to decay from max to min (0 = auctions disabled)
+    AuctionDuration,
+    /// System-wide debt limit in USD (scaled by 1e7, 0 = disabled)
+    GlobalBorrowLimit,
+    /// Fraction below the global limit total debt must fall to re-enable borrows (scaled by SCALE)
+    GlobalBorrowLimitHysteresis,
+    /// Whether new borrows are blocked because the global debt limit was reached
+    GlobalBorrowPaused,
+    /// Multiplier `k` applied to relative volatility when reducing LTV (scaled by SCALE, 0 = disabled)
+    VolatilityAdjustmentFactor,
+    /// Trailing window in seconds for TWAP collateral valuation (0 = spot price)
+    TwapWindow,
+    /// Percentage of new USDC supplies matched from the match budget (scaled by SCALE, 0 = disabled)
+    DepositMatchPct,
+    /// Remaining USDC available to match deposits
+    DepositMatchBudget,
+    /// Address allowed to pause the pool alongside the admin
+    Guardian,
+    /// Whether all user actions are paused
+    Paused,
+    /// Whether a single user action is paused
+    ActionPaused(PoolAction),
 
     // ========== POOL STATE (per asset) ==========
     /// Total underlying supplied to the pool
@@ -61,7 +144,7 @@ pub enum DataKey {
     TotalBorrow(Symbol),
     /// Exchange rate: underlying per sToken (scaled by 1e9)
     ExchangeRate(Symbol),
-    /// Borrow index for interest accrual (scaled by 1e9)
+    /// Borrow index for interest accrual (scaled by 1e27)
     BorrowIndex(Symbol),
     /// Last interest accrual timestamp
     LastAccrualTime(Symbol),
@@ -69,16 +152,249 @@ pub enum DataKey {
     ReserveFactor(Symbol),
     /// Total reserves accumulated
     TotalReserves(Symbol),
+    /// Portion of total borrows at a stable rate, including accrued interest
+    TotalStableDebt(Symbol),
+    /// Debt-weighted average rate of stable borrows (scaled by SCALE)
+    AvgStableRate(Symbol),
 
     // ========== USER STATE ==========
     /// User's sToken share balance per asset
     UserShares(Address, Symbol),
     /// User's collateral balance per asset (in underlying units)
     UserCollateral(Address, Symbol),
-    /// User's debt balance per asset (principal, before interest)
+    /// User's variable debt per asset as of their `UserBorrowIndex` snapshot
     UserDebt(Address, Symbol),
-    /// User's borrow index at time of last borrow (for interest calculation)
+    /// Borrow index (ray) when the user's debt was last written; current
+    /// debt = `UserDebt` * index / snapshot, so each user accrues only from
+    /// their own last interaction
     UserBorrowIndex(Address, Symbol),
+    /// User's stable-rate debt position per asset
+    UserStableDebt(Address, Symbol),
+    /// Timestamp at which a liquidation auction started for a borrower
+    AuctionStart(Address),
+}
+
+/// Storage keys for per-reserve limits and extensions
+///
+/// Kept apart from `DataKey`, which is at the 50-variant limit for contract
+/// types. Keys are encoded by variant name only, so the split does not
+/// change storage.
+#[derive(Clone)]
+#[contracttype]
+pub enum ReserveKey {
+    /// Maximum total supply per asset in canonical 7-decimal units (0 = no cap)
+    SupplyCap(Symbol),
+    /// Maximum total borrows per asset in canonical 7-decimal units (0 = no cap)
+    BorrowCap(Symbol),
+    /// Debt ceiling in USD for borrows backed by an isolated collateral asset (scaled by 1e7, 0 = not isolated)
+    DebtCeiling(Symbol),
+    /// USD debt borrowed against an isolated collateral asset (scaled by 1e7)
+    IsolatedDebt(Symbol),
+    /// sToken contract representing supplied shares of an asset
+    SToken(Symbol),
+    /// Liquidation bonus when seizing an asset outside of auctions (scaled by SCALE)
+    LiquidationBonus(Symbol),
+    /// Portion of the liquidation bonus on an asset sent to the treasury (scaled by SCALE)
+    LiquidationProtocolFee(Symbol),
+    /// Smallest debt position a borrow may leave (canonical units)
+    MinBorrow(Symbol),
+    /// Debt left by a repay below which the position is closed in full (canonical units)
+    DebtDust(Symbol),
+    /// Fee taken from supplies of an asset (scaled by SCALE, missing = 0)
+    DepositFee(Symbol),
+    /// Fee taken from withdrawals of an asset (scaled by SCALE, missing = 0)
+    WithdrawFee(Symbol),
+    /// Reserves paid to whoever pokes an asset's accrual (pool units, missing = 0)
+    PokeReward(Symbol),
+    /// Seconds since the last accrual before a poke earns the reward
+    PokeInterval(Symbol),
+}
+
+/// Storage keys for protocol-wide settings
+///
+/// Kept apart from `DataKey` for the same reason as `ReserveKey`.
+#[derive(Clone)]
+#[contracttype]
+pub enum ProtocolKey {
+    /// Address receiving protocol fees
+    Treasury,
+    /// Address holding an operational role
+    Role(Role),
+    /// Storage layout version of the deployed data (missing = 0)
+    StorageVersion,
+    /// Emissions controller notified of balance changes
+    Emissions,
+    /// Cumulative volume referred by a code per asset, in persistent storage
+    Referral(u32, Symbol),
+    /// Backstop fund covering bad debt
+    Backstop,
+    /// Age in seconds past which a stale oracle price triggers emergency mode (0 = disabled)
+    OracleHardLimit,
+    /// Whether emergency mode was triggered and not yet cleared by the admin
+    EmergencyMode,
+    /// Whether a swap adapter is approved for leverage and liquidation swaps
+    SwapAdapter(Address),
+    /// Number of addresses in the borrower registry
+    BorrowerCount,
+    /// Token users stake for a borrow rate discount
+    DiscountToken,
+    /// Borrow rate discount tiers (`Vec<DiscountTier>`)
+    DiscountTiers,
+}
+
+/// Storage keys for per-user settings, in persistent storage
+///
+/// Kept apart from `DataKey` for the same reason as `ReserveKey`.
+#[derive(Clone)]
+#[contracttype]
+pub enum UserKey {
+    /// Whether a user's supplied shares of an asset count as collateral
+    SupplyCollateral(Address, Symbol),
+    /// Borrower registry: address at a slot
+    BorrowerAt(u32),
+    /// Borrower registry: slot of an address with debt
+    BorrowerSlot(Address),
+    /// Session key allowance: (user, session, asset)
+    Session(Address, Address, Symbol),
+    /// Discount tokens a user has staked with the pool
+    Staked(Address),
+    /// Discount on the variable borrow rate a user's tier earned when last
+    /// evaluated (scaled by SCALE, missing = none)
+    RateDiscount(Address),
+    /// USD value a user's debt in an asset charged against an isolated
+    /// asset's debt ceiling when borrowed (scaled by SCALE)
+    IsolatedDebt(Address, Symbol),
+}
+
+/// User actions that can be paused individually
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PoolAction {
+    Supply,
+    Withdraw,
+    DepositCollateral,
+    WithdrawCollateral,
+    Borrow,
+    Repay,
+    Liquidate,
+    FlashLoan,
+}
+
+/// Interest rate mode of a borrow position
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RateMode {
+    /// Rate follows pool utilization through the borrow index
+    Variable,
+    /// Rate is locked when the debt is taken (subject to rebalancing)
+    Stable,
+}
+
+/// A user's stable-rate debt in one asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StableDebt {
+    /// Debt as of `last_update`, including interest accrued until then
+    pub principal: i128,
+    /// Locked annual rate (scaled by SCALE)
+    pub rate: i128,
+    /// Timestamp at which the position was last updated
+    pub last_update: u64,
+}
+
+/// What a session key may still move from a user's wallet in one asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SessionGrant {
+    /// Underlying left to supply or repay, in canonical 7-decimal units
+    pub remaining: i128,
+    /// Ledger timestamp after which the grant no longer applies
+    pub expires_at: u64,
+}
+
+/// A borrow rate discount tier; a user gets the largest discount among the
+/// tiers they qualify for
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DiscountTier {
+    /// Discount tokens staked to qualify (0 = staking does not qualify)
+    pub min_staked: i128,
+    /// Or: USD value supplied to the pool to qualify (scaled by 1e7, 0 =
+    /// supplying does not qualify)
+    pub min_supply_usd: i128,
+    /// Discount on the variable borrow rate, in basis points
+    pub discount_bps: u32,
+}
+
+/// One step of a `multicall`, acting on the caller's own position
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Operation {
+    /// `supply(asset, amount)` from and for the caller
+    Supply(Symbol, i128),
+    /// `withdraw(asset, share_amount)` with no minimum output or deadline
+    Withdraw(Symbol, i128),
+    /// `deposit_collateral(asset, amount)`
+    DepositCollateral(Symbol, i128),
+    /// `withdraw_collateral(asset, amount)`
+    WithdrawCollateral(Symbol, i128),
+    /// `borrow(asset, amount)` at the variable rate
+    Borrow(Symbol, i128),
+    /// `repay(asset, amount)` of the caller's own debt
+    Repay(Symbol, i128),
+}
+
+/// Pool totals for an asset with interest accrued up to the current ledger
+struct Accrual {
+    borrow_index: i128,
+    total_borrow: i128,
+    total_supply: i128,
+    total_reserves: i128,
+    total_stable_debt: i128,
+}
+
+/// Prices read from the oracle during one invocation
+///
+/// Each asset's spot and collateral price is fetched at most once, so paths
+/// that value an account more than once (liquidation) pay for a single
+/// oracle call per price.
+struct PriceCache {
+    oracle: Address,
+    spot: Map<Symbol, i128>,
+    collateral: Map<Symbol, i128>,
+}
+
+impl PriceCache {
+    fn new(env: &Env) -> Result<Self, PoolError> {
+        Ok(PriceCache {
+            oracle: LendingPool::get_oracle(env)?,
+            spot: Map::new(env),
+            collateral: Map::new(env),
+        })
+    }
+
+    /// Spot price of an asset; see `LendingPool::get_asset_price`
+    fn spot(&mut self, env: &Env, asset: &Symbol) -> Result<i128, PoolError> {
+        if let Some(price) = self.spot.get(asset.clone()) {
+            return Ok(price);
+        }
+        let price = LendingPool::get_asset_price(env, &self.oracle, asset)?;
+        self.spot.set(asset.clone(), price);
+        Ok(price)
+    }
+
+    /// Collateral price of an asset; see `LendingPool::get_collateral_price`
+    fn collateral(&mut self, env: &Env, asset: &Symbol) -> Result<i128, PoolError> {
+        if LendingPool::get_twap_window(env.clone()) == 0 {
+            return self.spot(env, asset);
+        }
+        if let Some(price) = self.collateral.get(asset.clone()) {
+            return Ok(price);
+        }
+        let price = LendingPool::get_collateral_price(env, &self.oracle, asset)?;
+        self.collateral.set(asset.clone(), price);
+        Ok(price)
+    }
 }
 
 /// Result struct for user position queries
@@ -91,6 +407,24 @@ pub struct UserPosition {
     pub health_factor: i128,
 }
 
+/// Account summary returned by `get_user_account_data`
+///
+/// USD values and ratios are scaled by SCALE (1e7).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UserAccountData {
+    pub total_collateral_usd: i128,
+    pub total_debt_usd: i128,
+    pub available_borrow_usd: i128,
+    /// Current debt / collateral
+    pub current_ltv: i128,
+    /// Collateral-weighted maximum LTV
+    pub max_ltv: i128,
+    /// Collateral-weighted liquidation threshold
+    pub liquidation_threshold: i128,
+    pub health_factor: i128,
+}
+
 /// Result struct for market info queries
 #[derive(Clone)]
 #[contracttype]
@@ -105,6 +439,231 @@ pub struct MarketInfo {
     pub ltv_ratio: i128,
 }
 
+/// One market's state with interest accrued up to now, for dashboards
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReserveData {
+    /// Underlying supplied, including interest
+    pub total_supply: i128,
+    /// Underlying borrowed, including interest
+    pub total_borrow: i128,
+    /// Underlying that can be borrowed or withdrawn now
+    pub available_liquidity: i128,
+    /// Scaled by 1e7
+    pub utilization_rate: i128,
+    /// Borrow APR compounded per second over a year (scaled by 1e7)
+    pub borrow_apy: i128,
+    /// Supply APR compounded per second over a year (scaled by 1e7)
+    pub supply_apy: i128,
+    /// Scaled by 1e27
+    pub borrow_index: i128,
+    /// When interest was last written to storage
+    pub last_accrual_time: u64,
+    /// Canonical units, 0 = no cap
+    pub supply_cap: i128,
+    /// Canonical units, 0 = no cap
+    pub borrow_cap: i128,
+}
+
+/// Pool-wide totals at oracle spot prices, for analytics and dashboards
+///
+/// USD values are scaled by 1e7 and include interest accrued up to now.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProtocolStats {
+    /// Tokens held by the pool (idle liquidity, collateral and reserves)
+    pub tvl_usd: i128,
+    /// Underlying supplied to the lending markets
+    pub total_supply_usd: i128,
+    /// Variable and stable debt owed by borrowers
+    pub total_debt_usd: i128,
+    /// Protocol reserves accrued for the treasury
+    pub treasury_usd: i128,
+    /// Reserves that still accept collateral or borrows
+    pub active_reserves: u32,
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// Cumulative supply and borrow volume brought in by a referral code
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct ReferralVolume {
+    /// Underlying supplied with the code
+    pub supplied: i128,
+    /// Underlying borrowed with the code
+    pub borrowed: i128,
+}
+
+/// Payload of `supply` and `withdraw` events
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DepositEvent {
+    pub user: Address,
+    pub asset: Symbol,
+    /// Underlying amount supplied or withdrawn
+    pub amount: i128,
+    /// sTokens minted or burned
+    pub shares: i128,
+    /// User's sToken balance after the action
+    pub new_balance: i128,
+    /// Exchange rate used (scaled by 1e9)
+    pub exchange_rate: i128,
+}
+
+/// Payload of `coll_dep` and `coll_wth` events
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CollateralEvent {
+    pub user: Address,
+    pub asset: Symbol,
+    pub amount: i128,
+    /// User's collateral balance after the action
+    pub new_balance: i128,
+}
+
+/// Payload of `borrow` and `repay` events
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BorrowEvent {
+    /// Account whose debt changed
+    pub user: Address,
+    /// Address that received the borrow or paid the repayment
+    pub counterparty: Address,
+    pub asset: Symbol,
+    pub amount: i128,
+    /// User's debt after the action, including accrued interest
+    pub new_debt: i128,
+    /// Borrow index at the time of the action (scaled by 1e27)
+    pub borrow_index: i128,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the lending pool
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum PoolError {
+    /// Contract has already been initialized, or a migration's target
+    /// address already holds a position
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Amount must be positive
+    InvalidAmount = 3,
+    /// Amount rounds down to zero shares, or leaves debt below the minimum borrow
+    AmountTooSmall = 4,
+    /// Asset is not supported by the pool, or the XLM token passed to
+    /// `initialize` is not the native asset contract
+    UnknownAsset = 5,
+    /// Asset is not enabled as collateral
+    CollateralNotEnabled = 6,
+    /// Asset is not enabled for borrowing
+    BorrowNotEnabled = 7,
+    /// User does not hold enough sToken shares
+    InsufficientShares = 8,
+    /// Pool does not have enough available liquidity
+    InsufficientLiquidity = 9,
+    /// User does not have enough collateral
+    InsufficientCollateral = 10,
+    /// Action would leave the position with health factor below 1.0
+    PositionUnhealthy = 11,
+    /// Borrow would exceed the user's LTV limit
+    ExceedsLtv = 12,
+    /// User has no outstanding debt in the asset
+    NoDebt = 13,
+    /// Borrower has no collateral in the asset
+    NoCollateral = 14,
+    /// Position is healthy and cannot be liquidated
+    PositionHealthy = 15,
+    /// System-wide borrow limit has been reached
+    GlobalBorrowLimitReached = 16,
+    /// Oracle has no price for the asset
+    PriceUnavailable = 17,
+    /// Close factor must be between 5% and 100%
+    InvalidCloseFactor = 18,
+    /// LTV must be below the liquidation threshold
+    LtvAboveThreshold = 19,
+    /// Liquidation threshold must not exceed 95%
+    ThresholdTooHigh = 20,
+    /// Reserve factor must be between 0 and 50%
+    InvalidReserveFactor = 21,
+    /// Origination fee must be between 0 and 5%
+    InvalidOriginationFee = 22,
+    /// Auction bonuses must satisfy 0 <= min <= max <= 100%
+    InvalidAuctionParams = 23,
+    /// Global borrow limit must not be negative, and its hysteresis must be
+    /// between 0 and 100%
+    InvalidBorrowLimit = 24,
+    // 25 and 26 were folded into InvalidBorrowLimit and InvalidCap to stay
+    // within the 50 error cases a contract spec allows
+    /// Match percentage must be between 0 and 100%
+    InvalidMatchPct = 27,
+    /// Asset is already listed as a reserve
+    ReserveAlreadyListed = 28,
+    /// Caller is not allowed to perform this action
+    Unauthorized = 29,
+    /// Action is paused by the admin or guardian, or frozen by emergency mode
+    /// or an oracle-confirmed depeg
+    ActionPaused = 30,
+    /// Flash loan was not repaid with its fee
+    FlashLoanNotRepaid = 31,
+    /// Flash loan fee is outside the allowed range
+    InvalidFlashLoanFee = 32,
+    /// Supply would exceed the asset's supply cap
+    SupplyCapExceeded = 33,
+    /// Borrow would exceed the asset's borrow cap
+    BorrowCapExceeded = 34,
+    /// Cap or volatility adjustment factor must not be negative
+    InvalidCap = 35,
+    /// Borrow exceeds the delegated allowance
+    InsufficientAllowance = 36,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 37,
+    /// Asset cannot be borrowed at a stable rate
+    StableBorrowNotEnabled = 38,
+    /// Stable rate does not meet the conditions for a rebalance
+    RebalanceNotAllowed = 39,
+    /// Isolated collateral cannot be mixed with other collateral
+    IsolationModeViolation = 40,
+    /// Borrow would exceed the isolated collateral's debt ceiling
+    DebtCeilingExceeded = 41,
+    /// No sToken is registered for the asset
+    STokenNotSet = 42,
+    /// Liquidation bonus must not be negative and, with the liquidation threshold, must not exceed 100%
+    InvalidLiquidationBonus = 43,
+    /// Liquidation protocol fee must be between 0 and 100%, deposit and
+    /// withdrawal fees at most 1%, rate discounts at most 100%
+    InvalidProtocolFee = 44,
+    /// Role cannot be granted or revoked this way
+    InvalidRole = 45,
+    /// Stored data is newer than this build understands
+    StorageVersionTooNew = 46,
+    /// Amount exceeds the protocol reserves of the asset
+    InsufficientReserves = 47,
+    /// No backstop fund is set
+    BackstopNotSet = 48,
+    /// Borrower still has collateral or has no debt
+    NoBadDebt = 49,
+    /// Asset is not the token staked in the backstop
+    BackstopAssetMismatch = 50,
+    /// Call was submitted after the caller's deadline
+    DeadlineExpired = 51,
+    /// Output falls short of the caller's minimum
+    SlippageExceeded = 52,
+}
+
+impl From<FixedPointError> for PoolError {
+    fn from(_: FixedPointError) -> Self {
+        PoolError::MathOverflow
+    }
+}
+
 // ============================================================================
 // CONTRACT
 // ============================================================================
@@ -130,6 +689,10 @@ pub struct LendingPool;
 // EXTERNAL CONTRACT INTERFACES
 // ============================================================================
 
+// Clients generated from the deployed contracts' WASM specs, so every
+// cross-contract call is checked against the callee's real interface at
+// build time. Build the oracle and rate model before the pool.
+
 // Oracle contract client for cross-contract calls
 mod oracle_contract {
     soroban_sdk::contractimport!(
@@ -137,8 +700,43 @@ mod oracle_contract {
     );
 }
 
-// Flag to enable/disable oracle calls (for testing without deployed oracle)
-const USE_ORACLE: bool = true; // Oracle is deployed and active
+// Interest rate model client for cross-contract calls
+mod interest_rate_model_contract {
+    soroban_sdk::contractimport!(
+        file = "../target/wasm32-unknown-unknown/release/stellend_interest_rate_model.wasm"
+    );
+}
+
+/// Callback interface implemented by flash loan receivers
+///
+/// Called by the pool after `amount` of `asset` has been sent to the
+/// receiver. The receiver must transfer `amount + fee` back to the pool
+/// before returning.
+#[contractclient(name = "FlashLoanReceiverClient")]
+pub trait FlashLoanReceiver {
+    fn exec_op(env: Env, asset: Symbol, token: Address, amount: i128, fee: i128, data: Bytes);
+}
+
+/// sToken hooks called when shares are minted or burned
+#[contractclient(name = "STokenHooksClient")]
+pub trait STokenHooks {
+    fn mint(env: Env, to: Address, amount: i128);
+    fn pool_burn(env: Env, from: Address, amount: i128);
+}
+
+/// Backstop fund functions used by the pool
+#[contractclient(name = "BackstopClient")]
+pub trait BackstopFund {
+    fn get_token(env: Env) -> Address;
+    fn distribute(env: Env, amount: i128);
+    fn cover_bad_debt(env: Env, amount: i128) -> i128;
+}
+
+/// Emissions controller hook called whenever a user's scaled balance changes
+#[contractclient(name = "EmissionsHooksClient")]
+pub trait EmissionsHooks {
+    fn handle_balance_change(env: Env, asset: Symbol, side: RewardSide, user: Address, balance: i128);
+}
 
 #[contractimpl]
 impl LendingPool {
@@ -152,7 +750,9 @@ impl LendingPool {
     /// * `admin` - Admin address for protocol management
     /// * `price_oracle` - Price oracle contract address
     /// * `interest_rate_model` - Interest rate model contract address
-    /// * `xlm_token` - Wrapped XLM token contract address
+    /// * `xlm_token` - Native XLM Stellar asset contract address; anything
+    ///   else is rejected with `UnknownAsset`, so a look-alike wrapped XLM
+    ///   token cannot be listed in its place
     /// * `usdc_token` - USDC token contract address
     pub fn initialize(
         env: Env,
@@ -161,41 +761,67 @@ impl LendingPool {
         interest_rate_model: Address,
         xlm_token: Address,
         usdc_token: Address,
-    ) {
+    ) -> Result<(), PoolError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("Already initialized");
+            return Err(PoolError::AlreadyInitialized);
+        }
+        if xlm_token != native_xlm(&env) {
+            return Err(PoolError::UnknownAsset);
         }
 
         // Store admin and external contract addresses
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::PriceOracle, &price_oracle);
         env.storage().instance().set(&DataKey::InterestRateModel, &interest_rate_model);
-
-        // Store token addresses
-        env.storage().instance().set(&DataKey::TokenAddress(XLM), &xlm_token);
-        env.storage().instance().set(&DataKey::TokenAddress(USDC), &usdc_token);
-
-        // Initialize XLM market (collateral only, not borrowable)
-        Self::init_market(&env, XLM, 7_500_000, 8_000_000, true, false); // 75% LTV, 80% liq threshold
+        env.storage().instance().set(&ProtocolKey::StorageVersion, &STORAGE_VERSION);
+
+        // Initialize XLM market (collateral, borrowable against USDC)
+        Self::init_market(&env, XLM, &ReserveConfig {
+            token: xlm_token,
+            decimals: NATIVE_DECIMALS,
+            ltv: 7_500_000,                   // 75% LTV
+            liquidation_threshold: 8_000_000, // 80% liq threshold
+            collateral_enabled: true,
+            borrow_enabled: true,
+            interest_rate_model: interest_rate_model.clone(),
+        });
 
         // Initialize USDC market (borrowable, can be collateral)
-        Self::init_market(&env, USDC, 8_000_000, 8_500_000, true, true); // 80% LTV, 85% liq threshold
+        Self::init_market(&env, USDC, &ReserveConfig {
+            token: usdc_token,
+            decimals: 7,
+            ltv: 8_000_000,                   // 80% LTV
+            liquidation_threshold: 8_500_000, // 85% liq threshold
+            collateral_enabled: true,
+            borrow_enabled: true,
+            interest_rate_model,
+        });
+
+        Ok(())
     }
 
-    /// Internal: Initialize a market for an asset
-    fn init_market(env: &Env, asset: Symbol, ltv: i128, liq_threshold: i128, collateral: bool, borrow: bool) {
-        env.storage().instance().set(&DataKey::LtvRatio(asset.clone()), &ltv);
-        env.storage().instance().set(&DataKey::LiquidationThreshold(asset.clone()), &liq_threshold);
-        env.storage().instance().set(&DataKey::CollateralEnabled(asset.clone()), &collateral);
-        env.storage().instance().set(&DataKey::BorrowEnabled(asset.clone()), &borrow);
+    /// Internal: Initialize a market for an asset and add it to the reserve list
+    fn init_market(env: &Env, asset: Symbol, config: &ReserveConfig) {
+        env.storage().instance().set(&DataKey::TokenAddress(asset.clone()), &config.token);
+        let decimals = token::Client::new(env, &config.token).decimals();
+        env.storage().instance().set(&DataKey::Decimals(asset.clone()), &decimals);
+        env.storage().instance().set(&DataKey::ReserveRateModel(asset.clone()), &config.interest_rate_model);
+        env.storage().instance().set(&DataKey::LtvRatio(asset.clone()), &config.ltv);
+        env.storage().instance().set(&DataKey::LiquidationThreshold(asset.clone()), &config.liquidation_threshold);
+        env.storage().instance().set(&DataKey::CollateralEnabled(asset.clone()), &config.collateral_enabled);
+        env.storage().instance().set(&DataKey::BorrowEnabled(asset.clone()), &config.borrow_enabled);
         env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &0i128);
         env.storage().instance().set(&DataKey::TotalShares(asset.clone()), &0i128);
         env.storage().instance().set(&DataKey::TotalBorrow(asset.clone()), &0i128);
         env.storage().instance().set(&DataKey::ExchangeRate(asset.clone()), &INITIAL_EXCHANGE_RATE);
-        env.storage().instance().set(&DataKey::BorrowIndex(asset.clone()), &INITIAL_EXCHANGE_RATE);
+        env.storage().instance().set(&DataKey::BorrowIndex(asset.clone()), &INITIAL_BORROW_INDEX);
         env.storage().instance().set(&DataKey::LastAccrualTime(asset.clone()), &env.ledger().timestamp());
         env.storage().instance().set(&DataKey::ReserveFactor(asset.clone()), &1_000_000i128); // 10%
         env.storage().instance().set(&DataKey::TotalReserves(asset.clone()), &0i128);
+
+        let mut reserves = Self::list_reserves(env.clone());
+        reserves.push_back(asset);
+        env.storage().instance().set(&DataKey::Reserves, &reserves);
     }
 
     // ========================================================================
@@ -206,59 +832,120 @@ impl LendingPool {
     /// 
     /// Deposits underlying tokens and mints sToken shares representing
     /// the user's claim on the pool (including future interest).
+    /// The tokens are paid by `from` and the shares credited to
+    /// `on_behalf_of`, so wallets and third parties can supply for a user.
     /// 
     /// # Arguments
-    /// * `user` - The depositor's address
+    /// * `from` - Address paying the underlying (must authorize)
+    /// * `on_behalf_of` - Address credited with the shares
     /// * `asset` - Asset symbol (XLM or USDC)
     /// * `amount` - Amount of underlying to deposit
+    /// * `referral_code` - Integrator or wallet credited with the volume, if any
     /// 
     /// # Returns
     /// Amount of sToken shares minted
-    pub fn supply(env: Env, user: Address, asset: Symbol, amount: i128) -> i128 {
-        user.require_auth();
+    pub fn supply(
+        env: Env,
+        from: Address,
+        on_behalf_of: Address,
+        asset: Symbol,
+        amount: i128,
+        referral_code: Option<u32>,
+    ) -> Result<i128, PoolError> {
+        from.require_auth();
+        Self::supply_internal(&env, &from, on_behalf_of, asset, amount, referral_code, false)
+    }
+
+    /// Internal: Supply `amount` paid by `from`, pulled through the pool's
+    /// token allowance when `use_allowance` is set
+    fn supply_internal(
+        env: &Env,
+        from: &Address,
+        on_behalf_of: Address,
+        asset: Symbol,
+        amount: i128,
+        referral_code: Option<u32>,
+        use_allowance: bool,
+    ) -> Result<i128, PoolError> {
+        Self::require_not_paused(env, PoolAction::Supply)?;
         
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
         // Accrue interest before state changes
-        Self::accrue_interest(&env, asset.clone());
+        Self::accrue_interest(env, asset.clone())?;
+
+        // The entry fee comes off the top; shares are minted for the rest
+        let fee = Self::compute_fee(env, from, amount, Self::get_deposit_fee(env.clone(), asset.clone()))?;
+        let net_amount = amount - fee;
 
         // Get current exchange rate
-        let exchange_rate = Self::get_exchange_rate_internal(&env, asset.clone());
+        let exchange_rate = Self::get_exchange_rate_internal(env, asset.clone())?;
         
         // Calculate shares to mint: shares = amount * 1e9 / exchange_rate
-        let shares_to_mint = (amount * INITIAL_EXCHANGE_RATE) / exchange_rate;
+        let shares_to_mint = mul_div(net_amount, INITIAL_EXCHANGE_RATE, exchange_rate, Rounding::Down)?;
         
         if shares_to_mint <= 0 {
-            panic!("Amount too small");
+            return Err(PoolError::AmountTooSmall);
         }
 
-        // Transfer underlying from user to pool
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&user, &env.current_contract_address(), &amount);
+        // Enforce the supply cap
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let supply_cap = Self::get_supply_cap(env.clone(), asset.clone());
+        if supply_cap > 0 && total_supply + net_amount > supply_cap {
+            return Err(PoolError::SupplyCapExceeded);
+        }
 
-        // Update user's share balance
+        // Update beneficiary's share balance
         let current_shares: i128 = env
             .storage()
             .persistent()
-            .get(&DataKey::UserShares(user.clone(), asset.clone()))
+            .get(&DataKey::UserShares(on_behalf_of.clone(), asset.clone()))
             .unwrap_or(0);
         env.storage()
             .persistent()
-            .set(&DataKey::UserShares(user.clone(), asset.clone()), &(current_shares + shares_to_mint));
+            .set(&DataKey::UserShares(on_behalf_of.clone(), asset.clone()), &(current_shares + shares_to_mint));
 
         // Update total supply and shares
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
         let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
-        env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &(total_supply + amount));
+        env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &(total_supply + net_amount));
         env.storage().instance().set(&DataKey::TotalShares(asset.clone()), &(total_shares + shares_to_mint));
 
+        // Transfer underlying from payer to pool
+        Self::pull_underlying(env, &asset, from, amount, use_allowance)?;
+        Self::collect_fee(env, &asset, fee)?;
+
+        if let Some(s_token) = Self::get_s_token(env.clone(), asset.clone()) {
+            STokenHooksClient::new(env, &s_token).mint(&on_behalf_of, &shares_to_mint);
+        }
+        Self::notify_supply_change(env, &asset, &on_behalf_of);
+
+        if asset == USDC {
+            Self::apply_deposit_match(env, &on_behalf_of, net_amount)?;
+        }
+
+        Self::extend_user_ttl(env, &on_behalf_of);
+
+        if let Some(code) = referral_code {
+            Self::record_referral(env, code, PoolAction::Supply, &on_behalf_of, &asset, amount);
+        }
+
         // Emit event
-        env.events().publish((symbol_short!("supply"), user, asset), (amount, shares_to_mint));
+        env.events().publish(
+            (symbol_short!("supply"), on_behalf_of.clone(), asset.clone()),
+            DepositEvent {
+                user: on_behalf_of,
+                asset,
+                amount: net_amount,
+                shares: shares_to_mint,
+                new_balance: current_shares + shares_to_mint,
+                exchange_rate,
+            },
+        );
 
-        shares_to_mint
+        Self::assert_invariants(env);
+        Ok(shares_to_mint)
     }
 
     /// Withdraw assets from the lending pool
@@ -268,19 +955,43 @@ impl LendingPool {
     /// # Arguments
     /// * `user` - The user's address
     /// * `asset` - Asset symbol
-    /// * `share_amount` - Amount of sToken shares to burn
+    /// * `share_amount` - Amount of sToken shares to burn (use i128::MAX to
+    ///   withdraw the whole balance, interest included)
+    /// * `min_out` - Least underlying the shares must redeem for (0 = any)
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
     /// 
     /// # Returns
-    /// Amount of underlying tokens returned
-    pub fn withdraw(env: Env, user: Address, asset: Symbol, share_amount: i128) -> i128 {
+    /// Amount of underlying tokens returned, net of the withdrawal fee
+    pub fn withdraw(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        share_amount: i128,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
         user.require_auth();
+        Self::withdraw_internal(env, user, asset, share_amount, min_out, deadline)
+    }
+
+    /// Internal: Withdraw once the caller has checked `user`'s authorization
+    fn withdraw_internal(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        share_amount: i128,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        Self::require_not_paused(&env, PoolAction::Withdraw)?;
+        Self::require_deadline(&env, deadline)?;
         
         if share_amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
         // Accrue interest before state changes
-        Self::accrue_interest(&env, asset.clone());
+        Self::accrue_interest(&env, asset.clone())?;
 
         // Check user has sufficient shares
         let user_shares: i128 = env
@@ -288,20 +999,26 @@ impl LendingPool {
             .persistent()
             .get(&DataKey::UserShares(user.clone(), asset.clone()))
             .unwrap_or(0);
-        if user_shares < share_amount {
-            panic!("Insufficient share balance");
+        let share_amount = if share_amount == i128::MAX { user_shares } else { share_amount };
+        if share_amount == 0 || user_shares < share_amount {
+            return Err(PoolError::InsufficientShares);
         }
 
         // Calculate underlying to return: underlying = shares * exchange_rate / 1e9
-        let exchange_rate = Self::get_exchange_rate_internal(&env, asset.clone());
-        let underlying_amount = (share_amount * exchange_rate) / INITIAL_EXCHANGE_RATE;
+        let exchange_rate = Self::get_exchange_rate_internal(&env, asset.clone())?;
+        let underlying_amount = mul_div(share_amount, exchange_rate, INITIAL_EXCHANGE_RATE, Rounding::Down)?;
+        let fee = Self::compute_fee(&env, &user, underlying_amount, Self::get_withdraw_fee(env.clone(), asset.clone()))?;
+        let net_amount = underlying_amount - fee;
+        if net_amount < min_out {
+            return Err(PoolError::SlippageExceeded);
+        }
 
         // Check pool has sufficient liquidity
         let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
         let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
         let available_liquidity = total_supply - total_borrow;
         if available_liquidity < underlying_amount {
-            panic!("Insufficient pool liquidity");
+            return Err(PoolError::InsufficientLiquidity);
         }
 
         // Update user's share balance
@@ -314,15 +1031,114 @@ impl LendingPool {
         env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &(total_supply - underlying_amount));
         env.storage().instance().set(&DataKey::TotalShares(asset.clone()), &(total_shares - share_amount));
 
+        if Self::is_using_as_collateral(env.clone(), user.clone(), asset.clone()) {
+            Self::require_healthy_after_withdrawal(&env, &user)?;
+        }
+        // A smaller supply may drop the user out of their discount tier
+        if Self::get_rate_discount(env.clone(), user.clone()) > 0 {
+            Self::refresh_discount(&env, &user)?;
+        }
+
+        if let Some(s_token) = Self::get_s_token(env.clone(), asset.clone()) {
+            STokenHooksClient::new(&env, &s_token).pool_burn(&user, &share_amount);
+        }
+        Self::notify_supply_change(&env, &asset, &user);
+
         // Transfer underlying from pool to user
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &user, &underlying_amount);
+        Self::send_underlying(&env, &asset, &user, net_amount)?;
+        Self::collect_fee(&env, &asset, fee)?;
+
+        Self::extend_user_ttl(&env, &user);
 
         // Emit event
-        env.events().publish((symbol_short!("withdraw"), user, asset), (underlying_amount, share_amount));
+        env.events().publish(
+            (symbol_short!("withdraw"), user.clone(), asset.clone()),
+            DepositEvent {
+                user,
+                asset,
+                amount: net_amount,
+                shares: share_amount,
+                new_balance: user_shares - share_amount,
+                exchange_rate,
+            },
+        );
+
+        Self::assert_invariants(&env);
+        Ok(net_amount)
+    }
+
+    /// Move supplied shares between users on behalf of the asset's sToken
+    ///
+    /// Called by the sToken on `transfer` / `transfer_from`, so sToken
+    /// balances always equal pool shares.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `from` - Current holder of the shares
+    /// * `to` - New holder of the shares
+    /// * `shares` - Amount of sToken shares to move
+    pub fn transfer_shares(env: Env, asset: Symbol, from: Address, to: Address, shares: i128) -> Result<(), PoolError> {
+        let s_token = Self::get_s_token(env.clone(), asset.clone()).ok_or(PoolError::STokenNotSet)?;
+        s_token.require_auth();
+
+        if shares < 0 {
+            return Err(PoolError::InvalidAmount);
+        }
 
-        underlying_amount
+        let from_shares = Self::get_user_shares(env.clone(), from.clone(), asset.clone());
+        if from_shares < shares {
+            return Err(PoolError::InsufficientShares);
+        }
+        let to_shares = Self::get_user_shares(env.clone(), to.clone(), asset.clone());
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserShares(from.clone(), asset.clone()), &(from_shares - shares));
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserShares(to.clone(), asset.clone()), &(to_shares + shares));
+        if Self::is_using_as_collateral(env.clone(), from.clone(), asset.clone()) {
+            Self::accrue_interest(&env, asset.clone())?;
+            Self::require_healthy_after_withdrawal(&env, &from)?;
+        }
+        if Self::get_rate_discount(env.clone(), from.clone()) > 0 {
+            Self::refresh_discount(&env, &from)?;
+        }
+        Self::notify_supply_change(&env, &asset, &from);
+        Self::notify_supply_change(&env, &asset, &to);
+
+        Self::extend_user_ttl(&env, &from);
+        Self::extend_user_ttl(&env, &to);
+
+        Self::assert_invariants(&env);
+        Ok(())
+    }
+
+    /// Internal: Match a new USDC deposit from the match budget
+    ///
+    /// The matched amount is paid into the supply side as interest, raising
+    /// the sToken exchange rate, and is deducted from the remaining budget.
+    fn apply_deposit_match(env: &Env, user: &Address, amount: i128) -> Result<(), PoolError> {
+        let match_pct: i128 = env.storage().instance().get(&DataKey::DepositMatchPct).unwrap_or(0);
+        let budget: i128 = env.storage().instance().get(&DataKey::DepositMatchBudget).unwrap_or(0);
+        if match_pct <= 0 || budget <= 0 {
+            return Ok(());
+        }
+
+        let desired = mul_div(amount, match_pct, SCALE, Rounding::Down)?;
+        let matched = if desired > budget { budget } else { desired };
+        if matched <= 0 {
+            return Ok(());
+        }
+
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(USDC)).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalSupply(USDC), &(total_supply + matched));
+        env.storage().instance().set(&DataKey::DepositMatchBudget, &(budget - matched));
+
+        env.events()
+            .publish((symbol_short!("match"), user.clone()), (amount, matched));
+
+        Ok(())
     }
 
     // ========================================================================
@@ -338,27 +1154,26 @@ impl LendingPool {
     /// * `user` - The user's address
     /// * `asset` - Asset symbol (typically XLM)
     /// * `amount` - Amount to deposit as collateral
-    pub fn deposit_collateral(env: Env, user: Address, asset: Symbol, amount: i128) -> i128 {
+    pub fn deposit_collateral(env: Env, user: Address, asset: Symbol, amount: i128) -> Result<i128, PoolError> {
         user.require_auth();
+        Self::deposit_collateral_internal(env, user, asset, amount)
+    }
+
+    /// Internal: Deposit collateral once the caller has checked `user`'s
+    /// authorization
+    fn deposit_collateral_internal(env: Env, user: Address, asset: Symbol, amount: i128) -> Result<i128, PoolError> {
+        Self::require_not_paused(&env, PoolAction::DepositCollateral)?;
         
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
         // Check asset is enabled as collateral
-        let collateral_enabled: bool = env
-            .storage()
-            .instance()
-            .get(&DataKey::CollateralEnabled(asset.clone()))
-            .unwrap_or(false);
-        if !collateral_enabled {
-            panic!("Asset not enabled as collateral");
+        if !Self::is_collateral_enabled(&env, &asset) {
+            return Err(PoolError::CollateralNotEnabled);
         }
 
-        // Transfer from user to pool
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&user, &env.current_contract_address(), &amount);
+        Self::require_isolation_allows(&env, &user, &asset)?;
 
         // Update user collateral balance
         let current_collateral: i128 = env
@@ -369,11 +1184,26 @@ impl LendingPool {
         env.storage()
             .persistent()
             .set(&DataKey::UserCollateral(user.clone(), asset.clone()), &(current_collateral + amount));
+        Self::close_auction_if_healthy(&env, &user);
+
+        // Transfer from user to pool
+        Self::pull_underlying(&env, &asset, &user, amount, false)?;
+
+        Self::extend_user_ttl(&env, &user);
 
         // Emit event
-        env.events().publish((symbol_short!("coll_dep"), user, asset), amount);
+        env.events().publish(
+            (symbol_short!("coll_dep"), user.clone(), asset.clone()),
+            CollateralEvent {
+                user,
+                asset,
+                amount,
+                new_balance: current_collateral + amount,
+            },
+        );
 
-        amount
+        Self::assert_invariants(&env);
+        Ok(amount)
     }
 
     /// Withdraw collateral
@@ -381,12 +1211,19 @@ impl LendingPool {
     /// # Arguments
     /// * `user` - The user's address
     /// * `asset` - Asset symbol
-    /// * `amount` - Amount to withdraw
-    pub fn withdraw_collateral(env: Env, user: Address, asset: Symbol, amount: i128) -> i128 {
+    /// * `amount` - Amount to withdraw (use i128::MAX to withdraw all)
+    pub fn withdraw_collateral(env: Env, user: Address, asset: Symbol, amount: i128) -> Result<i128, PoolError> {
         user.require_auth();
+        Self::withdraw_collateral_internal(env, user, asset, amount)
+    }
+
+    /// Internal: Withdraw collateral once the caller has checked `user`'s
+    /// authorization
+    fn withdraw_collateral_internal(env: Env, user: Address, asset: Symbol, amount: i128) -> Result<i128, PoolError> {
+        Self::require_not_paused(&env, PoolAction::WithdrawCollateral)?;
         
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
         let current_collateral: i128 = env
@@ -394,38 +1231,131 @@ impl LendingPool {
             .persistent()
             .get(&DataKey::UserCollateral(user.clone(), asset.clone()))
             .unwrap_or(0);
-        if current_collateral < amount {
-            panic!("Insufficient collateral");
+        let amount = if amount == i128::MAX { current_collateral } else { amount };
+        if amount == 0 || current_collateral < amount {
+            return Err(PoolError::InsufficientCollateral);
         }
 
-        // Check that withdrawal doesn't make position unhealthy
+        // Check that withdrawal doesn't make position unhealthy; a failed
+        // check reverts the update
         let new_collateral = current_collateral - amount;
-        
-        // Temporarily update collateral to check health
         env.storage()
             .persistent()
             .set(&DataKey::UserCollateral(user.clone(), asset.clone()), &new_collateral);
-        
-        let position = Self::get_user_position(env.clone(), user.clone());
-        
-        // If user has debt, ensure health factor stays above 1.0
-        if position.debt_value_usd > 0 && position.health_factor < SCALE {
-            // Revert the temporary update
-            env.storage()
-                .persistent()
-                .set(&DataKey::UserCollateral(user.clone(), asset.clone()), &current_collateral);
-            panic!("Withdrawal would make position unhealthy");
-        }
+        Self::require_healthy_after_withdrawal(&env, &user)?;
 
         // Transfer from pool to user
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &user, &amount);
+        Self::send_underlying(&env, &asset, &user, amount)?;
+
+        Self::extend_user_ttl(&env, &user);
 
         // Emit event
-        env.events().publish((symbol_short!("coll_wth"), user, asset), amount);
+        env.events().publish(
+            (symbol_short!("coll_wth"), user.clone(), asset.clone()),
+            CollateralEvent {
+                user,
+                asset,
+                amount,
+                new_balance: new_collateral,
+            },
+        );
+
+        Self::assert_invariants(&env);
+        Ok(amount)
+    }
+
+    /// Use supplied shares of an asset as collateral, or stop doing so
+    ///
+    /// Enabled supplies keep earning the supply rate and count towards
+    /// borrow capacity and the health factor like deposited collateral.
+    /// Withdrawing or transferring them is then checked against health, and
+    /// liquidations seize them once the asset's deposited collateral runs out.
+    ///
+    /// # Arguments
+    /// * `user` - The user's address
+    /// * `asset` - Asset symbol of the supply (e.g., USDC)
+    /// * `enabled` - Whether the supply backs the user's borrows
+    pub fn use_as_collateral(env: Env, user: Address, asset: Symbol, enabled: bool) -> Result<(), PoolError> {
+        user.require_auth();
+        if enabled == Self::is_using_as_collateral(env.clone(), user.clone(), asset.clone()) {
+            return Ok(());
+        }
+
+        let key = UserKey::SupplyCollateral(user.clone(), asset.clone());
+        if enabled {
+            Self::require_not_paused(&env, PoolAction::DepositCollateral)?;
+            if !Self::is_collateral_enabled(&env, &asset) {
+                return Err(PoolError::CollateralNotEnabled);
+            }
+            Self::require_isolation_allows(&env, &user, &asset)?;
+            env.storage().persistent().set(&key, &true);
+        } else {
+            Self::require_not_paused(&env, PoolAction::WithdrawCollateral)?;
+            env.storage().persistent().remove(&key);
+            Self::accrue_interest(&env, asset.clone())?;
+            Self::require_healthy_after_withdrawal(&env, &user)?;
+        }
+
+        Self::extend_user_ttl(&env, &user);
+
+        env.events()
+            .publish((symbol_short!("use_coll"), user, asset), enabled);
+
+        Self::assert_invariants(&env);
+        Ok(())
+    }
+
+    /// Whether a user's supplied shares of an asset count as collateral
+    pub fn is_using_as_collateral(env: Env, user: Address, asset: Symbol) -> bool {
+        env.storage()
+            .persistent()
+            .get(&UserKey::SupplyCollateral(user, asset))
+            .unwrap_or(false)
+    }
+
+    /// Internal: Underlying of a user's supply that backs their borrows (0 if not enabled)
+    fn get_supplied_collateral(env: &Env, user: &Address, asset: &Symbol) -> Result<i128, PoolError> {
+        if !Self::is_using_as_collateral(env.clone(), user.clone(), asset.clone()) {
+            return Ok(0);
+        }
+        Self::get_user_deposit(env.clone(), user.clone(), asset.clone())
+    }
+
+    /// Internal: Whether a user holds collateral in an asset, deposited or supplied
+    fn uses_as_collateral(env: &Env, user: &Address, asset: &Symbol) -> bool {
+        Self::get_user_collateral(env.clone(), user.clone(), asset.clone()) > 0
+            || (Self::is_using_as_collateral(env.clone(), user.clone(), asset.clone())
+                && Self::get_user_shares(env.clone(), user.clone(), asset.clone()) > 0)
+    }
+
+    /// Internal: Isolated collateral must be the user's only collateral
+    fn require_isolation_allows(env: &Env, user: &Address, asset: &Symbol) -> Result<(), PoolError> {
+        for other in Self::list_reserves(env.clone()).iter() {
+            if other == *asset || !Self::uses_as_collateral(env, user, &other) {
+                continue;
+            }
+            if Self::is_isolated(env.clone(), asset.clone()) || Self::is_isolated(env.clone(), other) {
+                return Err(PoolError::IsolationModeViolation);
+            }
+        }
+        Ok(())
+    }
 
-        amount
+    /// Internal: Check a position after collateral has left it
+    ///
+    /// Debt-free users need no prices, so they can always exit; in emergency
+    /// mode prices cannot be trusted to check anyone else.
+    fn require_healthy_after_withdrawal(env: &Env, user: &Address) -> Result<(), PoolError> {
+        if !Self::has_debt(env, user)? {
+            return Ok(());
+        }
+        if Self::is_emergency_mode(env.clone()) {
+            return Err(PoolError::ActionPaused);
+        }
+        if Self::account_data(env, user)?.health_factor < SCALE {
+            return Err(PoolError::PositionUnhealthy);
+        }
+        Ok(())
     }
 
     // ========================================================================
@@ -441,840 +1371,4750 @@ impl LendingPool {
     /// * `user` - The borrower's address
     /// * `asset` - Asset symbol to borrow (typically USDC)
     /// * `amount` - Amount to borrow
-    pub fn borrow(env: Env, user: Address, asset: Symbol, amount: i128) -> i128 {
+    /// * `referral_code` - Integrator or wallet credited with the volume, if any
+    pub fn borrow(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        amount: i128,
+        referral_code: Option<u32>,
+    ) -> Result<i128, PoolError> {
         user.require_auth();
-        
-        if amount <= 0 {
-            panic!("Amount must be positive");
+
+        let borrowed = Self::borrow_internal(&env, &user, &user, asset.clone(), amount, RateMode::Variable)?;
+        if let Some(code) = referral_code {
+            Self::record_referral(&env, code, PoolAction::Borrow, &user, &asset, borrowed);
         }
 
-        // Check asset is enabled for borrowing
-        let borrow_enabled: bool = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowEnabled(asset.clone()))
-            .unwrap_or(false);
-        if !borrow_enabled {
-            panic!("Asset not enabled for borrowing");
+        Ok(borrowed)
+    }
+
+    /// Internal: Add referred volume to a code and emit a `referral` event
+    fn record_referral(env: &Env, code: u32, action: PoolAction, user: &Address, asset: &Symbol, amount: i128) {
+        let key = ProtocolKey::Referral(code, asset.clone());
+        let mut volume = Self::get_referral_volume(env.clone(), code, asset.clone());
+        match action {
+            PoolAction::Borrow => volume.borrowed += amount,
+            _ => volume.supplied += amount,
         }
+        env.storage().persistent().set(&key, &volume);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
 
-        // Accrue interest before state changes
-        Self::accrue_interest(&env, asset.clone());
+        env.events().publish((symbol_short!("referral"), code, action), (user.clone(), asset.clone(), amount));
+    }
 
-        // Check pool has sufficient liquidity
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+    /// Get the cumulative volume referred by a code for an asset
+    pub fn get_referral_volume(env: Env, code: u32, asset: Symbol) -> ReferralVolume {
+        env.storage()
+            .persistent()
+            .get(&ProtocolKey::Referral(code, asset))
+            .unwrap_or_default()
+    }
+
+    /// Borrow assets at a stable rate
+    ///
+    /// Same checks as `borrow`, but the debt is kept in a separate bucket
+    /// whose rate is locked at origination from the rate model's stable rate.
+    /// Additional stable borrows blend into the existing locked rate.
+    ///
+    /// # Arguments
+    /// * `user` - The borrower's address
+    /// * `asset` - Asset symbol to borrow (must have stable borrowing enabled)
+    /// * `amount` - Amount to borrow
+    pub fn borrow_stable(env: Env, user: Address, asset: Symbol, amount: i128) -> Result<i128, PoolError> {
+        user.require_auth();
+
+        Self::borrow_internal(&env, &user, &user, asset, amount, RateMode::Stable)
+    }
+
+    /// Approve `delegatee` to borrow `asset` against the delegator's collateral
+    ///
+    /// The debt stays with the delegator; the borrowed funds go to the
+    /// delegatee. Overwrites any previous allowance (0 revokes it).
+    ///
+    /// # Arguments
+    /// * `delegator` - Collateral owner who will carry the debt
+    /// * `delegatee` - Address allowed to borrow
+    /// * `asset` - Asset symbol the allowance applies to
+    /// * `amount` - Maximum amount the delegatee may borrow
+    pub fn approve_delegation(
+        env: Env,
+        delegator: Address,
+        delegatee: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<(), PoolError> {
+        delegator.require_auth();
+
+        if amount < 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        env.storage().persistent().set(
+            &DataKey::BorrowAllowance(delegator.clone(), delegatee.clone(), asset.clone()),
+            &amount,
+        );
+
+        env.events()
+            .publish((symbol_short!("delegate"), delegator, delegatee), (asset, amount));
+
+        Ok(())
+    }
+
+    /// Get the remaining amount `delegatee` may borrow against `delegator`'s collateral
+    pub fn get_borrow_allowance(env: Env, delegator: Address, delegatee: Address, asset: Symbol) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::BorrowAllowance(delegator, delegatee, asset))
+            .unwrap_or(0)
+    }
+
+    /// Borrow against another user's collateral using a delegated allowance
+    ///
+    /// The debt is recorded on `delegator` and must fit within their LTV;
+    /// the borrowed funds are sent to `delegatee`.
+    ///
+    /// # Arguments
+    /// * `delegatee` - Address borrowing (must authorize)
+    /// * `delegator` - Collateral owner carrying the debt
+    /// * `asset` - Asset symbol to borrow
+    /// * `amount` - Amount to borrow
+    pub fn borrow_delegated(
+        env: Env,
+        delegatee: Address,
+        delegator: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<i128, PoolError> {
+        delegatee.require_auth();
+
+        let allowance = Self::get_borrow_allowance(env.clone(), delegator.clone(), delegatee.clone(), asset.clone());
+        if amount > allowance {
+            return Err(PoolError::InsufficientAllowance);
+        }
+        env.storage().persistent().set(
+            &DataKey::BorrowAllowance(delegator.clone(), delegatee.clone(), asset.clone()),
+            &(allowance - amount),
+        );
+
+        Self::borrow_internal(&env, &delegator, &delegatee, asset, amount, RateMode::Variable)
+    }
+
+    /// Internal: Borrow against `borrower`'s collateral and send the funds to `recipient`
+    fn borrow_internal(
+        env: &Env,
+        borrower: &Address,
+        recipient: &Address,
+        asset: Symbol,
+        amount: i128,
+        mode: RateMode,
+    ) -> Result<i128, PoolError> {
+        Self::require_not_paused(env, PoolAction::Borrow)?;
+        if Self::is_emergency_mode(env.clone()) {
+            return Err(PoolError::ActionPaused);
+        }
+        
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        if mode == RateMode::Stable && !Self::is_stable_borrow_enabled(env.clone(), asset.clone()) {
+            return Err(PoolError::StableBorrowNotEnabled);
+        }
+
+        // Check asset is enabled for borrowing
+        let borrow_enabled: bool = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowEnabled(asset.clone()))
+            .unwrap_or(false);
+        if !borrow_enabled {
+            return Err(PoolError::BorrowNotEnabled);
+        }
+
+        // Borrows of a stablecoin are frozen while the oracle reports it depegged
+        let oracle_client = oracle_contract::Client::new(env, &Self::get_oracle(env)?);
+        if oracle_client.is_depegged(&Self::get_token_address(env, &asset)?) {
+            return Err(PoolError::ActionPaused);
+        }
+
+        // Check the system-wide debt limit circuit breaker
+        if Self::is_global_borrow_paused(env.clone()) {
+            return Err(PoolError::GlobalBorrowLimitReached);
+        }
+
+        // Accrue interest before state changes
+        Self::accrue_interest_for(env, &asset, borrower)?;
+
+        // Check pool has sufficient liquidity
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
         let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
         let available_liquidity = total_supply - total_borrow;
         if available_liquidity < amount {
-            panic!("Insufficient pool liquidity");
+            return Err(PoolError::InsufficientLiquidity);
+        }
+
+        // Origination fee is added to the debt and credited to protocol reserves
+        let fee_rate: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::OriginationFee(asset.clone()))
+            .unwrap_or(0);
+        let origination_fee = Self::compute_fee(env, borrower, amount, fee_rate)?;
+        let new_debt_amount = amount + origination_fee;
+
+        // Enforce the borrow cap
+        let borrow_cap = Self::get_borrow_cap(env.clone(), asset.clone());
+        if borrow_cap > 0 && total_borrow + new_debt_amount > borrow_cap {
+            return Err(PoolError::BorrowCapExceeded);
+        }
+
+        // Positions too small to be worth liquidating cannot be opened
+        let current_debt = Self::get_user_total_debt(env, borrower, &asset)?;
+        if current_debt + new_debt_amount < Self::get_min_borrow(env.clone(), asset.clone()) {
+            return Err(PoolError::AmountTooSmall);
         }
 
-        // Get current user position
-        let position = Self::get_user_position(env.clone(), user.clone());
+        // Value the borrower's account at current prices
+        let mut prices = PriceCache::new(env)?;
+        let account = Self::account_data_with(env, borrower, &mut prices)?;
 
-        // Get borrow amount in USD
-        let oracle: Address = env.storage().instance().get(&DataKey::PriceOracle).unwrap();
-        let asset_price = Self::get_asset_price(&env, &oracle, &asset);
-        let borrow_value_usd = (amount * asset_price) / SCALE;
+        // Get new debt (borrow + fee) in USD
+        let asset_price = prices.spot(env, &asset)?;
+        let borrow_value_usd = mul_div(new_debt_amount, asset_price, SCALE, Rounding::Up)?;
 
         // Check LTV constraint: new_total_debt <= collateral * LTV
-        let new_total_debt_usd = position.debt_value_usd + borrow_value_usd;
-        if new_total_debt_usd > position.available_borrow_usd + position.debt_value_usd {
-            panic!("Borrow exceeds LTV limit");
+        let new_total_debt_usd = account.debt_value_usd + borrow_value_usd;
+        if new_total_debt_usd > account.borrow_capacity_usd {
+            return Err(PoolError::ExceedsLtv);
         }
 
-        // Update user's debt balance
-        let current_debt: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserDebt(user.clone(), asset.clone()))
-            .unwrap_or(0);
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserDebt(user.clone(), asset.clone()), &(current_debt + amount));
+        // Debt backed by isolated collateral is limited by its debt ceiling
+        if let Some(isolated_asset) = Self::get_isolated_collateral(env, borrower) {
+            let isolated_debt = Self::get_isolated_debt(env.clone(), isolated_asset.clone());
+            let ceiling = Self::get_debt_ceiling(env.clone(), isolated_asset.clone());
+            if isolated_debt + borrow_value_usd > ceiling {
+                return Err(PoolError::DebtCeilingExceeded);
+            }
+            env.storage()
+                .instance()
+                .set(&ReserveKey::IsolatedDebt(isolated_asset), &(isolated_debt + borrow_value_usd));
 
-        // Store user's borrow index for interest calculation
-        let borrow_index: i128 = env.storage().instance().get(&DataKey::BorrowIndex(asset.clone())).unwrap();
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserBorrowIndex(user.clone(), asset.clone()), &borrow_index);
+            // Remember the charge so repayments release it without a price
+            let key = UserKey::IsolatedDebt(borrower.clone(), asset.clone());
+            let charged: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &(charged + borrow_value_usd));
+        }
+
+        // Update borrower's debt balance (accrued interest is folded into principal
+        // and the borrow index snapshot is moved to the current index)
+        match mode {
+            RateMode::Variable => {
+                let variable_debt = Self::get_user_debt_with_interest(env, borrower.clone(), asset.clone())?;
+                Self::set_user_debt(env, borrower, &asset, variable_debt + new_debt_amount);
+            }
+            RateMode::Stable => {
+                // Lock the stable rate at the utilization after this borrow and
+                // blend it with the rate of any existing stable debt
+                let utilization = mul_div(total_borrow + new_debt_amount, SCALE, total_supply, Rounding::Up)?;
+                let offered_rate = Self::calculate_stable_rate(env, &asset, utilization)?;
+                let stable_debt = Self::get_user_stable_debt_with_interest(env, borrower, &asset)?;
+                let current_rate = Self::get_user_stable_rate(env.clone(), borrower.clone(), asset.clone());
+                let new_stable_debt = stable_debt + new_debt_amount;
+                let new_rate = Self::blend_stable_rate(stable_debt, current_rate, new_debt_amount, offered_rate)?;
+                Self::set_user_stable_debt(env, borrower, &asset, new_stable_debt, new_rate)?;
+            }
+        }
 
         // Update total borrow
-        env.storage().instance().set(&DataKey::TotalBorrow(asset.clone()), &(total_borrow + amount));
+        env.storage().instance().set(&DataKey::TotalBorrow(asset.clone()), &(total_borrow + new_debt_amount));
+
+        if origination_fee > 0 {
+            let current_reserves: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TotalReserves(asset.clone()))
+                .unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalReserves(asset.clone()), &(current_reserves + origination_fee));
+        }
 
-        // Transfer underlying from pool to user
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&env.current_contract_address(), &user, &amount);
+        Self::update_global_borrow_pause_with(env, &mut prices)?;
+
+        Self::extend_user_ttl(env, borrower);
+
+        // Interactions last: the emissions hook, then the funds. Funds kept
+        // as collateral by `leverage` never leave the pool
+        Self::notify_borrow_change(env, &asset, borrower)?;
+        if *recipient != env.current_contract_address() {
+            Self::send_underlying(env, &asset, recipient, amount)?;
+        }
 
         // Emit event
-        env.events().publish((symbol_short!("borrow"), user, asset), amount);
+        env.events().publish(
+            (symbol_short!("borrow"), borrower.clone(), asset.clone()),
+            BorrowEvent {
+                user: borrower.clone(),
+                counterparty: recipient.clone(),
+                asset: asset.clone(),
+                amount,
+                new_debt: current_debt + new_debt_amount,
+                borrow_index: Self::get_borrow_index(env.clone(), asset),
+            },
+        );
 
-        amount
+        Self::assert_invariants(env);
+        Ok(amount)
     }
 
     /// Repay borrowed assets
     /// 
-    /// Reduces user's debt balance and pool's total borrows.
+    /// Reduces user's debt balance and pool's total borrows. The repayment
+    /// is paid by `from`, so liquidation bots, smart wallets or anyone else
+    /// can repay a borrower's debt. Variable-rate debt is repaid before
+    /// stable-rate debt.
     /// 
     /// # Arguments
-    /// * `user` - The borrower's address
+    /// * `from` - Address paying the repayment (must authorize)
+    /// * `on_behalf_of` - Borrower whose debt is reduced
     /// * `asset` - Asset symbol
     /// * `amount` - Amount to repay (use i128::MAX to repay all)
     /// 
     /// # Returns
     /// Actual amount repaid
-    pub fn repay(env: Env, user: Address, asset: Symbol, amount: i128) -> i128 {
-        user.require_auth();
+    pub fn repay(
+        env: Env,
+        from: Address,
+        on_behalf_of: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<i128, PoolError> {
+        from.require_auth();
+        Self::repay_internal(&env, &from, on_behalf_of, asset, amount, false)
+    }
+
+    /// Internal: Repay up to `amount` paid by `from`, pulled through the
+    /// pool's token allowance when `use_allowance` is set
+    fn repay_internal(
+        env: &Env,
+        from: &Address,
+        on_behalf_of: Address,
+        asset: Symbol,
+        amount: i128,
+        use_allowance: bool,
+    ) -> Result<i128, PoolError> {
+        Self::require_not_paused(env, PoolAction::Repay)?;
         
         if amount <= 0 {
-            panic!("Amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
         // Accrue interest before state changes
-        Self::accrue_interest(&env, asset.clone());
+        Self::accrue_interest_for(env, &asset, &on_behalf_of)?;
 
-        // Get user's current debt (including accrued interest)
-        let user_debt = Self::get_user_debt_with_interest(&env, user.clone(), asset.clone());
+        // Get borrower's current debt across both rate modes (including accrued interest)
+        let user_debt = Self::get_user_total_debt(env, &on_behalf_of, &asset)?;
         
         if user_debt == 0 {
-            panic!("No outstanding debt");
+            return Err(PoolError::NoDebt);
         }
 
-        // Cap repayment at outstanding debt
-        let repay_amount = if amount > user_debt { user_debt } else { amount };
-
-        // Transfer underlying from user to pool
-        let token_address: Address = env.storage().instance().get(&DataKey::TokenAddress(asset.clone())).unwrap();
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(&user, &env.current_contract_address(), &repay_amount);
+        // Cap repayment at outstanding debt, and close the position instead of
+        // leaving dust behind
+        let mut repay_amount = if amount > user_debt { user_debt } else { amount };
+        if user_debt - repay_amount < Self::get_debt_dust(env.clone(), asset.clone()) {
+            repay_amount = user_debt;
+        }
 
-        // Update user's debt balance
-        let current_debt: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserDebt(user.clone(), asset.clone()))
-            .unwrap_or(0);
-        let new_debt = if repay_amount >= user_debt { 0 } else { current_debt - repay_amount };
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserDebt(user.clone(), asset.clone()), &new_debt);
+        // Update borrower's debt balance (variable debt is repaid first)
+        Self::reduce_user_debt(env, &on_behalf_of, &asset, repay_amount)?;
 
         // Update total borrow
         let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
         let new_total_borrow = if total_borrow > repay_amount { total_borrow - repay_amount } else { 0 };
         env.storage().instance().set(&DataKey::TotalBorrow(asset.clone()), &new_total_borrow);
 
+        Self::update_global_borrow_pause(env)?;
+        Self::close_auction_if_healthy(env, &on_behalf_of);
+
+        Self::extend_user_ttl(env, &on_behalf_of);
+
+        // Transfer underlying from payer to pool
+        Self::pull_underlying(env, &asset, from, repay_amount, use_allowance)?;
+
         // Emit event
-        env.events().publish((symbol_short!("repay"), user, asset), repay_amount);
+        env.events().publish(
+            (symbol_short!("repay"), on_behalf_of.clone(), asset.clone()),
+            BorrowEvent {
+                user: on_behalf_of,
+                counterparty: from.clone(),
+                asset: asset.clone(),
+                amount: repay_amount,
+                new_debt: user_debt - repay_amount,
+                borrow_index: Self::get_borrow_index(env.clone(), asset),
+            },
+        );
 
-        repay_amount
+        Self::assert_invariants(env);
+        Ok(repay_amount)
     }
 
     // ========================================================================
-    // INTEREST ACCRUAL
+    // MULTICALL (Several position changes under one signature)
     // ========================================================================
 
-    /// Accrue interest for an asset market
-    /// 
-    /// This function is called before any state-changing operation to ensure
-    /// interest is properly accrued. It:
-    /// 
-    /// 1. Calculates time elapsed since last accrual
-    /// 2. Gets the borrow rate from the Interest Rate Model based on utilization
-    /// 3. Updates the borrow index (used to track user debt with interest)
-    /// 4. Distributes interest between suppliers and reserves
-    /// 
-    /// ## Interest Model Integration
-    /// 
-    /// The borrow rate is determined by the external Interest Rate Model contract:
-    /// - Uses a kinked rate model based on pool utilization
-    /// - Base rate: 0%, Slope1: 4%, Slope2: 75%, Optimal: 80%
-    /// - For MVP, we use an internal fallback that mimics the external model
-    fn accrue_interest(env: &Env, asset: Symbol) {
-        // Get timestamps
-        let last_accrual: u64 = env
-            .storage()
-            .instance()
-            .get(&DataKey::LastAccrualTime(asset.clone()))
-            .unwrap_or(0);
-        let current_time = env.ledger().timestamp();
-        
-        // Skip if no time has passed
-        if current_time <= last_accrual {
-            return;
-        }
+    /// Run `ops` in order on `user`'s position, atomically
+    ///
+    /// `user` authorizes the whole batch once, so a wallet can e.g. deposit
+    /// collateral and borrow against it with a single signature. Each step
+    /// runs with the same checks as the matching entrypoint; if any fails the
+    /// whole batch reverts. Health is checked per step, so a borrow must be
+    /// covered by collateral deposited earlier in the batch.
+    ///
+    /// # Returns
+    /// What each operation's entrypoint returns, in order
+    pub fn multicall(env: Env, user: Address, ops: Vec<Operation>) -> Result<Vec<i128>, PoolError> {
+        user.require_auth();
 
-        let time_elapsed = current_time - last_accrual;
-        
-        // Get current pool state
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
-        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
-        
-        // Skip if nothing to accrue on
-        if total_borrow == 0 || total_supply == 0 {
-            env.storage().instance().set(&DataKey::LastAccrualTime(asset.clone()), &current_time);
-            return;
+        let mut results = Vec::new(&env);
+        for op in ops.iter() {
+            let result = match op {
+                Operation::Supply(asset, amount) => {
+                    Self::supply_internal(&env, &user, user.clone(), asset, amount, None, false)?
+                }
+                Operation::Withdraw(asset, share_amount) => {
+                    Self::withdraw_internal(env.clone(), user.clone(), asset, share_amount, 0, None)?
+                }
+                Operation::DepositCollateral(asset, amount) => {
+                    Self::deposit_collateral_internal(env.clone(), user.clone(), asset, amount)?
+                }
+                Operation::WithdrawCollateral(asset, amount) => {
+                    Self::withdraw_collateral_internal(env.clone(), user.clone(), asset, amount)?
+                }
+                Operation::Borrow(asset, amount) => {
+                    Self::borrow_internal(&env, &user, &user, asset, amount, RateMode::Variable)?
+                }
+                Operation::Repay(asset, amount) => {
+                    Self::repay_internal(&env, &user, user.clone(), asset, amount, false)?
+                }
+            };
+            results.push_back(result);
         }
+        Ok(results)
+    }
 
-        // ====================================================================
-        // STEP 1: Calculate utilization rate
-        // ====================================================================
-        // Utilization = Total Borrowed / Total Supplied
-        // Scaled by SCALE (1e7), so 80% = 8_000_000
-        let utilization = (total_borrow * SCALE) / total_supply;
+    // ========================================================================
+    // POSITION MIGRATION (Moving a position to a new address)
+    // ========================================================================
 
-        // ====================================================================
-        // STEP 2: Get borrow rate from Interest Rate Model
-        // ====================================================================
-        // For MVP, we use an internal implementation that matches the kinked model:
-        // - Base rate: 0%
-        // - Below 80% utilization: rate = (utilization / 80%) * 4%
-        // - Above 80%: rate = 4% + ((utilization - 80%) / 20%) * 75%
-        let annual_borrow_rate = Self::calculate_borrow_rate(utilization);
-        
-        // Convert annual rate to rate for elapsed time
-        // interest_factor = annual_rate * time_elapsed / seconds_per_year
-        let seconds_per_year: i128 = 31_557_600; // 365.25 days
-        let interest_factor = (annual_borrow_rate * time_elapsed as i128) / seconds_per_year;
+    /// Move `from`'s whole position to `to`
+    ///
+    /// Supplied shares, collateral, variable and stable debt move as they
+    /// are, so a user rotating wallets or moving into a smart wallet does not
+    /// have to repay and re-borrow. Debt keeps its accrual snapshot and any
+    /// liquidation auction keeps running. Staked discount tokens, session
+    /// grants and borrow delegations stay with `from`.
+    ///
+    /// # Arguments
+    /// * `from` - Current owner of the position
+    /// * `to` - New owner; must not hold a position in any reserve
+    pub fn migrate_position(env: Env, from: Address, to: Address) -> Result<(), PoolError> {
+        from.require_auth();
+        to.require_auth();
+        Self::require_not_paused(&env, PoolAction::WithdrawCollateral)?;
+
+        if from == to {
+            return Err(PoolError::InvalidAmount);
+        }
+        let reserves = Self::list_reserves(env.clone());
+        let to_has_position = reserves.iter().any(|asset| {
+            Self::get_user_shares(env.clone(), to.clone(), asset.clone()) > 0
+                || Self::get_user_collateral(env.clone(), to.clone(), asset.clone()) > 0
+                || env.storage().persistent().has(&DataKey::UserDebt(to.clone(), asset.clone()))
+                || env.storage().persistent().has(&DataKey::UserStableDebt(to.clone(), asset))
+        });
+        if to_has_position {
+            return Err(PoolError::AlreadyInitialized);
+        }
 
-        // ====================================================================
-        // STEP 3: Update borrow index
-        // ====================================================================
-        // The borrow index tracks accumulated interest over time
-        // User debt = principal * current_index / user_index_at_borrow
-        let current_borrow_index: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowIndex(asset.clone()))
-            .unwrap_or(INITIAL_EXCHANGE_RATE);
-        
-        // new_index = current_index * (1 + interest_factor)
-        let new_borrow_index = current_borrow_index + (current_borrow_index * interest_factor) / SCALE;
-        env.storage().instance().set(&DataKey::BorrowIndex(asset.clone()), &new_borrow_index);
+        let mut has_debt = false;
+        for asset in reserves.iter() {
+            // Settle `from`'s rate discount so the debt moves without it
+            Self::accrue_interest_for(&env, &asset, &from)?;
+
+            let shares = Self::get_user_shares(env.clone(), from.clone(), asset.clone());
+            Self::move_user_entry(
+                &env,
+                DataKey::UserShares(from.clone(), asset.clone()),
+                DataKey::UserShares(to.clone(), asset.clone()),
+            );
+            Self::move_user_entry(
+                &env,
+                UserKey::SupplyCollateral(from.clone(), asset.clone()),
+                UserKey::SupplyCollateral(to.clone(), asset.clone()),
+            );
+            Self::move_user_entry(
+                &env,
+                DataKey::UserCollateral(from.clone(), asset.clone()),
+                DataKey::UserCollateral(to.clone(), asset.clone()),
+            );
+            let variable = Self::move_user_entry(
+                &env,
+                DataKey::UserDebt(from.clone(), asset.clone()),
+                DataKey::UserDebt(to.clone(), asset.clone()),
+            );
+            Self::move_user_entry(
+                &env,
+                DataKey::UserBorrowIndex(from.clone(), asset.clone()),
+                DataKey::UserBorrowIndex(to.clone(), asset.clone()),
+            );
+            let stable = Self::move_user_entry(
+                &env,
+                DataKey::UserStableDebt(from.clone(), asset.clone()),
+                DataKey::UserStableDebt(to.clone(), asset.clone()),
+            );
+            Self::move_user_entry(
+                &env,
+                UserKey::IsolatedDebt(from.clone(), asset.clone()),
+                UserKey::IsolatedDebt(to.clone(), asset.clone()),
+            );
+            has_debt |= variable || stable;
+
+            if shares > 0 {
+                if let Some(s_token) = Self::get_s_token(env.clone(), asset.clone()) {
+                    let s_token = STokenHooksClient::new(&env, &s_token);
+                    s_token.pool_burn(&from, &shares);
+                    s_token.mint(&to, &shares);
+                }
+                Self::notify_supply_change(&env, &asset, &from);
+                Self::notify_supply_change(&env, &asset, &to);
+            }
+            if variable || stable {
+                Self::notify_borrow_change(&env, &asset, &from)?;
+                Self::notify_borrow_change(&env, &asset, &to)?;
+            }
+        }
+        Self::move_user_entry(&env, DataKey::AuctionStart(from.clone()), DataKey::AuctionStart(to.clone()));
 
-        // ====================================================================
-        // STEP 4: Calculate and distribute interest
-        // ====================================================================
-        // Total interest accrued on all borrows
-        let interest_accrued = (total_borrow * interest_factor) / SCALE;
+        Self::update_borrower_registry(&env, &from, false);
+        Self::update_borrower_registry(&env, &to, has_debt);
+        Self::refresh_discount(&env, &from)?;
+        Self::refresh_discount(&env, &to)?;
 
-        // Split between suppliers and protocol reserves
-        let reserve_factor: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::ReserveFactor(asset.clone()))
-            .unwrap_or(1_000_000); // Default 10%
-        
-        let reserve_interest = (interest_accrued * reserve_factor) / SCALE;
-        let supplier_interest = interest_accrued - reserve_interest;
+        Self::extend_user_ttl(&env, &to);
 
-        // Increase total supply by supplier's portion (this grows sToken value)
-        env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &(total_supply + supplier_interest));
-        
-        // Increase protocol reserves
-        let current_reserves: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalReserves(asset.clone()))
-            .unwrap_or(0);
-        env.storage().instance().set(&DataKey::TotalReserves(asset.clone()), &(current_reserves + reserve_interest));
+        env.events().publish((symbol_short!("migrate"), from), to);
 
-        // Update last accrual timestamp
-        env.storage().instance().set(&DataKey::LastAccrualTime(asset.clone()), &current_time);
+        Self::assert_invariants(&env);
+        Ok(())
     }
 
-    /// Calculate the borrow rate based on utilization
-    /// 
-    /// This implements the kinked interest rate model:
-    /// - Below optimal (80%): rate increases linearly with slope1 (4%)
-    /// - Above optimal: rate increases steeply with slope2 (75%)
-    /// 
-    /// ## Parameters (matching Interest Rate Model contract)
-    /// - Base rate: 0%
-    /// - Slope1: 4% (400_000 scaled)
-    /// - Slope2: 75% (7_500_000 scaled)
-    /// - Optimal utilization: 80% (8_000_000 scaled)
-    /// 
-    /// ## Example Rates
-    /// - 0% utilization → 0% APR
-    /// - 40% utilization → 2% APR
-    /// - 80% utilization → 4% APR
-    /// - 90% utilization → 41.5% APR
-    /// - 100% utilization → 79% APR
-    /// Calculate the borrow rate using Drift Protocol's multi-kink model
-    /// 
-    /// ## Rate Curve Zones (from https://docs.drift.trade/lend-borrow/borrow-interest-rate)
-    /// 
-    /// | Utilization | Behavior | Cumulative ΔR |
-    /// |-------------|----------|---------------|
-    /// | 0% - U* | Linear to R_opt | 0% |
-    /// | U* - 85% | +5% of ΔR | 5% |
-    /// | 85% - 90% | +10% of ΔR | 15% |
-    /// | 90% - 95% | +15% of ΔR | 30% |
-    /// | 95% - 99% | +20% of ΔR | 50% |
-    /// | 99% - 100% | +50% of ΔR | 100% |
-    /// 
-    /// Where: ΔR = R_max - R_opt
-    fn calculate_borrow_rate(utilization: i128) -> i128 {
-        // Model parameters (matching InterestRateModel contract)
-        let rate_min: i128 = 0;            // 0% minimum
-        let rate_opt: i128 = 400_000;      // 4% at optimal
-        let rate_max: i128 = 10_000_000;   // 100% maximum
-        let u_optimal: i128 = 8_000_000;   // 80% optimal utilization
-
-        // Utilization thresholds
-        let u_85: i128 = 8_500_000;
-        let u_90: i128 = 9_000_000;
-        let u_95: i128 = 9_500_000;
-        let u_99: i128 = 9_900_000;
-
-        // ΔR = max - optimal
-        let delta_r = rate_max - rate_opt;
-
-        let raw_rate = if utilization <= u_optimal {
-            // Zone 1: Linear ramp from 0 to R_opt
-            (rate_opt * utilization) / u_optimal
-            
-        } else if utilization <= u_85 {
-            // Zone 2: U* to 85% - adds 5% of ΔR
-            let range = u_85 - u_optimal;
-            let progress = utilization - u_optimal;
-            let penalty = (delta_r * 50 * progress) / (range * 1000);
-            rate_opt + penalty
-            
-        } else if utilization <= u_90 {
-            // Zone 3: 85% to 90% - adds 10% of ΔR
-            let base_penalty = (delta_r * 50) / 1000;
-            let range = u_90 - u_85;
-            let progress = utilization - u_85;
-            let extra_penalty = (delta_r * 100 * progress) / (range * 1000);
-            rate_opt + base_penalty + extra_penalty
-            
-        } else if utilization <= u_95 {
-            // Zone 4: 90% to 95% - adds 15% of ΔR
-            let base_penalty = (delta_r * 150) / 1000;
-            let range = u_95 - u_90;
-            let progress = utilization - u_90;
-            let extra_penalty = (delta_r * 150 * progress) / (range * 1000);
-            rate_opt + base_penalty + extra_penalty
-            
-        } else if utilization <= u_99 {
-            // Zone 5: 95% to 99% - adds 20% of ΔR
-            let base_penalty = (delta_r * 300) / 1000;
-            let range = u_99 - u_95;
-            let progress = utilization - u_95;
-            let extra_penalty = (delta_r * 200 * progress) / (range * 1000);
-            rate_opt + base_penalty + extra_penalty
-            
-        } else {
-            // Zone 6: 99% to 100% - adds 50% of ΔR
-            let base_penalty = (delta_r * 500) / 1000;
-            let range = SCALE - u_99;
-            let progress = if utilization >= SCALE { range } else { utilization - u_99 };
-            let extra_penalty = (delta_r * 500 * progress) / (range * 1000);
-            rate_opt + base_penalty + extra_penalty
+    /// Internal: Move a persistent entry to another key
+    ///
+    /// # Returns
+    /// Whether the entry existed
+    fn move_user_entry<K: IntoVal<Env, Val>>(env: &Env, from_key: K, to_key: K) -> bool {
+        let persistent = env.storage().persistent();
+        let Some(value) = persistent.get::<_, Val>(&from_key) else {
+            return false;
         };
-
-        // Apply minimum rate floor
-        if raw_rate < rate_min { rate_min } else { raw_rate }
+        persistent.set(&to_key, &value);
+        persistent.remove(&from_key);
+        true
     }
 
     // ========================================================================
-    // INTERNAL HELPERS
+    // SESSION KEYS (Pre-authorized supply and repay for smart wallets)
     // ========================================================================
 
-    /// Get exchange rate for sTokens
-    fn get_exchange_rate_internal(env: &Env, asset: Symbol) -> i128 {
-        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
-        
-        if total_shares == 0 {
-            return INITIAL_EXCHANGE_RATE;
-        }
+    /// Let `session` supply and repay `asset` from the user's wallet
+    ///
+    /// A passkey wallet can hand a short-lived session key to the frontend
+    /// instead of prompting for every action. The key signs only the
+    /// arguments of each call, and the funds are pulled through the
+    /// allowance the user gives the pool on the token, so the user must also
+    /// `approve` the pool there. Overwrites any previous grant (0 revokes it).
+    ///
+    /// # Arguments
+    /// * `user` - Wallet the funds come from (must authorize)
+    /// * `session` - Key or contract allowed to act for the user
+    /// * `asset` - Asset symbol the grant applies to
+    /// * `limit` - Maximum underlying the session may move in total
+    /// * `expires_at` - Ledger timestamp after which the grant lapses
+    pub fn approve_session(
+        env: Env,
+        user: Address,
+        session: Address,
+        asset: Symbol,
+        limit: i128,
+        expires_at: u64,
+    ) -> Result<(), PoolError> {
+        user.require_auth();
+        Self::get_token_address(&env, &asset)?;
 
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
-        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
-        let total_reserves: i128 = env.storage().instance().get(&DataKey::TotalReserves(asset.clone())).unwrap_or(0);
-        
-        // Total cash = supply - borrows + borrow interest (approximated by borrow amount)
-        let total_underlying = total_supply + total_borrow - total_reserves;
-        
-        (total_underlying * INITIAL_EXCHANGE_RATE) / total_shares
-    }
+        if limit < 0 {
+            return Err(PoolError::InvalidAmount);
+        }
 
-    /// Get user's debt including accrued interest
-    fn get_user_debt_with_interest(env: &Env, user: Address, asset: Symbol) -> i128 {
-        let principal: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserDebt(user.clone(), asset.clone()))
-            .unwrap_or(0);
-        
-        if principal == 0 {
-            return 0;
+        let key = UserKey::Session(user.clone(), session.clone(), asset.clone());
+        if limit == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &SessionGrant { remaining: limit, expires_at });
+            env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
         }
 
-        let user_borrow_index: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserBorrowIndex(user, asset.clone()))
-            .unwrap_or(INITIAL_EXCHANGE_RATE);
-        
-        let current_borrow_index: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::BorrowIndex(asset))
-            .unwrap_or(INITIAL_EXCHANGE_RATE);
+        env.events()
+            .publish((symbol_short!("session"), user, session), (asset, limit, expires_at));
 
-        // debt = principal * current_index / user_index
-        (principal * current_borrow_index) / user_borrow_index
+        Ok(())
     }
 
-    /// Get asset price from oracle
-    ///
-    /// Calls the Price Oracle contract to get current USD price for an asset.
-    /// Falls back to hardcoded prices if oracle is not available.
+    /// Get what `session` may still move for `user` in `asset`, if anything
+    pub fn get_session(env: Env, user: Address, session: Address, asset: Symbol) -> Option<SessionGrant> {
+        env.storage()
+            .persistent()
+            .get::<_, SessionGrant>(&UserKey::Session(user, session, asset))
+            .filter(|grant| env.ledger().timestamp() <= grant.expires_at)
+    }
+
+    /// Supply for `user` from their wallet, signed by a session key
     ///
-    /// # Arguments
-    /// * `env` - Soroban environment
-    /// * `oracle` - Oracle contract address
-    /// * `asset` - Asset symbol (XLM or USDC)
+    /// The session authorizes exactly `(user, asset, amount)`. The shares
+    /// are credited to `user`, who pays through their token allowance.
     ///
     /// # Returns
-    /// Price in USD (scaled by 1e7)
-    fn get_asset_price(env: &Env, oracle: &Address, asset: &Symbol) -> i128 {
-        if USE_ORACLE {
-            // Cross-contract call to Oracle
-            let oracle_client = oracle_contract::Client::new(env, oracle);
-            let price = oracle_client.get_price(asset);
-            
-            // Fallback if price not set
-            if price == 0 {
-                Self::get_fallback_price(asset)
-            } else {
-                price
-            }
-        } else {
-            // Use fallback prices (for testing without deployed oracle)
-            Self::get_fallback_price(asset)
+    /// Amount of sToken shares minted
+    pub fn supply_with_session(
+        env: Env,
+        session: Address,
+        user: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<i128, PoolError> {
+        session.require_auth_for_args((user.clone(), asset.clone(), amount).into_val(&env));
+        Self::spend_session(&env, &user, &session, &asset, amount)?;
+
+        Self::supply_internal(&env, &user, user.clone(), asset, amount, None, true)
+    }
+
+    /// Repay `user`'s debt from their wallet, signed by a session key
+    ///
+    /// Like `supply_with_session`; only the amount actually repaid counts
+    /// against the grant.
+    ///
+    /// # Returns
+    /// Amount actually repaid
+    pub fn repay_with_session(
+        env: Env,
+        session: Address,
+        user: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<i128, PoolError> {
+        session.require_auth_for_args((user.clone(), asset.clone(), amount).into_val(&env));
+        let grant = Self::get_session(env.clone(), user.clone(), session.clone(), asset.clone())
+            .ok_or(PoolError::InsufficientAllowance)?;
+
+        let repaid = Self::repay_internal(&env, &user, user.clone(), asset.clone(), amount.min(grant.remaining), true)?;
+        Self::spend_session(&env, &user, &session, &asset, repaid)?;
+        Ok(repaid)
+    }
+
+    /// Internal: Draw `amount` from a session grant, failing if it has
+    /// lapsed or is too small
+    fn spend_session(
+        env: &Env,
+        user: &Address,
+        session: &Address,
+        asset: &Symbol,
+        amount: i128,
+    ) -> Result<(), PoolError> {
+        let grant = Self::get_session(env.clone(), user.clone(), session.clone(), asset.clone())
+            .ok_or(PoolError::InsufficientAllowance)?;
+        if amount > grant.remaining {
+            return Err(PoolError::InsufficientAllowance);
         }
+
+        let key = UserKey::Session(user.clone(), session.clone(), asset.clone());
+        env.storage().persistent().set(&key, &SessionGrant { remaining: grant.remaining - amount, ..grant });
+        Ok(())
     }
 
-    /// Get fallback price for testing
+    /// Internal: Move `amount` of `asset` from `from` into the pool,
+    /// directly or through the allowance `from` gave the pool on the token
     ///
-    /// Used when oracle is not deployed or price not available.
-    fn get_fallback_price(asset: &Symbol) -> i128 {
-        if *asset == XLM {
-            3_000_000 // $0.30
-        } else if *asset == USDC {
-            SCALE // $1.00
+    /// Rounds the token amount up, so a token with fewer decimals than the
+    /// pool's accounting never under-pays.
+    fn pull_underlying(env: &Env, asset: &Symbol, from: &Address, amount: i128, use_allowance: bool) -> Result<(), PoolError> {
+        let token_client = token::Client::new(env, &Self::get_token_address(env, asset)?);
+        let token_amount = Self::to_token_units(env, asset, amount, Rounding::Up)?;
+        let pool = env.current_contract_address();
+        if use_allowance {
+            token_client.transfer_from(&pool, from, &pool, &token_amount);
         } else {
-            panic!("Unknown asset")
+            token_client.transfer(from, &pool, &token_amount);
+        }
+        Ok(())
+    }
+
+    /// Internal: Pay `amount` of `asset` out of the pool to `to`
+    ///
+    /// Rounds the token amount down; the dust stays with the pool.
+    fn send_underlying(env: &Env, asset: &Symbol, to: &Address, amount: i128) -> Result<(), PoolError> {
+        let token_client = token::Client::new(env, &Self::get_token_address(env, asset)?);
+        let token_amount = Self::to_token_units(env, asset, amount, Rounding::Down)?;
+        token_client.transfer(&env.current_contract_address(), to, &token_amount);
+        Ok(())
+    }
+
+    /// Internal: Route a deposit or withdrawal fee held by the pool to the
+    /// treasury, or into the asset's reserves while no treasury is set
+    fn collect_fee(env: &Env, asset: &Symbol, fee: i128) -> Result<(), PoolError> {
+        if fee <= 0 {
+            return Ok(());
+        }
+        match Self::get_treasury(env.clone()) {
+            Some(treasury) => Self::send_underlying(env, asset, &treasury, fee),
+            None => {
+                let reserves = Self::get_total_reserves(env.clone(), asset.clone());
+                env.storage().instance().set(&DataKey::TotalReserves(asset.clone()), &(reserves + fee));
+                Ok(())
+            }
         }
     }
 
     // ========================================================================
-    // VIEW FUNCTIONS
+    // SWAP ADAPTERS
     // ========================================================================
 
-    /// Get user's complete position across all assets
-    pub fn get_user_position(env: Env, user: Address) -> UserPosition {
-        let oracle: Address = env.storage().instance().get(&DataKey::PriceOracle).unwrap();
-
-        // Calculate total collateral value in USD
-        let mut collateral_value_usd: i128 = 0;
-        let mut weighted_collateral_usd: i128 = 0; // collateral * LTV
+    /// Approve or revoke a swap adapter (admin only)
+    ///
+    /// Leverage and liquidation routines hand pool funds to the adapter
+    /// before it swaps, so only audited adapters should be approved.
+    ///
+    /// # Arguments
+    /// * `adapter` - Contract implementing `SwapAdapter`
+    /// * `approved` - Whether the pool may swap through it
+    pub fn set_swap_adapter(env: Env, adapter: Address, approved: bool) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
 
-        // XLM collateral
-        let xlm_collateral: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserCollateral(user.clone(), XLM))
-            .unwrap_or(0);
-        if xlm_collateral > 0 {
-            let xlm_price = Self::get_asset_price(&env, &oracle, &XLM);
-            let xlm_value = (xlm_collateral * xlm_price) / SCALE;
-            collateral_value_usd += xlm_value;
-            
-            let xlm_ltv: i128 = env.storage().instance().get(&DataKey::LtvRatio(XLM)).unwrap_or(7_500_000);
-            weighted_collateral_usd += (xlm_value * xlm_ltv) / SCALE;
+        if approved {
+            env.storage().instance().set(&ProtocolKey::SwapAdapter(adapter.clone()), &true);
+        } else {
+            env.storage().instance().remove(&ProtocolKey::SwapAdapter(adapter.clone()));
         }
 
-        // USDC collateral (if any)
-        let usdc_collateral: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserCollateral(user.clone(), USDC))
-            .unwrap_or(0);
-        if usdc_collateral > 0 {
-            let usdc_price = Self::get_asset_price(&env, &oracle, &USDC);
-            let usdc_value = (usdc_collateral * usdc_price) / SCALE;
-            collateral_value_usd += usdc_value;
-            
-            let usdc_ltv: i128 = env.storage().instance().get(&DataKey::LtvRatio(USDC)).unwrap_or(8_000_000);
-            weighted_collateral_usd += (usdc_value * usdc_ltv) / SCALE;
-        }
+        env.events().publish((symbol_short!("set_swap"), adapter), approved);
 
-        // Calculate total debt value in USD
-        let mut debt_value_usd: i128 = 0;
+        Ok(())
+    }
+
+    /// Whether a swap adapter is approved
+    pub fn is_swap_adapter(env: Env, adapter: Address) -> bool {
+        env.storage()
+            .instance()
+            .get(&ProtocolKey::SwapAdapter(adapter))
+            .unwrap_or(false)
+    }
 
-        // USDC debt
-        let usdc_debt = Self::get_user_debt_with_interest(&env, user.clone(), USDC);
-        if usdc_debt > 0 {
-            let usdc_price = Self::get_asset_price(&env, &oracle, &USDC);
-            debt_value_usd += (usdc_debt * usdc_price) / SCALE;
+    /// Internal: Swap pool-held `amount_in` of `asset_in` for `asset_out` through an approved adapter
+    ///
+    /// The output is measured from the pool's balance rather than taken from
+    /// the adapter's return value.
+    ///
+    /// # Returns
+    /// Amount of `asset_out` received by the pool
+    fn swap(
+        env: &Env,
+        adapter: &Address,
+        asset_in: &Symbol,
+        asset_out: &Symbol,
+        amount_in: i128,
+        min_out: i128,
+    ) -> Result<i128, PoolError> {
+        if !Self::is_swap_adapter(env.clone(), adapter.clone()) {
+            return Err(PoolError::Unauthorized);
         }
 
-        // Calculate available borrow (max borrow - current debt)
-        let available_borrow_usd = if weighted_collateral_usd > debt_value_usd {
-            weighted_collateral_usd - debt_value_usd
-        } else {
-            0
-        };
+        let pool = env.current_contract_address();
+        let token_in = Self::get_token_address(env, asset_in)?;
+        let token_out = token::Client::new(env, &Self::get_token_address(env, asset_out)?);
+        let balance_before = token_out.balance(&pool);
 
-        // Calculate health factor
-        // HF = (collateral * liquidation_threshold) / debt
-        let health_factor = if debt_value_usd == 0 {
-            999 * SCALE // Infinite
-        } else {
-            // Use average liquidation threshold (simplified)
-            let liq_threshold: i128 = env.storage().instance().get(&DataKey::LiquidationThreshold(XLM)).unwrap_or(8_000_000);
-            (collateral_value_usd * liq_threshold) / debt_value_usd
-        };
+        let token_amount_in = Self::to_token_units(env, asset_in, amount_in, Rounding::Down)?;
+        let token_min_out = Self::to_token_units(env, asset_out, min_out, Rounding::Up)?;
+        token::Client::new(env, &token_in).transfer(&pool, adapter, &token_amount_in);
+        SwapAdapterClient::new(env, adapter).swap_exact_in(&token_in, &token_out.address, &token_amount_in, &token_min_out, &pool);
 
-        UserPosition {
-            collateral_value_usd,
-            debt_value_usd,
-            available_borrow_usd,
-            health_factor,
+        let amount_out = Self::from_token_units(env, asset_out, token_out.balance(&pool) - balance_before)?;
+        if amount_out < min_out {
+            return Err(PoolError::SlippageExceeded);
         }
+        Ok(amount_out)
     }
 
-    /// Get market information for an asset
-    /// Get market information for an asset
-    /// 
-    /// Returns comprehensive market data including supply, borrow, rates, etc.
-    pub fn get_market_info(env: Env, asset: Symbol) -> MarketInfo {
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
-        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
-        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
-        let exchange_rate = Self::get_exchange_rate_internal(&env, asset.clone());
-        let ltv_ratio: i128 = env.storage().instance().get(&DataKey::LtvRatio(asset.clone())).unwrap_or(0);
-
-        // Calculate utilization rate
-        let utilization_rate = if total_supply > 0 {
-            (total_borrow * SCALE) / total_supply
-        } else {
-            0
-        };
+    // ========================================================================
+    // LEVERAGE
+    // ========================================================================
 
-        // Calculate interest rates using the kinked model
-        let borrow_rate = Self::calculate_borrow_rate(utilization_rate);
-        
-        // Supply rate = borrow_rate * utilization * (1 - reserve_factor)
-        let reserve_factor: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::ReserveFactor(asset))
-            .unwrap_or(1_000_000);
-        let supply_rate = if utilization_rate > 0 {
-            (borrow_rate * utilization_rate * (SCALE - reserve_factor)) / (SCALE * SCALE)
-        } else {
-            0
-        };
+    /// Open a looped position in one transaction
+    ///
+    /// Deposits `collateral_amount` of `asset` as collateral, then borrows
+    /// the same asset and keeps the borrowed funds as collateral, which is
+    /// what repeated deposit/borrow rounds converge to. The debt is sized so
+    /// that debt / collateral equals `target_ltv`, before any origination
+    /// fee; the borrow must still fit within the asset's LTV.
+    ///
+    /// # Arguments
+    /// * `user` - The user's address
+    /// * `asset` - Asset symbol deposited and borrowed
+    /// * `collateral_amount` - Amount deposited from the user's wallet
+    /// * `target_ltv` - Debt to collateral ratio to reach (scaled by SCALE)
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    ///
+    /// # Returns
+    /// Amount borrowed and added to collateral
+    pub fn leverage(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        collateral_amount: i128,
+        target_ltv: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        user.require_auth();
+        Self::require_not_paused(&env, PoolAction::DepositCollateral)?;
+        Self::require_deadline(&env, deadline)?;
 
-        MarketInfo {
-            total_supply,
-            total_borrow,
-            total_shares,
-            exchange_rate,
-            utilization_rate,
-            borrow_rate,
-            supply_rate,
-            ltv_ratio,
+        if collateral_amount <= 0 || target_ltv <= 0 || target_ltv >= SCALE {
+            return Err(PoolError::InvalidAmount);
         }
-    }
+        if !Self::is_collateral_enabled(&env, &asset) {
+            return Err(PoolError::CollateralNotEnabled);
+        }
+        Self::require_isolation_allows(&env, &user, &asset)?;
 
-    /// Get total supply for an asset
-    pub fn get_total_supply(env: Env, asset: Symbol) -> i128 {
-        env.storage().instance().get(&DataKey::TotalSupply(asset)).unwrap_or(0)
-    }
+        // c / (1 - ltv) of collateral against c * ltv / (1 - ltv) of debt
+        let borrow_amount = mul_div(collateral_amount, target_ltv, SCALE - target_ltv, Rounding::Down)?;
 
-    /// Get total borrows for an asset
-    pub fn get_total_borrow(env: Env, asset: Symbol) -> i128 {
-        env.storage().instance().get(&DataKey::TotalBorrow(asset)).unwrap_or(0)
-    }
+        let current_collateral = Self::get_user_collateral(env.clone(), user.clone(), asset.clone());
+        let new_collateral = current_collateral + collateral_amount + borrow_amount;
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserCollateral(user.clone(), asset.clone()), &new_collateral);
 
-    /// Get user's share balance for an asset
-    pub fn get_user_shares(env: Env, user: Address, asset: Symbol) -> i128 {
-        env.storage().persistent().get(&DataKey::UserShares(user, asset)).unwrap_or(0)
-    }
+        Self::borrow_internal(
+            &env,
+            &user,
+            &env.current_contract_address(),
+            asset.clone(),
+            borrow_amount,
+            RateMode::Variable,
+        )?;
 
-    /// Get user's collateral balance for an asset
-    pub fn get_user_collateral(env: Env, user: Address, asset: Symbol) -> i128 {
-        env.storage().persistent().get(&DataKey::UserCollateral(user, asset)).unwrap_or(0)
-    }
+        Self::pull_underlying(&env, &asset, &user, collateral_amount, false)?;
 
-    /// Get user's debt balance for an asset (without interest)
-    pub fn get_user_debt(env: Env, user: Address, asset: Symbol) -> i128 {
-        env.storage().persistent().get(&DataKey::UserDebt(user, asset)).unwrap_or(0)
-    }
+        env.events().publish(
+            (symbol_short!("leverage"), user.clone(), asset.clone()),
+            CollateralEvent {
+                user,
+                asset,
+                amount: collateral_amount + borrow_amount,
+                new_balance: new_collateral,
+            },
+        );
 
-    /// Get user's debt balance with accrued interest
-    pub fn get_user_debt_total(env: Env, user: Address, asset: Symbol) -> i128 {
-        Self::get_user_debt_with_interest(&env, user, asset)
+        Self::assert_invariants(&env);
+        Ok(borrow_amount)
     }
 
-    /// Get exchange rate for sTokens
-    pub fn get_exchange_rate(env: Env, asset: Symbol) -> i128 {
-        Self::get_exchange_rate_internal(&env, asset)
-    }
+    /// Open a leveraged position in one asset funded by debt in another
+    ///
+    /// Deposits `collateral_amount` of `collateral_asset`, borrows
+    /// `borrow_asset` worth `target_ltv / (1 - target_ltv)` of it at oracle
+    /// prices, swaps the borrowed funds to `collateral_asset` through an
+    /// approved adapter and adds the output to collateral. The borrow is
+    /// checked against the LTV with the swapped collateral in place.
+    ///
+    /// # Arguments
+    /// * `user` - The user's address
+    /// * `collateral_asset` - Asset deposited and bought (e.g., XLM)
+    /// * `collateral_amount` - Amount deposited from the user's wallet
+    /// * `borrow_asset` - Asset borrowed and sold (e.g., USDC)
+    /// * `target_ltv` - Debt to collateral value to reach (scaled by SCALE)
+    /// * `adapter` - Approved swap adapter
+    /// * `min_out` - Least `collateral_asset` the swap must return
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    ///
+    /// # Returns
+    /// Amount of `borrow_asset` borrowed
+    #[allow(clippy::too_many_arguments)]
+    pub fn leverage_swap(
+        env: Env,
+        user: Address,
+        collateral_asset: Symbol,
+        collateral_amount: i128,
+        borrow_asset: Symbol,
+        target_ltv: i128,
+        adapter: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        user.require_auth();
+        Self::require_not_paused(&env, PoolAction::DepositCollateral)?;
+        Self::require_deadline(&env, deadline)?;
 
-    /// Get utilization rate for an asset
-    pub fn get_utilization_rate(env: Env, asset: Symbol) -> i128 {
-        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
-        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset)).unwrap_or(0);
-        
-        if total_supply == 0 {
-            return 0;
+        if collateral_amount <= 0 || target_ltv <= 0 || target_ltv >= SCALE {
+            return Err(PoolError::InvalidAmount);
         }
-        
-        (total_borrow * SCALE) / total_supply
-    }
+        if !Self::is_collateral_enabled(&env, &collateral_asset) {
+            return Err(PoolError::CollateralNotEnabled);
+        }
+        Self::require_isolation_allows(&env, &user, &collateral_asset)?;
+
+        let oracle = Self::get_oracle(&env)?;
+        let collateral_price = Self::get_collateral_price(&env, &oracle, &collateral_asset)?;
+        let borrow_price = Self::get_asset_price(&env, &oracle, &borrow_asset)?;
+        let collateral_usd = mul_div(collateral_amount, collateral_price, SCALE, Rounding::Down)?;
+        let debt_usd = mul_div(collateral_usd, target_ltv, SCALE - target_ltv, Rounding::Down)?;
+        let borrow_amount = mul_div(debt_usd, SCALE, borrow_price, Rounding::Down)?;
+
+        // Swap first so the borrow's LTV check sees the bought collateral;
+        // the borrow itself keeps the funds in the pool. The swap is the one
+        // interaction that has to precede the state it feeds
+        let bought = Self::swap(&env, &adapter, &borrow_asset, &collateral_asset, borrow_amount, min_out)?;
+        let current_collateral = Self::get_user_collateral(env.clone(), user.clone(), collateral_asset.clone());
+        let new_collateral = current_collateral + collateral_amount + bought;
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserCollateral(user.clone(), collateral_asset.clone()), &new_collateral);
 
-    /// Get LTV ratio for an asset
-    pub fn get_ltv_ratio(env: Env, asset: Symbol) -> i128 {
-        env.storage().instance().get(&DataKey::LtvRatio(asset)).unwrap_or(0)
-    }
+        Self::borrow_internal(
+            &env,
+            &user,
+            &env.current_contract_address(),
+            borrow_asset,
+            borrow_amount,
+            RateMode::Variable,
+        )?;
 
-    /// Get liquidation threshold for an asset
-    pub fn get_liquidation_threshold(env: Env, asset: Symbol) -> i128 {
-        env.storage().instance().get(&DataKey::LiquidationThreshold(asset)).unwrap_or(0)
-    }
+        Self::pull_underlying(&env, &collateral_asset, &user, collateral_amount, false)?;
 
-    /// Get the current borrow APR for an asset
-    /// 
-    /// Returns the annualized borrow rate based on current utilization.
-    /// Scaled by 1e7, so 5% = 500_000.
-    pub fn get_borrow_rate(env: Env, asset: Symbol) -> i128 {
-        let utilization = Self::get_utilization_rate(env, asset);
-        Self::calculate_borrow_rate(utilization)
+        env.events().publish(
+            (symbol_short!("leverage"), user.clone(), collateral_asset.clone()),
+            CollateralEvent {
+                user,
+                asset: collateral_asset,
+                amount: collateral_amount + bought,
+                new_balance: new_collateral,
+            },
+        );
+
+        Self::assert_invariants(&env);
+        Ok(borrow_amount)
     }
 
-    /// Get the current supply APY for an asset
-    /// 
-    /// Returns the annualized supply rate based on current utilization.
-    /// Scaled by 1e7, so 3.2% = 320_000.
-    pub fn get_supply_rate(env: Env, asset: Symbol) -> i128 {
-        let utilization = Self::get_utilization_rate(env.clone(), asset.clone());
-        let borrow_rate = Self::calculate_borrow_rate(utilization);
-        
-        let reserve_factor: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::ReserveFactor(asset))
-            .unwrap_or(1_000_000);
-        
-        // Supply rate = borrow_rate * utilization * (1 - reserve_factor)
-        if utilization > 0 {
-            (borrow_rate * utilization * (SCALE - reserve_factor)) / (SCALE * SCALE)
-        } else {
-            0
+    /// Unwind a looped position by repaying debt with collateral of the same asset
+    ///
+    /// Collateral worth `amount` of debt is released from custody and booked
+    /// as repayment, so no tokens move. The remaining collateral can then be
+    /// withdrawn as usual.
+    ///
+    /// # Arguments
+    /// * `user` - The user's address
+    /// * `asset` - Asset symbol of both the collateral and the debt
+    /// * `amount` - Debt to repay (use i128::MAX to repay all)
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    ///
+    /// # Returns
+    /// Amount of debt repaid
+    pub fn deleverage(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        amount: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        user.require_auth();
+        Self::require_not_paused(&env, PoolAction::Repay)?;
+        Self::require_not_paused(&env, PoolAction::WithdrawCollateral)?;
+        Self::require_deadline(&env, deadline)?;
+
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
         }
-    }
 
-    /// Get the borrow index for an asset
-    /// 
-    /// The borrow index tracks accumulated interest. Used to calculate
-    /// individual user debt with interest.
-    pub fn get_borrow_index(env: Env, asset: Symbol) -> i128 {
+        Self::accrue_interest_for(&env, &asset, &user)?;
+
+        let user_debt = Self::get_user_total_debt(&env, &user, &asset)?;
+        if user_debt == 0 {
+            return Err(PoolError::NoDebt);
+        }
+        let mut repay_amount = amount.min(user_debt);
+        if user_debt - repay_amount < Self::get_debt_dust(env.clone(), asset.clone()) {
+            repay_amount = user_debt;
+        }
+
+        let current_collateral = Self::get_user_collateral(env.clone(), user.clone(), asset.clone());
+        if current_collateral < repay_amount {
+            return Err(PoolError::InsufficientCollateral);
+        }
+        let new_collateral = current_collateral - repay_amount;
         env.storage()
-            .instance()
-            .get(&DataKey::BorrowIndex(asset))
-            .unwrap_or(INITIAL_EXCHANGE_RATE)
-    }
+            .persistent()
+            .set(&DataKey::UserCollateral(user.clone(), asset.clone()), &new_collateral);
 
-    /// Get the interest rate model contract address
-    pub fn get_interest_rate_model(env: Env) -> Address {
+        Self::reduce_user_debt(&env, &user, &asset, repay_amount)?;
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
         env.storage()
             .instance()
-            .get(&DataKey::InterestRateModel)
-            .unwrap()
-    }
+            .set(&DataKey::TotalBorrow(asset.clone()), &(total_borrow - repay_amount).max(0));
 
-    /// Get health factor for a specific user
-    /// 
-    /// Health Factor = (collateral_value * liquidation_threshold) / debt_value
-    /// 
-    /// # Returns
-    /// - HF >= 1.0 (SCALE): Safe position
-    /// - HF < 1.0 (SCALE): Unsafe position, eligible for liquidation
-    /// - 999 * SCALE: No debt (infinite health factor)
-    /// 
-    /// Scaled by SCALE (1e7), so HF = 1.0 is represented as 10_000_000
-    pub fn get_health_factor(env: Env, user: Address) -> i128 {
-        let position = Self::get_user_position(env, user);
-        position.health_factor
-    }
+        // Debt in other assets may lean on the released collateral
+        Self::require_healthy_after_withdrawal(&env, &user)?;
+        Self::update_global_borrow_pause(&env)?;
+        Self::extend_user_ttl(&env, &user);
 
-    // ========================================================================
-    // LIQUIDATION
-    // ========================================================================
+        env.events().publish(
+            (symbol_short!("deleverag"), user.clone(), asset.clone()),
+            BorrowEvent {
+                user: user.clone(),
+                counterparty: user,
+                asset: asset.clone(),
+                amount: repay_amount,
+                new_debt: user_debt - repay_amount,
+                borrow_index: Self::get_borrow_index(env.clone(), asset),
+            },
+        );
 
-    /// Liquidate an undercollateralized position
-    /// 
-    /// Allows a liquidator to repay a portion of a borrower's debt in exchange
-    /// for a portion of their collateral plus a liquidation bonus.
-    /// 
-    /// # Requirements
-    /// - Borrower's health factor must be < 1.0
-    /// - Liquidator can repay up to 50% of borrower's debt (close factor)
-    /// - Liquidator receives equivalent collateral value + 5% bonus
-    /// 
+        Self::assert_invariants(&env);
+        Ok(repay_amount)
+    }
+
+    /// Unwind a leveraged position by selling collateral for the debt asset
+    ///
+    /// Takes `collateral_amount` of `collateral_asset` out of custody, swaps
+    /// it to `debt_asset` through an approved adapter and repays the debt
+    /// with the output; anything beyond the debt is sent to the user.
+    ///
     /// # Arguments
-    /// * `liquidator` - Address calling the liquidation (repaying debt)
-    /// * `borrower` - Address being liquidated (underwater position)
-    /// * `repay_asset` - Asset to repay (e.g., USDC)
-    /// * `repay_amount` - Amount of debt to repay
-    /// * `collateral_asset` - Collateral asset to seize (e.g., XLM)
-    /// 
+    /// * `user` - The user's address
+    /// * `collateral_asset` - Collateral sold (e.g., XLM)
+    /// * `collateral_amount` - Amount of collateral sold
+    /// * `debt_asset` - Debt repaid (e.g., USDC)
+    /// * `adapter` - Approved swap adapter
+    /// * `min_out` - Least `debt_asset` the swap must return
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    ///
     /// # Returns
-    /// Amount of collateral seized
-    pub fn liquidate(
+    /// Amount of debt repaid
+    #[allow(clippy::too_many_arguments)]
+    pub fn deleverage_swap(
         env: Env,
-        liquidator: Address,
-        borrower: Address,
-        repay_asset: Symbol,
-        repay_amount: i128,
+        user: Address,
         collateral_asset: Symbol,
-    ) -> i128 {
-        liquidator.require_auth();
-        
-        if repay_amount <= 0 {
-            panic!("Repay amount must be positive");
+        collateral_amount: i128,
+        debt_asset: Symbol,
+        adapter: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        user.require_auth();
+        Self::require_not_paused(&env, PoolAction::Repay)?;
+        Self::require_not_paused(&env, PoolAction::WithdrawCollateral)?;
+        Self::require_deadline(&env, deadline)?;
+
+        if collateral_amount <= 0 {
+            return Err(PoolError::InvalidAmount);
         }
 
-        // ====================================================================
-        // STEP 1: Check borrower's health factor
-        // ====================================================================
-        
-        // Accrue interest first to get accurate debt
-        Self::accrue_interest(&env, repay_asset.clone());
-        
-        let borrower_position = Self::get_user_position(env.clone(), borrower.clone());
-        
-        // Health factor must be < 1.0 to be liquidatable
-        if borrower_position.health_factor >= SCALE {
-            panic!("Position is healthy, cannot liquidate");
+        Self::accrue_interest_for(&env, &debt_asset, &user)?;
+        let user_debt = Self::get_user_total_debt(&env, &user, &debt_asset)?;
+        if user_debt == 0 {
+            return Err(PoolError::NoDebt);
         }
 
-        // ====================================================================
-        // STEP 2: Calculate maximum repayable amount (close factor)
-        // ====================================================================
-        
-        let borrower_debt = Self::get_user_debt_with_interest(&env, borrower.clone(), repay_asset.clone());
-        
-        if borrower_debt == 0 {
-            panic!("Borrower has no debt in this asset");
+        let current_collateral = Self::get_user_collateral(env.clone(), user.clone(), collateral_asset.clone());
+        if current_collateral < collateral_amount {
+            return Err(PoolError::InsufficientCollateral);
         }
-        
+        env.storage().persistent().set(
+            &DataKey::UserCollateral(user.clone(), collateral_asset.clone()),
+            &(current_collateral - collateral_amount),
+        );
+
+        let proceeds = Self::swap(&env, &adapter, &collateral_asset, &debt_asset, collateral_amount, min_out)?;
+        let repay_amount = proceeds.min(user_debt);
+        Self::reduce_user_debt(&env, &user, &debt_asset, repay_amount)?;
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(debt_asset.clone())).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalBorrow(debt_asset.clone()), &(total_borrow - repay_amount).max(0));
+
+        Self::require_healthy_after_withdrawal(&env, &user)?;
+        Self::update_global_borrow_pause(&env)?;
+        Self::extend_user_ttl(&env, &user);
+
+        if proceeds > repay_amount {
+            Self::send_underlying(&env, &debt_asset, &user, proceeds - repay_amount)?;
+        }
+
+        env.events().publish(
+            (symbol_short!("deleverag"), user.clone(), debt_asset.clone()),
+            BorrowEvent {
+                user: user.clone(),
+                counterparty: user,
+                asset: debt_asset.clone(),
+                amount: repay_amount,
+                new_debt: user_debt - repay_amount,
+                borrow_index: Self::get_borrow_index(env.clone(), debt_asset),
+            },
+        );
+
+        Self::assert_invariants(&env);
+        Ok(repay_amount)
+    }
+
+    // ========================================================================
+    // STABLE RATE
+    // ========================================================================
+
+    /// Move a user's debt in an asset into `to_mode`
+    ///
+    /// All of the debt in the other mode is moved, with accrued interest.
+    /// Moving to stable locks the current stable rate (blended with any
+    /// existing stable debt); moving to variable starts at the current
+    /// borrow index.
+    ///
+    /// # Arguments
+    /// * `user` - The borrower's address
+    /// * `asset` - Asset symbol
+    /// * `to_mode` - Rate mode to move the debt into
+    ///
+    /// # Returns
+    /// Amount of debt moved
+    pub fn swap_rate_mode(env: Env, user: Address, asset: Symbol, to_mode: RateMode) -> Result<i128, PoolError> {
+        user.require_auth();
+        Self::require_not_paused(&env, PoolAction::Borrow)?;
+
+        Self::accrue_interest_for(&env, &asset, &user)?;
+
+        let variable_debt = Self::get_user_debt_with_interest(&env, user.clone(), asset.clone())?;
+        let stable_debt = Self::get_user_stable_debt_with_interest(&env, &user, &asset)?;
+
+        let moved = match to_mode {
+            RateMode::Stable => {
+                if !Self::is_stable_borrow_enabled(env.clone(), asset.clone()) {
+                    return Err(PoolError::StableBorrowNotEnabled);
+                }
+                if variable_debt == 0 {
+                    return Err(PoolError::NoDebt);
+                }
+                let utilization = Self::get_utilization_rate(env.clone(), asset.clone())?;
+                let offered_rate = Self::calculate_stable_rate(&env, &asset, utilization)?;
+                let current_rate = Self::get_user_stable_rate(env.clone(), user.clone(), asset.clone());
+                let new_stable_debt = stable_debt + variable_debt;
+                let new_rate = Self::blend_stable_rate(stable_debt, current_rate, variable_debt, offered_rate)?;
+
+                Self::set_user_debt(&env, &user, &asset, 0);
+                Self::set_user_stable_debt(&env, &user, &asset, new_stable_debt, new_rate)?;
+                variable_debt
+            }
+            RateMode::Variable => {
+                if stable_debt == 0 {
+                    return Err(PoolError::NoDebt);
+                }
+
+                Self::set_user_stable_debt(&env, &user, &asset, 0, 0)?;
+                Self::set_user_debt(&env, &user, &asset, variable_debt + stable_debt);
+                stable_debt
+            }
+        };
+        Self::notify_borrow_change(&env, &asset, &user)?;
+
+        Self::extend_user_ttl(&env, &user);
+
+        env.events().publish((symbol_short!("swap_rate"), user, asset), (to_mode, moved));
+
+        Self::assert_invariants(&env);
+        Ok(moved)
+    }
+
+    /// Reset a user's locked stable rate to the current stable rate
+    ///
+    /// Permissionless, so keepers can protect the pool when utilization gets
+    /// extreme. Allowed when either:
+    /// - utilization is at least 95% and the user's rate is below the
+    ///   current variable rate (rebalance up), or
+    /// - the user's rate is more than 20% above the current stable rate
+    ///   (rebalance down)
+    ///
+    /// # Returns
+    /// The user's new stable rate
+    pub fn rebalance_stable_rate(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        Self::require_not_paused(&env, PoolAction::Borrow)?;
+
+        Self::accrue_interest(&env, asset.clone())?;
+
+        let stable_debt = Self::get_user_stable_debt_with_interest(&env, &user, &asset)?;
+        if stable_debt == 0 {
+            return Err(PoolError::NoDebt);
+        }
+        let user_rate = Self::get_user_stable_rate(env.clone(), user.clone(), asset.clone());
+
+        let utilization = Self::get_utilization_rate(env.clone(), asset.clone())?;
+        let variable_rate = Self::calculate_borrow_rate(&env, &asset, utilization)?;
+        let stable_rate = Self::calculate_stable_rate(&env, &asset, utilization)?;
+
+        let rebalance_up = utilization >= REBALANCE_UP_UTILIZATION && user_rate < variable_rate;
+        let rebalance_down = user_rate > mul_div(stable_rate, SCALE + REBALANCE_DOWN_DELTA, SCALE, Rounding::Down)?;
+        if !rebalance_up && !rebalance_down {
+            return Err(PoolError::RebalanceNotAllowed);
+        }
+
+        Self::set_user_stable_debt(&env, &user, &asset, stable_debt, stable_rate)?;
+        Self::notify_borrow_change(&env, &asset, &user)?;
+
+        Self::extend_user_ttl(&env, &user);
+
+        env.events().publish((symbol_short!("rebalance"), user, asset), (user_rate, stable_rate));
+
+        Self::assert_invariants(&env);
+        Ok(stable_rate)
+    }
+
+    // ========================================================================
+    // RATE DISCOUNTS (Borrow rate tiers for stakers and large suppliers)
+    // ========================================================================
+
+    /// Set the token staked for rate discounts and the discount tiers (admin only)
+    ///
+    /// The token cannot change once set, so stakes are never stranded.
+    /// Users keep the discount they were last evaluated at until they stake,
+    /// unstake, withdraw or `refresh_rate_discount` is called for them.
+    ///
+    /// # Arguments
+    /// * `token` - Token users stake with the pool
+    /// * `tiers` - Discount tiers; each discount at most 10_000 bps
+    pub fn set_discount_tiers(env: Env, token: Address, tiers: Vec<DiscountTier>) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        if let Some(current) = env.storage().instance().get::<_, Address>(&ProtocolKey::DiscountToken) {
+            if current != token {
+                return Err(PoolError::AlreadyInitialized);
+            }
+        }
+        for tier in tiers.iter() {
+            if tier.discount_bps as i128 > BPS_SCALE || tier.min_staked < 0 || tier.min_supply_usd < 0 {
+                return Err(PoolError::InvalidProtocolFee);
+            }
+        }
+
+        env.storage().instance().set(&ProtocolKey::DiscountToken, &token);
+        env.storage().instance().set(&ProtocolKey::DiscountTiers, &tiers);
+
+        env.events().publish((symbol_short!("dsc_tiers"), token), tiers.len());
+
+        Ok(())
+    }
+
+    /// Get the rate discount tiers
+    pub fn get_discount_tiers(env: Env) -> Vec<DiscountTier> {
+        env.storage()
+            .instance()
+            .get(&ProtocolKey::DiscountTiers)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Stake discount tokens towards a rate discount tier
+    ///
+    /// # Returns
+    /// The user's rate discount after staking (scaled by SCALE)
+    pub fn stake_for_discount(env: Env, user: Address, amount: i128) -> Result<i128, PoolError> {
+        user.require_auth();
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&ProtocolKey::DiscountToken)
+            .ok_or(PoolError::NotInitialized)?;
+
+        let staked = Self::get_staked(env.clone(), user.clone());
+        env.storage().persistent().set(&UserKey::Staked(user.clone()), &(staked + amount));
+        let discount = Self::refresh_discount(&env, &user)?;
+
+        token::Client::new(&env, &token).transfer(&user, &env.current_contract_address(), &amount);
+
+        env.events().publish((symbol_short!("stake"), user), (amount, discount));
+
+        Ok(discount)
+    }
+
+    /// Withdraw staked discount tokens
+    ///
+    /// # Returns
+    /// The user's rate discount after unstaking (scaled by SCALE)
+    pub fn unstake_for_discount(env: Env, user: Address, amount: i128) -> Result<i128, PoolError> {
+        user.require_auth();
+        let staked = Self::get_staked(env.clone(), user.clone());
+        if amount <= 0 || amount > staked {
+            return Err(PoolError::InvalidAmount);
+        }
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&ProtocolKey::DiscountToken)
+            .ok_or(PoolError::NotInitialized)?;
+
+        if staked == amount {
+            env.storage().persistent().remove(&UserKey::Staked(user.clone()));
+        } else {
+            env.storage().persistent().set(&UserKey::Staked(user.clone()), &(staked - amount));
+        }
+        let discount = Self::refresh_discount(&env, &user)?;
+
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &user, &amount);
+
+        env.events().publish((symbol_short!("unstake"), user), (amount, discount));
+
+        Ok(discount)
+    }
+
+    /// Re-evaluate a user's rate discount tier; anyone may call this
+    ///
+    /// Lets keepers apply new tiers, and downgrade users whose supplied
+    /// value fell below their tier.
+    ///
+    /// # Returns
+    /// The user's rate discount (scaled by SCALE)
+    pub fn refresh_rate_discount(env: Env, user: Address) -> Result<i128, PoolError> {
+        Self::refresh_discount(&env, &user)
+    }
+
+    /// Get the discount tokens a user has staked
+    pub fn get_staked(env: Env, user: Address) -> i128 {
+        env.storage().persistent().get(&UserKey::Staked(user)).unwrap_or(0)
+    }
+
+    /// Get a user's discount on the variable borrow rate (scaled by SCALE)
+    pub fn get_rate_discount(env: Env, user: Address) -> i128 {
+        env.storage().persistent().get(&UserKey::RateDiscount(user)).unwrap_or(0)
+    }
+
+    /// Internal: Store the discount of the best tier a user qualifies for
+    ///
+    /// Interest accrued so far is settled at the old discount first, so a
+    /// new discount only applies from now on.
+    fn refresh_discount(env: &Env, user: &Address) -> Result<i128, PoolError> {
+        let old_discount = Self::get_rate_discount(env.clone(), user.clone());
+        let new_discount = Self::qualifying_discount(env, user)?;
+        if new_discount == old_discount {
+            return Ok(new_discount);
+        }
+
+        for asset in Self::list_reserves(env.clone()).iter() {
+            if env.storage().persistent().has(&DataKey::UserDebt(user.clone(), asset.clone())) {
+                Self::accrue_interest_for(env, &asset, user)?;
+                let debt = Self::get_user_debt_with_interest(env, user.clone(), asset.clone())?;
+                Self::set_user_debt(env, user, &asset, debt);
+            }
+        }
+
+        if new_discount == 0 {
+            env.storage().persistent().remove(&UserKey::RateDiscount(user.clone()));
+        } else {
+            env.storage().persistent().set(&UserKey::RateDiscount(user.clone()), &new_discount);
+        }
+        env.events().publish((symbol_short!("rate_dsc"), user.clone()), new_discount);
+
+        Ok(new_discount)
+    }
+
+    /// Internal: Largest discount among the tiers a user qualifies for
+    fn qualifying_discount(env: &Env, user: &Address) -> Result<i128, PoolError> {
+        let staked = Self::get_staked(env.clone(), user.clone());
+        let mut supply_usd: Option<i128> = None;
+        let mut best = 0;
+
+        for tier in Self::get_discount_tiers(env.clone()).iter() {
+            let discount = bps_to_scale(tier.discount_bps as i128);
+            if discount <= best {
+                continue;
+            }
+            let mut qualifies = tier.min_staked > 0 && staked >= tier.min_staked;
+            if !qualifies && tier.min_supply_usd > 0 {
+                if supply_usd.is_none() {
+                    supply_usd = Some(Self::user_supply_usd(env, user)?);
+                }
+                qualifies = supply_usd.unwrap_or(0) >= tier.min_supply_usd;
+            }
+            if qualifies {
+                best = discount;
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Internal: USD value of a user's supplied balances (scaled by 1e7)
+    fn user_supply_usd(env: &Env, user: &Address) -> Result<i128, PoolError> {
+        let mut prices = PriceCache::new(env)?;
+        let mut total_usd = 0;
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let deposit = Self::get_user_deposit(env.clone(), user.clone(), asset.clone())?;
+            if deposit > 0 {
+                total_usd += mul_div(deposit, prices.spot(env, &asset)?, SCALE, Rounding::Down)?;
+            }
+        }
+        Ok(total_usd)
+    }
+
+    /// Internal: Accrue an asset's interest and settle a borrower's rate
+    /// discount on it
+    ///
+    /// The interest forgiven since the borrower's snapshot comes off
+    /// `TotalBorrow` and is funded from the asset's reserves, then from
+    /// suppliers; the debt is rebased so it is never forgiven twice. Every
+    /// path that writes a user's variable debt accrues through here.
+    fn accrue_interest_for(env: &Env, asset: &Symbol, user: &Address) -> Result<(), PoolError> {
+        Self::accrue_interest(env, asset.clone())?;
+        if Self::get_rate_discount(env.clone(), user.clone()) == 0 {
+            return Ok(());
+        }
+
+        let borrow_index: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowIndex(asset.clone()))
+            .unwrap_or(INITIAL_BORROW_INDEX);
+        let (_, full_debt) = Self::get_user_debt_undiscounted(env, user.clone(), asset.clone(), borrow_index)?;
+        let debt = Self::get_user_debt_at_index(env, user.clone(), asset.clone(), borrow_index)?;
+        let rebate = full_debt - debt;
+        if rebate <= 0 {
+            return Ok(());
+        }
+        Self::set_user_debt(env, user, asset, debt);
+
+        let storage = env.storage().instance();
+        let total_borrow: i128 = storage.get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        storage.set(&DataKey::TotalBorrow(asset.clone()), &(total_borrow - rebate).max(0));
+        let reserves = Self::get_total_reserves(env.clone(), asset.clone());
+        let from_reserves = rebate.min(reserves);
+        storage.set(&DataKey::TotalReserves(asset.clone()), &(reserves - from_reserves));
+        if rebate > from_reserves {
+            let total_supply: i128 = storage.get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+            storage.set(&DataKey::TotalSupply(asset.clone()), &(total_supply - (rebate - from_reserves)));
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // FLASH LOANS
+    // ========================================================================
+
+    /// Borrow liquidity and repay it within the same invocation
+    ///
+    /// Sends `amount` of `asset` to `receiver`, calls its `exec_op` callback,
+    /// then checks that `amount + fee` has been returned to the pool. The fee
+    /// is split between suppliers and protocol reserves by the reserve factor.
+    ///
+    /// # Arguments
+    /// * `receiver` - Contract implementing `FlashLoanReceiver`
+    /// * `asset` - Asset symbol to borrow
+    /// * `amount` - Amount to borrow
+    /// * `data` - Opaque data forwarded to the callback
+    ///
+    /// # Returns
+    /// Fee paid
+    pub fn flash_loan(
+        env: Env,
+        receiver: Address,
+        asset: Symbol,
+        amount: i128,
+        data: Bytes,
+    ) -> Result<i128, PoolError> {
+        receiver.require_auth();
+        Self::require_not_paused(&env, PoolAction::FlashLoan)?;
+
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        Self::accrue_interest(&env, asset.clone())?;
+
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        if total_supply - total_borrow < amount {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+
+        let fee = Self::compute_fee(&env, &receiver, amount, Self::get_flash_loan_fee(env.clone(), asset.clone()))?;
+
+        let token_address = Self::get_token_address(&env, &asset)?;
+        let token_client = token::Client::new(&env, &token_address);
+        let pool = env.current_contract_address();
+        let balance_before = token_client.balance(&pool);
+
+        // The receiver works in the token's own units
+        let token_amount = Self::to_token_units(&env, &asset, amount, Rounding::Down)?;
+        let token_fee = Self::to_token_units(&env, &asset, fee, Rounding::Up)?;
+        token_client.transfer(&pool, &receiver, &token_amount);
+        FlashLoanReceiverClient::new(&env, &receiver).exec_op(&asset, &token_address, &token_amount, &token_fee, &data);
+
+        if token_client.balance(&pool) < balance_before + token_fee {
+            return Err(PoolError::FlashLoanNotRepaid);
+        }
+
+        Self::credit_flash_fee(&env, &asset, fee)?;
+
+        env.events().publish((symbol_short!("flash"), receiver, asset), (amount, fee));
+
+        Self::assert_invariants(&env);
+        Ok(fee)
+    }
+
+    /// Internal: Credit a flash loan fee to suppliers and protocol reserves
+    fn credit_flash_fee(env: &Env, asset: &Symbol, fee: i128) -> Result<(), PoolError> {
+        if fee <= 0 {
+            return Ok(());
+        }
+        let reserve_factor: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::ReserveFactor(asset.clone()))
+            .unwrap_or(1_000_000);
+        let reserve_fee = mul_div(fee, reserve_factor, SCALE, Rounding::Down)?;
+        let current_reserves: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalReserves(asset.clone()))
+            .unwrap_or(0);
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalReserves(asset.clone()), &(current_reserves + reserve_fee));
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalSupply(asset.clone()), &(total_supply + fee - reserve_fee));
+        Ok(())
+    }
+
+    // ========================================================================
+    // INTEREST ACCRUAL
+    // ========================================================================
+
+    /// Accrue interest for an asset market
+    /// 
+    /// This function is called before any state-changing operation to ensure
+    /// interest is properly accrued. It:
+    /// 
+    /// 1. Calculates time elapsed since last accrual
+    /// 2. Gets the borrow rate from the Interest Rate Model based on utilization
+    /// 3. Compounds the borrow index (used to track user debt with interest)
+    /// 4. Adds the interest to total borrows and splits it between suppliers
+    ///    and reserves
+    /// 
+    /// ## Interest Model Integration
+    /// 
+    /// The borrow rate is read from the Interest Rate Model contract stored at
+    /// `DataKey::InterestRateModel` (multi-kink model based on utilization).
+    fn accrue_interest(env: &Env, asset: Symbol) -> Result<(), PoolError> {
+        let current_time = env.ledger().timestamp();
+        let last_accrual: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastAccrualTime(asset.clone()))
+            .unwrap_or(0);
+
+        // Skip if no time has passed
+        if current_time <= last_accrual {
+            return Ok(());
+        }
+
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+
+        if let Some(accrual) = Self::project_accrual(env, &asset)? {
+            // Let an adaptive rate model see the utilization this period ran at
+            let irm = Self::get_reserve_rate_model(env, &asset)?;
+            let token = Self::get_token_address(env, &asset)?;
+            interest_rate_model_contract::Client::new(env, &irm)
+                .report_utilization(&token, &utilization_rate(total_borrow, total_supply)?);
+
+            let storage = env.storage().instance();
+            storage.set(&DataKey::BorrowIndex(asset.clone()), &accrual.borrow_index);
+            if accrual.total_stable_debt > 0 {
+                storage.set(&DataKey::TotalStableDebt(asset.clone()), &accrual.total_stable_debt);
+            }
+            storage.set(&DataKey::TotalBorrow(asset.clone()), &accrual.total_borrow);
+            storage.set(&DataKey::TotalSupply(asset.clone()), &accrual.total_supply);
+            storage.set(&DataKey::TotalReserves(asset.clone()), &accrual.total_reserves);
+        }
+
+        // Update last accrual timestamp
+        env.storage().instance().set(&DataKey::LastAccrualTime(asset), &current_time);
+
+        Ok(())
+    }
+
+    /// Compute the pool state `accrue_interest` would write now, without
+    /// writing it
+    ///
+    /// Returns `None` if no time has passed or there is nothing to accrue on.
+    fn project_accrual(env: &Env, asset: &Symbol) -> Result<Option<Accrual>, PoolError> {
+        // Get timestamps
+        let last_accrual: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastAccrualTime(asset.clone()))
+            .unwrap_or(0);
+        let current_time = env.ledger().timestamp();
+        if current_time <= last_accrual {
+            return Ok(None);
+        }
+
+        let time_elapsed = current_time - last_accrual;
+        
+        // Get current pool state
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        
+        // Skip if nothing to accrue on
+        if total_borrow == 0 || total_supply == 0 {
+            return Ok(None);
+        }
+
+        // ====================================================================
+        // STEP 1: Calculate utilization rate
+        // ====================================================================
+        // Utilization = Total Borrowed / Total Supplied
+        // Scaled by SCALE (1e7), so 80% = 8_000_000
+        let utilization = utilization_rate(total_borrow, total_supply)?;
+
+        // ====================================================================
+        // STEP 2: Get borrow rate from Interest Rate Model
+        // ====================================================================
+        let annual_borrow_rate = Self::calculate_borrow_rate(env, asset, utilization)?;
+        
+        // Growth of a unit of debt over the elapsed time, in ray:
+        // (1 + annual_rate / seconds_per_year) ^ time_elapsed, compounded per second in ray precision, so frequent accruals at
+        // ledger cadence do not truncate the interest away
+        let rate_per_second = ray_rate_per_second(annual_borrow_rate, SCALE, SECONDS_PER_YEAR)?;
+        let growth = compound_interest(rate_per_second, time_elapsed)?;
+
+        // ====================================================================
+        // STEP 3: Update borrow index
+        // ====================================================================
+        // The borrow index tracks accumulated interest over time
+        // User debt = principal * current_index / user_index_at_borrow
+        let current_borrow_index: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowIndex(asset.clone()))
+            .unwrap_or(INITIAL_BORROW_INDEX);
+        
+        // new_index = current_index * growth
+        let borrow_index = mul_div(current_borrow_index, growth, RAY, Rounding::Down)?;
+
+        // ====================================================================
+        // STEP 4: Calculate and distribute interest
+        // ====================================================================
+        // Variable borrows accrue at the utilization rate, stable borrows at
+        // their debt-weighted average locked rate
+        let total_stable_debt: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalStableDebt(asset.clone()))
+            .unwrap_or(0);
+        let variable_borrow = if total_borrow > total_stable_debt { total_borrow - total_stable_debt } else { 0 };
+        let variable_interest = mul_div(variable_borrow, growth - RAY, RAY, Rounding::Down)?;
+
+        let stable_interest = if total_stable_debt > 0 {
+            let avg_stable_rate: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::AvgStableRate(asset.clone()))
+                .unwrap_or(0);
+            let stable_factor = mul_div(avg_stable_rate, time_elapsed as i128, SECONDS_PER_YEAR, Rounding::Down)?;
+            mul_div(total_stable_debt, stable_factor, SCALE, Rounding::Down)?
+        } else {
+            0
+        };
+
+        // Total interest accrued on all borrows
+        let interest_accrued = variable_interest + stable_interest;
+
+        // Split between suppliers and protocol reserves
+        let reserve_factor: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::ReserveFactor(asset.clone()))
+            .unwrap_or(1_000_000); // Default 10%
+        
+        let reserve_interest = mul_div(interest_accrued, reserve_factor, SCALE, Rounding::Down)?;
+        let supplier_interest = interest_accrued - reserve_interest;
+
+        let current_reserves: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalReserves(asset.clone()))
+            .unwrap_or(0);
+
+        Ok(Some(Accrual {
+            borrow_index,
+            // Outstanding borrows grow by the accrued interest
+            total_borrow: total_borrow + interest_accrued,
+            // The suppliers' portion grows the sToken value
+            total_supply: total_supply + supplier_interest,
+            total_reserves: current_reserves + reserve_interest,
+            total_stable_debt: total_stable_debt + stable_interest,
+        }))
+    }
+
+    /// Get the annual borrow rate for a utilization from the asset's Interest Rate Model
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `utilization` - Pool utilization (scaled by 1e7)
+    ///
+    /// # Returns
+    /// Annualized borrow rate (scaled by 1e7)
+    fn calculate_borrow_rate(env: &Env, asset: &Symbol, utilization: i128) -> Result<i128, PoolError> {
+        let irm = Self::get_reserve_rate_model(env, asset)?;
+        let irm_client = interest_rate_model_contract::Client::new(env, &irm);
+        let token = Self::get_token_address(env, asset)?;
+        Ok(irm_client.get_asset_borrow_rate(&token, &utilization))
+    }
+
+    /// Get the borrow and supply APRs at a utilization, with one rate model call
+    ///
+    /// Supply rate = borrow_rate * utilization * (1 - reserve_factor)
+    fn rates_at(env: &Env, asset: &Symbol, utilization: i128) -> Result<(i128, i128), PoolError> {
+        let reserve_factor: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::ReserveFactor(asset.clone()))
+            .unwrap_or(1_000_000);
+        let irm = Self::get_reserve_rate_model(env, asset)?;
+        let token = Self::get_token_address(env, asset)?;
+        Ok(interest_rate_model_contract::Client::new(env, &irm).get_asset_rates(&token, &utilization, &reserve_factor))
+    }
+
+    // ========================================================================
+    // INTERNAL HELPERS
+    // ========================================================================
+
+    /// Get exchange rate for sTokens
+    ///
+    /// `TotalSupply` is the underlying owed to suppliers: deposits plus the
+    /// suppliers' share of accrued interest (reserves are tracked separately).
+    /// exchange_rate = total_supply * 1e9 / total_shares
+    fn get_exchange_rate_internal(env: &Env, asset: Symbol) -> Result<i128, PoolError> {
+        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
+        
+        if total_shares == 0 {
+            return Ok(INITIAL_EXCHANGE_RATE);
+        }
+
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        
+        Ok(mul_div(total_supply, INITIAL_EXCHANGE_RATE, total_shares, Rounding::Down)?)
+    }
+
+    /// Get user's debt including accrued interest
+    fn get_user_debt_with_interest(env: &Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let current_borrow_index: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowIndex(asset.clone()))
+            .unwrap_or(INITIAL_BORROW_INDEX);
+        Self::get_user_debt_at_index(env, user, asset, current_borrow_index)
+    }
+
+    /// Get user's variable-rate debt at a given borrow index
+    ///
+    /// Interest accrued since the user's snapshot is reduced by their rate
+    /// discount, if any.
+    fn get_user_debt_at_index(env: &Env, user: Address, asset: Symbol, borrow_index: i128) -> Result<i128, PoolError> {
+        let (principal, debt) = Self::get_user_debt_undiscounted(env, user.clone(), asset, borrow_index)?;
+        let discount = Self::get_rate_discount(env.clone(), user);
+        if discount == 0 || debt <= principal {
+            return Ok(debt);
+        }
+        Ok(debt - mul_div(debt - principal, discount, SCALE, Rounding::Down)?)
+    }
+
+    /// Get user's variable-rate principal and its value at a given borrow
+    /// index, before any rate discount
+    fn get_user_debt_undiscounted(
+        env: &Env,
+        user: Address,
+        asset: Symbol,
+        borrow_index: i128,
+    ) -> Result<(i128, i128), PoolError> {
+        let principal: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserDebt(user.clone(), asset.clone()))
+            .unwrap_or(0);
+        
+        if principal == 0 {
+            return Ok((0, 0));
+        }
+
+        let mut user_borrow_index: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserBorrowIndex(user, asset))
+            .unwrap_or(INITIAL_BORROW_INDEX);
+        if user_borrow_index < LEGACY_BORROW_INDEX_LIMIT {
+            user_borrow_index *= RAY / EXCHANGE_RATE_SCALE;
+        }
+
+        // debt = principal * current_index / user_index
+        Ok((principal, mul_div(principal, borrow_index, user_borrow_index, Rounding::Down)?))
+    }
+
+    /// Store a user's debt as principal at the current borrow index
+    ///
+    /// `debt` must already include interest accrued up to now.
+    fn set_user_debt(env: &Env, user: &Address, asset: &Symbol, debt: i128) {
+        let borrow_index: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowIndex(asset.clone()))
+            .unwrap_or(INITIAL_BORROW_INDEX);
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserDebt(user.clone(), asset.clone()), &debt);
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserBorrowIndex(user.clone(), asset.clone()), &borrow_index);
+        Self::update_borrower_registry(env, user, debt > 0);
+    }
+
+    /// Add a user to the borrower registry, or remove them once no debt is left
+    ///
+    /// `has_debt` is whether the debt just written is nonzero; a user is only
+    /// removed when no reserve holds debt for them.
+    fn update_borrower_registry(env: &Env, user: &Address, has_debt: bool) {
+        let persistent = env.storage().persistent();
+        let slot_key = UserKey::BorrowerSlot(user.clone());
+        let slot: Option<u32> = persistent.get(&slot_key);
+        let count: u32 = env.storage().instance().get(&ProtocolKey::BorrowerCount).unwrap_or(0);
+
+        if has_debt {
+            if slot.is_none() {
+                persistent.set(&UserKey::BorrowerAt(count), user);
+                persistent.set(&slot_key, &count);
+                env.storage().instance().set(&ProtocolKey::BorrowerCount, &(count + 1));
+            }
+            return;
+        }
+
+        let Some(slot) = slot else {
+            return;
+        };
+        let still_borrowing = Self::list_reserves(env.clone()).iter().any(|asset| {
+            persistent.get::<_, i128>(&DataKey::UserDebt(user.clone(), asset.clone())).unwrap_or(0) > 0
+                || persistent.has(&DataKey::UserStableDebt(user.clone(), asset))
+        });
+        if still_borrowing {
+            return;
+        }
+
+        // Move the last entry into the freed slot
+        let last = count - 1;
+        if slot != last {
+            let moved: Address = persistent.get(&UserKey::BorrowerAt(last)).unwrap();
+            persistent.set(&UserKey::BorrowerAt(slot), &moved);
+            persistent.set(&UserKey::BorrowerSlot(moved), &slot);
+        }
+        persistent.remove(&UserKey::BorrowerAt(last));
+        persistent.remove(&slot_key);
+        env.storage().instance().set(&ProtocolKey::BorrowerCount, &last);
+    }
+
+    /// Get a user's stable-rate debt including interest accrued at the locked rate
+    fn get_user_stable_debt_with_interest(env: &Env, user: &Address, asset: &Symbol) -> Result<i128, PoolError> {
+        let position: Option<StableDebt> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserStableDebt(user.clone(), asset.clone()));
+        let Some(position) = position else {
+            return Ok(0);
+        };
+
+        let elapsed = env.ledger().timestamp().saturating_sub(position.last_update) as i128;
+        let interest_factor = mul_div(position.rate, elapsed, SECONDS_PER_YEAR, Rounding::Down)?;
+        Ok(position.principal + mul_div(position.principal, interest_factor, SCALE, Rounding::Down)?)
+    }
+
+    /// Get a user's debt in both rate modes, including accrued interest
+    fn get_user_total_debt(env: &Env, user: &Address, asset: &Symbol) -> Result<i128, PoolError> {
+        let variable_debt = Self::get_user_debt_with_interest(env, user.clone(), asset.clone())?;
+        Ok(variable_debt + Self::get_user_stable_debt_with_interest(env, user, asset)?)
+    }
+
+    /// Store a user's stable-rate debt and locked rate as of now
+    ///
+    /// `debt` must already include interest accrued up to now. The pool-wide
+    /// stable debt and its average rate are updated to match (0 removes the
+    /// position).
+    fn set_user_stable_debt(env: &Env, user: &Address, asset: &Symbol, debt: i128, rate: i128) -> Result<(), PoolError> {
+        let old_debt = Self::get_user_stable_debt_with_interest(env, user, asset)?;
+        let old_rate = Self::get_user_stable_rate(env.clone(), user.clone(), asset.clone());
+
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalStableDebt(asset.clone()))
+            .unwrap_or(0);
+        let avg_rate: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::AvgStableRate(asset.clone()))
+            .unwrap_or(0);
+
+        // Swap the user's old contribution to the weighted average for the new one.
+        // Weights are yearly interest (debt * rate / SCALE). Per-user and
+        // pool-wide accrual round independently, so clamp at zero.
+        let remaining = if total > old_debt { total - old_debt } else { 0 };
+        let remaining_weight = (mul_div(total, avg_rate, SCALE, Rounding::Down)?
+            - mul_div(old_debt, old_rate, SCALE, Rounding::Down)?)
+        .max(0);
+        let new_total = remaining + debt;
+        let new_avg_rate = if new_total == 0 {
+            0
+        } else {
+            let new_weight = remaining_weight + mul_div(debt, rate, SCALE, Rounding::Down)?;
+            mul_div(new_weight, SCALE, new_total, Rounding::Down)?
+        };
+        env.storage().instance().set(&DataKey::TotalStableDebt(asset.clone()), &new_total);
+        env.storage().instance().set(&DataKey::AvgStableRate(asset.clone()), &new_avg_rate);
+
+        let key = DataKey::UserStableDebt(user.clone(), asset.clone());
+        if debt == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(
+                &key,
+                &StableDebt {
+                    principal: debt,
+                    rate,
+                    last_update: env.ledger().timestamp(),
+                },
+            );
+        }
+        Self::update_borrower_registry(env, user, debt > 0);
+
+        Ok(())
+    }
+
+    /// Reduce a user's debt by `amount`, variable-rate debt first
+    ///
+    /// `amount` must not exceed the user's total debt. Repaid debt backed by
+    /// isolated collateral releases the same share of the USD value it was
+    /// charged against that asset's debt ceiling at borrow time, so no price
+    /// is needed.
+    fn reduce_user_debt(env: &Env, user: &Address, asset: &Symbol, amount: i128) -> Result<(), PoolError> {
+        let key = UserKey::IsolatedDebt(user.clone(), asset.clone());
+        let charged: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if charged > 0 {
+            let total_debt = Self::get_user_total_debt(env, user, asset)?;
+            let released = if amount >= total_debt {
+                charged
+            } else {
+                mul_div(charged, amount, total_debt, Rounding::Down)?
+            };
+            if released == charged {
+                env.storage().persistent().remove(&key);
+            } else {
+                env.storage().persistent().set(&key, &(charged - released));
+            }
+
+            if let Some(isolated_asset) = Self::get_isolated_collateral(env, user) {
+                let isolated_debt = Self::get_isolated_debt(env.clone(), isolated_asset.clone());
+                env.storage()
+                    .instance()
+                    .set(&ReserveKey::IsolatedDebt(isolated_asset), &(isolated_debt - released).max(0));
+            }
+        }
+
+        let variable_debt = Self::get_user_debt_with_interest(env, user.clone(), asset.clone())?;
+        let from_variable = amount.min(variable_debt);
+        if from_variable > 0 {
+            Self::set_user_debt(env, user, asset, variable_debt - from_variable);
+        }
+
+        let from_stable = amount - from_variable;
+        if from_stable > 0 {
+            let stable_debt = Self::get_user_stable_debt_with_interest(env, user, asset)?;
+            let rate = Self::get_user_stable_rate(env.clone(), user.clone(), asset.clone());
+            let remaining = if stable_debt > from_stable { stable_debt - from_stable } else { 0 };
+            Self::set_user_stable_debt(env, user, asset, remaining, if remaining > 0 { rate } else { 0 })?;
+        }
+
+        Self::notify_borrow_change(env, asset, user)
+    }
+
+    /// Internal: Report a user's sToken shares to the emissions controller
+    fn notify_supply_change(env: &Env, asset: &Symbol, user: &Address) {
+        if let Some(emissions) = Self::get_emissions(env.clone()) {
+            let shares = Self::get_user_shares(env.clone(), user.clone(), asset.clone());
+            EmissionsHooksClient::new(env, &emissions).handle_balance_change(asset, &RewardSide::Supply, user, &shares);
+        }
+    }
+
+    /// Internal: Report a user's debt, scaled down by the borrow index, to the emissions controller
+    ///
+    /// Dividing by the index keeps the balance constant as interest accrues,
+    /// so borrowers are weighted by what they borrowed, not by their interest.
+    fn notify_borrow_change(env: &Env, asset: &Symbol, user: &Address) -> Result<(), PoolError> {
+        if let Some(emissions) = Self::get_emissions(env.clone()) {
+            let borrow_index: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::BorrowIndex(asset.clone()))
+                .unwrap_or(INITIAL_BORROW_INDEX);
+            let debt = Self::get_user_total_debt(env, user, asset)?;
+            let scaled_debt = mul_div(debt, INITIAL_BORROW_INDEX, borrow_index, Rounding::Down)?;
+            EmissionsHooksClient::new(env, &emissions).handle_balance_change(asset, &RewardSide::Borrow, user, &scaled_debt);
+        }
+        Ok(())
+    }
+
+    /// Get the stable borrow rate the rate model offers at a utilization
+    fn calculate_stable_rate(env: &Env, asset: &Symbol, utilization: i128) -> Result<i128, PoolError> {
+        let irm = Self::get_reserve_rate_model(env, asset)?;
+        let irm_client = interest_rate_model_contract::Client::new(env, &irm);
+        let token = Self::get_token_address(env, asset)?;
+        Ok(irm_client.get_asset_stable_rate(&token, &utilization))
+    }
+
+    /// Internal: Debt-weighted rate after adding `added` at `offered_rate` to
+    /// `debt` at `current_rate`
+    ///
+    /// Computed as `current + added * (offered - current) / (debt + added)`
+    /// and rounded toward the higher rate, against the borrower.
+    fn blend_stable_rate(debt: i128, current_rate: i128, added: i128, offered_rate: i128) -> Result<i128, PoolError> {
+        let rounding = if offered_rate >= current_rate { Rounding::Up } else { Rounding::Down };
+        Ok(current_rate + mul_div(added, offered_rate - current_rate, debt + added, rounding)?)
+    }
+
+    /// Get asset price from oracle
+    ///
+    /// Calls the Price Oracle contract to get current USD price for an asset,
+    /// keyed by the asset's token contract so that same-code assets from
+    /// different issuers are never confused.
+    ///
+    /// # Arguments
+    /// * `env` - Soroban environment
+    /// * `oracle` - Oracle contract address
+    /// * `asset` - Asset symbol (XLM or USDC)
+    ///
+    /// # Returns
+    /// Price in USD (scaled by 1e7)
+    ///
+    /// # Errors
+    /// `PriceUnavailable` if the oracle has no price for the asset
+    fn get_asset_price(env: &Env, oracle: &Address, asset: &Symbol) -> Result<i128, PoolError> {
+        let oracle_client = oracle_contract::Client::new(env, oracle);
+        match oracle_client.try_get_price(&Self::get_token_address(env, asset)?) {
+            // Never value positions at a made-up price
+            Ok(Ok(price)) if price > 0 => Ok(price),
+            _ => Err(PoolError::PriceUnavailable),
+        }
+    }
+
+    /// Get the price used to value an asset held as collateral
+    ///
+    /// Uses the oracle's time-weighted average over the configured TWAP
+    /// window, so a short-lived spot spike cannot inflate borrowing power.
+    /// Falls back to the spot price when the window is 0.
+    fn get_collateral_price(env: &Env, oracle: &Address, asset: &Symbol) -> Result<i128, PoolError> {
+        let window = Self::get_twap_window(env.clone());
+        if window == 0 {
+            return Self::get_asset_price(env, oracle, asset);
+        }
+
+        let oracle_client = oracle_contract::Client::new(env, oracle);
+        let token = Self::get_token_address(env, asset)?;
+        match oracle_client.try_get_twap(&token, &window) {
+            Ok(Ok(price)) if price > 0 => Ok(price),
+            _ => Err(PoolError::PriceUnavailable),
+        }
+    }
+
+    /// Get the token contract for an asset
+    fn get_token_address(env: &Env, asset: &Symbol) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::TokenAddress(asset.clone()))
+            .ok_or(PoolError::UnknownAsset)
+    }
+
+    /// Internal: Factor between an asset's token units and canonical units,
+    /// and whether the token has more decimals than `CANONICAL_DECIMALS`
+    fn decimals_factor(env: &Env, asset: &Symbol) -> Result<(i128, bool), PoolError> {
+        let decimals: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Decimals(asset.clone()))
+            .unwrap_or(CANONICAL_DECIMALS);
+        let factor = 10i128
+            .checked_pow(decimals.abs_diff(CANONICAL_DECIMALS))
+            .ok_or(PoolError::MathOverflow)?;
+        Ok((factor, decimals > CANONICAL_DECIMALS))
+    }
+
+    /// Internal: Convert a canonical amount of `asset` to token units
+    fn to_token_units(env: &Env, asset: &Symbol, amount: i128, rounding: Rounding) -> Result<i128, PoolError> {
+        match Self::decimals_factor(env, asset)? {
+            (factor, true) => amount.checked_mul(factor).ok_or(PoolError::MathOverflow),
+            (factor, false) => Ok(mul_div(amount, 1, factor, rounding)?),
+        }
+    }
+
+    /// Internal: Convert an amount in `asset`'s token units to canonical
+    /// units, rounding down
+    fn from_token_units(env: &Env, asset: &Symbol, amount: i128) -> Result<i128, PoolError> {
+        match Self::decimals_factor(env, asset)? {
+            (factor, true) => Ok(mul_div(amount, 1, factor, Rounding::Down)?),
+            (factor, false) => amount.checked_mul(factor).ok_or(PoolError::MathOverflow),
+        }
+    }
+
+    /// Get the interest rate model for an asset, falling back to the pool default
+    fn get_reserve_rate_model(env: &Env, asset: &Symbol) -> Result<Address, PoolError> {
+        match env
+            .storage()
+            .instance()
+            .get(&DataKey::ReserveRateModel(asset.clone()))
+        {
+            Some(irm) => Ok(irm),
+            None => Self::get_interest_rate_model(env.clone()),
+        }
+    }
+
+    /// Get the price oracle contract
+    fn get_oracle(env: &Env) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::PriceOracle)
+            .ok_or(PoolError::NotInitialized)
+    }
+
+    /// Extend the TTL of the contract instance and a user's position entries
+    ///
+    /// Called whenever a position is touched so that long-idle positions are
+    /// not archived. Entries that do not exist are skipped.
+    fn extend_user_ttl(env: &Env, user: &Address) {
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        let persistent = env.storage().persistent();
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let keys = [
+                DataKey::UserShares(user.clone(), asset.clone()),
+                DataKey::UserCollateral(user.clone(), asset.clone()),
+                DataKey::UserDebt(user.clone(), asset.clone()),
+                DataKey::UserBorrowIndex(user.clone(), asset.clone()),
+                DataKey::UserStableDebt(user.clone(), asset.clone()),
+            ];
+            for key in keys.iter() {
+                if persistent.has(key) {
+                    persistent.extend_ttl(key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+                }
+            }
+            let keys = [
+                UserKey::SupplyCollateral(user.clone(), asset.clone()),
+                UserKey::IsolatedDebt(user.clone(), asset.clone()),
+            ];
+            for key in keys.iter() {
+                if persistent.has(key) {
+                    persistent.extend_ttl(key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+                }
+            }
+        }
+        let slot_key = UserKey::BorrowerSlot(user.clone());
+        if let Some(slot) = persistent.get::<_, u32>(&slot_key) {
+            persistent.extend_ttl(&slot_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+            persistent.extend_ttl(&UserKey::BorrowerAt(slot), LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        }
+    }
+
+    /// Compute a protocol fee owed by `payer`
+    ///
+    /// All protocol fees (origination, deposit, withdrawal, flash loan) go through this
+    /// helper so that fee-exempt addresses are handled in a single place.
+    /// Liquidation bonuses are not fees and never pass through here.
+    ///
+    /// # Returns
+    /// `amount * fee_rate / SCALE` rounded up against the payer, or 0 if
+    /// `payer` is fee-exempt
+    fn compute_fee(env: &Env, payer: &Address, amount: i128, fee_rate: i128) -> Result<i128, PoolError> {
+        if fee_rate <= 0 || Self::is_fee_exempt(env.clone(), payer.clone()) {
+            return Ok(0);
+        }
+        Ok(mul_div(amount, fee_rate, SCALE, Rounding::Up)?)
+    }
+
+    /// Total outstanding debt across all borrowable assets, in USD (scaled by 1e7)
+    fn total_borrows_usd(env: &Env, prices: &mut PriceCache) -> Result<i128, PoolError> {
+        let mut total_usd: i128 = 0;
+
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+            if total_borrow > 0 {
+                let price = prices.spot(env, &asset)?;
+                total_usd += mul_div(total_borrow, price, SCALE, Rounding::Down)?;
+            }
+        }
+
+        Ok(total_usd)
+    }
+
+    /// Trip or release the global borrow pause based on system-wide debt
+    ///
+    /// Borrows are blocked once total debt reaches the global limit, and only
+    /// re-enabled when it falls below `limit * (1 - hysteresis)`.
+    fn update_global_borrow_pause(env: &Env) -> Result<(), PoolError> {
+        Self::update_global_borrow_pause_with(env, &mut PriceCache::new(env)?)
+    }
+
+    /// `update_global_borrow_pause`, reading prices through `prices`
+    fn update_global_borrow_pause_with(env: &Env, prices: &mut PriceCache) -> Result<(), PoolError> {
+        let limit = Self::get_global_borrow_limit(env.clone());
+        let paused = Self::is_global_borrow_paused(env.clone());
+
+        if limit <= 0 {
+            if paused {
+                env.storage().instance().set(&DataKey::GlobalBorrowPaused, &false);
+            }
+            return Ok(());
+        }
+
+        let total_usd = Self::total_borrows_usd(env, prices)?;
+
+        if !paused && total_usd >= limit {
+            env.storage().instance().set(&DataKey::GlobalBorrowPaused, &true);
+            env.events().publish((symbol_short!("gbl_pause"),), (true, total_usd));
+        } else if paused {
+            let hysteresis: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::GlobalBorrowLimitHysteresis)
+                .unwrap_or(DEFAULT_GLOBAL_BORROW_HYSTERESIS);
+            let release_level = mul_div(limit, SCALE - hysteresis, SCALE, Rounding::Down)?;
+
+            if total_usd < release_level {
+                env.storage().instance().set(&DataKey::GlobalBorrowPaused, &false);
+                env.events().publish((symbol_short!("gbl_pause"),), (false, total_usd));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Integer square root (floor) using Newton's method
+    fn isqrt(n: i128) -> i128 {
+        if n <= 0 {
+            return 0;
+        }
+        let mut x = n;
+        let mut y = (x + 1) / 2;
+        while y < x {
+            x = y;
+            y = (x + n / x) / 2;
+        }
+        x
+    }
+
+    /// Value a user's collateral and debt across all reserves
+    ///
+    /// Collateral counts towards borrow capacity at its (volatility-adjusted)
+    /// LTV and towards the health factor at its liquidation threshold. Debt
+    /// includes interest accrued through the borrow index. Collateral in a
+    /// reserve that is no longer enabled as collateral adds no borrow
+    /// capacity but still backs the health factor, so disabling a reserve
+    /// never makes existing positions liquidatable.
+    fn account_data(env: &Env, user: &Address) -> Result<AccountData, PoolError> {
+        Self::account_data_with(env, user, &mut PriceCache::new(env)?)
+    }
+
+    /// `account_data`, reading prices through `prices`
+    fn account_data_with(env: &Env, user: &Address, prices: &mut PriceCache) -> Result<AccountData, PoolError> {
+        let mut collateral_value_usd: i128 = 0;
+        let mut borrow_capacity_usd: i128 = 0; // collateral * LTV
+        let mut threshold_collateral_usd: i128 = 0; // collateral * liquidation threshold
+        let mut debt_value_usd: i128 = 0;
+
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let collateral: i128 = env
+                .storage()
+                .persistent()
+                .get::<_, i128>(&DataKey::UserCollateral(user.clone(), asset.clone()))
+                .unwrap_or(0)
+                + Self::get_supplied_collateral(env, user, &asset)?;
+            let debt = Self::get_user_total_debt(env, user, &asset)?;
+            if collateral == 0 && debt == 0 {
+                continue;
+            }
+
+            if collateral > 0 {
+                let price = prices.collateral(env, &asset)?;
+                let value = mul_div(collateral, price, SCALE, Rounding::Down)?;
+                collateral_value_usd += value;
+
+                if Self::is_collateral_enabled(env, &asset) {
+                    let ltv = Self::volatility_adjusted_cf(env, &asset, prices)?;
+                    borrow_capacity_usd += mul_div(value, ltv, SCALE, Rounding::Down)?;
+                }
+
+                let liq_threshold = Self::get_liquidation_threshold(env.clone(), asset.clone());
+                threshold_collateral_usd += mul_div(value, liq_threshold, SCALE, Rounding::Down)?;
+            }
+
+            if debt > 0 {
+                let price = prices.spot(env, &asset)?;
+                debt_value_usd += mul_div(debt, price, SCALE, Rounding::Down)?;
+            }
+        }
+
+        // HF = sum(collateral_usd * liquidation_threshold) / debt
+        let health_factor = if debt_value_usd == 0 {
+            999 * SCALE // Infinite
+        } else {
+            mul_div(threshold_collateral_usd, SCALE, debt_value_usd, Rounding::Down)?
+        };
+
+        Ok(AccountData {
+            collateral_value_usd,
+            debt_value_usd,
+            borrow_capacity_usd,
+            threshold_collateral_usd,
+            health_factor,
+        })
+    }
+
+    /// Internal: The isolated asset backing a user's debt, if their collateral is isolated
+    fn get_isolated_collateral(env: &Env, user: &Address) -> Option<Symbol> {
+        Self::list_reserves(env.clone()).iter().find(|asset| {
+            Self::is_isolated(env.clone(), asset.clone()) && Self::uses_as_collateral(env, user, asset)
+        })
+    }
+
+    /// Internal: Whether a reserve accepts collateral
+    fn is_collateral_enabled(env: &Env, asset: &Symbol) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::CollateralEnabled(asset.clone()))
+            .unwrap_or(false)
+    }
+
+    // ========================================================================
+    // KEEPER INCENTIVES
+    // ========================================================================
+
+    /// Accrue an asset's interest and extend the pool's TTL
+    ///
+    /// Permissionless keeper function: keeps the indexes fresh when no user
+    /// touches the reserve. If at least the poke interval has passed since
+    /// the last accrual, `caller` is paid the poke reward out of the asset's
+    /// protocol reserves, or whatever is left of them.
+    ///
+    /// # Returns
+    /// Reward paid
+    pub fn poke(env: Env, caller: Address, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_token_address(&env, &asset)?;
+        let last_accrual: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastAccrualTime(asset.clone()))
+            .unwrap_or(0);
+        let elapsed = env.ledger().timestamp().saturating_sub(last_accrual);
+
+        Self::accrue_interest(&env, asset.clone())?;
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        let (reward, interval) = Self::get_poke_reward(env.clone(), asset.clone());
+        let reserves = Self::get_total_reserves(env.clone(), asset.clone());
+        // A poke in the same ledger as the last accrual did no work
+        let paid = if elapsed > 0 && elapsed >= interval { reward.min(reserves) } else { 0 };
+        if paid > 0 {
+            env.storage().instance().set(&DataKey::TotalReserves(asset.clone()), &(reserves - paid));
+            Self::send_underlying(&env, &asset, &caller, paid)?;
+        }
+
+        env.events().publish((symbol_short!("poke"), caller, asset), (elapsed, paid));
+
+        Self::assert_invariants(&env);
+        Ok(paid)
+    }
+
+    /// Set the reward for poking an asset (admin only)
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `reward` - Reserves paid per rewarded poke, in pool units (0 = none)
+    /// * `min_interval` - Seconds since the last accrual before a poke is
+    ///   rewarded; must be positive when `reward` is
+    pub fn set_poke_reward(env: Env, asset: Symbol, reward: i128, min_interval: u64) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+        Self::get_token_address(&env, &asset)?;
+
+        if reward < 0 || (reward > 0 && min_interval == 0) {
+            return Err(PoolError::InvalidAmount);
+        }
+        env.storage().instance().set(&ReserveKey::PokeReward(asset.clone()), &reward);
+        env.storage().instance().set(&ReserveKey::PokeInterval(asset.clone()), &min_interval);
+
+        env.events().publish((symbol_short!("poke_rwd"), asset), (reward, min_interval));
+
+        Ok(())
+    }
+
+    /// Get an asset's poke reward and the interval that earns it
+    pub fn get_poke_reward(env: Env, asset: Symbol) -> (i128, u64) {
+        let storage = env.storage().instance();
+        (
+            storage.get(&ReserveKey::PokeReward(asset.clone())).unwrap_or(0),
+            storage.get(&ReserveKey::PokeInterval(asset)).unwrap_or(0),
+        )
+    }
+
+    // ========================================================================
+    // VIEW FUNCTIONS
+    // ========================================================================
+
+    /// Extend the storage TTL of a user's position
+    ///
+    /// Permissionless keeper function: positions are extended automatically
+    /// when touched, but idle positions need an occasional bump to avoid
+    /// being archived.
+    pub fn bump_user(env: Env, user: Address) {
+        Self::extend_user_ttl(&env, &user);
+    }
+
+    /// Get user's complete position across all assets
+    pub fn get_user_position(env: Env, user: Address) -> Result<UserPosition, PoolError> {
+        let account = Self::account_data(&env, &user)?;
+
+        // Calculate available borrow (max borrow - current debt)
+        let available_borrow_usd = if account.borrow_capacity_usd > account.debt_value_usd {
+            account.borrow_capacity_usd - account.debt_value_usd
+        } else {
+            0
+        };
+
+        Ok(UserPosition {
+            collateral_value_usd: account.collateral_value_usd,
+            debt_value_usd: account.debt_value_usd,
+            available_borrow_usd,
+            health_factor: account.health_factor,
+        })
+    }
+
+    /// Get the largest amount of `asset` a user can borrow right now
+    ///
+    /// Accounts for remaining LTV capacity at current oracle prices (debt
+    /// includes accrued interest), the origination fee, available pool
+    /// liquidity and the borrow cap. Returns 0 if borrowing is disabled,
+    /// paused or frozen by emergency mode.
+    pub fn get_max_borrowable(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let borrow_enabled: bool = env
+            .storage()
+            .instance()
+            .get(&DataKey::BorrowEnabled(asset.clone()))
+            .unwrap_or(false);
+        if !borrow_enabled
+            || Self::is_global_borrow_paused(env.clone())
+            || Self::is_action_paused(env.clone(), PoolAction::Borrow)
+            || Self::is_emergency_mode(env.clone())
+        {
+            return Ok(0);
+        }
+
+        let account = Self::account_data(&env, &user)?;
+        if account.borrow_capacity_usd <= account.debt_value_usd {
+            return Ok(0);
+        }
+
+        // Convert remaining capacity to asset units, leaving room for the fee
+        let oracle = Self::get_oracle(&env)?;
+        let price = Self::get_asset_price(&env, &oracle, &asset)?;
+        let capacity = mul_div(account.borrow_capacity_usd - account.debt_value_usd, SCALE, price, Rounding::Down)?;
+        let fee_rate = if Self::is_fee_exempt(env.clone(), user) {
+            0
+        } else {
+            Self::get_origination_fee(env.clone(), asset.clone())
+        };
+        let mut max_amount = mul_div(capacity, SCALE, SCALE + fee_rate, Rounding::Down)?;
+
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        max_amount = max_amount.min(total_supply - total_borrow);
+
+        let borrow_cap = Self::get_borrow_cap(env.clone(), asset);
+        if borrow_cap > 0 {
+            let headroom = mul_div(borrow_cap - total_borrow, SCALE, SCALE + fee_rate, Rounding::Down)?;
+            max_amount = max_amount.min(headroom);
+        }
+
+        Ok(max_amount.max(0))
+    }
+
+    /// Get the largest amount of collateral `asset` a user can withdraw
+    ///
+    /// Keeps just enough collateral for the health factor to stay at or above
+    /// 1.0 at current oracle prices. Returns the full balance if the user has
+    /// no debt.
+    pub fn get_max_withdrawable_collateral(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let collateral = Self::get_user_collateral(env.clone(), user.clone(), asset.clone());
+        if collateral == 0 {
+            return Ok(0);
+        }
+
+        let account = Self::account_data(&env, &user)?;
+        if account.debt_value_usd == 0 {
+            return Ok(collateral);
+        }
+
+        // Threshold-weighted value contributed by the other collateral assets
+        let oracle = Self::get_oracle(&env)?;
+        let price = Self::get_collateral_price(&env, &oracle, &asset)?;
+        let liq_threshold = Self::get_liquidation_threshold(env.clone(), asset);
+        let own_value_usd = mul_div(collateral, price, SCALE, Rounding::Down)?;
+        let own_threshold_usd = mul_div(own_value_usd, liq_threshold, SCALE, Rounding::Down)?;
+        let other_threshold_usd = account.threshold_collateral_usd - own_threshold_usd;
+
+        let needed_threshold_usd = account.debt_value_usd - other_threshold_usd;
+        if needed_threshold_usd <= 0 {
+            return Ok(collateral);
+        }
+        if liq_threshold == 0 {
+            return Ok(0);
+        }
+
+        // Round the collateral to keep up so the remaining position stays healthy
+        let keep_value_usd = mul_div(needed_threshold_usd, SCALE, liq_threshold, Rounding::Up)?;
+        let keep_amount = mul_div(keep_value_usd, SCALE, price, Rounding::Up)?;
+
+        Ok((collateral - keep_amount).max(0))
+    }
+
+    /// Get a summary of a user's account in a single call
+    ///
+    /// Collateral-weighted LTV and liquidation threshold are averaged over
+    /// all of the user's collateral assets; ratios are 0 without collateral.
+    pub fn get_user_account_data(env: Env, user: Address) -> Result<UserAccountData, PoolError> {
+        let account = Self::account_data(&env, &user)?;
+        let collateral = account.collateral_value_usd;
+
+        let (current_ltv, max_ltv, liquidation_threshold) = if collateral > 0 {
+            (
+                mul_div(account.debt_value_usd, SCALE, collateral, Rounding::Up)?,
+                mul_div(account.borrow_capacity_usd, SCALE, collateral, Rounding::Down)?,
+                mul_div(account.threshold_collateral_usd, SCALE, collateral, Rounding::Down)?,
+            )
+        } else {
+            (0, 0, 0)
+        };
+
+        Ok(UserAccountData {
+            total_collateral_usd: collateral,
+            total_debt_usd: account.debt_value_usd,
+            available_borrow_usd: (account.borrow_capacity_usd - account.debt_value_usd).max(0),
+            current_ltv,
+            max_ltv,
+            liquidation_threshold,
+            health_factor: account.health_factor,
+        })
+    }
+
+    /// Get market information for an asset
+    /// Get market information for an asset
+    /// 
+    /// Returns comprehensive market data including supply, borrow, rates, etc.
+    pub fn get_market_info(env: Env, asset: Symbol) -> Result<MarketInfo, PoolError> {
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
+        let exchange_rate = Self::get_exchange_rate_internal(&env, asset.clone())?;
+        let ltv_ratio: i128 = env.storage().instance().get(&DataKey::LtvRatio(asset.clone())).unwrap_or(0);
+
+        let utilization = utilization_rate(total_borrow, total_supply)?;
+        let (borrow_rate, supply_rate) = Self::rates_at(&env, &asset, utilization)?;
+
+        Ok(MarketInfo {
+            total_supply,
+            total_borrow,
+            total_shares,
+            exchange_rate,
+            utilization_rate: utilization,
+            borrow_rate,
+            supply_rate,
+            ltv_ratio,
+        })
+    }
+
+    /// Get a market's totals, rates, index and caps in one call
+    ///
+    /// Totals and the index include interest accrued since the last write,
+    /// so a dashboard row needs a single simulation.
+    pub fn get_reserve_data(env: Env, asset: Symbol) -> Result<ReserveData, PoolError> {
+        Self::get_token_address(&env, &asset)?;
+
+        let storage = env.storage().instance();
+        let (total_supply, total_borrow, borrow_index) = match Self::project_accrual(&env, &asset)? {
+            Some(accrual) => (accrual.total_supply, accrual.total_borrow, accrual.borrow_index),
+            None => (
+                storage.get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0),
+                storage.get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0),
+                Self::get_borrow_index(env.clone(), asset.clone()),
+            ),
+        };
+        let utilization = utilization_rate(total_borrow, total_supply)?;
+        let (borrow_rate, supply_rate) = Self::rates_at(&env, &asset, utilization)?;
+
+        Ok(ReserveData {
+            total_supply,
+            total_borrow,
+            available_liquidity: (total_supply - total_borrow).max(0),
+            utilization_rate: utilization,
+            borrow_apy: Self::compound_annual_rate(borrow_rate)?,
+            supply_apy: Self::compound_annual_rate(supply_rate)?,
+            borrow_index,
+            last_accrual_time: storage.get(&DataKey::LastAccrualTime(asset.clone())).unwrap_or(0),
+            supply_cap: Self::get_supply_cap(env.clone(), asset.clone()),
+            borrow_cap: Self::get_borrow_cap(env, asset),
+        })
+    }
+
+    /// Internal: APY of an annual rate compounded per second, the way the
+    /// borrow index accrues (both scaled by SCALE)
+    fn compound_annual_rate(annual_rate: i128) -> Result<i128, PoolError> {
+        let rate_per_second = ray_rate_per_second(annual_rate, SCALE, SECONDS_PER_YEAR)?;
+        let growth = compound_interest(rate_per_second, SECONDS_PER_YEAR as u64)?;
+        Ok(mul_div(growth - RAY, SCALE, RAY, Rounding::Down)?)
+    }
+
+    /// Get pool-wide USD totals across all reserves
+    ///
+    /// TVL counts the tokens the pool holds, so lent-out liquidity is not
+    /// counted twice. Each reserve's price is read from the oracle once.
+    pub fn get_protocol_stats(env: Env) -> Result<ProtocolStats, PoolError> {
+        let mut prices = PriceCache::new(&env)?;
+        let pool = env.current_contract_address();
+        let mut stats = ProtocolStats {
+            tvl_usd: 0,
+            total_supply_usd: 0,
+            total_debt_usd: 0,
+            treasury_usd: 0,
+            active_reserves: 0,
+        };
+
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let config = Self::get_reserve(env.clone(), asset.clone())?;
+            if config.collateral_enabled || config.borrow_enabled {
+                stats.active_reserves += 1;
+            }
+
+            let storage = env.storage().instance();
+            let (total_supply, total_borrow, total_reserves) = match Self::project_accrual(&env, &asset)? {
+                Some(accrual) => (accrual.total_supply, accrual.total_borrow, accrual.total_reserves),
+                None => (
+                    storage.get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0),
+                    storage.get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0),
+                    storage.get(&DataKey::TotalReserves(asset.clone())).unwrap_or(0),
+                ),
+            };
+            let held = Self::from_token_units(&env, &asset, token::Client::new(&env, &config.token).balance(&pool))?;
+            if held == 0 && total_supply == 0 && total_borrow == 0 && total_reserves == 0 {
+                continue;
+            }
+
+            let price = prices.spot(&env, &asset)?;
+            let usd = |amount: i128| mul_div(amount, price, SCALE, Rounding::Down);
+            stats.tvl_usd += usd(held)?;
+            stats.total_supply_usd += usd(total_supply)?;
+            stats.total_debt_usd += usd(total_borrow)?;
+            stats.treasury_usd += usd(total_reserves)?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Get the USD value of the tokens held by the pool, scaled by 1e7
+    pub fn get_tvl(env: Env) -> Result<i128, PoolError> {
+        Ok(Self::get_protocol_stats(env)?.tvl_usd)
+    }
+
+    /// Get a page of the addresses that currently have debt
+    ///
+    /// Lets liquidation bots find positions without replaying events. The
+    /// order is not stable: repaying in full moves the last borrower into the
+    /// freed slot. Positions untouched since before the registry existed
+    /// appear once they next borrow or repay.
+    ///
+    /// # Arguments
+    /// * `offset` - Index of the first borrower to return
+    /// * `limit` - Maximum number of borrowers to return
+    pub fn get_borrowers(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        let count = Self::get_borrower_count(env.clone());
+        let end = offset.saturating_add(limit).min(count);
+        let mut borrowers = Vec::new(&env);
+        for slot in offset..end {
+            if let Some(borrower) = env.storage().persistent().get(&UserKey::BorrowerAt(slot)) {
+                borrowers.push_back(borrower);
+            }
+        }
+        borrowers
+    }
+
+    /// Get the number of addresses that currently have debt
+    pub fn get_borrower_count(env: Env) -> u32 {
+        env.storage().instance().get(&ProtocolKey::BorrowerCount).unwrap_or(0)
+    }
+
+    /// Get total supply for an asset
+    pub fn get_total_supply(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::TotalSupply(asset)).unwrap_or(0)
+    }
+
+    /// Get total borrows for an asset
+    pub fn get_total_borrow(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::TotalBorrow(asset)).unwrap_or(0)
+    }
+
+    /// Get user's share balance for an asset
+    pub fn get_user_shares(env: Env, user: Address, asset: Symbol) -> i128 {
+        env.storage().persistent().get(&DataKey::UserShares(user, asset)).unwrap_or(0)
+    }
+
+    /// Get user's supplied balance in underlying (shares at the current exchange rate)
+    pub fn get_user_deposit(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let shares: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserShares(user, asset.clone()))
+            .unwrap_or(0);
+        Ok(mul_div(shares, Self::get_exchange_rate_internal(&env, asset)?, INITIAL_EXCHANGE_RATE, Rounding::Down)?)
+    }
+
+    /// Get user's collateral balance for an asset
+    pub fn get_user_collateral(env: Env, user: Address, asset: Symbol) -> i128 {
+        env.storage().persistent().get(&DataKey::UserCollateral(user, asset)).unwrap_or(0)
+    }
+
+    /// Get a user's collateral balances, keyed by asset
+    ///
+    /// Only assets with a non-zero balance are included.
+    pub fn get_user_collaterals(env: Env, user: Address) -> Map<Symbol, i128> {
+        let mut collaterals = Map::new(&env);
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let collateral = Self::get_user_collateral(env.clone(), user.clone(), asset.clone());
+            if collateral > 0 {
+                collaterals.set(asset, collateral);
+            }
+        }
+        collaterals
+    }
+
+    /// Get user's debt balance for an asset (without interest)
+    pub fn get_user_debt(env: Env, user: Address, asset: Symbol) -> i128 {
+        env.storage().persistent().get(&DataKey::UserDebt(user, asset)).unwrap_or(0)
+    }
+
+    /// Get user's debt balance with accrued interest, in both rate modes
+    pub fn get_user_debt_total(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_user_total_debt(&env, &user, &asset)
+    }
+
+    /// Get user's debt with interest accrued up to now, in both rate modes
+    ///
+    /// `get_user_debt_total` only includes variable-rate interest up to the
+    /// last accrual; this also projects the interest accrued since, as the
+    /// next interaction with the reserve would. Nothing is written.
+    pub fn get_user_borrow_with_interest(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let borrow_index = Self::get_borrow_index_with_interest(env.clone(), asset.clone())?;
+        let variable_debt = Self::get_user_debt_at_index(&env, user.clone(), asset.clone(), borrow_index)?;
+        Ok(variable_debt + Self::get_user_stable_debt_with_interest(&env, &user, &asset)?)
+    }
+
+    /// Get user's supplied balance in underlying with interest accrued up to now
+    ///
+    /// The projected counterpart of `get_user_deposit`. Nothing is written.
+    pub fn get_user_deposit_with_interest(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        let shares: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserShares(user, asset.clone()))
+            .unwrap_or(0);
+        Ok(mul_div(shares, Self::get_exchange_rate_with_interest(env, asset)?, INITIAL_EXCHANGE_RATE, Rounding::Down)?)
+    }
+
+    /// Get user's stable-rate debt with accrued interest
+    pub fn get_user_stable_debt(env: Env, user: Address, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_user_stable_debt_with_interest(&env, &user, &asset)
+    }
+
+    /// Get the stable rate locked for a user's debt (scaled by 1e7, 0 if none)
+    pub fn get_user_stable_rate(env: Env, user: Address, asset: Symbol) -> i128 {
+        env.storage()
+            .persistent()
+            .get::<_, StableDebt>(&DataKey::UserStableDebt(user, asset))
+            .map(|position| position.rate)
+            .unwrap_or(0)
+    }
+
+    /// Get the stable-rate portion of total borrows for an asset
+    pub fn get_total_stable_debt(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::TotalStableDebt(asset)).unwrap_or(0)
+    }
+
+    /// Get the debt-weighted average rate of stable borrows (scaled by 1e7)
+    pub fn get_avg_stable_rate(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::AvgStableRate(asset)).unwrap_or(0)
+    }
+
+    /// Get exchange rate for sTokens
+    pub fn get_exchange_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_exchange_rate_internal(&env, asset)
+    }
+
+    /// Get the sToken exchange rate with interest accrued up to now, without
+    /// writing it
+    pub fn get_exchange_rate_with_interest(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        let Some(accrual) = Self::project_accrual(&env, &asset)? else {
+            return Self::get_exchange_rate_internal(&env, asset);
+        };
+        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset)).unwrap_or(0);
+        if total_shares == 0 {
+            return Ok(INITIAL_EXCHANGE_RATE);
+        }
+        Ok(mul_div(accrual.total_supply, INITIAL_EXCHANGE_RATE, total_shares, Rounding::Down)?)
+    }
+
+    /// Get utilization rate for an asset (scaled by 1e7)
+    ///
+    /// Computed with `apogee_common::utilization_rate`, the same value
+    /// interest accrues at.
+    pub fn get_utilization_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset)).unwrap_or(0);
+        Ok(utilization_rate(total_borrow, total_supply)?)
+    }
+
+    /// Get all listed reserve assets, in listing order
+    pub fn list_reserves(env: Env) -> Vec<Symbol> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Reserves)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the configuration of a listed reserve
+    pub fn get_reserve(env: Env, asset: Symbol) -> Result<ReserveConfig, PoolError> {
+        let token = Self::get_token_address(&env, &asset)?;
+        let interest_rate_model = Self::get_reserve_rate_model(&env, &asset)?;
+
+        Ok(ReserveConfig {
+            token,
+            decimals: env.storage().instance().get(&DataKey::Decimals(asset.clone())).unwrap_or(CANONICAL_DECIMALS),
+            ltv: Self::get_ltv_ratio(env.clone(), asset.clone()),
+            liquidation_threshold: Self::get_liquidation_threshold(env.clone(), asset.clone()),
+            collateral_enabled: Self::is_collateral_enabled(&env, &asset),
+            borrow_enabled: env
+                .storage()
+                .instance()
+                .get(&DataKey::BorrowEnabled(asset))
+                .unwrap_or(false),
+            interest_rate_model,
+        })
+    }
+
+    /// Get LTV ratio for an asset
+    pub fn get_ltv_ratio(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::LtvRatio(asset)).unwrap_or(0)
+    }
+
+    /// Get the collateral factor (LTV) for an asset after volatility adjustment
+    ///
+    /// Starts from the base LTV and subtracts `k * sqrt(variance) / price`,
+    /// where variance is the oracle's rolling variance of price changes and
+    /// `k` is the volatility adjustment factor. Returns the base LTV when the
+    /// adjustment is disabled.
+    ///
+    /// # Returns
+    /// Adjusted LTV (scaled by SCALE), never below 0
+    pub fn get_volatility_adjusted_cf(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        Self::volatility_adjusted_cf(&env, &asset, &mut PriceCache::new(&env)?)
+    }
+
+    /// `get_volatility_adjusted_cf`, reading prices through `prices`
+    fn volatility_adjusted_cf(env: &Env, asset: &Symbol, prices: &mut PriceCache) -> Result<i128, PoolError> {
+        let base_ltv: i128 = env.storage().instance().get(&DataKey::LtvRatio(asset.clone())).unwrap_or(0);
+
+        let k: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::VolatilityAdjustmentFactor)
+            .unwrap_or(0);
+        if k == 0 {
+            return Ok(base_ltv);
+        }
+
+        let oracle_client = oracle_contract::Client::new(env, &prices.oracle);
+        let variance = oracle_client.get_price_variance(&Self::get_token_address(env, asset)?);
+        let price = prices.spot(env, asset)?;
+
+        // Relative volatility (std dev / price), scaled by SCALE; rounded up
+        // so the haircut never understates it
+        let relative_volatility = mul_div(Self::isqrt(variance), SCALE, price, Rounding::Up)?;
+        let reduction = mul_div(k, relative_volatility, SCALE, Rounding::Up)?;
+
+        if reduction >= base_ltv {
+            Ok(0)
+        } else {
+            Ok(base_ltv - reduction)
+        }
+    }
+
+    /// Get liquidation threshold for an asset
+    pub fn get_liquidation_threshold(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::LiquidationThreshold(asset)).unwrap_or(0)
+    }
+
+    /// Get the current borrow APR for an asset
+    /// 
+    /// Returns the annualized borrow rate the rate model quotes at current
+    /// utilization. Scaled by 1e7, so 5% = 500_000.
+    pub fn get_current_borrow_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        let utilization = Self::get_utilization_rate(env.clone(), asset.clone())?;
+        Self::calculate_borrow_rate(&env, &asset, utilization)
+    }
+
+    /// Get the current supply APR for an asset
+    ///
+    /// The borrow rate scaled down by utilization and the reserve factor.
+    /// Scaled by 1e7, so 3.2% = 320_000.
+    pub fn get_current_supply_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        let utilization = Self::get_utilization_rate(env.clone(), asset.clone())?;
+        Ok(Self::rates_at(&env, &asset, utilization)?.1)
+    }
+
+    /// Same as `get_current_borrow_rate`, kept for existing integrations
+    pub fn get_borrow_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_current_borrow_rate(env, asset)
+    }
+
+    /// Get the stable borrow APR currently offered for an asset
+    ///
+    /// Quoted at current utilization; `borrow_stable` locks the rate at the
+    /// utilization after the borrow. Scaled by 1e7.
+    pub fn get_stable_borrow_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        let utilization = Self::get_utilization_rate(env.clone(), asset.clone())?;
+        Self::calculate_stable_rate(&env, &asset, utilization)
+    }
+
+    /// Same as `get_current_supply_rate`, kept for existing integrations
+    pub fn get_supply_rate(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        Self::get_current_supply_rate(env, asset)
+    }
+
+    /// Get the borrow index for an asset
+    /// 
+    /// The borrow index tracks accumulated interest. Used to calculate
+    /// individual user debt with interest. Scaled by 1e27 (ray).
+    pub fn get_borrow_index(env: Env, asset: Symbol) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::BorrowIndex(asset))
+            .unwrap_or(INITIAL_BORROW_INDEX)
+    }
+
+    /// Get the borrow index with interest accrued up to now, without writing it
+    pub fn get_borrow_index_with_interest(env: Env, asset: Symbol) -> Result<i128, PoolError> {
+        match Self::project_accrual(&env, &asset)? {
+            Some(accrual) => Ok(accrual.borrow_index),
+            None => Ok(Self::get_borrow_index(env, asset)),
+        }
+    }
+
+    /// Get total protocol reserves accumulated for an asset
+    pub fn get_total_reserves(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::TotalReserves(asset)).unwrap_or(0)
+    }
+
+    /// Get the interest rate model contract address
+    pub fn get_interest_rate_model(env: Env) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::InterestRateModel)
+            .ok_or(PoolError::NotInitialized)
+    }
+
+    /// Get health factor for a specific user
+    /// 
+    /// Health Factor = (collateral_value * liquidation_threshold) / debt_value
+    /// 
+    /// # Returns
+    /// - HF >= 1.0 (SCALE): Safe position
+    /// - HF < 1.0 (SCALE): Unsafe position, eligible for liquidation
+    /// - 999 * SCALE: No debt (infinite health factor)
+    /// 
+    /// Scaled by SCALE (1e7), so HF = 1.0 is represented as 10_000_000
+    pub fn get_health_factor(env: Env, user: Address) -> Result<i128, PoolError> {
+        Ok(Self::account_data(&env, &user)?.health_factor)
+    }
+
+    // ========================================================================
+    // LIQUIDATION
+    // ========================================================================
+
+    /// Liquidate an undercollateralized position
+    /// 
+    /// Allows a liquidator to repay a portion of a borrower's debt in exchange
+    /// for a portion of their collateral plus a liquidation bonus.
+    /// 
+    /// # Requirements
+    /// - Borrower's health factor must be < 1.0
+    /// - Liquidator can repay up to the close factor of borrower's debt (default 50%)
+    /// - Liquidator receives equivalent collateral value + the current auction
+    ///   bonus (or the collateral asset's bonus when auctions are disabled),
+    ///   less the protocol's share of the bonus, which goes to the treasury
+    /// - If the borrower's collateral cannot cover repayment + bonus, all of it
+    ///   is seized and the repayment is reduced to match
+    /// - Deposited collateral is seized first, then supplied shares the
+    ///   borrower uses as collateral, paid out in underlying
+    /// 
+    /// # Arguments
+    /// * `liquidator` - Address calling the liquidation (repaying debt)
+    /// * `borrower` - Address being liquidated (underwater position)
+    /// * `repay_asset` - Asset to repay (e.g., USDC)
+    /// * `repay_amount` - Amount of debt to repay
+    /// * `collateral_asset` - Collateral asset to seize (e.g., XLM)
+    /// * `min_seized` - Least collateral the liquidator must receive (0 = any)
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    /// 
+    /// # Returns
+    /// Amount of collateral transferred to the liquidator
+    #[allow(clippy::too_many_arguments)]
+    pub fn liquidate(
+        env: Env,
+        liquidator: Address,
+        borrower: Address,
+        repay_asset: Symbol,
+        repay_amount: i128,
+        collateral_asset: Symbol,
+        min_seized: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        liquidator.require_auth();
+        Self::require_deadline(&env, deadline)?;
+
+        let (_, seized) = Self::liquidate_internal(
+            &env,
+            &liquidator,
+            &borrower,
+            repay_asset,
+            repay_amount,
+            collateral_asset,
+            false,
+        )?;
+        if seized < min_seized {
+            return Err(PoolError::SlippageExceeded);
+        }
+        Ok(seized)
+    }
+
+    /// Liquidate without capital: repay from a flash loan and sell the seized collateral
+    ///
+    /// The repayment is flash-borrowed from the pool's own `repay_asset`
+    /// liquidity, the liquidation runs as in `liquidate`, and the seized
+    /// collateral is swapped to `repay_asset` through an approved adapter.
+    /// The swap must cover the loan, the flash loan fee and `min_profit`;
+    /// what is left is sent to the liquidator.
+    ///
+    /// # Arguments
+    /// * `liquidator` - Address calling the liquidation and receiving the profit
+    /// * `borrower` - Address being liquidated (underwater position)
+    /// * `repay_asset` - Asset to repay (e.g., USDC)
+    /// * `repay_amount` - Amount of debt to repay
+    /// * `collateral_asset` - Collateral asset to seize (e.g., XLM)
+    /// * `adapter` - Approved swap adapter
+    /// * `min_profit` - Least `repay_asset` the liquidator must receive
+    /// * `deadline` - Ledger timestamp after which the call reverts, if any
+    ///
+    /// # Returns
+    /// Profit sent to the liquidator, in `repay_asset`
+    #[allow(clippy::too_many_arguments)]
+    pub fn liquidate_with_flash(
+        env: Env,
+        liquidator: Address,
+        borrower: Address,
+        repay_asset: Symbol,
+        repay_amount: i128,
+        collateral_asset: Symbol,
+        adapter: Address,
+        min_profit: i128,
+        deadline: Option<u64>,
+    ) -> Result<i128, PoolError> {
+        liquidator.require_auth();
+        Self::require_not_paused(&env, PoolAction::FlashLoan)?;
+        Self::require_deadline(&env, deadline)?;
+
+        if min_profit < 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        Self::accrue_interest(&env, repay_asset.clone())?;
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(repay_asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(repay_asset.clone())).unwrap_or(0);
+
+        let (repaid, seized) = Self::liquidate_internal(
+            &env,
+            &liquidator,
+            &borrower,
+            repay_asset.clone(),
+            repay_amount,
+            collateral_asset.clone(),
+            true,
+        )?;
+        if total_supply - total_borrow < repaid {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+
+        let fee_rate = Self::get_flash_loan_fee(env.clone(), repay_asset.clone());
+        let fee = Self::compute_fee(&env, &liquidator, repaid, fee_rate)?;
+        let proceeds = if collateral_asset == repay_asset {
+            seized
+        } else {
+            Self::swap(&env, &adapter, &collateral_asset, &repay_asset, seized, repaid + fee + min_profit)?
+        };
+        if proceeds < repaid + fee + min_profit {
+            return Err(PoolError::FlashLoanNotRepaid);
+        }
+        Self::credit_flash_fee(&env, &repay_asset, fee)?;
+
+        let profit = proceeds - repaid - fee;
+        if profit > 0 {
+            Self::send_underlying(&env, &repay_asset, &liquidator, profit)?;
+        }
+
+        env.events().publish(
+            (symbol_short!("liq_flash"), liquidator, borrower),
+            (repay_asset, repaid, collateral_asset, seized, fee, profit),
+        );
+
+        Ok(profit)
+    }
+
+    /// Internal: Liquidate a position; with `flash`, the repayment is taken
+    /// from pool liquidity and the seized collateral stays in the pool
+    ///
+    /// # Returns
+    /// Debt repaid and collateral seized for the liquidator
+    fn liquidate_internal(
+        env: &Env,
+        liquidator: &Address,
+        borrower: &Address,
+        repay_asset: Symbol,
+        repay_amount: i128,
+        collateral_asset: Symbol,
+        flash: bool,
+    ) -> Result<(i128, i128), PoolError> {
+        Self::require_not_paused(env, PoolAction::Liquidate)?;
+        
+        if repay_amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        // ====================================================================
+        // STEP 1: Check borrower's health factor
+        // ====================================================================
+        
+        // Accrue interest first to get accurate debt
+        Self::accrue_interest_for(env, &repay_asset, borrower)?;
+        
+        // Each price is read from the oracle once for the whole liquidation
+        let mut prices = PriceCache::new(env)?;
+        let borrower_account = Self::account_data_with(env, borrower, &mut prices)?;
+        
+        // Health factor must be < 1.0 to be liquidatable
+        if borrower_account.health_factor >= SCALE {
+            return Err(PoolError::PositionHealthy);
+        }
+
+        // Open the Dutch auction if no one has started it yet
+        let started_at = Self::start_auction_internal(env, borrower);
+        let liquidation_bonus = if Self::get_auction_params(env.clone()).2 == 0 {
+            Self::get_liquidation_bonus(env.clone(), collateral_asset.clone())
+        } else {
+            Self::auction_bonus(env, Some(started_at))
+        };
+
+        // ====================================================================
+        // STEP 2: Calculate maximum repayable amount (close factor)
+        // ====================================================================
+        
+        let borrower_debt = Self::get_user_total_debt(env, borrower, &repay_asset)?;
+        
+        if borrower_debt == 0 {
+            return Err(PoolError::NoDebt);
+        }
+        
         // Maximum repayable = 50% of borrower's debt
-        let max_repay = (borrower_debt * CLOSE_FACTOR) / SCALE;
+        let max_repay = mul_div(borrower_debt, Self::get_close_factor(env.clone()), SCALE, Rounding::Down)?;
         
         // Cap repay_amount to max allowed
-        let actual_repay = if repay_amount > max_repay {
+        let mut actual_repay = if repay_amount > max_repay {
             max_repay
         } else {
             repay_amount
         };
 
-        // ====================================================================
-        // STEP 3: Calculate collateral to seize
-        // ====================================================================
-        
-        let oracle: Address = env.storage().instance().get(&DataKey::PriceOracle).unwrap();
-        
-        // Get prices
-        let repay_price = Self::get_asset_price(&env, &oracle, &repay_asset);
-        let collateral_price = Self::get_asset_price(&env, &oracle, &collateral_asset);
-        
-        // Calculate repay value in USD
-        let repay_value_usd = (actual_repay * repay_price) / SCALE;
-        
-        // Add liquidation bonus (5%)
-        let bonus_value_usd = (repay_value_usd * LIQUIDATION_BONUS) / SCALE;
-        let total_value_usd = repay_value_usd + bonus_value_usd;
-        
-        // Convert to collateral amount
-        let collateral_to_seize = (total_value_usd * SCALE) / collateral_price;
-        
-        // Check borrower has sufficient collateral
-        let borrower_collateral: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserCollateral(borrower.clone(), collateral_asset.clone()))
-            .unwrap_or(0);
-        
-        if borrower_collateral < collateral_to_seize {
-            panic!("Insufficient collateral to seize");
+        // ====================================================================
+        // STEP 3: Calculate collateral to seize
+        // ====================================================================
+        
+        // Get prices
+        let repay_price = prices.spot(env, &repay_asset)?;
+        let collateral_price = prices.collateral(env, &collateral_asset)?;
+        
+        // Repayment expressed in collateral, plus the liquidation bonus
+        // (current Dutch auction bonus)
+        let repay_in_collateral = convert_amount(actual_repay, repay_price, collateral_price)?;
+        let bonus_in_collateral = mul_div(repay_in_collateral, liquidation_bonus, SCALE, Rounding::Down)?;
+        let mut collateral_to_seize = repay_in_collateral + bonus_in_collateral;
+        
+        // Deposited collateral is seized first, then supply used as collateral
+        let deposited_collateral: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserCollateral(borrower.clone(), collateral_asset.clone()))
+            .unwrap_or(0);
+        if Self::is_using_as_collateral(env.clone(), borrower.clone(), collateral_asset.clone()) {
+            Self::accrue_interest(env, collateral_asset.clone())?;
+        }
+        let borrower_collateral =
+            deposited_collateral + Self::get_supplied_collateral(env, borrower, &collateral_asset)?;
+        if borrower_collateral == 0 {
+            return Err(PoolError::NoCollateral);
+        }
+        
+        // Deeply underwater: seize all remaining collateral and only take
+        // as much repayment as that collateral covers (net of the bonus)
+        if collateral_to_seize > borrower_collateral {
+            collateral_to_seize = borrower_collateral;
+            let covered_collateral = mul_div(borrower_collateral, SCALE, SCALE + liquidation_bonus, Rounding::Down)?;
+            actual_repay = convert_amount(covered_collateral, collateral_price, repay_price)?;
+        }
+
+        // ====================================================================
+        // STEP 4: Execute liquidation
+        // ====================================================================
+        
+        // Reduce borrower's debt
+        Self::reduce_user_debt(env, borrower, &repay_asset, actual_repay)?;
+        
+        // Reduce total borrows
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(repay_asset.clone())).unwrap_or(0);
+        let new_total_borrow = if total_borrow > actual_repay {
+            total_borrow - actual_repay
+        } else {
+            0
+        };
+        env.storage().instance().set(&DataKey::TotalBorrow(repay_asset.clone()), &new_total_borrow);
+        
+        // Transfer collateral from borrower to liquidator
+        let from_deposit = collateral_to_seize.min(deposited_collateral);
+        let new_borrower_collateral = deposited_collateral - from_deposit;
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserCollateral(borrower.clone(), collateral_asset.clone()), &new_borrower_collateral);
+        if collateral_to_seize > from_deposit {
+            Self::seize_supply(env, borrower, &collateral_asset, collateral_to_seize - from_deposit)?;
+        }
+        
+        // Split off the protocol's share of the bonus
+        let base_collateral = mul_div(collateral_to_seize, SCALE, SCALE + liquidation_bonus, Rounding::Up)?;
+        let bonus_collateral = collateral_to_seize - base_collateral;
+        let protocol_fee = match Self::get_treasury(env.clone()) {
+            Some(_) => {
+                let fee_rate = Self::get_liquidation_protocol_fee(env.clone(), collateral_asset.clone());
+                mul_div(bonus_collateral, fee_rate, SCALE, Rounding::Down)?
+            }
+            None => 0,
+        };
+        let liquidator_collateral = collateral_to_seize - protocol_fee;
+
+        Self::update_global_borrow_pause_with(env, &mut prices)?;
+
+        // Close the auction once the position is healthy again
+        let health_factor_after = Self::account_data_with(env, borrower, &mut prices)?.health_factor;
+        if health_factor_after >= SCALE {
+            env.storage().persistent().remove(&DataKey::AuctionStart(borrower.clone()));
+        }
+
+        Self::extend_user_ttl(env, borrower);
+
+        // Move tokens once the books are settled: the repayment in, then the
+        // collateral out to the liquidator and treasury
+        if !flash {
+            Self::pull_underlying(env, &repay_asset, liquidator, actual_repay, false)?;
+            Self::send_underlying(env, &collateral_asset, liquidator, liquidator_collateral)?;
+        }
+        if let (Some(treasury), true) = (Self::get_treasury(env.clone()), protocol_fee > 0) {
+            Self::send_underlying(env, &collateral_asset, &treasury, protocol_fee)?;
+        }
+
+        // ====================================================================
+        // STEP 5: Emit event and return
+        // ====================================================================
+        
+        env.events().publish(
+            (symbol_short!("liquidate"), liquidator.clone(), borrower.clone()),
+            LiquidationEvent {
+                liquidator: liquidator.clone(),
+                borrower: borrower.clone(),
+                repay_asset,
+                collateral_asset,
+                repay_amount: actual_repay,
+                collateral_seized: liquidator_collateral,
+                protocol_fee,
+                bonus: liquidation_bonus,
+                bonus_amount: bonus_collateral,
+                new_debt: borrower_debt - actual_repay,
+                new_collateral: new_borrower_collateral,
+                health_factor_after,
+                repay_price,
+                collateral_price,
+            },
+        );
+
+        Self::assert_invariants(env);
+        Ok((actual_repay, liquidator_collateral))
+    }
+
+    /// Internal: Burn enough of a borrower's supplied shares to release `amount` of underlying
+    fn seize_supply(env: &Env, borrower: &Address, asset: &Symbol, amount: i128) -> Result<(), PoolError> {
+        let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+        if total_supply - total_borrow < amount {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+
+        let exchange_rate = Self::get_exchange_rate_internal(env, asset.clone())?;
+        let user_shares = Self::get_user_shares(env.clone(), borrower.clone(), asset.clone());
+        let shares = mul_div(amount, INITIAL_EXCHANGE_RATE, exchange_rate, Rounding::Up)?.min(user_shares);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserShares(borrower.clone(), asset.clone()), &(user_shares - shares));
+        let total_shares: i128 = env.storage().instance().get(&DataKey::TotalShares(asset.clone())).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalSupply(asset.clone()), &(total_supply - amount));
+        env.storage().instance().set(&DataKey::TotalShares(asset.clone()), &(total_shares - shares));
+
+        if let Some(s_token) = Self::get_s_token(env.clone(), asset.clone()) {
+            STokenHooksClient::new(env, &s_token).pool_burn(borrower, &shares);
+        }
+        Self::notify_supply_change(env, asset, borrower);
+        Ok(())
+    }
+
+    /// Start a liquidation auction for an unhealthy position
+    ///
+    /// Permissionless: anyone (typically a keeper) can open the auction as soon
+    /// as the borrower's health factor drops below 1.0. The liquidation bonus
+    /// then decays linearly from the max to the min auction bonus over the
+    /// auction duration. `liquidate` opens the auction automatically if needed.
+    ///
+    /// # Returns
+    /// Timestamp at which the auction started
+    pub fn start_auction(env: Env, borrower: Address) -> Result<u64, PoolError> {
+        Self::require_not_paused(&env, PoolAction::Liquidate)?;
+
+        if Self::account_data(&env, &borrower)?.health_factor >= SCALE {
+            return Err(PoolError::PositionHealthy);
+        }
+
+        Ok(Self::start_auction_internal(&env, &borrower))
+    }
+
+    /// Internal: Record the auction start time if not already started
+    fn start_auction_internal(env: &Env, borrower: &Address) -> u64 {
+        if let Some(started_at) = env
+            .storage()
+            .persistent()
+            .get::<_, u64>(&DataKey::AuctionStart(borrower.clone()))
+        {
+            return started_at;
+        }
+
+        let now = env.ledger().timestamp();
+        env.storage()
+            .persistent()
+            .set(&DataKey::AuctionStart(borrower.clone()), &now);
+        env.events()
+            .publish((symbol_short!("auction"), borrower.clone()), now);
+        now
+    }
+
+    /// Internal: Close a borrower's auction once their position is healthy
+    ///
+    /// A later shortfall then opens a fresh auction at the max bonus instead
+    /// of resuming the decayed one.
+    fn close_auction_if_healthy(env: &Env, borrower: &Address) {
+        let key = DataKey::AuctionStart(borrower.clone());
+        if env.storage().persistent().has(&key) && Self::is_healthy(env, borrower) {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    /// Internal: Whether a position can currently be valued and is not liquidatable
+    fn is_healthy(env: &Env, user: &Address) -> bool {
+        matches!(Self::account_data(env, user), Ok(account) if account.health_factor >= SCALE)
+    }
+
+    /// Get the timestamp at which a borrower's liquidation auction started
+    pub fn get_auction_start(env: Env, borrower: Address) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::AuctionStart(borrower))
+    }
+
+    /// Get the liquidation bonus a liquidator would receive right now
+    ///
+    /// Bonus = max - (max - min) * elapsed / duration, floored at min.
+    /// If auctions are disabled, returns the default 5% bonus; `liquidate`
+    /// then uses the collateral asset's own bonus instead. If no auction
+    /// is running (none started, or the position is healthy again), the
+    /// max bonus applies.
+    ///
+    /// # Returns
+    /// Liquidation bonus (scaled by SCALE, 5% = 500_000)
+    pub fn get_current_auction_bonus(env: Env, borrower: Address) -> i128 {
+        let started_at = Self::get_auction_start(env.clone(), borrower.clone())
+            .filter(|_| !Self::is_healthy(&env, &borrower));
+        Self::auction_bonus(&env, started_at)
+    }
+
+    /// Internal: Auction bonus for an auction started at `started_at`
+    fn auction_bonus(env: &Env, started_at: Option<u64>) -> i128 {
+        let duration: u64 = env.storage().instance().get(&DataKey::AuctionDuration).unwrap_or(0);
+        if duration == 0 {
+            return LIQUIDATION_BONUS;
+        }
+
+        let max_bonus: i128 = env.storage().instance().get(&DataKey::MaxAuctionBonus).unwrap_or(LIQUIDATION_BONUS);
+        let min_bonus: i128 = env.storage().instance().get(&DataKey::MinAuctionBonus).unwrap_or(LIQUIDATION_BONUS);
+
+        let started_at = match started_at {
+            Some(started_at) => started_at,
+            None => return max_bonus,
+        };
+
+        let elapsed = env.ledger().timestamp().saturating_sub(started_at);
+        if elapsed >= duration {
+            return min_bonus;
+        }
+
+        max_bonus - ((max_bonus - min_bonus) * elapsed as i128) / duration as i128
+    }
+
+    // ========================================================================
+    // BAD DEBT / BACKSTOP
+    // ========================================================================
+
+    /// Write off the debt of a borrower with no collateral left
+    ///
+    /// Callable by anyone once liquidations have seized all of a borrower's
+    /// collateral, both deposited and supply used as collateral. For each
+    /// asset the debt is cleared, the backstop covers what it can (if it
+    /// stakes that asset) and suppliers absorb the rest through a lower
+    /// exchange rate.
+    ///
+    /// # Arguments
+    /// * `borrower` - Account holding the bad debt
+    pub fn write_off_bad_debt(env: Env, borrower: Address) -> Result<(), PoolError> {
+        let reserves = Self::list_reserves(env.clone());
+        for asset in reserves.iter() {
+            if Self::uses_as_collateral(&env, &borrower, &asset) {
+                return Err(PoolError::NoBadDebt);
+            }
+        }
+
+        let backstop = Self::get_backstop(env.clone());
+        let mut written_off = false;
+        for asset in reserves.iter() {
+            Self::accrue_interest_for(&env, &asset, &borrower)?;
+            let debt = Self::get_user_total_debt(&env, &borrower, &asset)?;
+            if debt == 0 {
+                continue;
+            }
+            written_off = true;
+
+            Self::reduce_user_debt(&env, &borrower, &asset, debt)?;
+            let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalBorrow(asset.clone()), &(total_borrow - debt).max(0));
+
+            // The backstop accounts in the token's own units
+            let covered = match &backstop {
+                Some(backstop) if BackstopClient::new(&env, backstop).get_token() == Self::get_token_address(&env, &asset)? => {
+                    let token_debt = Self::to_token_units(&env, &asset, debt, Rounding::Up)?;
+                    let token_covered = BackstopClient::new(&env, backstop).cover_bad_debt(&token_debt);
+                    Self::from_token_units(&env, &asset, token_covered)?.min(debt)
+                }
+                _ => 0,
+            };
+
+            // Whatever the backstop could not pay is lost by suppliers
+            let total_supply: i128 = env.storage().instance().get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TotalSupply(asset.clone()), &(total_supply - (debt - covered)).max(0));
+
+            env.events().publish((symbol_short!("bad_debt"), borrower.clone(), asset), (debt, covered));
+        }
+        if !written_off {
+            return Err(PoolError::NoBadDebt);
+        }
+
+        Self::update_global_borrow_pause(&env)?;
+
+        Self::assert_invariants(&env);
+        Ok(())
+    }
+
+    /// Send protocol reserves to the backstop as staker rewards (treasury manager only)
+    ///
+    /// # Arguments
+    /// * `asset` - Asset staked in the backstop
+    /// * `amount` - Reserves to send
+    pub fn fund_backstop(env: Env, asset: Symbol, amount: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::TreasuryManager)?;
+
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        let backstop = Self::get_backstop(env.clone()).ok_or(PoolError::BackstopNotSet)?;
+        let backstop_client = BackstopClient::new(&env, &backstop);
+        let token_address = Self::get_token_address(&env, &asset)?;
+        if backstop_client.get_token() != token_address {
+            return Err(PoolError::BackstopAssetMismatch);
+        }
+
+        Self::accrue_interest(&env, asset.clone())?;
+        let reserves = Self::get_total_reserves(env.clone(), asset.clone());
+        if amount > reserves {
+            return Err(PoolError::InsufficientReserves);
+        }
+        env.storage().instance().set(&DataKey::TotalReserves(asset.clone()), &(reserves - amount));
+
+        // The backstop accounts in the token's own units
+        let token_amount = Self::to_token_units(&env, &asset, amount, Rounding::Down)?;
+        token::Client::new(&env, &token_address).transfer(&env.current_contract_address(), &backstop, &token_amount);
+        backstop_client.distribute(&token_amount);
+
+        env.events().publish((symbol_short!("fund_bstp"), asset), amount);
+
+        Self::assert_invariants(&env);
+        Ok(())
+    }
+
+    /// Set the backstop fund covering bad debt (admin only)
+    pub fn set_backstop(env: Env, backstop: Address) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&ProtocolKey::Backstop, &backstop);
+        env.events().publish((symbol_short!("backstop"),), backstop);
+
+        Ok(())
+    }
+
+    /// Get the backstop fund, if one is set
+    pub fn get_backstop(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ProtocolKey::Backstop)
+    }
+
+    // ========================================================================
+    // ADMIN FUNCTIONS
+    // ========================================================================
+
+    /// Internal: Require authorization from the stored admin
+    fn require_admin(env: &Env) -> Result<Address, PoolError> {
+        let admin = Self::get_admin(env.clone())?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Get current admin address
+    pub fn get_admin(env: Env) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(PoolError::NotInitialized)
+    }
+
+    /// Internal: Require authorization from the holder of `role`
+    fn require_role(env: &Env, role: Role) -> Result<Address, PoolError> {
+        let holder = Self::get_role_holder(env.clone(), role)?;
+        holder.require_auth();
+        Ok(holder)
+    }
+
+    /// Grant a role to an address (admin only)
+    ///
+    /// Replaces the previous holder. Granting `Admin` transfers the admin key
+    /// and granting `Pauser` sets the guardian.
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        match role {
+            Role::Admin => env.storage().instance().set(&DataKey::Admin, &account),
+            Role::Pauser => env.storage().instance().set(&DataKey::Guardian, &account),
+            _ => env.storage().instance().set(&ProtocolKey::Role(role), &account),
+        }
+
+        env.events().publish((symbol_short!("role_grnt"), role), account);
+
+        Ok(())
+    }
+
+    /// Revoke a role so it falls back to the admin (admin only)
+    ///
+    /// The admin role itself cannot be revoked, only transferred.
+    pub fn revoke_role(env: Env, role: Role) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        match role {
+            Role::Admin => return Err(PoolError::InvalidRole),
+            Role::Pauser => env.storage().instance().remove(&DataKey::Guardian),
+            _ => env.storage().instance().remove(&ProtocolKey::Role(role)),
+        }
+
+        env.events().publish((symbol_short!("role_rvk"), role), ());
+
+        Ok(())
+    }
+
+    /// Get the address currently acting for a role
+    pub fn get_role_holder(env: Env, role: Role) -> Result<Address, PoolError> {
+        let holder: Option<Address> = match role {
+            Role::Admin => None,
+            Role::Pauser => env.storage().instance().get(&DataKey::Guardian),
+            _ => env.storage().instance().get(&ProtocolKey::Role(role)),
+        };
+        match holder {
+            Some(holder) => Ok(holder),
+            None => Self::get_admin(env),
+        }
+    }
+
+    /// Check whether `account` currently acts for `role`
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        Self::get_role_holder(env, role) == Ok(account)
+    }
+
+    /// Internal: Check that all risk parameters are within safe bounds
+    ///
+    /// Called after every admin setter that touches LTV, liquidation
+    /// threshold, close factor, reserve factor, origination or flash loan fee.
+    /// Returns the violated constraint as an error so the whole update is
+    /// reverted.
+    fn validate_risk_params(env: &Env) -> Result<(), PoolError> {
+        let close_factor = Self::get_close_factor(env.clone());
+        if !(MIN_CLOSE_FACTOR..=SCALE).contains(&close_factor) {
+            return Err(PoolError::InvalidCloseFactor);
+        }
+        let (max_auction_bonus, _, auction_duration) = Self::get_auction_params(env.clone());
+
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let ltv: i128 = env.storage().instance().get(&DataKey::LtvRatio(asset.clone())).unwrap_or(0);
+            let liq_threshold: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::LiquidationThreshold(asset.clone()))
+                .unwrap_or(0);
+            let reserve_factor: i128 = env.storage().instance().get(&DataKey::ReserveFactor(asset.clone())).unwrap_or(0);
+            let origination_fee: i128 = env.storage().instance().get(&DataKey::OriginationFee(asset.clone())).unwrap_or(0);
+            let flash_loan_fee = Self::get_flash_loan_fee(env.clone(), asset.clone());
+
+            if ltv < 0 || ltv >= liq_threshold {
+                return Err(PoolError::LtvAboveThreshold);
+            }
+            if liq_threshold > MAX_LIQUIDATION_THRESHOLD {
+                return Err(PoolError::ThresholdTooHigh);
+            }
+            if !(0..=MAX_RESERVE_FACTOR).contains(&reserve_factor) {
+                return Err(PoolError::InvalidReserveFactor);
+            }
+            if !(0..=MAX_ORIGINATION_FEE).contains(&origination_fee) {
+                return Err(PoolError::InvalidOriginationFee);
+            }
+            if !(0..=MAX_FLASH_LOAN_FEE).contains(&flash_loan_fee) {
+                return Err(PoolError::InvalidFlashLoanFee);
+            }
+
+            // Seizing threshold-weighted collateral plus the bonus must not
+            // take more than the collateral is worth
+            let bonus = Self::get_liquidation_bonus(env.clone(), asset.clone());
+            if bonus < 0 || mul_div(liq_threshold, SCALE + bonus, SCALE, Rounding::Up)? > SCALE {
+                return Err(PoolError::InvalidLiquidationBonus);
+            }
+            // Likewise for the bonus a liquidation auction opens at
+            if auction_duration > 0 && mul_div(liq_threshold, SCALE + max_auction_bonus, SCALE, Rounding::Up)? > SCALE {
+                return Err(PoolError::InvalidAuctionParams);
+            }
+            let protocol_fee = Self::get_liquidation_protocol_fee(env.clone(), asset);
+            if !(0..=SCALE).contains(&protocol_fee) {
+                return Err(PoolError::InvalidProtocolFee);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// List a new reserve asset
+    ///
+    /// Lets new Stellar assets be supplied, used as collateral, or borrowed
+    /// without redeploying the pool. The oracle must serve a price for `asset`
+    /// before positions in it can be valued.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol (must match the oracle's symbol)
+    /// * `config` - Token, risk parameters and rate model for the asset; the
+    ///   decimals are read from the token itself
+    pub fn add_reserve(env: Env, asset: Symbol, config: ReserveConfig) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        if env.storage().instance().has(&DataKey::TokenAddress(asset.clone())) {
+            return Err(PoolError::ReserveAlreadyListed);
+        }
+
+        Self::init_market(&env, asset.clone(), &config);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("add_rsv"), asset), config.token);
+
+        Ok(())
+    }
+
+    /// Set the LTV ratio for an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `ltv` - LTV ratio (scaled by SCALE, 75% = 7_500_000), must stay below the liquidation threshold
+    pub fn set_ltv_ratio(env: Env, asset: Symbol, ltv: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::LtvRatio(asset.clone()), &ltv);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("set_ltv"), asset), ltv);
+
+        Ok(())
+    }
+
+    /// Set the liquidation threshold for an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `threshold` - Liquidation threshold (scaled by SCALE, 80% = 8_000_000), at most 95%
+    pub fn set_liquidation_threshold(env: Env, asset: Symbol, threshold: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::LiquidationThreshold(asset.clone()), &threshold);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("set_liqth"), asset), threshold);
+
+        Ok(())
+    }
+
+    /// Enable or disable an asset as collateral
+    ///
+    /// Disabling stops new deposits of the asset as collateral and removes
+    /// its borrow capacity; collateral already posted still counts towards
+    /// the health factor and can be withdrawn or liquidated.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `enabled` - Whether the asset is accepted as collateral
+    pub fn set_collateral_enabled(env: Env, asset: Symbol, enabled: bool) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+        Self::get_token_address(&env, &asset)?;
+
+        env.storage().instance().set(&DataKey::CollateralEnabled(asset.clone()), &enabled);
+
+        env.events().publish((symbol_short!("set_coll"), asset), enabled);
+
+        Ok(())
+    }
+
+    /// Enable or disable borrowing of an asset
+    ///
+    /// Each borrowable asset has its own supply, utilization and rate track;
+    /// disabling stops new borrows while existing debt keeps accruing and
+    /// can be repaid or liquidated.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `enabled` - Whether the asset can be borrowed
+    pub fn set_borrow_enabled(env: Env, asset: Symbol, enabled: bool) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+        Self::get_token_address(&env, &asset)?;
+
+        env.storage().instance().set(&DataKey::BorrowEnabled(asset.clone()), &enabled);
+
+        env.events().publish((symbol_short!("set_borr"), asset), enabled);
+
+        Ok(())
+    }
+
+    /// Set the reserve factor for an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `reserve_factor` - Portion of interest sent to reserves (scaled by SCALE), at most 50%
+    pub fn set_reserve_factor(env: Env, asset: Symbol, reserve_factor: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        // Accrue at the old rate before changing the split
+        Self::accrue_interest(&env, asset.clone())?;
+        env.storage().instance().set(&DataKey::ReserveFactor(asset.clone()), &reserve_factor);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("set_rf"), asset), reserve_factor);
+
+        Ok(())
+    }
+
+    /// Get the reserve factor for an asset (scaled by SCALE)
+    pub fn get_reserve_factor(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::ReserveFactor(asset)).unwrap_or(1_000_000)
+    }
+
+    /// Set the liquidation close factor
+    ///
+    /// # Arguments
+    /// * `close_factor` - Maximum portion of debt repayable per liquidation (scaled by SCALE), at least 5%
+    pub fn set_close_factor(env: Env, close_factor: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::CloseFactor, &close_factor);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("set_cf"),), close_factor);
+
+        Ok(())
+    }
+
+    /// Get the liquidation close factor (scaled by SCALE)
+    pub fn get_close_factor(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::CloseFactor).unwrap_or(CLOSE_FACTOR)
+    }
+
+    /// Set the origination fee charged on new borrows of an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `fee` - Fee rate (scaled by SCALE, 0.5% = 50_000), at most 5%
+    pub fn set_origination_fee(env: Env, asset: Symbol, fee: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::OriginationFee(asset.clone()), &fee);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("orig_fee"), asset), fee);
+
+        Ok(())
+    }
+
+    /// Get the origination fee for an asset (scaled by SCALE)
+    pub fn get_origination_fee(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&DataKey::OriginationFee(asset)).unwrap_or(0)
+    }
+
+    /// Set the fees taken from supplies and withdrawals of an asset (admin only)
+    ///
+    /// Fees go to the treasury, or to the asset's reserves while none is
+    /// set. Both default to zero and fee-exempt addresses never pay them.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `deposit_fee_bps` - Fee on supplies in basis points, at most 1%
+    /// * `withdraw_fee_bps` - Fee on withdrawals in basis points, at most 1%
+    pub fn set_deposit_withdraw_fees(
+        env: Env,
+        asset: Symbol,
+        deposit_fee_bps: u32,
+        withdraw_fee_bps: u32,
+    ) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+        Self::get_token_address(&env, &asset)?;
+
+        if deposit_fee_bps > MAX_DEPOSIT_WITHDRAW_FEE_BPS || withdraw_fee_bps > MAX_DEPOSIT_WITHDRAW_FEE_BPS {
+            return Err(PoolError::InvalidProtocolFee);
+        }
+        let deposit_fee = bps_to_scale(deposit_fee_bps as i128);
+        let withdraw_fee = bps_to_scale(withdraw_fee_bps as i128);
+        env.storage().instance().set(&ReserveKey::DepositFee(asset.clone()), &deposit_fee);
+        env.storage().instance().set(&ReserveKey::WithdrawFee(asset.clone()), &withdraw_fee);
+
+        env.events().publish((symbol_short!("dw_fees"), asset), (deposit_fee, withdraw_fee));
+
+        Ok(())
+    }
+
+    /// Get the fee taken from supplies of an asset (scaled by SCALE)
+    pub fn get_deposit_fee(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::DepositFee(asset)).unwrap_or(0)
+    }
+
+    /// Get the fee taken from withdrawals of an asset (scaled by SCALE)
+    pub fn get_withdraw_fee(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::WithdrawFee(asset)).unwrap_or(0)
+    }
+
+    /// Enable or disable stable-rate borrowing for an asset
+    ///
+    /// Disabling only blocks new stable borrows and swaps into stable;
+    /// existing stable debt keeps its locked rate.
+    pub fn set_stable_borrow_enabled(env: Env, asset: Symbol, enabled: bool) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::StableBorrowEnabled(asset.clone()), &enabled);
+
+        env.events().publish((symbol_short!("stbl_brw"), asset), enabled);
+
+        Ok(())
+    }
+
+    /// Check whether an asset can be borrowed at a stable rate
+    pub fn is_stable_borrow_enabled(env: Env, asset: Symbol) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::StableBorrowEnabled(asset))
+            .unwrap_or(false)
+    }
+
+    /// Set the fee charged on flash loans of an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `fee` - Fee rate (scaled by SCALE, 0.09% = 9_000), at most 1%
+    pub fn set_flash_loan_fee(env: Env, asset: Symbol, fee: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::FlashLoanFee(asset.clone()), &fee);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("flash_fee"), asset), fee);
+
+        Ok(())
+    }
+
+    /// Get the flash loan fee for an asset (scaled by SCALE)
+    pub fn get_flash_loan_fee(env: Env, asset: Symbol) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::FlashLoanFee(asset))
+            .unwrap_or(DEFAULT_FLASH_LOAN_FEE)
+    }
+
+    /// Set the maximum total supply of an asset
+    ///
+    /// Limits exposure to illiquid assets. Existing deposits above a lowered
+    /// cap are unaffected; only new supply is rejected.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `cap` - Cap in canonical 7-decimal units, like the pool's totals,
+    ///   whatever the token's decimals (0 = no cap)
+    pub fn set_supply_cap(env: Env, asset: Symbol, cap: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if cap < 0 {
+            return Err(PoolError::InvalidCap);
+        }
+        env.storage().instance().set(&ReserveKey::SupplyCap(asset.clone()), &cap);
+
+        env.events().publish((symbol_short!("sup_cap"), asset), cap);
+
+        Ok(())
+    }
+
+    /// Get the supply cap for an asset (0 = no cap)
+    pub fn get_supply_cap(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::SupplyCap(asset)).unwrap_or(0)
+    }
+
+    /// Set the maximum total borrows of an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `cap` - Cap in canonical 7-decimal units, like the pool's totals,
+    ///   whatever the token's decimals (0 = no cap)
+    pub fn set_borrow_cap(env: Env, asset: Symbol, cap: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if cap < 0 {
+            return Err(PoolError::InvalidCap);
+        }
+        env.storage().instance().set(&ReserveKey::BorrowCap(asset.clone()), &cap);
+
+        env.events().publish((symbol_short!("bor_cap"), asset), cap);
+
+        Ok(())
+    }
+
+    /// Get the borrow cap for an asset (0 = no cap)
+    pub fn get_borrow_cap(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::BorrowCap(asset)).unwrap_or(0)
+    }
+
+    /// Set the minimum borrow and the dust floor for an asset
+    ///
+    /// Borrows must leave the borrower's debt at or above `min_borrow`, and a
+    /// repay that would leave less than `dust` outstanding repays the whole
+    /// debt instead, so no position is too small to liquidate profitably.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `min_borrow` - Smallest debt position in canonical 7-decimal units (0 = no minimum)
+    /// * `dust` - Debt floor in canonical 7-decimal units (0 = disabled)
+    pub fn set_borrow_limits(env: Env, asset: Symbol, min_borrow: i128, dust: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if min_borrow < 0 || dust < 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        env.storage().instance().set(&ReserveKey::MinBorrow(asset.clone()), &min_borrow);
+        env.storage().instance().set(&ReserveKey::DebtDust(asset.clone()), &dust);
+
+        env.events().publish((symbol_short!("bor_lim"), asset), (min_borrow, dust));
+
+        Ok(())
+    }
+
+    /// Get the minimum debt position for an asset (0 = no minimum)
+    pub fn get_min_borrow(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::MinBorrow(asset)).unwrap_or(0)
+    }
+
+    /// Get the dust floor below which repays close the debt (0 = disabled)
+    pub fn get_debt_dust(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::DebtDust(asset)).unwrap_or(0)
+    }
+
+    /// Register the sToken contract for an asset
+    ///
+    /// The sToken must be initialized with this pool and the same asset
+    /// symbol. Shares supplied before registration become transferable too.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `s_token` - sToken contract address
+    pub fn set_s_token(env: Env, asset: Symbol, s_token: Address) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+        Self::get_token_address(&env, &asset)?;
+
+        env.storage().instance().set(&ReserveKey::SToken(asset.clone()), &s_token);
+
+        env.events().publish((symbol_short!("set_stkn"), asset), s_token);
+
+        Ok(())
+    }
+
+    /// Get the sToken contract for an asset, if registered
+    pub fn get_s_token(env: Env, asset: Symbol) -> Option<Address> {
+        env.storage().instance().get(&ReserveKey::SToken(asset))
+    }
+
+    /// Put an asset in isolation mode with a debt ceiling
+    ///
+    /// Collateral in an isolated asset cannot be combined with other
+    /// collateral, and all debt borrowed against it is limited to the
+    /// ceiling, valued at the time of borrowing. Use this to list long-tail
+    /// assets safely.
+    ///
+    /// # Arguments
+    /// * `asset` - Asset symbol
+    /// * `ceiling` - Debt ceiling in USD (scaled by 1e7, 0 = not isolated)
+    pub fn set_debt_ceiling(env: Env, asset: Symbol, ceiling: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if ceiling < 0 {
+            return Err(PoolError::InvalidCap);
+        }
+        env.storage().instance().set(&ReserveKey::DebtCeiling(asset.clone()), &ceiling);
+
+        env.events().publish((symbol_short!("debt_ceil"), asset), ceiling);
+
+        Ok(())
+    }
+
+    /// Get the debt ceiling of an isolated asset (0 = not isolated)
+    pub fn get_debt_ceiling(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::DebtCeiling(asset)).unwrap_or(0)
+    }
+
+    /// Whether an asset is in isolation mode
+    pub fn is_isolated(env: Env, asset: Symbol) -> bool {
+        Self::get_debt_ceiling(env, asset) > 0
+    }
+
+    /// Get the USD debt currently borrowed against an isolated asset (scaled by 1e7)
+    pub fn get_isolated_debt(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance().get(&ReserveKey::IsolatedDebt(asset)).unwrap_or(0)
+    }
+
+    /// Set the liquidation bonus for a collateral asset
+    ///
+    /// Applies when Dutch auctions are disabled. `threshold * (1 + bonus)`
+    /// must not exceed 100%.
+    ///
+    /// # Arguments
+    /// * `asset` - Collateral asset symbol
+    /// * `bonus` - Bonus (scaled by SCALE, 5% = 500_000)
+    pub fn set_liquidation_bonus(env: Env, asset: Symbol, bonus: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&ReserveKey::LiquidationBonus(asset.clone()), &bonus);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("liq_bonus"), asset), bonus);
+
+        Ok(())
+    }
+
+    /// Get the liquidation bonus for a collateral asset (scaled by SCALE)
+    pub fn get_liquidation_bonus(env: Env, asset: Symbol) -> i128 {
+        env.storage()
+            .instance()
+            .get(&ReserveKey::LiquidationBonus(asset))
+            .unwrap_or(LIQUIDATION_BONUS)
+    }
+
+    /// Set the portion of the liquidation bonus on a collateral asset sent to the treasury
+    ///
+    /// # Arguments
+    /// * `asset` - Collateral asset symbol
+    /// * `fee` - Portion of the bonus (scaled by SCALE, 10% = 1_000_000)
+    pub fn set_liquidation_protocol_fee(env: Env, asset: Symbol, fee: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&ReserveKey::LiquidationProtocolFee(asset.clone()), &fee);
+        Self::validate_risk_params(&env)?;
+
+        env.events().publish((symbol_short!("liq_fee"), asset), fee);
+
+        Ok(())
+    }
+
+    /// Get the liquidation protocol fee for a collateral asset (scaled by SCALE)
+    pub fn get_liquidation_protocol_fee(env: Env, asset: Symbol) -> i128 {
+        env.storage()
+            .instance()
+            .get(&ReserveKey::LiquidationProtocolFee(asset))
+            .unwrap_or(0)
+    }
+
+    /// Set the emissions controller notified of supply and debt changes (admin only)
+    ///
+    /// The controller must accept `handle_balance_change` calls from this
+    /// pool; balances changed before it was set are reported on the next
+    /// action of each user.
+    pub fn set_emissions(env: Env, emissions: Address) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&ProtocolKey::Emissions, &emissions);
+        env.events().publish((symbol_short!("emissions"),), emissions);
+
+        Ok(())
+    }
+
+    /// Get the emissions controller, if one is set
+    pub fn get_emissions(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ProtocolKey::Emissions)
+    }
+
+    /// Set the address receiving protocol fees
+    ///
+    /// Liquidation protocol fees are only taken once a treasury is set.
+    pub fn set_treasury(env: Env, treasury: Address) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::TreasuryManager)?;
+
+        env.storage().instance().set(&ProtocolKey::Treasury, &treasury);
+
+        env.events().publish((symbol_short!("treasury"),), treasury);
+
+        Ok(())
+    }
+
+    /// Get the address receiving protocol fees, if set
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ProtocolKey::Treasury)
+    }
+
+    /// Configure the Dutch auction used for liquidations
+    ///
+    /// While auctions are enabled, `threshold * (1 + max_bonus)` must not
+    /// exceed 100% for any listed reserve.
+    ///
+    /// # Arguments
+    /// * `max_bonus` - Bonus when the auction starts (scaled by SCALE, 20% = 2_000_000)
+    /// * `min_bonus` - Bonus once fully decayed (scaled by SCALE, 5% = 500_000)
+    /// * `duration` - Seconds to decay from max to min (0 disables auctions)
+    pub fn set_auction_params(env: Env, max_bonus: i128, min_bonus: i128, duration: u64) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if min_bonus < 0 || max_bonus < min_bonus {
+            return Err(PoolError::InvalidAuctionParams);
+        }
+        if max_bonus > SCALE {
+            return Err(PoolError::InvalidAuctionParams);
         }
 
-        // ====================================================================
-        // STEP 4: Execute liquidation
-        // ====================================================================
-        
-        // Transfer repay_asset from liquidator to pool
-        let repay_token: Address = env.storage().instance().get(&DataKey::TokenAddress(repay_asset.clone())).unwrap();
-        let repay_token_client = token::Client::new(&env, &repay_token);
-        repay_token_client.transfer(&liquidator, &env.current_contract_address(), &actual_repay);
-        
-        // Reduce borrower's debt
-        let borrower_debt_principal: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserDebt(borrower.clone(), repay_asset.clone()))
-            .unwrap_or(0);
-        let new_debt = if actual_repay >= borrower_debt {
-            0
+        env.storage().instance().set(&DataKey::MaxAuctionBonus, &max_bonus);
+        env.storage().instance().set(&DataKey::MinAuctionBonus, &min_bonus);
+        env.storage().instance().set(&DataKey::AuctionDuration, &duration);
+        Self::validate_risk_params(&env)?;
+
+        env.events()
+            .publish((symbol_short!("auct_cfg"),), (max_bonus, min_bonus, duration));
+
+        Ok(())
+    }
+
+    /// Get the Dutch auction configuration
+    ///
+    /// # Returns
+    /// (max_bonus, min_bonus, duration)
+    pub fn get_auction_params(env: Env) -> (i128, i128, u64) {
+        (
+            env.storage().instance().get(&DataKey::MaxAuctionBonus).unwrap_or(LIQUIDATION_BONUS),
+            env.storage().instance().get(&DataKey::MinAuctionBonus).unwrap_or(LIQUIDATION_BONUS),
+            env.storage().instance().get(&DataKey::AuctionDuration).unwrap_or(0),
+        )
+    }
+
+    /// Set the system-wide debt limit
+    ///
+    /// When total debt across all assets reaches this USD value, new borrows
+    /// are blocked automatically until repayments bring it back below
+    /// `limit * (1 - hysteresis)`.
+    ///
+    /// # Arguments
+    /// * `limit` - Limit in USD (scaled by 1e7), 0 disables the limit
+    pub fn set_global_borrow_limit(env: Env, limit: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if limit < 0 {
+            return Err(PoolError::InvalidBorrowLimit);
+        }
+
+        env.storage().instance().set(&DataKey::GlobalBorrowLimit, &limit);
+        Self::update_global_borrow_pause(&env)?;
+
+        env.events().publish((symbol_short!("gbl_limit"),), limit);
+
+        Ok(())
+    }
+
+    /// Get the system-wide debt limit in USD (0 = disabled)
+    pub fn get_global_borrow_limit(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::GlobalBorrowLimit).unwrap_or(0)
+    }
+
+    /// Set the hysteresis for re-enabling borrows after the global limit trips
+    ///
+    /// # Arguments
+    /// * `hysteresis` - Fraction of the limit (scaled by SCALE, 5% = 500_000)
+    pub fn set_global_borrow_hysteresis(env: Env, hysteresis: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if !(0..SCALE).contains(&hysteresis) {
+            return Err(PoolError::InvalidBorrowLimit);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::GlobalBorrowLimitHysteresis, &hysteresis);
+
+        Ok(())
+    }
+
+    /// Get the hysteresis for the global borrow limit (scaled by SCALE)
+    pub fn get_global_borrow_hysteresis(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::GlobalBorrowLimitHysteresis)
+            .unwrap_or(DEFAULT_GLOBAL_BORROW_HYSTERESIS)
+    }
+
+    /// Check whether new borrows are blocked by the global debt limit
+    pub fn is_global_borrow_paused(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::GlobalBorrowPaused)
+            .unwrap_or(false)
+    }
+
+    /// Set the volatility adjustment factor `k` used to reduce LTV
+    ///
+    /// # Arguments
+    /// * `factor` - Multiplier on relative volatility (scaled by SCALE, 1.0 = 10_000_000), 0 disables
+    pub fn set_volatility_adjustment_factor(env: Env, factor: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if factor < 0 {
+            return Err(PoolError::InvalidCap);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::VolatilityAdjustmentFactor, &factor);
+
+        Ok(())
+    }
+
+    /// Get the volatility adjustment factor (scaled by SCALE)
+    pub fn get_volatility_adjustment_factor(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::VolatilityAdjustmentFactor)
+            .unwrap_or(0)
+    }
+
+    /// Set the TWAP window used to value collateral
+    ///
+    /// # Arguments
+    /// * `window` - Trailing window in seconds, 0 values collateral at the spot price
+    pub fn set_twap_window(env: Env, window: u64) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage().instance().set(&DataKey::TwapWindow, &window);
+
+        env.events().publish((symbol_short!("twap_win"),), window);
+
+        Ok(())
+    }
+
+    /// Get the TWAP window used to value collateral (0 = spot price)
+    pub fn get_twap_window(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::TwapWindow).unwrap_or(0)
+    }
+
+    /// Fund the deposit matching program
+    ///
+    /// Transfers USDC from the treasury manager to the pool and adds it to the budget
+    /// used to match new USDC deposits.
+    ///
+    /// # Arguments
+    /// * `amount` - USDC to add to the match budget
+    pub fn fund_match_program(env: Env, amount: i128) -> Result<(), PoolError> {
+        let manager = Self::require_role(&env, Role::TreasuryManager)?;
+
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        Self::pull_underlying(&env, &USDC, &manager, amount, false)?;
+
+        let budget = Self::get_deposit_match_budget(env.clone()) + amount;
+        env.storage().instance().set(&DataKey::DepositMatchBudget, &budget);
+
+        env.events().publish((symbol_short!("mtch_fund"),), (amount, budget));
+
+        Ok(())
+    }
+
+    /// Set the percentage of new USDC deposits matched from the budget
+    ///
+    /// # Arguments
+    /// * `match_pct` - Match rate (scaled by SCALE, 10% = 1_000_000), 0 disables
+    pub fn set_deposit_match_pct(env: Env, match_pct: i128) -> Result<(), PoolError> {
+        Self::require_role(&env, Role::TreasuryManager)?;
+
+        if !(0..=SCALE).contains(&match_pct) {
+            return Err(PoolError::InvalidMatchPct);
+        }
+
+        env.storage().instance().set(&DataKey::DepositMatchPct, &match_pct);
+
+        Ok(())
+    }
+
+    /// Get the deposit match percentage (scaled by SCALE)
+    pub fn get_deposit_match_pct(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::DepositMatchPct).unwrap_or(0)
+    }
+
+    /// Get the remaining deposit match budget (USDC)
+    pub fn get_deposit_match_budget(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::DepositMatchBudget).unwrap_or(0)
+    }
+
+    /// Add or remove an address from the fee exemption list
+    ///
+    /// Exempt addresses (typically protocol-owned contracts acting on behalf
+    /// of users) skip origination, withdrawal, and flash loan fees.
+    ///
+    /// # Arguments
+    /// * `addr` - Address to update
+    /// * `exempt` - `true` to exempt from fees, `false` to remove the exemption
+    pub fn set_fee_exempt(env: Env, addr: Address, exempt: bool) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        if exempt {
+            env.storage().persistent().set(&DataKey::FeeExempt(addr.clone()), &true);
         } else {
-            // Calculate new principal based on repayment
-            let debt_reduction_ratio = (actual_repay * INITIAL_EXCHANGE_RATE) / borrower_debt;
-            borrower_debt_principal - (borrower_debt_principal * debt_reduction_ratio) / INITIAL_EXCHANGE_RATE
-        };
+            env.storage().persistent().remove(&DataKey::FeeExempt(addr.clone()));
+        }
+
+        env.events().publish((symbol_short!("fee_exmt"), addr), exempt);
+
+        Ok(())
+    }
+
+    /// Check whether an address is exempt from protocol fees
+    pub fn is_fee_exempt(env: Env, addr: Address) -> bool {
         env.storage()
             .persistent()
-            .set(&DataKey::UserDebt(borrower.clone(), repay_asset.clone()), &new_debt);
-        
-        // Reduce total borrows
-        let total_borrow: i128 = env.storage().instance().get(&DataKey::TotalBorrow(repay_asset.clone())).unwrap_or(0);
-        let new_total_borrow = if total_borrow > actual_repay {
-            total_borrow - actual_repay
+            .get(&DataKey::FeeExempt(addr))
+            .unwrap_or(false)
+    }
+
+    // ========================================================================
+    // UPGRADES
+    // ========================================================================
+
+    /// Replace the contract code (admin only)
+    ///
+    /// Storage is kept as is; call `migrate` afterwards so the new code can
+    /// bring older data layouts up to date.
+    ///
+    /// # Arguments
+    /// * `new_wasm_hash` - Hash of the already uploaded WASM
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        env.events().publish((symbol_short!("upgraded"),), new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Run the storage migrations between the stored and current layout (admin only)
+    ///
+    /// Each step runs once and records its version, so calling `migrate`
+    /// again (or on an up-to-date pool) does nothing.
+    ///
+    /// # Returns
+    /// The storage version after migrating
+    pub fn migrate(env: Env) -> Result<u32, PoolError> {
+        Self::require_admin(&env)?;
+
+        let from = Self::get_storage_version(env.clone());
+        if from > STORAGE_VERSION {
+            return Err(PoolError::StorageVersionTooNew);
+        }
+
+        for version in (from + 1)..=STORAGE_VERSION {
+            Self::migrate_step(&env, version);
+            env.storage().instance().set(&ProtocolKey::StorageVersion, &version);
+        }
+
+        if from < STORAGE_VERSION {
+            env.events().publish((symbol_short!("migrated"),), (from, STORAGE_VERSION));
+        }
+
+        Ok(STORAGE_VERSION)
+    }
+
+    /// Get the storage layout version of the deployed data
+    pub fn get_storage_version(env: Env) -> u32 {
+        env.storage().instance().get(&ProtocolKey::StorageVersion).unwrap_or(0)
+    }
+
+    /// Internal: Bring storage from `version - 1` to `version`
+    ///
+    /// Steps must leave already migrated data untouched.
+    fn migrate_step(env: &Env, version: u32) {
+        if version == 1 {
+            // Pools deployed before multi-asset listing have no reserve list;
+            // list their built-in markets so risk checks and views see them
+            let mut reserves = Self::list_reserves(env.clone());
+            for asset in [XLM, USDC] {
+                if !reserves.contains(&asset) && env.storage().instance().has(&DataKey::TokenAddress(asset.clone())) {
+                    reserves.push_back(asset);
+                }
+            }
+            env.storage().instance().set(&DataKey::Reserves, &reserves);
+        }
+        if version == 2 {
+            // The borrow index moved from the exchange rate scale (1e9) to
+            // ray; user indices are rescaled when read
+            for asset in Self::list_reserves(env.clone()).iter() {
+                let key = DataKey::BorrowIndex(asset);
+                let index: i128 = env.storage().instance().get(&key).unwrap_or(INITIAL_EXCHANGE_RATE);
+                if index < LEGACY_BORROW_INDEX_LIMIT {
+                    env.storage().instance().set(&key, &(index * (RAY / EXCHANGE_RATE_SCALE)));
+                }
+            }
+        }
+    }
+
+    // ========================================================================
+    // PAUSE / GUARDIAN
+    // ========================================================================
+
+    /// Set the guardian allowed to pause the pool (admin only)
+    pub fn set_guardian(env: Env, guardian: Address) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&DataKey::Guardian, &guardian);
+        env.events().publish((symbol_short!("guardian"),), guardian);
+
+        Ok(())
+    }
+
+    /// Get the guardian, if one is set
+    pub fn get_guardian(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Guardian)
+    }
+
+    /// Pause all user actions
+    ///
+    /// Callable by the admin or the guardian, e.g. during an oracle incident.
+    pub fn pause(env: Env, caller: Address) -> Result<(), PoolError> {
+        Self::require_admin_or_guardian(&env, &caller)?;
+
+        env.storage().instance().set(&DataKey::Paused, &true);
+        env.events().publish((symbol_short!("paused"),), caller);
+
+        Ok(())
+    }
+
+    /// Resume all user actions (admin only)
+    ///
+    /// Actions paused individually stay paused.
+    pub fn unpause(env: Env) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+
+        env.storage().instance().set(&DataKey::Paused, &false);
+        env.events().publish((symbol_short!("unpaused"),), admin);
+
+        Ok(())
+    }
+
+    /// Pause or resume a single user action
+    ///
+    /// The admin or guardian can pause an action (e.g. pause borrows while
+    /// still allowing repay and withdraw); only the admin can resume it.
+    pub fn set_action_paused(
+        env: Env,
+        caller: Address,
+        action: PoolAction,
+        paused: bool,
+    ) -> Result<(), PoolError> {
+        if paused {
+            Self::require_admin_or_guardian(&env, &caller)?;
         } else {
-            0
+            caller.require_auth();
+            if caller != Self::get_admin(env.clone())? {
+                return Err(PoolError::Unauthorized);
+            }
+        }
+
+        env.storage().instance().set(&DataKey::ActionPaused(action), &paused);
+        env.events().publish((symbol_short!("act_pause"), action), paused);
+
+        Ok(())
+    }
+
+    /// Check whether all user actions are paused
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+    }
+
+    /// Check whether a user action is paused, globally or individually
+    pub fn is_action_paused(env: Env, action: PoolAction) -> bool {
+        Self::is_paused(env.clone())
+            || env
+                .storage()
+                .instance()
+                .get(&DataKey::ActionPaused(action))
+                .unwrap_or(false)
+    }
+
+    /// Internal: Require `caller` to be the admin or the guardian
+    fn require_admin_or_guardian(env: &Env, caller: &Address) -> Result<(), PoolError> {
+        caller.require_auth();
+
+        if *caller == Self::get_admin(env.clone())? {
+            return Ok(());
+        }
+        match Self::get_guardian(env.clone()) {
+            Some(guardian) if guardian == *caller => Ok(()),
+            _ => Err(PoolError::Unauthorized),
+        }
+    }
+
+    /// Internal: Fail if a user action is paused
+    fn require_not_paused(env: &Env, action: PoolAction) -> Result<(), PoolError> {
+        if Self::is_action_paused(env.clone(), action) {
+            return Err(PoolError::ActionPaused);
+        }
+        Ok(())
+    }
+
+    /// Internal: Fail if the ledger is past the caller's deadline, so a
+    /// transaction held back by the network cannot execute at later prices
+    fn require_deadline(env: &Env, deadline: Option<u64>) -> Result<(), PoolError> {
+        match deadline {
+            Some(deadline) if env.ledger().timestamp() > deadline => Err(PoolError::DeadlineExpired),
+            _ => Ok(()),
+        }
+    }
+
+    /// Internal: Check pool-wide accounting at the end of a state-changing
+    /// call (test builds only)
+    ///
+    /// In every reserve, borrows stay backed by deposits plus the reserves'
+    /// share of interest, and the borrow index never moves backwards.
+    #[cfg(any(test, feature = "testutils"))]
+    fn assert_invariants(env: &Env) {
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let storage = env.storage().instance();
+            let total_supply: i128 = storage.get(&DataKey::TotalSupply(asset.clone())).unwrap_or(0);
+            let total_borrow: i128 = storage.get(&DataKey::TotalBorrow(asset.clone())).unwrap_or(0);
+            let total_reserves: i128 = storage.get(&DataKey::TotalReserves(asset.clone())).unwrap_or(0);
+            assert!(
+                total_borrow <= total_supply + total_reserves,
+                "borrows exceed deposits"
+            );
+
+            let key = (symbol_short!("inv_index"), asset.clone());
+            let borrow_index = Self::get_borrow_index(env.clone(), asset);
+            let last_index: i128 = env.storage().temporary().get(&key).unwrap_or(0);
+            assert!(borrow_index >= last_index, "borrow index decreased");
+            env.storage().temporary().set(&key, &borrow_index);
+        }
+    }
+
+    #[cfg(not(any(test, feature = "testutils")))]
+    fn assert_invariants(_env: &Env) {}
+
+    // ========================================================================
+    // EMERGENCY MODE
+    // ========================================================================
+
+    /// Set how old a reserve's oracle price may get before the pool enters
+    /// emergency mode (admin only)
+    ///
+    /// # Arguments
+    /// * `max_age` - Seconds since the last price update (0 = disabled)
+    pub fn set_oracle_hard_limit(env: Env, max_age: u64) -> Result<(), PoolError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&ProtocolKey::OracleHardLimit, &max_age);
+        env.events().publish((symbol_short!("hard_lim"),), max_age);
+
+        Ok(())
+    }
+
+    /// Get the oracle hard staleness limit in seconds (0 = disabled)
+    pub fn get_oracle_hard_limit(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&ProtocolKey::OracleHardLimit)
+            .unwrap_or(0)
+    }
+
+    /// Enter emergency mode because a reserve's price is past the hard limit
+    ///
+    /// Permissionless, so anyone can latch the mode once the oracle goes
+    /// quiet. Borrows are frozen and only debt-free users can withdraw
+    /// collateral; supply withdrawals and repayments still work. The mode
+    /// stays on after prices recover until the admin clears it.
+    ///
+    /// # Errors
+    /// * `Unauthorized` if the limit is disabled or every price is within it
+    pub fn trigger_emergency(env: Env) -> Result<(), PoolError> {
+        let Some((asset, age)) = Self::stale_reserve(&env)? else {
+            return Err(PoolError::Unauthorized);
         };
-        env.storage().instance().set(&DataKey::TotalBorrow(repay_asset.clone()), &new_total_borrow);
-        
-        // Transfer collateral from borrower to liquidator
-        let new_borrower_collateral = borrower_collateral - collateral_to_seize;
+
+        env.storage().instance().set(&ProtocolKey::EmergencyMode, &true);
+        env.events().publish((symbol_short!("emergency"), asset), age);
+
+        Ok(())
+    }
+
+    /// Leave emergency mode (admin only)
+    ///
+    /// Borrows stay frozen while a price is still past the hard limit;
+    /// raising or disabling the limit overrides that as well.
+    pub fn clear_emergency(env: Env) -> Result<(), PoolError> {
+        let admin = Self::require_admin(&env)?;
+
+        env.storage().instance().set(&ProtocolKey::EmergencyMode, &false);
+        env.events().publish((symbol_short!("emrg_clr"),), admin);
+
+        Ok(())
+    }
+
+    /// Check whether the pool is in emergency mode
+    ///
+    /// True once triggered until cleared, and also whenever a reserve's
+    /// price is currently past the hard limit, so borrows are refused even
+    /// before anyone triggers the mode.
+    pub fn is_emergency_mode(env: Env) -> bool {
         env.storage()
-            .persistent()
-            .set(&DataKey::UserCollateral(borrower.clone(), collateral_asset.clone()), &new_borrower_collateral);
-        
-        // Transfer collateral tokens to liquidator
-        let collateral_token: Address = env.storage().instance().get(&DataKey::TokenAddress(collateral_asset.clone())).unwrap();
-        let collateral_token_client = token::Client::new(&env, &collateral_token);
-        collateral_token_client.transfer(&env.current_contract_address(), &liquidator, &collateral_to_seize);
+            .instance()
+            .get(&ProtocolKey::EmergencyMode)
+            .unwrap_or(false)
+            || matches!(Self::stale_reserve(&env), Ok(Some(_)))
+    }
 
-        // ====================================================================
-        // STEP 5: Emit event and return
-        // ====================================================================
-        
-        env.events().publish(
-            (symbol_short!("liquidate"), liquidator, borrower),
-            (actual_repay, collateral_to_seize)
-        );
+    /// Internal: The first reserve whose price is older than the hard limit,
+    /// with its age in seconds
+    fn stale_reserve(env: &Env) -> Result<Option<(Symbol, u64)>, PoolError> {
+        let limit = Self::get_oracle_hard_limit(env.clone());
+        if limit == 0 {
+            return Ok(None);
+        }
+
+        let oracle = oracle_contract::Client::new(env, &Self::get_oracle(env)?);
+        let now = env.ledger().timestamp();
+        for asset in Self::list_reserves(env.clone()).iter() {
+            let last_update = oracle.get_last_update(&Self::get_token_address(env, &asset)?);
+            let age = now.saturating_sub(last_update);
+            if age > limit {
+                return Ok(Some((asset, age)));
+            }
+        }
+        Ok(None)
+    }
 
-        collateral_to_seize
+    /// Internal: Whether a user owes anything in any reserve
+    fn has_debt(env: &Env, user: &Address) -> Result<bool, PoolError> {
+        for asset in Self::list_reserves(env.clone()).iter() {
+            if Self::get_user_total_debt(env, user, &asset)? > 0 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 }
 
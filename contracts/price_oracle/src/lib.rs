@@ -8,9 +8,17 @@
 //!
 //! ## Features
 //!
-//! - **Admin-only price updates**: Only authorized keeper can set prices
+//! - **Keeper price updates**: Only the oracle feeder role can set prices
+//! - **Roles**: Feeder and risk-parameter keys can be split off from the admin
+//! - **Deviation circuit breaker**: Updates that move a price by more than
+//!   the max deviation are held until a second signer confirms them
+//! - **Multi-feeder mode**: Once feeders are registered, the price is the
+//!   median of their recent submissions and requires a quorum
 //! - **Staleness checks**: Prices can be verified as fresh
-//! - **Chaos mode**: 50% price crash simulation for demos
+//! - **Price bounds**: Per-asset min/max sanity bounds reject absurd updates
+//! - **Stablecoin peg band**: Updates that take a pegged asset outside its
+//!   band are held until `confirm_depeg`, which flags the asset as depegged
+//! - **Chaos mode**: Time-limited price haircut on reads for crash demos
 //! - **Multiple assets**: Supports XLM, USDC, and extensible for more
 //!
 //! ## Price Scaling
@@ -22,25 +30,68 @@
 //!
 //! ## Demo Flow
 //!
-//! 1. Deploy oracle, initialize with admin
-//! 2. Keeper script calls `set_price(XLM, price)` periodically
-//! 3. Pool contract calls `get_price(XLM)` to value collateral
-//! 4. For crash demo: keeper calls `set_price(XLM, price * 0.5)` or uses --crash flag
+//! 1. Deploy oracle, initialize with admin and the XLM/USDC token contracts
+//! 2. Keeper script calls `set_price_by_symbol(XLM, price)` periodically
+//! 3. Pool contract calls `get_price(xlm_token)` to value collateral
+//! 4. For crash demo: admin calls `set_chaos(xlm_token, 5000, duration)` to
+//!    serve XLM 50% lower until the chaos window ends
+//!
+//! ## SEP-40
+//!
+//! The contract also exposes the standard SEP-40 price feed interface
+//! (`base`, `assets`, `decimals`, `resolution`, `price`, `prices`,
+//! `lastprice`), so consumers can swap it for any SEP-40 feed such as
+//! Reflector. A short price history per asset backs `price` and `prices`.
+//!
+//! ## Asset Keys
+//!
+//! Prices are keyed by token contract address, so two assets with the same
+//! code from different issuers never share a price. A symbol → address
+//! registry lets keepers and UIs keep using tickers.
 
-use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, Address, Env, Symbol};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Symbol, Vec,
+};
+use stellend_fixed_point::{div_fixed, mul_div, mul_fixed, FixedPointError, Rounding};
+
+pub use apogee_common::Role;
+use apogee_common::{bps_to_scale, convert_amount, BPS_SCALE, DAY_IN_LEDGERS, PRICE_SCALE, SCALE};
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Scaling factor for prices (1e7 = 10_000_000)
-/// - $1.00 = 10_000_000
-/// - $0.01 = 100_000
-const PRICE_SCALE: i128 = 10_000_000;
-
 /// Default staleness threshold: 1 hour (3600 seconds)
 const DEFAULT_STALENESS_THRESHOLD: u64 = 3600;
 
+/// Number of decimals in prices (SEP-40 `decimals`)
+const PRICE_DECIMALS: u32 = 7;
+
+/// Expected seconds between keeper updates (SEP-40 `resolution`)
+const RESOLUTION: u32 = 300;
+
+/// Token decimals assumed for assets without an explicit setting
+const DEFAULT_TOKEN_DECIMALS: u32 = 7;
+
+/// Largest supported token decimals
+const MAX_TOKEN_DECIMALS: u32 = 18;
+
+/// Number of past prices kept per asset for SEP-40 history queries
+const MAX_PRICE_HISTORY: u32 = 48;
+
+/// Storage TTL for price rounds (in ledgers, ~5s each)
+/// TTL that round entries are extended to
+const ROUND_BUMP_AMOUNT: u32 = 90 * DAY_IN_LEDGERS;
+/// Round entries are only extended once their TTL drops below this
+const ROUND_LIFETIME_THRESHOLD: u32 = ROUND_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Default peg band for USDC: $0.95 to $1.05 (scaled by 1e7)
+const DEFAULT_PEG_LOWER: i128 = 9_500_000;
+const DEFAULT_PEG_UPPER: i128 = 10_500_000;
+
+/// Weight of the latest squared price change in the variance EWMA (20%, scaled by 1e7)
+const VARIANCE_EWMA_WEIGHT: i128 = 2_000_000;
+
 /// Asset symbols
 pub const XLM: Symbol = symbol_short!("XLM");
 pub const USDC: Symbol = symbol_short!("USDC");
@@ -53,14 +104,159 @@ pub const USDC: Symbol = symbol_short!("USDC");
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
-    /// Admin/keeper address authorized to update prices
+    /// Admin address; also acts for any role nobody was granted
     Admin,
+    /// Address holding an operational role
+    Role(Role),
+    /// Token contract address registered for an asset symbol
+    AssetAddress(Symbol),
+    /// Registered token contracts, in registration order
+    Assets,
     /// Price for an asset (scaled by 1e7)
-    Price(Symbol),
+    Price(Address),
     /// Last update timestamp for an asset
-    LastUpdate(Symbol),
-    /// Staleness threshold in seconds
+    LastUpdate(Address),
+    /// Default staleness threshold in seconds
     StalenessThreshold,
+    /// Per-asset staleness threshold in seconds (overrides the default)
+    Heartbeat(Address),
+    /// Explicit expiry timestamp for an asset's price (overrides staleness threshold)
+    PriceExpiry(Address),
+    /// Exponentially weighted moving average of squared price changes (price units squared)
+    PriceVariance(Address),
+    /// Most recent prices for an asset, oldest first (at most `MAX_PRICE_HISTORY`)
+    PriceHistory(Address),
+    /// Registered price feeders
+    Feeders,
+    /// Number of recent feeder submissions required to update a price
+    Quorum,
+    /// Latest price submitted by a feeder for an asset (asset, feeder)
+    Submission(Address, Address),
+    /// Maximum move of a price in one update (scaled by 1e7, 0 = disabled)
+    MaxDeviation,
+    /// Address that confirms held updates (falls back to the admin)
+    Confirmer,
+    /// Update held by the deviation circuit breaker, awaiting confirmation
+    PendingPrice(Address),
+    /// Id of the latest price round for an asset
+    LatestRound(Address),
+    /// Price round for an asset (asset, round id), in persistent storage
+    Round(Address, u64),
+    /// Active chaos mode for an asset
+    Chaos(Address),
+    /// Decimals of an asset's token amounts (defaults to 7)
+    Decimals(Address),
+    /// Price band (lower, upper) of an asset flagged as a stablecoin
+    PegBand(Address),
+    /// Update outside the peg band, awaiting `confirm_depeg`
+    PendingDepeg(Address),
+    /// Set once a depeg has been confirmed, until the price is back in band
+    Depegged(Address),
+    /// Set while an asset's feed is paused
+    FeedPaused(Address),
+    /// Sanity bounds (min, max) on updates to an asset's price
+    PriceBounds(Address),
+}
+
+/// SEP-40 asset identifier
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Asset {
+    /// Stellar asset, identified by its token contract
+    Stellar(Address),
+    /// Off-chain asset, identified by its registered symbol
+    Other(Symbol),
+}
+
+/// A recorded price update
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceRound {
+    /// Sequential id per asset, starting at 1
+    pub round_id: u64,
+    /// Price in USD (scaled by 1e7)
+    pub price: i128,
+    /// Timestamp of the update
+    pub timestamp: u64,
+    /// Ledger sequence of the update
+    pub ledger: u32,
+}
+
+/// Simulated crash applied to price reads
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ChaosMode {
+    /// Haircut applied to reads, in basis points
+    pub bps_drop: u32,
+    /// Timestamp at which reads return to the stored price
+    pub ends_at: u64,
+}
+
+/// SEP-40 price record
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceData {
+    /// Price in USD (scaled by 1e7)
+    pub price: i128,
+    /// Timestamp of the update
+    pub timestamp: u64,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the price oracle
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum OracleError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Price must be positive
+    InvalidPrice = 3,
+    /// Expiry must be positive
+    InvalidExpiry = 4,
+    /// No price has been set for the asset
+    PriceNotSet = 5,
+    /// Price is older than its expiry or the staleness threshold
+    PriceStale = 6,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 7,
+    /// No token contract is registered for the symbol
+    AssetNotRegistered = 8,
+    /// Caller is not a registered feeder
+    NotFeeder = 9,
+    /// Prices come from feeders; direct admin updates are disabled
+    FeedersActive = 10,
+    /// Quorum must be between 1 and the number of feeders
+    InvalidQuorum = 11,
+    /// No held price update for the asset
+    NoPendingPrice = 12,
+    /// Max deviation must be between 0 and 100%
+    InvalidDeviation = 13,
+    /// Chaos drop must be at most 100% and its duration positive
+    InvalidChaos = 14,
+    /// Token decimals must be at most 18
+    InvalidDecimals = 15,
+    /// Role cannot be granted or revoked this way
+    InvalidRole = 16,
+    /// Peg band bounds must be positive with lower below upper
+    InvalidPegBand = 17,
+    /// The asset's price feed is paused
+    FeedPaused = 18,
+    /// Price bounds must satisfy 0 < min < max
+    InvalidPriceBounds = 19,
+    /// Price is outside the asset's sanity bounds
+    PriceOutOfBounds = 20,
+}
+
+impl From<FixedPointError> for OracleError {
+    fn from(_: FixedPointError) -> Self {
+        OracleError::MathOverflow
+    }
 }
 
 // ============================================================================
@@ -83,19 +279,30 @@ impl PriceOracle {
     ///
     /// # Arguments
     /// * `admin` - Address authorized to update prices (keeper wallet)
+    /// * `xlm_token` - XLM token contract, registered as `XLM`
+    /// * `usdc_token` - USDC token contract, registered as `USDC`
     ///
     /// # Initial State
-    /// - USDC price set to $1.00 (stablecoin)
+    /// - USDC price set to $1.00 (stablecoin), pegged to $0.95 - $1.05
     /// - XLM price unset (must be set by keeper)
     /// - Staleness threshold: 1 hour
-    pub fn initialize(env: Env, admin: Address) {
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        xlm_token: Address,
+        usdc_token: Address,
+    ) -> Result<(), OracleError> {
         if env.storage().instance().has(&DataKey::Admin) {
-            panic!("Already initialized");
+            return Err(OracleError::AlreadyInitialized);
         }
 
         // Store admin
         env.storage().instance().set(&DataKey::Admin, &admin);
 
+        // Register the symbols used by the pool
+        Self::store_asset(&env, XLM, xlm_token);
+        Self::store_asset(&env, USDC, usdc_token.clone());
+
         // Set staleness threshold
         env.storage()
             .instance()
@@ -104,13 +311,19 @@ impl PriceOracle {
         // Initialize USDC to $1.00 (stablecoin assumption)
         env.storage()
             .instance()
-            .set(&DataKey::Price(USDC), &PRICE_SCALE);
+            .set(&DataKey::Price(usdc_token.clone()), &PRICE_SCALE);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastUpdate(usdc_token.clone()), &env.ledger().timestamp());
+        Self::record_history(&env, &usdc_token, PRICE_SCALE);
         env.storage()
             .instance()
-            .set(&DataKey::LastUpdate(USDC), &env.ledger().timestamp());
+            .set(&DataKey::PegBand(usdc_token), &(DEFAULT_PEG_LOWER, DEFAULT_PEG_UPPER));
 
         // Emit initialization event
         env.events().publish((symbol_short!("init"),), admin);
+
+        Ok(())
     }
 
     // ========================================================================
@@ -122,19 +335,60 @@ impl PriceOracle {
     /// Only callable by the admin/keeper address.
     ///
     /// # Arguments
-    /// * `asset` - Asset symbol (e.g., XLM, USDC)
+    /// * `asset` - Token contract address
     /// * `price` - Price in USD scaled by 1e7 (e.g., $0.30 = 3_000_000)
     ///
     /// # Events
     /// Emits `("set_price", asset)` with the new price
-    pub fn set_price(env: Env, asset: Symbol, price: i128) {
+    pub fn set_price(env: Env, asset: Address, price: i128) -> Result<(), OracleError> {
         // Verify admin authorization
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+        Self::require_direct_update(&env)?;
+
+        Self::write_price(&env, asset, price)?;
+        Ok(())
+    }
+
+    /// Set price for an asset by its registered symbol
+    ///
+    /// Same as `set_price`, for keepers that track assets by ticker.
+    ///
+    /// # Arguments
+    /// * `symbol` - Registered asset symbol (e.g., XLM, USDC)
+    /// * `price` - Price in USD scaled by 1e7
+    pub fn set_price_by_symbol(env: Env, symbol: Symbol, price: i128) -> Result<(), OracleError> {
+        Self::require_direct_update(&env)?;
+
+        let asset = Self::get_asset_address(env.clone(), symbol)?;
+        Self::write_price(&env, asset, price)?;
+        Ok(())
+    }
 
+    /// Internal: Validate a price and store it, unless the circuit breaker holds it
+    ///
+    /// # Returns
+    /// `true` if the price was applied, `false` if it is held for confirmation
+    fn write_price(env: &Env, asset: Address, price: i128) -> Result<bool, OracleError> {
         if price <= 0 {
-            panic!("Price must be positive");
+            return Err(OracleError::InvalidPrice);
         }
+        Self::check_price_bounds(env, &asset, price)?;
+
+        if Self::breaks_peg(env, &asset, price) {
+            Self::hold_depeg(env, asset, price);
+            return Ok(false);
+        }
+        if Self::exceeds_max_deviation(env, &asset, price)? {
+            Self::hold_price(env, asset, price)?;
+            return Ok(false);
+        }
+
+        Self::apply_price(env, asset, price)?;
+        Ok(true)
+    }
+
+    /// Internal: Store a price and its timestamp
+    fn apply_price(env: &Env, asset: Address, price: i128) -> Result<(), OracleError> {
+        Self::update_variance(env, &asset, price)?;
 
         // Store price and timestamp
         env.storage()
@@ -143,276 +397,1379 @@ impl PriceOracle {
         env.storage()
             .instance()
             .set(&DataKey::LastUpdate(asset.clone()), &env.ledger().timestamp());
+        Self::record_history(env, &asset, price);
+        Self::update_peg_status(env, &asset, price);
+
+        // A plain update falls back to the staleness threshold and supersedes
+        // any held update
+        env.storage()
+            .instance()
+            .remove(&DataKey::PriceExpiry(asset.clone()));
+        env.storage()
+            .instance()
+            .remove(&DataKey::PendingPrice(asset.clone()));
 
         // Emit event for indexers/UI
         env.events().publish((symbol_short!("set_price"), asset), price);
+        Ok(())
     }
 
-    /// Set multiple prices in a single transaction
+    /// Set price for an asset with an explicit time-to-live
     ///
-    /// More efficient for updating XLM and USDC together.
+    /// Only callable by the admin/keeper address. While an expiry is set,
+    /// `get_price_safe` and `is_stale` use it instead of the staleness threshold.
     ///
     /// # Arguments
-    /// * `xlm_price` - XLM price in USD (scaled by 1e7)
-    /// * `usdc_price` - USDC price in USD (scaled by 1e7, typically 10_000_000)
-    pub fn set_prices(env: Env, xlm_price: i128, usdc_price: i128) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    /// * `asset` - Token contract address
+    /// * `price` - Price in USD scaled by 1e7
+    /// * `expiry` - Seconds from now after which the price is expired
+    ///
+    /// # Events
+    /// Emits `("set_price", asset)` with `(price, expires_at)`
+    pub fn set_price_with_expiry(
+        env: Env,
+        asset: Address,
+        price: i128,
+        expiry: u64,
+    ) -> Result<(), OracleError> {
+        Self::require_direct_update(&env)?;
+
+        if price <= 0 {
+            return Err(OracleError::InvalidPrice);
+        }
+        if expiry == 0 {
+            return Err(OracleError::InvalidExpiry);
+        }
+        Self::check_price_bounds(&env, &asset, price)?;
 
-        if xlm_price <= 0 || usdc_price <= 0 {
-            panic!("Prices must be positive");
+        if Self::breaks_peg(&env, &asset, price) {
+            Self::hold_depeg(&env, asset, price);
+            return Ok(());
         }
+        if Self::exceeds_max_deviation(&env, &asset, price)? {
+            Self::hold_price(&env, asset, price)?;
+            return Ok(());
+        }
+
+        Self::update_variance(&env, &asset, price)?;
 
         let timestamp = env.ledger().timestamp();
+        let expires_at = timestamp + expiry;
 
-        // Set XLM
-        env.storage().instance().set(&DataKey::Price(XLM), &xlm_price);
-        env.storage().instance().set(&DataKey::LastUpdate(XLM), &timestamp);
+        env.storage()
+            .instance()
+            .set(&DataKey::Price(asset.clone()), &price);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastUpdate(asset.clone()), &timestamp);
+        env.storage()
+            .instance()
+            .set(&DataKey::PriceExpiry(asset.clone()), &expires_at);
+        env.storage()
+            .instance()
+            .remove(&DataKey::PendingPrice(asset.clone()));
+        Self::record_history(&env, &asset, price);
+        Self::update_peg_status(&env, &asset, price);
 
-        // Set USDC
-        env.storage().instance().set(&DataKey::Price(USDC), &usdc_price);
-        env.storage().instance().set(&DataKey::LastUpdate(USDC), &timestamp);
+        env.events()
+            .publish((symbol_short!("set_price"), asset), (price, expires_at));
 
-        // Emit events
-        env.events().publish((symbol_short!("set_price"), XLM), xlm_price);
-        env.events().publish((symbol_short!("set_price"), USDC), usdc_price);
+        Ok(())
     }
 
-    /// Simulate a price crash (50% drop) for demo purposes
+    /// Set the prices of several assets in a single transaction
     ///
-    /// This is a convenience function for the chaos mode demo.
-    /// It takes the CURRENT price and halves it.
+    /// One feeder authorization covers the whole batch, and the batch is
+    /// atomic: every entry is validated before any price is written, so
+    /// keepers never leave some prices fresh and others stale. Each entry
+    /// still goes through the circuit breaker and peg band like a single
+    /// `set_price_by_symbol`.
     ///
     /// # Arguments
-    /// * `asset` - Asset to crash (typically XLM)
+    /// * `prices` - (registered symbol, price in USD scaled by 1e7) pairs
     ///
     /// # Events
-    /// Emits `("crash", asset)` with the crashed price
-    pub fn crash_price(env: Env, asset: Symbol) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    /// Emits `("set_price", asset)` for each applied price
+    pub fn set_prices(env: Env, prices: Vec<(Symbol, i128)>) -> Result<(), OracleError> {
+        Self::require_direct_update(&env)?;
+
+        let mut updates: Vec<(Address, i128)> = Vec::new(&env);
+        for (symbol, price) in prices.iter() {
+            if price <= 0 {
+                return Err(OracleError::InvalidPrice);
+            }
+            updates.push_back((Self::get_asset_address(env.clone(), symbol)?, price));
+        }
 
-        let current_price: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Price(asset.clone()))
-            .unwrap_or(0);
+        for (asset, price) in updates.iter() {
+            Self::write_price(&env, asset, price)?;
+        }
+        Ok(())
+    }
 
-        if current_price == 0 {
-            panic!("Cannot crash: price not set");
+    /// Start or end chaos mode for an asset
+    ///
+    /// While active, price reads return the stored price less `bps_drop`
+    /// basis points, so demos can simulate a crash without touching the
+    /// stored price, its history or the circuit breaker. Chaos ends on its
+    /// own after `duration` seconds; a `bps_drop` of 0 ends it now.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract of the asset to crash (typically XLM)
+    /// * `bps_drop` - Haircut in basis points (5000 = 50%)
+    /// * `duration` - Seconds the haircut applies for
+    ///
+    /// # Events
+    /// Emits `("chaos", asset)` with `(bps_drop, ends_at)` on start and
+    /// `("chaos_end", asset)` when ended early
+    pub fn set_chaos(env: Env, asset: Address, bps_drop: u32, duration: u64) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        if bps_drop == 0 {
+            env.storage().instance().remove(&DataKey::Chaos(asset.clone()));
+            env.events().publish((symbol_short!("chaos_end"), asset), ());
+            return Ok(());
         }
 
-        // Apply 50% reduction
-        let crashed_price = current_price / 2;
+        if bps_drop as i128 > BPS_SCALE || duration == 0 {
+            return Err(OracleError::InvalidChaos);
+        }
 
+        let ends_at = env.ledger().timestamp().saturating_add(duration);
         env.storage()
             .instance()
-            .set(&DataKey::Price(asset.clone()), &crashed_price);
-        env.storage()
-            .instance()
-            .set(&DataKey::LastUpdate(asset.clone()), &env.ledger().timestamp());
+            .set(&DataKey::Chaos(asset.clone()), &ChaosMode { bps_drop, ends_at });
 
-        // Emit crash event
         env.events()
-            .publish((symbol_short!("crash"), asset), crashed_price);
+            .publish((symbol_short!("chaos"), asset), (bps_drop, ends_at));
+
+        Ok(())
     }
 
-    // ========================================================================
-    // PRICE QUERIES (Public)
-    // ========================================================================
+    /// Get the chaos mode applied to an asset, if still active
+    pub fn get_chaos(env: Env, asset: Address) -> Option<ChaosMode> {
+        let chaos: ChaosMode = env.storage().instance().get(&DataKey::Chaos(asset))?;
+        if env.ledger().timestamp() >= chaos.ends_at {
+            return None;
+        }
+        Some(chaos)
+    }
 
-    /// Get current price for an asset
+    /// Internal: Apply any active chaos haircut to a price read
+    fn apply_chaos(env: &Env, asset: &Address, price: i128) -> Result<i128, OracleError> {
+        match Self::get_chaos(env.clone(), asset.clone()) {
+            Some(chaos) => Ok(mul_div(price, SCALE - bps_to_scale(chaos.bps_drop as i128), SCALE, Rounding::Down)?),
+            None => Ok(price),
+        }
+    }
+
+    /// Register the token contract for an asset symbol
     ///
-    /// # Arguments
-    /// * `asset` - Asset symbol
+    /// Re-registering a symbol points it at a new token; prices stay with
+    /// the token they were set for.
     ///
-    /// # Returns
-    /// Price in USD (scaled by 1e7), or 0 if not set
-    pub fn get_price(env: Env, asset: Symbol) -> i128 {
+    /// # Arguments
+    /// * `symbol` - Asset symbol (e.g., EURC)
+    /// * `token` - Token contract address
+    pub fn register_asset(env: Env, symbol: Symbol, token: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        Self::store_asset(&env, symbol.clone(), token.clone());
+
+        env.events().publish((symbol_short!("register"), symbol), token);
+
+        Ok(())
+    }
+
+    /// Get the token contract registered for an asset symbol
+    pub fn get_asset_address(env: Env, symbol: Symbol) -> Result<Address, OracleError> {
         env.storage()
             .instance()
-            .get(&DataKey::Price(asset))
-            .unwrap_or(0)
+            .get(&DataKey::AssetAddress(symbol))
+            .ok_or(OracleError::AssetNotRegistered)
     }
 
-    /// Get price with staleness check
+    /// Set the decimals of an asset's token amounts
     ///
-    /// Use this in production to ensure prices are fresh.
+    /// Prices are always quoted per whole token; the decimals tell
+    /// `to_usd`/`from_usd` how many base units make up one token, so bridged
+    /// assets with 6 or 18 decimals are valued correctly.
     ///
-    /// # Panics
-    /// - If price is not set
-    /// - If price is stale (older than staleness threshold)
-    pub fn get_price_safe(env: Env, asset: Symbol) -> i128 {
-        let price: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Price(asset.clone()))
-            .unwrap_or(0);
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `decimals` - Token decimals (0 to 18)
+    pub fn set_decimals(env: Env, asset: Address, decimals: u32) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
 
-        if price == 0 {
-            panic!("Price not set for asset");
+        if decimals > MAX_TOKEN_DECIMALS {
+            return Err(OracleError::InvalidDecimals);
         }
 
-        let last_update: u64 = env
+        env.storage().instance().set(&DataKey::Decimals(asset.clone()), &decimals);
+
+        env.events().publish((symbol_short!("decimals"), asset), decimals);
+
+        Ok(())
+    }
+
+    /// Get the decimals of an asset's token amounts (7 unless set)
+    pub fn get_decimals(env: Env, asset: Address) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Decimals(asset))
+            .unwrap_or(DEFAULT_TOKEN_DECIMALS)
+    }
+
+    /// Internal: Map a symbol to a token and add the token to the asset list
+    fn store_asset(env: &Env, symbol: Symbol, token: Address) {
+        env.storage()
+            .instance()
+            .set(&DataKey::AssetAddress(symbol), &token);
+
+        let mut assets: Vec<Address> = env
             .storage()
             .instance()
-            .get(&DataKey::LastUpdate(asset))
-            .unwrap_or(0);
+            .get(&DataKey::Assets)
+            .unwrap_or(Vec::new(env));
+        if !assets.contains(&token) {
+            assets.push_back(token);
+            env.storage().instance().set(&DataKey::Assets, &assets);
+        }
+    }
+
+    /// Internal: Record a price update as a new round and in the asset's
+    /// history, dropping the oldest history record once `MAX_PRICE_HISTORY`
+    /// is reached
+    fn record_history(env: &Env, asset: &Address, price: i128) {
+        Self::record_round(env, asset, price);
 
-        let threshold: u64 = env
+        let key = DataKey::PriceHistory(asset.clone());
+        let mut history: Vec<PriceData> = env
             .storage()
             .instance()
-            .get(&DataKey::StalenessThreshold)
-            .unwrap_or(DEFAULT_STALENESS_THRESHOLD);
+            .get(&key)
+            .unwrap_or(Vec::new(env));
 
-        let current_time = env.ledger().timestamp();
-        if current_time > last_update && current_time - last_update > threshold {
-            panic!("Price is stale");
+        let timestamp = env.ledger().timestamp();
+        // Several updates in one ledger keep only the last price
+        if let Some(last) = history.last() {
+            if last.timestamp == timestamp {
+                history.pop_back();
+            }
+        }
+        history.push_back(PriceData { price, timestamp });
+        if history.len() > MAX_PRICE_HISTORY {
+            history.pop_front();
         }
 
-        price
+        env.storage().instance().set(&key, &history);
     }
 
-    /// Get timestamp of last price update
-    pub fn get_last_update(env: Env, asset: Symbol) -> u64 {
+    /// Internal: Persist a price update as the asset's next round
+    fn record_round(env: &Env, asset: &Address, price: i128) {
+        let round_id = Self::get_latest_round_id(env, asset) + 1;
+        let round = PriceRound {
+            round_id,
+            price,
+            timestamp: env.ledger().timestamp(),
+            ledger: env.ledger().sequence(),
+        };
+
+        let key = DataKey::Round(asset.clone(), round_id);
+        env.storage().persistent().set(&key, &round);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, ROUND_LIFETIME_THRESHOLD, ROUND_BUMP_AMOUNT);
         env.storage()
             .instance()
-            .get(&DataKey::LastUpdate(asset))
+            .set(&DataKey::LatestRound(asset.clone()), &round_id);
+    }
+
+    /// Internal: Id of the latest round for an asset (0 if none)
+    fn get_latest_round_id(env: &Env, asset: &Address) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::LatestRound(asset.clone()))
             .unwrap_or(0)
     }
 
-    /// Check if price is stale
-    pub fn is_stale(env: Env, asset: Symbol) -> bool {
-        let last_update: u64 = env
+    /// Internal: Require authorization from the admin/keeper
+    fn require_admin(env: &Env) -> Result<Address, OracleError> {
+        let admin: Address = env
             .storage()
             .instance()
-            .get(&DataKey::LastUpdate(asset))
+            .get(&DataKey::Admin)
+            .ok_or(OracleError::NotInitialized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Internal: Require authorization from the holder of `role`
+    fn require_role(env: &Env, role: Role) -> Result<Address, OracleError> {
+        let holder = Self::get_role_holder(env.clone(), role)?;
+        holder.require_auth();
+        Ok(holder)
+    }
+
+    /// Internal: Fold the change from the stored price into the variance EWMA
+    ///
+    /// variance = w * (new - old)^2 + (1 - w) * variance
+    fn update_variance(env: &Env, asset: &Address, new_price: i128) -> Result<(), OracleError> {
+        let old_price: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Price(asset.clone()))
             .unwrap_or(0);
+        if old_price == 0 {
+            return Ok(());
+        }
 
-        let threshold: u64 = env
+        let delta = new_price - old_price;
+        let variance: i128 = env
             .storage()
             .instance()
-            .get(&DataKey::StalenessThreshold)
-            .unwrap_or(DEFAULT_STALENESS_THRESHOLD);
+            .get(&DataKey::PriceVariance(asset.clone()))
+            .unwrap_or(0);
+        let squared = delta.checked_mul(delta).ok_or(OracleError::MathOverflow)?;
+        let new_variance = mul_div(VARIANCE_EWMA_WEIGHT, squared, PRICE_SCALE, Rounding::Down)?
+            + mul_div(PRICE_SCALE - VARIANCE_EWMA_WEIGHT, variance, PRICE_SCALE, Rounding::Down)?;
 
-        let current_time = env.ledger().timestamp();
-        current_time > last_update && current_time - last_update > threshold
+        env.storage()
+            .instance()
+            .set(&DataKey::PriceVariance(asset.clone()), &new_variance);
+        Ok(())
     }
 
     // ========================================================================
-    // CONVENIENCE FUNCTIONS
+    // PRICE QUERIES (Public)
     // ========================================================================
 
-    /// Get XLM price
-    pub fn get_xlm_price(env: Env) -> i128 {
-        Self::get_price(env, XLM)
+    /// Get current price for an asset
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    ///
+    /// # Returns
+    /// Price in USD (scaled by 1e7), or 0 if not set or the feed is paused
+    pub fn get_price(env: Env, asset: Address) -> Result<i128, OracleError> {
+        if Self::is_feed_paused(env.clone(), asset.clone()) {
+            return Ok(0);
+        }
+        Self::current_price(&env, &asset)
     }
 
-    /// Get USDC price
-    pub fn get_usdc_price(env: Env) -> i128 {
-        Self::get_price(env, USDC)
+    /// Internal: Stored price as served to readers, ignoring a feed pause
+    fn current_price(env: &Env, asset: &Address) -> Result<i128, OracleError> {
+        let price: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Price(asset.clone()))
+            .unwrap_or(0);
+        Self::apply_chaos(env, asset, price)
     }
 
-    /// Convert XLM amount to USD value
-    ///
-    /// # Arguments
-    /// * `xlm_amount` - Amount of XLM (in base units, 1e7 stroops per XLM)
+    /// Get current price for an asset by its registered symbol
     ///
     /// # Returns
-    /// USD value (scaled by 1e7)
-    pub fn xlm_to_usd(env: Env, xlm_amount: i128) -> i128 {
-        let price = Self::get_price(env, XLM);
-        if price == 0 {
-            return 0;
+    /// Price in USD (scaled by 1e7), or 0 if not set or not registered
+    pub fn get_price_by_symbol(env: Env, symbol: Symbol) -> Result<i128, OracleError> {
+        match Self::get_asset_address(env.clone(), symbol) {
+            Ok(asset) => Self::get_price(env, asset),
+            Err(_) => Ok(0),
         }
-        (xlm_amount * price) / PRICE_SCALE
     }
 
-    /// Convert USD value to XLM amount
+    /// Get price with staleness check
     ///
-    /// # Arguments
-    /// * `usd_amount` - USD value (scaled by 1e7)
+    /// Use this in production to ensure prices are fresh.
     ///
-    /// # Returns
-    /// XLM amount (in base units)
-    pub fn usd_to_xlm(env: Env, usd_amount: i128) -> i128 {
-        let price = Self::get_price(env, XLM);
+    /// # Errors
+    /// - `FeedPaused` if the asset's feed is paused
+    /// - `PriceNotSet` if price is not set
+    /// - `PriceStale` if price is stale (older than staleness threshold)
+    pub fn get_price_safe(env: Env, asset: Address) -> Result<i128, OracleError> {
+        if Self::is_feed_paused(env.clone(), asset.clone()) {
+            return Err(OracleError::FeedPaused);
+        }
+
+        let price = Self::get_price(env.clone(), asset.clone())?;
+
         if price == 0 {
-            panic!("XLM price not set");
+            return Err(OracleError::PriceNotSet);
+        }
+
+        if Self::is_stale(env, asset) {
+            return Err(OracleError::PriceStale);
         }
-        (usd_amount * PRICE_SCALE) / price
+
+        Ok(price)
     }
 
-    /// Get both XLM and USDC prices
+    /// Get the time-weighted average price of an asset over a trailing window
+    ///
+    /// Prices are treated as constant between updates and weighted by how
+    /// long each was in effect during `[now - window_secs, now]`. The window
+    /// is limited to the retained price history (`MAX_PRICE_HISTORY`
+    /// updates); if it reaches further back, the average covers the retained
+    /// period only. A zero window returns the latest price.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `window_secs` - Length of the trailing window in seconds
     ///
     /// # Returns
-    /// (xlm_price, usdc_price) - both scaled by 1e7
-    pub fn get_all_prices(env: Env) -> (i128, i128) {
-        let xlm = Self::get_price(env.clone(), XLM);
-        let usdc = Self::get_price(env, USDC);
-        (xlm, usdc)
-    }
-
-    // ========================================================================
-    // ADMIN FUNCTIONS
-    // ========================================================================
+    /// TWAP in USD (scaled by 1e7)
+    ///
+    /// # Errors
+    /// - `FeedPaused` if the asset's feed is paused
+    /// - `PriceNotSet` if the asset has no price
+    pub fn get_twap(env: Env, asset: Address, window_secs: u64) -> Result<i128, OracleError> {
+        if Self::is_feed_paused(env.clone(), asset.clone()) {
+            return Err(OracleError::FeedPaused);
+        }
+        let history = Self::price_history(&env, &Asset::Stellar(asset.clone())).ok_or(OracleError::PriceNotSet)?;
+        let latest = history.last_unchecked();
+
+        let now = env.ledger().timestamp();
+        let window_start = now.saturating_sub(window_secs);
+
+        let mut weighted_sum: i128 = 0;
+        let mut covered: i128 = 0;
+        let mut segment_end = now;
+        for record in history.iter().rev() {
+            let segment_start = record.timestamp.max(window_start);
+            if segment_end > segment_start {
+                let duration = (segment_end - segment_start) as i128;
+                weighted_sum += record.price * duration;
+                covered += duration;
+            }
+            if record.timestamp <= window_start {
+                break;
+            }
+            segment_end = record.timestamp;
+        }
 
-    /// Get current admin address
-    pub fn get_admin(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+        if covered == 0 {
+            return Self::apply_chaos(&env, &asset, latest.price);
+        }
+        Self::apply_chaos(&env, &asset, weighted_sum / covered)
     }
 
-    /// Transfer admin role
+    /// Get a recorded price round
+    ///
+    /// Every applied update is kept as a round, so the price used at a given
+    /// ledger can be looked up later (e.g. to review a liquidation).
     ///
     /// # Arguments
-    /// * `new_admin` - New admin address
-    pub fn set_admin(env: Env, new_admin: Address) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    /// * `asset` - Token contract address
+    /// * `round_id` - Round id (1 = first update)
+    pub fn get_round(env: Env, asset: Address, round_id: u64) -> Option<PriceRound> {
+        env.storage().persistent().get(&DataKey::Round(asset, round_id))
+    }
 
-        env.storage().instance().set(&DataKey::Admin, &new_admin);
-        env.events().publish((symbol_short!("new_admin"),), new_admin);
+    /// Get the latest price round for an asset
+    pub fn latest_round(env: Env, asset: Address) -> Option<PriceRound> {
+        let round_id = Self::get_latest_round_id(&env, &asset);
+        Self::get_round(env, asset, round_id)
     }
 
-    /// Set staleness threshold
+    /// Get the rolling price variance for an asset
     ///
-    /// # Arguments
-    /// * `threshold` - New threshold in seconds
-    pub fn set_staleness_threshold(env: Env, threshold: u64) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
-
+    /// # Returns
+    /// EWMA of squared price changes, in squared price units (1e14 = $1.00^2)
+    pub fn get_price_variance(env: Env, asset: Address) -> i128 {
         env.storage()
             .instance()
-            .set(&DataKey::StalenessThreshold, &threshold);
+            .get(&DataKey::PriceVariance(asset))
+            .unwrap_or(0)
     }
 
-    /// Get staleness threshold
-    pub fn get_staleness_threshold(env: Env) -> u64 {
+    /// Get timestamp of last price update
+    pub fn get_last_update(env: Env, asset: Address) -> u64 {
         env.storage()
             .instance()
-            .get(&DataKey::StalenessThreshold)
-            .unwrap_or(DEFAULT_STALENESS_THRESHOLD)
+            .get(&DataKey::LastUpdate(asset))
+            .unwrap_or(0)
     }
-}
 
-// ============================================================================
-// TESTS
-// ============================================================================
+    /// Get the expiry timestamp of an asset's price, if one was set
+    pub fn get_price_expiry(env: Env, asset: Address) -> Option<u64> {
+        env.storage().instance().get(&DataKey::PriceExpiry(asset))
+    }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{testutils::Address as _, Env};
+    /// Get price together with its age
+    ///
+    /// Lets consumers apply their own freshness policy instead of the
+    /// oracle's heartbeat.
+    ///
+    /// # Returns
+    /// (price scaled by 1e7, seconds since the last update)
+    ///
+    /// # Errors
+    /// - `PriceNotSet` if price is not set
+    pub fn get_price_with_age(env: Env, asset: Address) -> Result<(i128, u64), OracleError> {
+        let price = Self::get_price(env.clone(), asset.clone())?;
+        if price == 0 {
+            return Err(OracleError::PriceNotSet);
+        }
 
-    #[test]
-    fn test_initialize() {
-        let env = Env::default();
+        let last_update = Self::get_last_update(env.clone(), asset);
+        Ok((price, env.ledger().timestamp().saturating_sub(last_update)))
+    }
+
+    /// Check if price is stale
+    ///
+    /// If the price was published with an expiry, it is stale once the
+    /// expiry is reached. Otherwise the asset's heartbeat applies.
+    pub fn is_stale(env: Env, asset: Address) -> bool {
+        let current_time = env.ledger().timestamp();
+
+        if let Some(expires_at) = Self::get_price_expiry(env.clone(), asset.clone()) {
+            return current_time >= expires_at;
+        }
+
+        let last_update: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastUpdate(asset.clone()))
+            .unwrap_or(0);
+
+        let threshold = Self::get_asset_heartbeat(env, asset);
+
+        current_time > last_update && current_time - last_update > threshold
+    }
+
+    // ========================================================================
+    // FEED PAUSE
+    // ========================================================================
+
+    /// Stop serving an asset's price (pauser only)
+    ///
+    /// For a compromised or misbehaving source: `get_price_safe` and
+    /// `get_twap` fail and `get_price` reads 0 for the asset regardless of
+    /// freshness, so the pool stops valuing it, while other assets stay live.
+    /// Updates are still accepted so the feed can resume with a fresh price.
+    pub fn pause_feed(env: Env, asset: Address) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::Pauser)?;
+
+        env.storage().instance().set(&DataKey::FeedPaused(asset.clone()), &true);
+        env.events().publish((symbol_short!("feed_paus"), asset), true);
+
+        Ok(())
+    }
+
+    /// Resume serving a paused asset's price (admin only)
+    pub fn unpause_feed(env: Env, asset: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().remove(&DataKey::FeedPaused(asset.clone()));
+        env.events().publish((symbol_short!("feed_paus"), asset), false);
+
+        Ok(())
+    }
+
+    /// Check whether an asset's feed is paused
+    pub fn is_feed_paused(env: Env, asset: Address) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::FeedPaused(asset))
+            .unwrap_or(false)
+    }
+
+    // ========================================================================
+    // DEVIATION CIRCUIT BREAKER
+    // ========================================================================
+
+    /// Apply an update held by the deviation circuit breaker
+    ///
+    /// Must be authorized by the confirmer, or by the admin if no confirmer
+    /// is set, so a single fat-fingered keeper update cannot take effect on
+    /// its own.
+    ///
+    /// # Returns
+    /// The confirmed price
+    pub fn confirm_price(env: Env, asset: Address) -> Result<i128, OracleError> {
+        Self::get_confirmer(env.clone())?.require_auth();
+
+        let pending: PriceData = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingPrice(asset.clone()))
+            .ok_or(OracleError::NoPendingPrice)?;
+
+        Self::apply_price(&env, asset, pending.price)?;
+
+        Ok(pending.price)
+    }
+
+    /// Get the update held for confirmation for an asset, if any
+    pub fn get_pending_price(env: Env, asset: Address) -> Option<PriceData> {
+        env.storage().instance().get(&DataKey::PendingPrice(asset))
+    }
+
+    /// Set the maximum price move allowed in one update (admin only)
+    ///
+    /// # Arguments
+    /// * `max_deviation` - Scaled by 1e7 (20% = 2_000_000), 0 disables the breaker
+    pub fn set_max_deviation(env: Env, max_deviation: i128) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if !(0..=PRICE_SCALE).contains(&max_deviation) {
+            return Err(OracleError::InvalidDeviation);
+        }
+        env.storage().instance().set(&DataKey::MaxDeviation, &max_deviation);
+
+        env.events().publish((symbol_short!("max_dev"),), max_deviation);
+
+        Ok(())
+    }
+
+    /// Get the maximum price move allowed in one update (scaled by 1e7, 0 = disabled)
+    pub fn get_max_deviation(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::MaxDeviation).unwrap_or(0)
+    }
+
+    /// Set the address that confirms held updates (admin only)
+    pub fn set_confirmer(env: Env, confirmer: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&DataKey::Confirmer, &confirmer);
+
+        env.events().publish((symbol_short!("confirmer"),), confirmer);
+
+        Ok(())
+    }
+
+    /// Get the address that confirms held updates (the admin if none is set)
+    pub fn get_confirmer(env: Env) -> Result<Address, OracleError> {
+        match env.storage().instance().get(&DataKey::Confirmer) {
+            Some(confirmer) => Ok(confirmer),
+            None => Self::get_admin(env),
+        }
+    }
+
+    /// Internal: Check whether `price` moves the asset's price by more than the max deviation
+    fn exceeds_max_deviation(env: &Env, asset: &Address, price: i128) -> Result<bool, OracleError> {
+        let max_deviation = Self::get_max_deviation(env.clone());
+        let previous = Self::current_price(env, asset)?;
+        if max_deviation == 0 || previous == 0 {
+            return Ok(false);
+        }
+
+        let deviation = mul_div((price - previous).abs(), PRICE_SCALE, previous, Rounding::Down)?;
+        Ok(deviation > max_deviation)
+    }
+
+    /// Internal: Hold an update for confirmation and flag it
+    fn hold_price(env: &Env, asset: Address, price: i128) -> Result<(), OracleError> {
+        let previous = Self::current_price(env, &asset)?;
+        env.storage().instance().set(
+            &DataKey::PendingPrice(asset.clone()),
+            &PriceData {
+                price,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+
+        env.events()
+            .publish((symbol_short!("price_flg"), asset), (previous, price));
+        Ok(())
+    }
+
+    // ========================================================================
+    // PRICE BOUNDS
+    // ========================================================================
+
+    /// Set sanity bounds on an asset's price (risk manager only)
+    ///
+    /// Updates outside `[min_price, max_price]` are rejected outright, so a
+    /// keeper bug cannot publish an absurd price. Bounds of (0, 0) remove them.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `min_price` - Lowest accepted price (scaled by 1e7)
+    /// * `max_price` - Highest accepted price (scaled by 1e7)
+    pub fn set_price_bounds(env: Env, asset: Address, min_price: i128, max_price: i128) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if min_price == 0 && max_price == 0 {
+            env.storage().instance().remove(&DataKey::PriceBounds(asset.clone()));
+        } else if min_price <= 0 || min_price >= max_price {
+            return Err(OracleError::InvalidPriceBounds);
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::PriceBounds(asset.clone()), &(min_price, max_price));
+        }
+
+        env.events()
+            .publish((symbol_short!("prc_bound"), asset), (min_price, max_price));
+
+        Ok(())
+    }
+
+    /// Get the sanity bounds (min, max) on an asset's price, if set
+    pub fn get_price_bounds(env: Env, asset: Address) -> Option<(i128, i128)> {
+        env.storage().instance().get(&DataKey::PriceBounds(asset))
+    }
+
+    /// Internal: Reject a price outside the asset's sanity bounds
+    fn check_price_bounds(env: &Env, asset: &Address, price: i128) -> Result<(), OracleError> {
+        if let Some((min_price, max_price)) = Self::get_price_bounds(env.clone(), asset.clone()) {
+            if price < min_price || price > max_price {
+                return Err(OracleError::PriceOutOfBounds);
+            }
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // STABLECOIN PEG
+    // ========================================================================
+
+    /// Set the price band of an asset flagged as a stablecoin (risk manager only)
+    ///
+    /// Updates that take the price outside the band are held until the
+    /// confirmer calls `confirm_depeg`. Bounds of (0, 0) remove the band
+    /// and any depeg state.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `lower` - Lowest in-peg price (scaled by 1e7)
+    /// * `upper` - Highest in-peg price (scaled by 1e7)
+    pub fn set_peg_band(env: Env, asset: Address, lower: i128, upper: i128) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if lower == 0 && upper == 0 {
+            env.storage().instance().remove(&DataKey::PegBand(asset.clone()));
+            env.storage().instance().remove(&DataKey::PendingDepeg(asset.clone()));
+            env.storage().instance().remove(&DataKey::Depegged(asset.clone()));
+        } else if lower <= 0 || lower >= upper {
+            return Err(OracleError::InvalidPegBand);
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::PegBand(asset.clone()), &(lower, upper));
+        }
+
+        env.events()
+            .publish((symbol_short!("peg_band"), asset), (lower, upper));
+
+        Ok(())
+    }
+
+    /// Get the peg band (lower, upper) of an asset, if it is flagged as a stablecoin
+    pub fn get_peg_band(env: Env, asset: Address) -> Option<(i128, i128)> {
+        env.storage().instance().get(&DataKey::PegBand(asset))
+    }
+
+    /// Apply an update held for leaving the peg band and flag the asset as depegged
+    ///
+    /// Must be authorized by the confirmer, or by the admin if no confirmer
+    /// is set. The pool freezes borrows of a depegged asset until an update
+    /// brings the price back into the band.
+    ///
+    /// # Returns
+    /// The confirmed price
+    ///
+    /// # Events
+    /// Emits `("depeg", asset)` with the confirmed price
+    pub fn confirm_depeg(env: Env, asset: Address) -> Result<i128, OracleError> {
+        Self::get_confirmer(env.clone())?.require_auth();
+
+        let pending: PriceData = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingDepeg(asset.clone()))
+            .ok_or(OracleError::NoPendingPrice)?;
+
+        Self::apply_price(&env, asset.clone(), pending.price)?;
+        env.storage().instance().set(&DataKey::Depegged(asset.clone()), &true);
+
+        env.events()
+            .publish((symbol_short!("depeg"), asset), pending.price);
+
+        Ok(pending.price)
+    }
+
+    /// Get the update held for leaving the peg band, if any
+    pub fn get_pending_depeg(env: Env, asset: Address) -> Option<PriceData> {
+        env.storage().instance().get(&DataKey::PendingDepeg(asset))
+    }
+
+    /// Check whether a depeg has been confirmed for an asset and its price
+    /// is still outside the band
+    pub fn is_depegged(env: Env, asset: Address) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::Depegged(asset))
+            .unwrap_or(false)
+    }
+
+    /// Internal: Check whether `price` takes a pegged asset out of its band
+    /// without a confirmed depeg
+    fn breaks_peg(env: &Env, asset: &Address, price: i128) -> bool {
+        match Self::get_peg_band(env.clone(), asset.clone()) {
+            Some((lower, upper)) => {
+                (price < lower || price > upper) && !Self::is_depegged(env.clone(), asset.clone())
+            }
+            None => false,
+        }
+    }
+
+    /// Internal: Hold an out-of-band update for `confirm_depeg` and flag it
+    fn hold_depeg(env: &Env, asset: Address, price: i128) {
+        env.storage().instance().set(
+            &DataKey::PendingDepeg(asset.clone()),
+            &PriceData {
+                price,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+
+        env.events()
+            .publish((symbol_short!("depeg_flg"), asset), price);
+    }
+
+    /// Internal: Drop a superseded out-of-band update and clear the depeg
+    /// flag once an applied price is back in the band
+    fn update_peg_status(env: &Env, asset: &Address, price: i128) {
+        env.storage()
+            .instance()
+            .remove(&DataKey::PendingDepeg(asset.clone()));
+
+        let in_band = match Self::get_peg_band(env.clone(), asset.clone()) {
+            Some((lower, upper)) => price >= lower && price <= upper,
+            None => return,
+        };
+        if in_band && Self::is_depegged(env.clone(), asset.clone()) {
+            env.storage().instance().remove(&DataKey::Depegged(asset.clone()));
+            env.events()
+                .publish((symbol_short!("repeg"), asset.clone()), price);
+        }
+    }
+
+    // ========================================================================
+    // FEEDERS
+    // ========================================================================
+
+    /// Submit a price as a registered feeder
+    ///
+    /// The asset's price is recomputed as the median of all feeders'
+    /// submissions that are within the staleness threshold. It is only
+    /// updated once at least `quorum` such submissions exist, so a single
+    /// compromised feeder cannot move it.
+    ///
+    /// # Arguments
+    /// * `feeder` - Registered feeder address (must authorize)
+    /// * `asset` - Token contract address
+    /// * `price` - Price in USD scaled by 1e7
+    ///
+    /// # Returns
+    /// `true` if the submission updated the asset's price
+    pub fn submit_price(env: Env, feeder: Address, asset: Address, price: i128) -> Result<bool, OracleError> {
+        feeder.require_auth();
+
+        let feeders = Self::get_feeders(env.clone());
+        if !feeders.contains(&feeder) {
+            return Err(OracleError::NotFeeder);
+        }
+        if price <= 0 {
+            return Err(OracleError::InvalidPrice);
+        }
+
+        let now = env.ledger().timestamp();
+        env.storage().instance().set(
+            &DataKey::Submission(asset.clone(), feeder.clone()),
+            &PriceData { price, timestamp: now },
+        );
+        env.events()
+            .publish((symbol_short!("submit"), asset.clone()), (feeder, price));
+
+        // Collect recent submissions
+        let threshold = Self::get_asset_heartbeat(env.clone(), asset.clone());
+        let mut recent: Vec<i128> = Vec::new(&env);
+        for f in feeders.iter() {
+            let submission: Option<PriceData> = env
+                .storage()
+                .instance()
+                .get(&DataKey::Submission(asset.clone(), f));
+            if let Some(submission) = submission {
+                if now - submission.timestamp <= threshold {
+                    recent.push_back(submission.price);
+                }
+            }
+        }
+
+        if recent.len() < Self::get_quorum(env.clone()) {
+            return Ok(false);
+        }
+
+        Self::write_price(&env, asset, Self::median(&env, recent))
+    }
+
+    /// Register a price feeder (admin only)
+    pub fn add_feeder(env: Env, feeder: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        let mut feeders = Self::get_feeders(env.clone());
+        if !feeders.contains(&feeder) {
+            feeders.push_back(feeder.clone());
+            env.storage().instance().set(&DataKey::Feeders, &feeders);
+        }
+
+        env.events().publish((symbol_short!("feeder"), feeder), true);
+
+        Ok(())
+    }
+
+    /// Remove a price feeder (admin only)
+    ///
+    /// # Errors
+    /// `InvalidQuorum` if the configured quorum would exceed the remaining feeders
+    pub fn remove_feeder(env: Env, feeder: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        let mut feeders = Self::get_feeders(env.clone());
+        if let Some(index) = feeders.first_index_of(&feeder) {
+            feeders.remove(index);
+        }
+
+        let quorum: Option<u32> = env.storage().instance().get(&DataKey::Quorum);
+        if quorum.is_some_and(|q| q > feeders.len()) {
+            return Err(OracleError::InvalidQuorum);
+        }
+        env.storage().instance().set(&DataKey::Feeders, &feeders);
+
+        env.events().publish((symbol_short!("feeder"), feeder), false);
+
+        Ok(())
+    }
+
+    /// Get the registered feeders
+    pub fn get_feeders(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Feeders)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Set the number of recent submissions required to update a price (admin only)
+    ///
+    /// # Arguments
+    /// * `quorum` - Between 1 and the number of registered feeders
+    pub fn set_quorum(env: Env, quorum: u32) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        if quorum == 0 || quorum > Self::get_feeders(env.clone()).len() {
+            return Err(OracleError::InvalidQuorum);
+        }
+        env.storage().instance().set(&DataKey::Quorum, &quorum);
+
+        env.events().publish((symbol_short!("quorum"),), quorum);
+
+        Ok(())
+    }
+
+    /// Get the submission quorum (defaults to a majority of feeders)
+    pub fn get_quorum(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Quorum)
+            .unwrap_or(Self::get_feeders(env).len() / 2 + 1)
+    }
+
+    /// Internal: Median of a non-empty set of prices
+    ///
+    /// An even number of prices averages the two middle values.
+    fn median(env: &Env, prices: Vec<i128>) -> i128 {
+        // Insertion sort; the number of feeders is small
+        let mut sorted: Vec<i128> = Vec::new(env);
+        for price in prices.iter() {
+            let mut index = sorted.len();
+            while index > 0 && sorted.get_unchecked(index - 1) > price {
+                index -= 1;
+            }
+            sorted.insert(index, price);
+        }
+
+        let mid = sorted.len() / 2;
+        if sorted.len() % 2 == 1 {
+            sorted.get_unchecked(mid)
+        } else {
+            (sorted.get_unchecked(mid - 1) + sorted.get_unchecked(mid)) / 2
+        }
+    }
+
+    /// Internal: Require oracle feeder authorization for a direct price update
+    ///
+    /// Direct updates are only allowed while no feeders are registered.
+    fn require_direct_update(env: &Env) -> Result<(), OracleError> {
+        Self::require_role(env, Role::OracleFeeder)?;
+        if !Self::get_feeders(env.clone()).is_empty() {
+            return Err(OracleError::FeedersActive);
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // SEP-40 PRICE FEED
+    // ========================================================================
+
+    /// Base asset all prices are quoted in (USD)
+    pub fn base(_env: Env) -> Asset {
+        Asset::Other(symbol_short!("USD"))
+    }
+
+    /// Assets with a registered token contract
+    pub fn assets(env: Env) -> Vec<Asset> {
+        let tokens: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Assets)
+            .unwrap_or(Vec::new(&env));
+
+        let mut assets = Vec::new(&env);
+        for token in tokens.iter() {
+            assets.push_back(Asset::Stellar(token));
+        }
+        assets
+    }
+
+    /// Number of decimals in prices
+    pub fn decimals(_env: Env) -> u32 {
+        PRICE_DECIMALS
+    }
+
+    /// Expected seconds between price updates
+    pub fn resolution(_env: Env) -> u32 {
+        RESOLUTION
+    }
+
+    /// Price of an asset at a point in time
+    ///
+    /// Returns the most recent recorded price at or before `timestamp`, or
+    /// `None` if the timestamp predates the retained history.
+    pub fn price(env: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let history = Self::price_history(&env, &asset)?;
+        history.iter().rev().find(|record| record.timestamp <= timestamp)
+    }
+
+    /// Most recent `records` prices of an asset, newest first
+    pub fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>> {
+        let history = Self::price_history(&env, &asset)?;
+
+        let mut result = Vec::new(&env);
+        for record in history.iter().rev().take(records as usize) {
+            result.push_back(record);
+        }
+        Some(result)
+    }
+
+    /// Latest price of an asset (with any chaos haircut applied)
+    pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+        let token = Self::asset_token(&env, &asset)?;
+        let mut latest = Self::price_history(&env, &asset)?.last()?;
+        latest.price = Self::apply_chaos(&env, &token, latest.price).ok()?;
+        Some(latest)
+    }
+
+    /// Internal: Token contract of a SEP-40 asset, if known
+    fn asset_token(env: &Env, asset: &Asset) -> Option<Address> {
+        match asset {
+            Asset::Stellar(token) => Some(token.clone()),
+            Asset::Other(symbol) => Self::get_asset_address(env.clone(), symbol.clone()).ok(),
+        }
+    }
+
+    /// Internal: Recorded prices of a SEP-40 asset, oldest first
+    ///
+    /// `Other` assets are resolved through the symbol registry. Returns `None`
+    /// if the asset is unknown or has no price yet.
+    fn price_history(env: &Env, asset: &Asset) -> Option<Vec<PriceData>> {
+        let token = Self::asset_token(env, asset)?;
+        let history: Vec<PriceData> = env
+            .storage()
+            .instance()
+            .get(&DataKey::PriceHistory(token))?;
+        if history.is_empty() {
+            return None;
+        }
+        Some(history)
+    }
+
+    // ========================================================================
+    // CONVENIENCE FUNCTIONS
+    // ========================================================================
+
+    /// Get XLM price
+    pub fn get_xlm_price(env: Env) -> Result<i128, OracleError> {
+        Self::get_price_by_symbol(env, XLM)
+    }
+
+    /// Get USDC price
+    pub fn get_usdc_price(env: Env) -> Result<i128, OracleError> {
+        Self::get_price_by_symbol(env, USDC)
+    }
+
+    /// Convert XLM amount to USD value
+    ///
+    /// # Arguments
+    /// * `xlm_amount` - Amount of XLM (in base units, 1e7 stroops per XLM)
+    ///
+    /// # Returns
+    /// USD value (scaled by 1e7)
+    pub fn xlm_to_usd(env: Env, xlm_amount: i128) -> Result<i128, OracleError> {
+        let price = Self::get_price_by_symbol(env, XLM)?;
+        if price == 0 {
+            return Ok(0);
+        }
+        Ok(mul_fixed(xlm_amount, price, PRICE_SCALE, Rounding::Down)?)
+    }
+
+    /// Convert USD value to XLM amount
+    ///
+    /// # Arguments
+    /// * `usd_amount` - USD value (scaled by 1e7)
+    ///
+    /// # Returns
+    /// XLM amount (in base units)
+    pub fn usd_to_xlm(env: Env, usd_amount: i128) -> Result<i128, OracleError> {
+        let price = Self::get_price_by_symbol(env, XLM)?;
+        if price == 0 {
+            return Err(OracleError::PriceNotSet);
+        }
+        Ok(div_fixed(usd_amount, price, PRICE_SCALE, Rounding::Down)?)
+    }
+
+    /// Convert a token amount to its USD value
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `amount` - Amount in the token's base units (see `get_decimals`)
+    ///
+    /// # Returns
+    /// USD value (scaled by 1e7)
+    ///
+    /// # Errors
+    /// - `PriceNotSet` if the asset has no price
+    pub fn to_usd(env: Env, asset: Address, amount: i128) -> Result<i128, OracleError> {
+        let price = Self::get_price(env.clone(), asset.clone())?;
+        if price == 0 {
+            return Err(OracleError::PriceNotSet);
+        }
+        let unit = 10i128.pow(Self::get_decimals(env, asset));
+        Ok(mul_div(amount, price, unit, Rounding::Down)?)
+    }
+
+    /// Convert a USD value to a token amount
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `usd_amount` - USD value (scaled by 1e7)
+    ///
+    /// # Returns
+    /// Amount in the token's base units (see `get_decimals`)
+    ///
+    /// # Errors
+    /// - `PriceNotSet` if the asset has no price
+    pub fn from_usd(env: Env, asset: Address, usd_amount: i128) -> Result<i128, OracleError> {
+        let price = Self::get_price(env.clone(), asset.clone())?;
+        if price == 0 {
+            return Err(OracleError::PriceNotSet);
+        }
+        let unit = 10i128.pow(Self::get_decimals(env, asset));
+        Ok(mul_div(usd_amount, unit, price, Rounding::Down)?)
+    }
+
+    /// Convert an amount of one asset into the equivalent amount of another
+    ///
+    /// Uses both assets' prices and token decimals, so it works for any
+    /// registered pair (e.g. XLM → USDC, or USDC → a 6-decimal token).
+    /// Rounds down.
+    ///
+    /// # Arguments
+    /// * `asset_from` - Token contract of the amount given
+    /// * `asset_to` - Token contract to express it in
+    /// * `amount` - Amount in `asset_from`'s base units
+    ///
+    /// # Returns
+    /// Amount in `asset_to`'s base units
+    ///
+    /// # Errors
+    /// - `PriceNotSet` if either asset has no price
+    pub fn convert(env: Env, asset_from: Address, asset_to: Address, amount: i128) -> Result<i128, OracleError> {
+        let price_from = Self::get_price(env.clone(), asset_from.clone())?;
+        let price_to = Self::get_price(env.clone(), asset_to.clone())?;
+        if price_from == 0 || price_to == 0 {
+            return Err(OracleError::PriceNotSet);
+        }
+
+        // Fold each side's decimals into its price so the conversion rounds once
+        let unit_from = 10i128.pow(Self::get_decimals(env.clone(), asset_from));
+        let unit_to = 10i128.pow(Self::get_decimals(env, asset_to));
+        let scaled_from = price_from.checked_mul(unit_to).ok_or(OracleError::MathOverflow)?;
+        let scaled_to = price_to.checked_mul(unit_from).ok_or(OracleError::MathOverflow)?;
+        Ok(convert_amount(amount, scaled_from, scaled_to)?)
+    }
+
+    /// Get both XLM and USDC prices
+    ///
+    /// # Returns
+    /// (xlm_price, usdc_price) - both scaled by 1e7
+    pub fn get_all_prices(env: Env) -> Result<(i128, i128), OracleError> {
+        let xlm = Self::get_price_by_symbol(env.clone(), XLM)?;
+        let usdc = Self::get_price_by_symbol(env, USDC)?;
+        Ok((xlm, usdc))
+    }
+
+    // ========================================================================
+    // ADMIN FUNCTIONS
+    // ========================================================================
+
+    /// Get current admin address
+    pub fn get_admin(env: Env) -> Result<Address, OracleError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(OracleError::NotInitialized)
+    }
+
+    /// Transfer admin role
+    ///
+    /// # Arguments
+    /// * `new_admin` - New admin address
+    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.events().publish((symbol_short!("new_admin"),), new_admin);
+
+        Ok(())
+    }
+
+    /// Grant a role to an address (admin only)
+    ///
+    /// Replaces the previous holder. Granting `Admin` transfers the admin key.
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        match role {
+            Role::Admin => env.storage().instance().set(&DataKey::Admin, &account),
+            _ => env.storage().instance().set(&DataKey::Role(role), &account),
+        }
+
+        env.events().publish((symbol_short!("role_grnt"), role), account);
+
+        Ok(())
+    }
+
+    /// Revoke a role so it falls back to the admin (admin only)
+    pub fn revoke_role(env: Env, role: Role) -> Result<(), OracleError> {
+        Self::require_admin(&env)?;
+
+        if role == Role::Admin {
+            return Err(OracleError::InvalidRole);
+        }
+        env.storage().instance().remove(&DataKey::Role(role));
+
+        env.events().publish((symbol_short!("role_rvk"), role), ());
+
+        Ok(())
+    }
+
+    /// Get the address currently acting for a role
+    pub fn get_role_holder(env: Env, role: Role) -> Result<Address, OracleError> {
+        let holder: Option<Address> = match role {
+            Role::Admin => None,
+            _ => env.storage().instance().get(&DataKey::Role(role)),
+        };
+        match holder {
+            Some(holder) => Ok(holder),
+            None => Self::get_admin(env),
+        }
+    }
+
+    /// Check whether `account` currently acts for `role`
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        Self::get_role_holder(env, role) == Ok(account)
+    }
+
+    /// Set the default staleness threshold
+    ///
+    /// Applies to assets without their own heartbeat.
+    ///
+    /// # Arguments
+    /// * `threshold` - New threshold in seconds
+    pub fn set_staleness_threshold(env: Env, threshold: u64) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        env.storage()
+            .instance()
+            .set(&DataKey::StalenessThreshold, &threshold);
+
+        Ok(())
+    }
+
+    /// Get the default staleness threshold
+    pub fn get_staleness_threshold(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::StalenessThreshold)
+            .unwrap_or(DEFAULT_STALENESS_THRESHOLD)
+    }
+
+    /// Set the staleness threshold for one asset
+    ///
+    /// Volatile assets can require more frequent updates than stablecoins.
+    ///
+    /// # Arguments
+    /// * `asset` - Token contract address
+    /// * `heartbeat` - Threshold in seconds, 0 falls back to the default
+    pub fn set_asset_heartbeat(env: Env, asset: Address, heartbeat: u64) -> Result<(), OracleError> {
+        Self::require_role(&env, Role::RiskManager)?;
+
+        if heartbeat == 0 {
+            env.storage().instance().remove(&DataKey::Heartbeat(asset.clone()));
+        } else {
+            env.storage().instance().set(&DataKey::Heartbeat(asset.clone()), &heartbeat);
+        }
+
+        env.events().publish((symbol_short!("heartbeat"), asset), heartbeat);
+
+        Ok(())
+    }
+
+    /// Get the staleness threshold for an asset (its heartbeat, or the default)
+    pub fn get_asset_heartbeat(env: Env, asset: Address) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Heartbeat(asset))
+            .unwrap_or_else(|| Self::get_staleness_threshold(env))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        vec, Env, IntoVal,
+    };
+
+    #[test]
+    fn test_initialize() {
+        let env = Env::default();
         let contract_id = env.register_contract(None, PriceOracle);
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         assert_eq!(client.get_admin(), admin);
         assert_eq!(client.get_usdc_price(), PRICE_SCALE); // $1.00
@@ -428,13 +1785,14 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         // Set XLM price to $0.30
-        client.set_price(&XLM, &3_000_000);
+        client.set_price(&xlm, &3_000_000);
 
         assert_eq!(client.get_xlm_price(), 3_000_000);
-        assert_eq!(client.get_price(&XLM), 3_000_000);
+        assert_eq!(client.get_price(&xlm), 3_000_000);
     }
 
     #[test]
@@ -446,10 +1804,11 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         // Set both prices at once
-        client.set_prices(&3_000_000, &10_000_000);
+        client.set_prices(&vec![&env, (XLM, 3_000_000), (USDC, 10_000_000)]);
 
         assert_eq!(client.get_xlm_price(), 3_000_000);
         assert_eq!(client.get_usdc_price(), 10_000_000);
@@ -457,10 +1816,21 @@ mod test {
         let (xlm, usdc) = client.get_all_prices();
         assert_eq!(xlm, 3_000_000);
         assert_eq!(usdc, 10_000_000);
+
+        // One bad entry rejects the whole batch
+        assert_eq!(
+            client.try_set_prices(&vec![&env, (XLM, 3_500_000), (symbol_short!("BTC"), 1)]),
+            Err(Ok(OracleError::AssetNotRegistered))
+        );
+        assert_eq!(
+            client.try_set_prices(&vec![&env, (XLM, 3_500_000), (USDC, 0)]),
+            Err(Ok(OracleError::InvalidPrice))
+        );
+        assert_eq!(client.get_xlm_price(), 3_000_000);
     }
 
     #[test]
-    fn test_crash_price() {
+    fn test_chaos_mode() {
         let env = Env::default();
         env.mock_all_auths();
 
@@ -468,15 +1838,37 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         // Set XLM to $0.30
-        client.set_price(&XLM, &3_000_000);
+        client.set_price(&xlm, &3_000_000);
         assert_eq!(client.get_xlm_price(), 3_000_000);
 
-        // Crash it (50% drop)
-        client.crash_price(&XLM);
+        // Crash it (50% drop) for 10 minutes
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_chaos(&xlm, &5_000, &600);
         assert_eq!(client.get_xlm_price(), 1_500_000); // $0.15
+        assert_eq!(client.get_price_safe(&xlm), 1_500_000);
+        assert_eq!(client.lastprice(&Asset::Stellar(xlm.clone())).unwrap().price, 1_500_000);
+        assert_eq!(client.get_chaos(&xlm), Some(ChaosMode { bps_drop: 5_000, ends_at: 1_600 }));
+
+        // The stored price is untouched
+        assert_eq!(client.latest_round(&xlm).unwrap().price, 3_000_000);
+
+        // Reads recover once the window ends
+        env.ledger().with_mut(|li| li.timestamp = 1_600);
+        assert_eq!(client.get_chaos(&xlm), None);
+        assert_eq!(client.get_xlm_price(), 3_000_000);
+
+        // Chaos can be ended early
+        client.set_chaos(&xlm, &2_000, &600);
+        assert_eq!(client.get_xlm_price(), 2_400_000);
+        client.set_chaos(&xlm, &0, &0);
+        assert_eq!(client.get_xlm_price(), 3_000_000);
+
+        assert_eq!(client.try_set_chaos(&xlm, &10_001, &600), Err(Ok(OracleError::InvalidChaos)));
+        assert_eq!(client.try_set_chaos(&xlm, &5_000, &0), Err(Ok(OracleError::InvalidChaos)));
     }
 
     #[test]
@@ -488,15 +1880,20 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         // Set XLM to $0.30
-        client.set_price(&XLM, &3_000_000);
+        client.set_price(&xlm, &3_000_000);
 
         // 100 XLM = $30
         let xlm_amount: i128 = 100 * PRICE_SCALE;
         let usd_value = client.xlm_to_usd(&xlm_amount);
         assert_eq!(usd_value, 30 * PRICE_SCALE);
+
+        // Results that do not fit in an i128 are rejected instead of wrapping
+        client.set_price(&xlm, &(30 * PRICE_SCALE));
+        assert_eq!(client.try_xlm_to_usd(&i128::MAX), Err(Ok(OracleError::MathOverflow)));
     }
 
     #[test]
@@ -508,10 +1905,11 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
 
         // Set XLM to $0.30
-        client.set_price(&XLM, &3_000_000);
+        client.set_price(&xlm, &3_000_000);
 
         // $30 = 100 XLM
         let usd_amount: i128 = 30 * PRICE_SCALE;
@@ -520,19 +1918,20 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "Already initialized")]
+    #[should_panic(expected = "Error(Contract, #1)")]
     fn test_double_initialize() {
         let env = Env::default();
         let contract_id = env.register_contract(None, PriceOracle);
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
-        client.initialize(&admin); // Should panic
+        let (xlm, usdc) = (Address::generate(&env), Address::generate(&env));
+        client.initialize(&admin, &xlm, &usdc);
+        client.initialize(&admin, &xlm, &usdc); // Should panic
     }
 
     #[test]
-    #[should_panic(expected = "Price must be positive")]
+    #[should_panic(expected = "Error(Contract, #3)")]
     fn test_zero_price() {
         let env = Env::default();
         env.mock_all_auths();
@@ -541,7 +1940,596 @@ mod test {
         let client = PriceOracleClient::new(&env, &contract_id);
 
         let admin = Address::generate(&env);
-        client.initialize(&admin);
-        client.set_price(&XLM, &0); // Should panic
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+        client.set_price(&xlm, &0); // Should panic
+    }
+
+    #[test]
+    fn test_price_with_expiry() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_price_with_expiry(&xlm, &3_000_000, &60);
+        assert_eq!(client.get_price_expiry(&xlm), Some(1_060));
+
+        // 59 seconds later: still valid
+        env.ledger().with_mut(|li| li.timestamp = 1_059);
+        assert!(!client.is_stale(&xlm));
+        assert_eq!(client.get_price_safe(&xlm), 3_000_000);
+
+        // 61 seconds later: expired
+        env.ledger().with_mut(|li| li.timestamp = 1_061);
+        assert!(client.is_stale(&xlm));
+        assert_eq!(client.try_get_price_safe(&xlm), Err(Ok(OracleError::PriceStale)));
+
+        // A plain update clears the expiry and falls back to the staleness threshold
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(client.get_price_expiry(&xlm), None);
+        assert!(!client.is_stale(&xlm));
+    }
+
+    #[test]
+    fn test_price_variance_tracks_volatility() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+
+        // First price has nothing to compare against
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(client.get_price_variance(&xlm), 0);
+
+        // Stable prices keep variance at zero
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(client.get_price_variance(&xlm), 0);
+
+        // A $0.10 move: variance = 20% * (1_000_000)^2
+        client.set_price(&xlm, &2_000_000);
+        assert_eq!(client.get_price_variance(&xlm), 200_000_000_000);
+
+        // Another $0.10 move: 20% * 1e12 + 80% * 2e11
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(client.get_price_variance(&xlm), 360_000_000_000);
+    }
+
+    #[test]
+    fn test_prices_keyed_by_token_address() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let (xlm, usdc) = (Address::generate(&env), Address::generate(&env));
+        client.initialize(&admin, &xlm, &usdc);
+
+        assert_eq!(client.get_asset_address(&XLM), xlm);
+        assert_eq!(client.get_price(&usdc), PRICE_SCALE);
+
+        // Symbol updates land on the registered token
+        client.set_price_by_symbol(&XLM, &3_000_000);
+        assert_eq!(client.get_price(&xlm), 3_000_000);
+
+        // Two "USDC" tokens from different issuers keep separate prices
+        let other_usdc = Address::generate(&env);
+        client.set_price(&other_usdc, &9_900_000);
+        assert_eq!(client.get_price(&other_usdc), 9_900_000);
+        assert_eq!(client.get_price_by_symbol(&USDC), PRICE_SCALE);
+
+        // Re-pointing the symbol does not move prices between tokens
+        client.register_asset(&USDC, &other_usdc);
+        assert_eq!(client.get_price_by_symbol(&USDC), 9_900_000);
+        assert_eq!(client.get_price(&usdc), PRICE_SCALE);
+
+        let eurc = symbol_short!("EURC");
+        assert_eq!(client.get_price_by_symbol(&eurc), 0);
+        assert_eq!(
+            client.try_set_price_by_symbol(&eurc, &10_800_000),
+            Err(Ok(OracleError::AssetNotRegistered))
+        );
+    }
+
+    #[test]
+    fn test_sep40_interface() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let (xlm, usdc) = (Address::generate(&env), Address::generate(&env));
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.initialize(&admin, &xlm, &usdc);
+
+        assert_eq!(client.base(), Asset::Other(symbol_short!("USD")));
+        assert_eq!(client.decimals(), 7);
+        assert_eq!(client.resolution(), RESOLUTION);
+        assert_eq!(
+            client.assets(),
+            soroban_sdk::vec![&env, Asset::Stellar(xlm.clone()), Asset::Stellar(usdc.clone())]
+        );
+
+        // No XLM price yet
+        let xlm_asset = Asset::Stellar(xlm.clone());
+        assert_eq!(client.lastprice(&xlm_asset), None);
+
+        client.set_price(&xlm, &3_000_000);
+        env.ledger().with_mut(|li| li.timestamp = 1_300);
+        client.set_price(&xlm, &3_100_000);
+        env.ledger().with_mut(|li| li.timestamp = 1_600);
+        client.set_price(&xlm, &2_900_000);
+
+        let latest = PriceData { price: 2_900_000, timestamp: 1_600 };
+        assert_eq!(client.lastprice(&xlm_asset), Some(latest.clone()));
+        // Symbols resolve through the registry
+        assert_eq!(client.lastprice(&Asset::Other(XLM)), Some(latest.clone()));
+        assert_eq!(client.lastprice(&Asset::Other(symbol_short!("EURC"))), None);
+
+        // Point-in-time lookups return the price in effect at that time
+        assert_eq!(client.price(&xlm_asset, &1_450), Some(PriceData { price: 3_100_000, timestamp: 1_300 }));
+        assert_eq!(client.price(&xlm_asset, &1_600), Some(latest.clone()));
+        assert_eq!(client.price(&xlm_asset, &999), None);
+
+        let recent = client.prices(&xlm_asset, &2).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent.get(0), Some(latest));
+        assert_eq!(recent.get(1).unwrap().price, 3_100_000);
+
+        // USDC was seeded at initialization
+        assert_eq!(
+            client.lastprice(&Asset::Stellar(usdc)),
+            Some(PriceData { price: PRICE_SCALE, timestamp: 1_000 })
+        );
+    }
+
+    #[test]
+    fn test_price_history_is_bounded() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+
+        for i in 0..(MAX_PRICE_HISTORY as u64 + 5) {
+            env.ledger().with_mut(|li| li.timestamp = 1_000 + i * 300);
+            client.set_price(&xlm, &(3_000_000 + i as i128));
+        }
+
+        let all = client.prices(&Asset::Stellar(xlm.clone()), &1_000).unwrap();
+        assert_eq!(all.len(), MAX_PRICE_HISTORY);
+        // The oldest records were dropped
+        assert_eq!(client.price(&Asset::Stellar(xlm), &(1_000 + 4 * 300)), None);
+    }
+
+    #[test]
+    fn test_feeders_median_and_quorum() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+
+        let feeders = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        for feeder in feeders.iter() {
+            client.add_feeder(feeder);
+        }
+        assert_eq!(client.get_quorum(), 2);
+
+        // Direct admin updates are disabled once feeders exist
+        assert_eq!(client.try_set_price(&xlm, &3_000_000), Err(Ok(OracleError::FeedersActive)));
+        assert_eq!(
+            client.try_submit_price(&Address::generate(&env), &xlm, &3_000_000),
+            Err(Ok(OracleError::NotFeeder))
+        );
+
+        // One submission is below quorum
+        assert!(!client.submit_price(&feeders[0], &xlm, &3_000_000));
+        assert_eq!(client.get_price(&xlm), 0);
+
+        // Two submissions: median of two averages them
+        assert!(client.submit_price(&feeders[1], &xlm, &3_200_000));
+        assert_eq!(client.get_price(&xlm), 3_100_000);
+
+        // A compromised feeder cannot move the median
+        assert!(client.submit_price(&feeders[2], &xlm, &100));
+        assert_eq!(client.get_price(&xlm), 3_000_000);
+
+        // Submissions older than the staleness threshold drop out of the quorum
+        env.ledger().with_mut(|li| li.timestamp += DEFAULT_STALENESS_THRESHOLD + 1);
+        assert!(!client.submit_price(&feeders[2], &xlm, &100));
+        assert_eq!(client.get_price(&xlm), 3_000_000);
+    }
+
+    #[test]
+    fn test_quorum_validation() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &Address::generate(&env), &Address::generate(&env));
+
+        let (a, b) = (Address::generate(&env), Address::generate(&env));
+        client.add_feeder(&a);
+        client.add_feeder(&b);
+
+        assert_eq!(client.try_set_quorum(&0), Err(Ok(OracleError::InvalidQuorum)));
+        assert_eq!(client.try_set_quorum(&3), Err(Ok(OracleError::InvalidQuorum)));
+        client.set_quorum(&2);
+
+        // Removing a feeder may not leave the quorum unreachable
+        assert_eq!(client.try_remove_feeder(&a), Err(Ok(OracleError::InvalidQuorum)));
+        client.set_quorum(&1);
+        client.remove_feeder(&a);
+        assert_eq!(client.get_feeders(), soroban_sdk::vec![&env, b]);
+    }
+
+    #[test]
+    fn test_twap() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+        assert_eq!(client.try_get_twap(&xlm, &3600), Err(Ok(OracleError::PriceNotSet)));
+
+        env.ledger().with_mut(|li| li.timestamp = 10_000);
+        client.set_price(&xlm, &3_000_000);
+
+        // A fresh spike has no weight yet
+        env.ledger().with_mut(|li| li.timestamp = 13_600);
+        client.set_price(&xlm, &6_000_000);
+        assert_eq!(client.get_twap(&xlm, &3600), 3_000_000);
+        assert_eq!(client.get_twap(&xlm, &0), 6_000_000);
+
+        // 15 minutes later: 45 min at $0.30 and 15 min at $0.60
+        env.ledger().with_mut(|li| li.timestamp = 14_500);
+        assert_eq!(client.get_twap(&xlm, &3600), 3_750_000);
+
+        // Shorter window only sees the new price
+        assert_eq!(client.get_twap(&xlm, &600), 6_000_000);
+
+        // Window longer than the history averages over the covered period
+        assert_eq!(client.get_twap(&xlm, &100_000), (3_000_000 * 3600 + 6_000_000 * 900) / 4500);
+    }
+
+    #[test]
+    fn test_deviation_circuit_breaker() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+
+        assert_eq!(client.try_set_max_deviation(&(PRICE_SCALE + 1)), Err(Ok(OracleError::InvalidDeviation)));
+        client.set_max_deviation(&2_000_000); // 20%
+
+        // The first price has nothing to deviate from
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(client.get_price(&xlm), 3_000_000);
+
+        // Within 20%: applied
+        client.set_price(&xlm, &3_500_000);
+        assert_eq!(client.get_price(&xlm), 3_500_000);
+
+        // Fat-finger update: held, not applied
+        client.set_price(&xlm, &350_000);
+        assert_eq!(client.get_price(&xlm), 3_500_000);
+        assert_eq!(client.get_pending_price(&xlm).unwrap().price, 350_000);
+        assert_eq!(client.try_confirm_price(&Address::generate(&env)), Err(Ok(OracleError::NoPendingPrice)));
+
+        // A corrected update supersedes the held one
+        client.set_price(&xlm, &3_400_000);
+        assert_eq!(client.get_price(&xlm), 3_400_000);
+        assert_eq!(client.get_pending_price(&xlm), None);
+
+        // A genuine crash is applied once the confirmer signs off
+        let confirmer = Address::generate(&env);
+        client.set_confirmer(&confirmer);
+        client.set_price(&xlm, &1_700_000);
+        assert_eq!(client.get_price(&xlm), 3_400_000);
+        assert_eq!(client.confirm_price(&xlm), 1_700_000);
+        assert_eq!(client.get_price(&xlm), 1_700_000);
+        assert_eq!(client.get_pending_price(&xlm), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Auth, InvalidAction)")]
+    fn test_confirm_price_requires_confirmer() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+        client.set_max_deviation(&2_000_000);
+        client.set_price(&xlm, &3_000_000);
+        client.set_price(&xlm, &1_000_000);
+        client.set_confirmer(&Address::generate(&env));
+
+        // Only the admin signs: the confirmer's authorization is missing
+        env.mock_auths(&[soroban_sdk::testutils::MockAuth {
+            address: &admin,
+            invoke: &soroban_sdk::testutils::MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "confirm_price",
+                args: (xlm.clone(),).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        client.confirm_price(&xlm);
+    }
+
+    #[test]
+    fn test_price_bounds() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+        assert_eq!(
+            client.try_set_price_bounds(&xlm, &10_000_000, &1_000_000),
+            Err(Ok(OracleError::InvalidPriceBounds))
+        );
+
+        // XLM between $0.01 and $10
+        client.set_price_bounds(&xlm, &100_000, &100_000_000);
+        client.set_price(&xlm, &3_000_000);
+
+        // A keeper bug publishing $30,000 is rejected
+        assert_eq!(
+            client.try_set_price(&xlm, &300_000_000_000),
+            Err(Ok(OracleError::PriceOutOfBounds))
+        );
+        assert_eq!(
+            client.try_set_price_with_expiry(&xlm, &10_000, &60),
+            Err(Ok(OracleError::PriceOutOfBounds))
+        );
+        assert_eq!(client.get_xlm_price(), 3_000_000);
+
+        client.set_price_bounds(&xlm, &0, &0);
+        assert_eq!(client.get_price_bounds(&xlm), None);
+        client.set_price(&xlm, &10_000);
+        assert_eq!(client.get_xlm_price(), 10_000);
+    }
+
+    #[test]
+    fn test_pause_feed() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        let usdc = Address::generate(&env);
+        client.initialize(&admin, &xlm, &usdc);
+        client.set_price(&xlm, &3_000_000);
+
+        // A paused feed fails even while fresh; other assets stay live
+        client.pause_feed(&xlm);
+        assert!(client.is_feed_paused(&xlm));
+        assert_eq!(client.try_get_price_safe(&xlm), Err(Ok(OracleError::FeedPaused)));
+        assert_eq!(client.get_price_safe(&usdc), PRICE_SCALE);
+        assert_eq!(client.get_price(&xlm), 0);
+        assert_eq!(client.try_get_twap(&xlm, &3_600), Err(Ok(OracleError::FeedPaused)));
+
+        client.unpause_feed(&xlm);
+        assert_eq!(client.get_price_safe(&xlm), 3_000_000);
+    }
+
+    #[test]
+    fn test_usdc_peg_band_and_depeg() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let usdc = Address::generate(&env);
+        client.initialize(&admin, &Address::generate(&env), &usdc);
+        assert_eq!(client.get_peg_band(&usdc), Some((DEFAULT_PEG_LOWER, DEFAULT_PEG_UPPER)));
+        assert_eq!(
+            client.try_set_peg_band(&usdc, &10_500_000, &9_500_000),
+            Err(Ok(OracleError::InvalidPegBand))
+        );
+
+        // Moves inside the band apply directly
+        client.set_price(&usdc, &9_800_000);
+        assert_eq!(client.get_usdc_price(), 9_800_000);
+
+        // Leaving the band is held until the depeg is confirmed
+        client.set_price(&usdc, &9_000_000);
+        assert_eq!(client.get_usdc_price(), 9_800_000);
+        assert_eq!(client.get_pending_depeg(&usdc).unwrap().price, 9_000_000);
+        assert!(!client.is_depegged(&usdc));
+
+        assert_eq!(client.confirm_depeg(&usdc), 9_000_000);
+        assert_eq!(client.get_usdc_price(), 9_000_000);
+        assert!(client.is_depegged(&usdc));
+        assert_eq!(client.get_pending_depeg(&usdc), None);
+
+        // While depegged, out-of-band updates apply; returning to the band clears the flag
+        client.set_price(&usdc, &8_500_000);
+        assert_eq!(client.get_usdc_price(), 8_500_000);
+        client.set_price(&usdc, &9_900_000);
+        assert!(!client.is_depegged(&usdc));
+        assert_eq!(client.try_confirm_depeg(&usdc), Err(Ok(OracleError::NoPendingPrice)));
+    }
+
+    #[test]
+    fn test_asset_heartbeat_and_price_age() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let (xlm, usdc) = (Address::generate(&env), Address::generate(&env));
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.initialize(&admin, &xlm, &usdc);
+        assert_eq!(client.try_get_price_with_age(&xlm), Err(Ok(OracleError::PriceNotSet)));
+
+        client.set_price(&xlm, &3_000_000);
+        client.set_asset_heartbeat(&xlm, &600);
+        assert_eq!(client.get_asset_heartbeat(&xlm), 600);
+        assert_eq!(client.get_asset_heartbeat(&usdc), DEFAULT_STALENESS_THRESHOLD);
+
+        // 15 minutes later XLM is stale but USDC still uses the 1 hour default
+        env.ledger().with_mut(|li| li.timestamp = 1_900);
+        assert!(client.is_stale(&xlm));
+        assert!(!client.is_stale(&usdc));
+        assert_eq!(client.get_price_with_age(&xlm), (3_000_000, 900));
+
+        // Clearing the heartbeat falls back to the default
+        client.set_asset_heartbeat(&xlm, &0);
+        assert!(!client.is_stale(&xlm));
+    }
+
+    #[test]
+    fn test_price_rounds() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        client.initialize(&admin, &xlm, &Address::generate(&env));
+        assert_eq!(client.latest_round(&xlm), None);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1_000;
+            li.sequence_number = 10;
+        });
+        client.set_price(&xlm, &3_000_000);
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1_005;
+            li.sequence_number = 11;
+        });
+        client.set_price(&xlm, &1_500_000);
+
+        let first = PriceRound { round_id: 1, price: 3_000_000, timestamp: 1_000, ledger: 10 };
+        let second = PriceRound { round_id: 2, price: 1_500_000, timestamp: 1_005, ledger: 11 };
+        assert_eq!(client.get_round(&xlm, &1), Some(first));
+        assert_eq!(client.get_round(&xlm, &2), Some(second.clone()));
+        assert_eq!(client.latest_round(&xlm), Some(second));
+        assert_eq!(client.get_round(&xlm, &3), None);
+    }
+
+    #[test]
+    fn test_token_decimals() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        let usdc = Address::generate(&env);
+        client.initialize(&admin, &xlm, &usdc);
+        client.set_price(&xlm, &3_000_000);
+
+        // 100 XLM (7 decimals) at $0.30
+        assert_eq!(client.get_decimals(&xlm), 7);
+        assert_eq!(client.to_usd(&xlm, &1_000_000_000), 300_000_000);
+
+        // Bridged USDC with 6 decimals: 250 USDC = $250
+        client.set_decimals(&usdc, &6);
+        assert_eq!(client.get_decimals(&usdc), 6);
+        assert_eq!(client.to_usd(&usdc, &250_000_000), 2_500_000_000);
+        assert_eq!(client.from_usd(&usdc, &2_500_000_000), 250_000_000);
+
+        // 100 XLM at $0.30 is 30 USDC in 6-decimal units, and back
+        assert_eq!(client.convert(&xlm, &usdc, &1_000_000_000), 30_000_000);
+        assert_eq!(client.convert(&usdc, &xlm, &30_000_000), 1_000_000_000);
+        assert_eq!(
+            client.try_convert(&xlm, &Address::generate(&env), &1),
+            Err(Ok(OracleError::PriceNotSet))
+        );
+
+        // 18 decimals: 1 token at $0.30
+        client.set_decimals(&xlm, &18);
+        assert_eq!(client.to_usd(&xlm, &1_000_000_000_000_000_000), 3_000_000);
+        assert_eq!(client.from_usd(&xlm, &3_000_000), 1_000_000_000_000_000_000);
+
+        assert_eq!(client.try_set_decimals(&xlm, &19), Err(Ok(OracleError::InvalidDecimals)));
+        assert_eq!(client.try_to_usd(&Address::generate(&env), &1), Err(Ok(OracleError::PriceNotSet)));
+    }
+
+    #[test]
+    fn test_roles_split_feeder_from_admin() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, PriceOracle);
+        let client = PriceOracleClient::new(&env, &contract_id);
+
+        let admin = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let xlm = Address::generate(&env);
+        let usdc = Address::generate(&env);
+        client.initialize(&admin, &xlm, &usdc);
+
+        assert!(client.has_role(&Role::OracleFeeder, &admin));
+        client.grant_role(&Role::OracleFeeder, &keeper);
+        assert_eq!(client.get_role_holder(&Role::OracleFeeder), keeper);
+
+        client.set_price(&xlm, &3_000_000);
+        assert_eq!(env.auths()[0].0, keeper);
+
+        // Risk parameters still belong to the admin
+        client.set_max_deviation(&2_000_000);
+        assert_eq!(env.auths()[0].0, admin);
+
+        client.revoke_role(&Role::OracleFeeder);
+        assert!(client.has_role(&Role::OracleFeeder, &admin));
+        assert_eq!(client.try_revoke_role(&Role::Admin), Err(Ok(OracleError::InvalidRole)));
     }
 }
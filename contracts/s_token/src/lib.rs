@@ -0,0 +1,404 @@
+#![no_std]
+
+//! # Stellend sToken Contract
+//!
+//! A SEP-41 token representing a supplier's shares of one pool reserve.
+//! Holding sTokens is a claim on the underlying asset plus accrued interest,
+//! redeemable through the pool's `withdraw`.
+//!
+//! ## How It Works
+//!
+//! - The pool stays the ledger of record: `balance` reads the holder's
+//!   shares from the pool, and transfers move shares through the pool's
+//!   `transfer_shares`, so the two can never disagree
+//! - The pool calls `mint` on supply and `pool_burn` on withdraw, which emit
+//!   the standard SEP-41 `mint` and `burn` events for wallets and indexers
+//! - Allowances are kept here, so sTokens work with any dapp that uses
+//!   `approve` / `transfer_from`
+//! - Holders cannot `burn` directly; shares are redeemed through the pool
+//!
+//! One sToken is deployed per reserve and registered with the pool via
+//! `set_s_token`.
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, panic_with_error,
+    symbol_short, token::TokenInterface, Address, Env, String, Symbol,
+};
+
+// ============================================================================
+// POOL INTERFACE
+// ============================================================================
+
+/// Lending pool functions used by the sToken
+#[contractclient(name = "PoolClient")]
+pub trait Pool {
+    fn get_user_shares(env: Env, user: Address, asset: Symbol) -> i128;
+    fn transfer_shares(env: Env, asset: Symbol, from: Address, to: Address, shares: i128);
+}
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the sToken
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Lending pool that owns the shares
+    Pool,
+    /// Reserve asset symbol in the pool
+    Asset,
+    /// Token decimals (matches the underlying)
+    Decimals,
+    /// Token name
+    Name,
+    /// Token symbol
+    Symbol,
+    /// Allowance granted by an owner to a spender (owner, spender), in temporary storage
+    Allowance(Address, Address),
+}
+
+/// An allowance and the ledger it expires at
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the sToken
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum STokenError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Amount must not be negative
+    InvalidAmount = 3,
+    /// Spender's allowance is too low
+    InsufficientAllowance = 4,
+    /// Expiration ledger is in the past
+    InvalidExpiration = 5,
+    /// sTokens are redeemed through the pool, not burned
+    BurnNotSupported = 6,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+/// Stellend sToken Contract
+///
+/// Transferable receipt token for shares supplied to one pool reserve.
+#[contract]
+pub struct SToken;
+
+#[contractimpl]
+impl SToken {
+    // ========================================================================
+    // INITIALIZATION
+    // ========================================================================
+
+    /// Initialize the sToken
+    ///
+    /// # Arguments
+    /// * `pool` - Lending pool that holds the shares
+    /// * `asset` - Reserve asset symbol in the pool (e.g., USDC)
+    /// * `decimals` - Token decimals
+    /// * `name` - Token name (e.g., "Stellend USDC")
+    /// * `symbol` - Token symbol (e.g., "sUSDC")
+    pub fn initialize(
+        env: Env,
+        pool: Address,
+        asset: Symbol,
+        decimals: u32,
+        name: String,
+        symbol: String,
+    ) -> Result<(), STokenError> {
+        if env.storage().instance().has(&DataKey::Pool) {
+            return Err(STokenError::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().set(&DataKey::Asset, &asset);
+        env.storage().instance().set(&DataKey::Decimals, &decimals);
+        env.storage().instance().set(&DataKey::Name, &name);
+        env.storage().instance().set(&DataKey::Symbol, &symbol);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // POOL HOOKS
+    // ========================================================================
+
+    /// Record shares minted by the pool on supply
+    ///
+    /// # Events
+    /// Emits `("mint", pool, to)` with the amount
+    pub fn mint(env: Env, to: Address, amount: i128) -> Result<(), STokenError> {
+        let pool = Self::get_pool(env.clone())?;
+        pool.require_auth();
+
+        env.events().publish((symbol_short!("mint"), pool, to), amount);
+
+        Ok(())
+    }
+
+    /// Record shares burned by the pool on withdraw
+    ///
+    /// # Events
+    /// Emits `("burn", from)` with the amount
+    pub fn pool_burn(env: Env, from: Address, amount: i128) -> Result<(), STokenError> {
+        Self::get_pool(env.clone())?.require_auth();
+
+        env.events().publish((symbol_short!("burn"), from), amount);
+
+        Ok(())
+    }
+
+    /// Get the lending pool that holds the shares
+    pub fn get_pool(env: Env) -> Result<Address, STokenError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Pool)
+            .ok_or(STokenError::NotInitialized)
+    }
+
+    /// Get the reserve asset symbol in the pool
+    pub fn get_asset(env: Env) -> Result<Symbol, STokenError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Asset)
+            .ok_or(STokenError::NotInitialized)
+    }
+
+    // ========================================================================
+    // INTERNAL HELPERS
+    // ========================================================================
+
+    /// Internal: Client for the pool and the reserve asset symbol
+    fn pool(env: &Env) -> (PoolClient<'_>, Symbol) {
+        let pool = Self::get_pool(env.clone()).unwrap_or_else(|e| panic_with_error!(env, e));
+        let asset = Self::get_asset(env.clone()).unwrap_or_else(|e| panic_with_error!(env, e));
+        (PoolClient::new(env, &pool), asset)
+    }
+
+    /// Internal: Move shares in the pool and emit the transfer event
+    fn move_shares(env: &Env, from: Address, to: Address, amount: i128) {
+        if amount < 0 {
+            panic_with_error!(env, STokenError::InvalidAmount);
+        }
+
+        let (pool, asset) = Self::pool(env);
+        pool.transfer_shares(&asset, &from, &to, &amount);
+
+        env.events()
+            .publish((symbol_short!("transfer"), from, to), amount);
+    }
+
+    /// Internal: Read an allowance, treating expired ones as zero
+    fn read_allowance(env: &Env, from: &Address, spender: &Address) -> AllowanceValue {
+        let key = DataKey::Allowance(from.clone(), spender.clone());
+        match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+            _ => AllowanceValue {
+                amount: 0,
+                expiration_ledger: 0,
+            },
+        }
+    }
+
+    /// Internal: Deduct `amount` from a spender's allowance
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Self::read_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic_with_error!(env, STokenError::InsufficientAllowance);
+        }
+        if amount > 0 {
+            env.storage().temporary().set(
+                &DataKey::Allowance(from.clone(), spender.clone()),
+                &AllowanceValue {
+                    amount: allowance.amount - amount,
+                    expiration_ledger: allowance.expiration_ledger,
+                },
+            );
+        }
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for SToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        Self::read_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+
+        if amount < 0 {
+            panic_with_error!(&env, STokenError::InvalidAmount);
+        }
+        let ledger = env.ledger().sequence();
+        if amount > 0 && expiration_ledger < ledger {
+            panic_with_error!(&env, STokenError::InvalidExpiration);
+        }
+
+        let key = DataKey::Allowance(from.clone(), spender.clone());
+        env.storage().temporary().set(
+            &key,
+            &AllowanceValue {
+                amount,
+                expiration_ledger,
+            },
+        );
+        if amount > 0 {
+            let live_for = expiration_ledger - ledger;
+            env.storage().temporary().extend_ttl(&key, live_for, live_for);
+        }
+
+        env.events().publish(
+            (symbol_short!("approve"), from, spender),
+            (amount, expiration_ledger),
+        );
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        let (pool, asset) = Self::pool(&env);
+        pool.get_user_shares(&id, &asset)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::move_shares(&env, from, to, amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::move_shares(&env, from, to, amount);
+    }
+
+    fn burn(env: Env, _from: Address, _amount: i128) {
+        panic_with_error!(&env, STokenError::BurnNotSupported);
+    }
+
+    fn burn_from(env: Env, _spender: Address, _from: Address, _amount: i128) {
+        panic_with_error!(&env, STokenError::BurnNotSupported);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Decimals).unwrap_or(7)
+    }
+
+    fn name(env: Env) -> String {
+        env.storage()
+            .instance()
+            .get(&DataKey::Name)
+            .unwrap_or_else(|| panic_with_error!(&env, STokenError::NotInitialized))
+    }
+
+    fn symbol(env: Env) -> String {
+        env.storage()
+            .instance()
+            .get(&DataKey::Symbol)
+            .unwrap_or_else(|| panic_with_error!(&env, STokenError::NotInitialized))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{symbol_short, testutils::Address as _, token::TokenClient};
+
+    /// Minimal pool keeping share balances, standing in for the lending pool
+    #[contract]
+    struct MockPool;
+
+    #[contractimpl]
+    impl MockPool {
+        pub fn set_shares(env: Env, user: Address, shares: i128) {
+            env.storage().instance().set(&user, &shares);
+        }
+
+        pub fn get_user_shares(env: Env, user: Address, _asset: Symbol) -> i128 {
+            env.storage().instance().get(&user).unwrap_or(0)
+        }
+
+        pub fn transfer_shares(env: Env, _asset: Symbol, from: Address, to: Address, shares: i128) {
+            let from_shares = Self::get_user_shares(env.clone(), from.clone(), symbol_short!("USDC"));
+            let to_shares = Self::get_user_shares(env.clone(), to.clone(), symbol_short!("USDC"));
+            assert!(from_shares >= shares);
+            env.storage().instance().set(&from, &(from_shares - shares));
+            env.storage().instance().set(&to, &(to_shares + shares));
+        }
+    }
+
+    fn setup(env: &Env) -> (MockPoolClient<'_>, Address) {
+        let pool_id = env.register_contract(None, MockPool);
+        let s_token_id = env.register_contract(None, SToken);
+        STokenClient::new(env, &s_token_id).initialize(
+            &pool_id,
+            &symbol_short!("USDC"),
+            &7,
+            &String::from_str(env, "Stellend USDC"),
+            &String::from_str(env, "sUSDC"),
+        );
+        (MockPoolClient::new(env, &pool_id), s_token_id)
+    }
+
+    #[test]
+    fn test_balances_and_transfers_follow_pool_shares() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (pool, s_token_id) = setup(&env);
+        let token = TokenClient::new(&env, &s_token_id);
+
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        pool.set_shares(&alice, &1_000);
+
+        assert_eq!(token.name(), String::from_str(&env, "Stellend USDC"));
+        assert_eq!(token.symbol(), String::from_str(&env, "sUSDC"));
+        assert_eq!(token.decimals(), 7);
+        assert_eq!(token.balance(&alice), 1_000);
+
+        token.transfer(&alice, &bob, &400);
+        assert_eq!(token.balance(&alice), 600);
+        assert_eq!(pool.get_user_shares(&bob, &symbol_short!("USDC")), 400);
+
+        assert!(token.try_burn(&alice, &1).is_err());
+    }
+
+    #[test]
+    fn test_transfer_from_spends_allowance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (pool, s_token_id) = setup(&env);
+        let token = TokenClient::new(&env, &s_token_id);
+
+        let alice = Address::generate(&env);
+        let spender = Address::generate(&env);
+        let bob = Address::generate(&env);
+        pool.set_shares(&alice, &1_000);
+
+        token.approve(&alice, &spender, &500, &100);
+        assert_eq!(token.allowance(&alice, &spender), 500);
+
+        token.transfer_from(&spender, &alice, &bob, &300);
+        assert_eq!(token.allowance(&alice, &spender), 200);
+        assert_eq!(token.balance(&bob), 300);
+
+        assert!(token.try_transfer_from(&spender, &alice, &bob, &300).is_err());
+    }
+}
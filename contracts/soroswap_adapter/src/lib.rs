@@ -0,0 +1,226 @@
+#![no_std]
+
+//! # Stellend Soroswap Adapter Contract
+//!
+//! Implements the pool's `SwapAdapter` interface on top of the Soroswap
+//! router, so leverage and liquidation routines can swap one reserve asset
+//! for another in the same transaction.
+//!
+//! ## How It Works
+//!
+//! - The pool transfers the input tokens to the adapter, then calls
+//!   `swap_exact_in`
+//! - The adapter authorizes the router to pull those tokens into the pair
+//!   and swaps along the direct `token_in -> token_out` pair
+//! - The router enforces `min_out`; the output is forwarded to `to`
+//!
+//! The adapter keeps no balances between calls. The pool only swaps through
+//! adapters its admin has approved.
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, panic_with_error,
+    symbol_short, token, vec, Address, Env, IntoVal, Symbol, Vec,
+};
+
+use apogee_common::{SwapAdapter, BUMP_AMOUNT, LIFETIME_THRESHOLD};
+
+// ============================================================================
+// ROUTER INTERFACE
+// ============================================================================
+
+/// Soroswap router functions used by the adapter
+#[contractclient(name = "SoroswapRouterClient")]
+pub trait SoroswapRouter {
+    fn router_pair_for(env: Env, token_a: Address, token_b: Address) -> Address;
+    fn swap_exact_tokens_for_tokens(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128>;
+}
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the adapter
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Soroswap router swaps are routed through
+    Router,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the adapter
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AdapterError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Amount must be positive
+    InvalidAmount = 3,
+    /// Swap returned less than the minimum output
+    InsufficientOutput = 4,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct SoroswapAdapter;
+
+#[contractimpl]
+impl SoroswapAdapter {
+    /// Initialize the adapter with the Soroswap router
+    pub fn initialize(env: Env, router: Address) -> Result<(), AdapterError> {
+        if env.storage().instance().has(&DataKey::Router) {
+            return Err(AdapterError::AlreadyInitialized);
+        }
+        env.storage().instance().set(&DataKey::Router, &router);
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    /// Get the Soroswap router
+    pub fn get_router(env: Env) -> Result<Address, AdapterError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Router)
+            .ok_or(AdapterError::NotInitialized)
+    }
+}
+
+#[contractimpl]
+impl SwapAdapter for SoroswapAdapter {
+    fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128 {
+        if amount_in <= 0 || min_out < 0 {
+            panic_with_error!(&env, AdapterError::InvalidAmount);
+        }
+        let router = SoroswapRouterClient::new(
+            &env,
+            &Self::get_router(env.clone()).unwrap_or_else(|e| panic_with_error!(&env, e)),
+        );
+        let adapter = env.current_contract_address();
+
+        // The router moves the input from the adapter into the pair
+        let pair = router.router_pair_for(&token_in, &token_out);
+        env.authorize_as_current_contract(vec![
+            &env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: token_in.clone(),
+                    fn_name: Symbol::new(&env, "transfer"),
+                    args: (adapter.clone(), pair, amount_in).into_val(&env),
+                },
+                sub_invocations: vec![&env],
+            }),
+        ]);
+
+        let path = vec![&env, token_in.clone(), token_out.clone()];
+        let amounts = router.swap_exact_tokens_for_tokens(
+            &amount_in,
+            &min_out,
+            &path,
+            &adapter,
+            &env.ledger().timestamp(),
+        );
+        let amount_out = amounts.last().unwrap_or(0);
+        if amount_out < min_out {
+            panic_with_error!(&env, AdapterError::InsufficientOutput);
+        }
+
+        token::Client::new(&env, &token_out).transfer(&adapter, &to, &amount_out);
+
+        env.events()
+            .publish((symbol_short!("swap"), token_in, token_out), (amount_in, amount_out, to));
+
+        amount_out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_common::SwapAdapterClient;
+    use apogee_testutils::{create_token, fund};
+    use soroban_sdk::testutils::Address as _;
+
+    /// Router swapping at a fixed 2:1 rate out of its own balance, pulling
+    /// the input the way Soroswap does
+    #[contract]
+    struct MockRouter;
+
+    #[contractimpl]
+    impl MockRouter {
+        pub fn router_pair_for(env: Env, _token_a: Address, _token_b: Address) -> Address {
+            env.current_contract_address()
+        }
+
+        pub fn swap_exact_tokens_for_tokens(
+            env: Env,
+            amount_in: i128,
+            amount_out_min: i128,
+            path: Vec<Address>,
+            to: Address,
+            _deadline: u64,
+        ) -> Vec<i128> {
+            to.require_auth();
+            let amount_out = amount_in * 2;
+            assert!(amount_out >= amount_out_min);
+            let router = env.current_contract_address();
+            token::Client::new(&env, &path.get(0).unwrap()).transfer(&to, &router, &amount_in);
+            token::Client::new(&env, &path.get(1).unwrap()).transfer(&router, &to, &amount_out);
+            vec![&env, amount_in, amount_out]
+        }
+    }
+
+    #[test]
+    fn test_swap_through_router() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let pool = Address::generate(&env);
+        let (token_in, _) = create_token(&env, &admin);
+        let (token_out, _) = create_token(&env, &admin);
+
+        let router = env.register_contract(None, MockRouter);
+        let adapter_id = env.register_contract(None, SoroswapAdapter);
+        SoroswapAdapterClient::new(&env, &adapter_id).initialize(&router);
+        fund(&env, &token_out.address, &[&router], 1_000);
+        fund(&env, &token_in.address, &[&adapter_id], 200);
+
+        // Only the adapter's own authorization moves its tokens
+        env.set_auths(&[]);
+        let adapter = SwapAdapterClient::new(&env, &adapter_id);
+        assert_eq!(
+            adapter.swap_exact_in(&token_in.address, &token_out.address, &100, &200, &pool),
+            200
+        );
+        assert_eq!(token_out.balance(&pool), 200);
+        assert_eq!(token_in.balance(&router), 100);
+        assert_eq!(token_in.balance(&adapter_id), 100);
+
+        assert!(adapter
+            .try_swap_exact_in(&token_in.address, &token_out.address, &100, &201, &pool)
+            .is_err());
+    }
+}
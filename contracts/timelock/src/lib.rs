@@ -0,0 +1,413 @@
+#![no_std]
+
+//! # Stellend Timelock Contract
+//!
+//! Delays sensitive admin operations (risk parameters, oracle and rate
+//! model changes, treasury withdrawals, upgrades) so users have time to
+//! exit before they take effect.
+//!
+//! ## How It Works
+//!
+//! - The timelock is set as the admin of the protocol contracts
+//! - The proposer queues a call (target, function, args) with a delay of at
+//!   least `min_delay`
+//! - Once the delay has passed, anyone can execute the call within the grace
+//!   period; the timelock makes the call as the target's admin
+//! - The proposer can cancel a queued call at any time before execution
+//! - The timelock's own settings (`set_min_delay`, `set_proposer`) can only
+//!   be changed through the timelock itself, so they are delayed too
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Symbol,
+    Val, Vec,
+};
+
+use apogee_common::DAY_IN_LEDGERS;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Time after the ETA during which a call can still be executed (14 days)
+const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+/// Upper bound for the minimum delay (30 days)
+const MAX_DELAY: u64 = 30 * 24 * 60 * 60;
+
+/// Storage TTL for queued operations (in ledgers, ~5s each)
+/// Covers the longest delay plus the grace period
+const OPERATION_BUMP_AMOUNT: u32 = 45 * DAY_IN_LEDGERS;
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the timelock
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Address allowed to queue and cancel calls
+    Proposer,
+    /// Minimum delay between queueing and execution, in seconds
+    MinDelay,
+    /// Number of operations queued so far
+    OperationCount,
+    /// Operation by sequential ID (starting at 0)
+    Operation(u64),
+}
+
+/// Lifecycle of a queued operation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum OperationState {
+    Queued,
+    Executed,
+    Cancelled,
+}
+
+/// A delayed contract call
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Operation {
+    pub target: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+    /// Earliest execution timestamp
+    pub eta: u64,
+    pub state: OperationState,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the timelock
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum TimelockError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Delay is below the minimum or above the maximum
+    InvalidDelay = 3,
+    /// No operation with the given ID
+    OperationNotFound = 4,
+    /// Operation was already executed or cancelled
+    OperationNotQueued = 5,
+    /// Operation's delay has not passed yet
+    NotReady = 6,
+    /// Operation's grace period has passed
+    Expired = 7,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+/// Stellend Timelock Contract
+///
+/// Queues admin calls and executes them after a delay.
+#[contract]
+pub struct Timelock;
+
+#[contractimpl]
+impl Timelock {
+    // ========================================================================
+    // INITIALIZATION
+    // ========================================================================
+
+    /// Initialize the timelock
+    ///
+    /// # Arguments
+    /// * `proposer` - Address allowed to queue and cancel calls
+    /// * `min_delay` - Minimum delay in seconds (at most 30 days)
+    pub fn initialize(env: Env, proposer: Address, min_delay: u64) -> Result<(), TimelockError> {
+        if env.storage().instance().has(&DataKey::Proposer) {
+            return Err(TimelockError::AlreadyInitialized);
+        }
+        if min_delay > MAX_DELAY {
+            return Err(TimelockError::InvalidDelay);
+        }
+
+        env.storage().instance().set(&DataKey::Proposer, &proposer);
+        env.storage().instance().set(&DataKey::MinDelay, &min_delay);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // OPERATIONS
+    // ========================================================================
+
+    /// Queue a call for delayed execution
+    ///
+    /// # Arguments
+    /// * `target` - Contract to call
+    /// * `function` - Function to call on the target
+    /// * `args` - Call arguments
+    /// * `delay` - Seconds until the call can be executed (at least `min_delay`)
+    ///
+    /// # Returns
+    /// ID of the queued operation
+    ///
+    /// # Events
+    /// Emits `("queue", id)` with `(target, function, eta)`
+    pub fn queue(
+        env: Env,
+        target: Address,
+        function: Symbol,
+        args: Vec<Val>,
+        delay: u64,
+    ) -> Result<u64, TimelockError> {
+        Self::get_proposer(env.clone())?.require_auth();
+
+        if delay < Self::get_min_delay(env.clone()) || delay > MAX_DELAY {
+            return Err(TimelockError::InvalidDelay);
+        }
+
+        let id = Self::get_operation_count(env.clone());
+        let eta = env.ledger().timestamp() + delay;
+        let operation = Operation {
+            target: target.clone(),
+            function: function.clone(),
+            args,
+            eta,
+            state: OperationState::Queued,
+        };
+
+        let key = DataKey::Operation(id);
+        env.storage().persistent().set(&key, &operation);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, OPERATION_BUMP_AMOUNT, OPERATION_BUMP_AMOUNT);
+        env.storage().instance().set(&DataKey::OperationCount, &(id + 1));
+
+        env.events()
+            .publish((symbol_short!("queue"), id), (target, function, eta));
+
+        Ok(id)
+    }
+
+    /// Cancel a queued call
+    ///
+    /// # Events
+    /// Emits `("cancel", id)`
+    pub fn cancel(env: Env, id: u64) -> Result<(), TimelockError> {
+        Self::get_proposer(env.clone())?.require_auth();
+
+        let mut operation = Self::queued_operation(&env, id)?;
+        operation.state = OperationState::Cancelled;
+        env.storage().persistent().set(&DataKey::Operation(id), &operation);
+
+        env.events().publish((symbol_short!("cancel"), id), ());
+
+        Ok(())
+    }
+
+    /// Execute a queued call once its delay has passed
+    ///
+    /// Permissionless: the call was approved when it was queued. It must be
+    /// executed within the grace period after its ETA.
+    ///
+    /// # Returns
+    /// Return value of the call
+    ///
+    /// # Events
+    /// Emits `("execute", id)`
+    pub fn execute(env: Env, id: u64) -> Result<Val, TimelockError> {
+        let mut operation = Self::queued_operation(&env, id)?;
+
+        let now = env.ledger().timestamp();
+        if now < operation.eta {
+            return Err(TimelockError::NotReady);
+        }
+        if now > operation.eta + GRACE_PERIOD {
+            return Err(TimelockError::Expired);
+        }
+
+        // Mark as executed before the call so it cannot be re-entered
+        operation.state = OperationState::Executed;
+        env.storage().persistent().set(&DataKey::Operation(id), &operation);
+
+        let result: Val = env.invoke_contract(&operation.target, &operation.function, operation.args);
+
+        env.events().publish((symbol_short!("execute"), id), ());
+
+        Ok(result)
+    }
+
+    /// Internal: Load an operation that is still queued
+    fn queued_operation(env: &Env, id: u64) -> Result<Operation, TimelockError> {
+        let operation = Self::get_operation(env.clone(), id).ok_or(TimelockError::OperationNotFound)?;
+        if operation.state != OperationState::Queued {
+            return Err(TimelockError::OperationNotQueued);
+        }
+        Ok(operation)
+    }
+
+    // ========================================================================
+    // SETTINGS (only through the timelock itself)
+    // ========================================================================
+
+    /// Set the minimum delay; must be queued through the timelock
+    pub fn set_min_delay(env: Env, min_delay: u64) -> Result<(), TimelockError> {
+        env.current_contract_address().require_auth();
+
+        if min_delay > MAX_DELAY {
+            return Err(TimelockError::InvalidDelay);
+        }
+        env.storage().instance().set(&DataKey::MinDelay, &min_delay);
+
+        env.events().publish((symbol_short!("min_delay"),), min_delay);
+
+        Ok(())
+    }
+
+    /// Set the proposer; must be queued through the timelock
+    pub fn set_proposer(env: Env, proposer: Address) -> Result<(), TimelockError> {
+        env.current_contract_address().require_auth();
+
+        env.storage().instance().set(&DataKey::Proposer, &proposer);
+
+        env.events().publish((symbol_short!("proposer"),), proposer);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // VIEW FUNCTIONS
+    // ========================================================================
+
+    /// Get the address allowed to queue and cancel calls
+    pub fn get_proposer(env: Env) -> Result<Address, TimelockError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Proposer)
+            .ok_or(TimelockError::NotInitialized)
+    }
+
+    /// Get the minimum delay in seconds
+    pub fn get_min_delay(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::MinDelay).unwrap_or(0)
+    }
+
+    /// Get a queued, executed or cancelled operation by ID
+    pub fn get_operation(env: Env, id: u64) -> Option<Operation> {
+        env.storage().persistent().get(&DataKey::Operation(id))
+    }
+
+    /// Get the number of operations queued so far
+    pub fn get_operation_count(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::OperationCount).unwrap_or(0)
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke},
+        vec, IntoVal,
+    };
+    use apogee_testutils::{create_native_xlm, create_token};
+    use stellend_pool::{LendingPool, LendingPoolClient};
+
+    const DELAY: u64 = 2 * 24 * 60 * 60;
+
+    /// Pool whose admin is the timelock
+    fn setup(env: &Env) -> (TimelockClient<'_>, LendingPoolClient<'_>, Address) {
+        let proposer = Address::generate(env);
+        let timelock_id = env.register_contract(None, Timelock);
+        let timelock = TimelockClient::new(env, &timelock_id);
+        timelock.initialize(&proposer, &DELAY);
+
+        let pool_id = env.register_contract(None, LendingPool);
+        let pool = LendingPoolClient::new(env, &pool_id);
+        pool.initialize(
+            &timelock_id,
+            &Address::generate(env),
+            &Address::generate(env),
+            &create_native_xlm(env, &proposer).0.address,
+            &create_token(env, &proposer).0.address,
+        );
+
+        (timelock, pool, proposer)
+    }
+
+    /// Queue `set_ltv_ratio(XLM, ltv)` on the pool as the proposer
+    fn queue_set_ltv(env: &Env, timelock: &TimelockClient, pool: &LendingPoolClient, proposer: &Address, ltv: i128) -> u64 {
+        let args: Vec<Val> = vec![env, symbol_short!("XLM").into_val(env), ltv.into_val(env)];
+        let function = Symbol::new(env, "set_ltv_ratio");
+        env.mock_auths(&[MockAuth {
+            address: proposer,
+            invoke: &MockAuthInvoke {
+                contract: &timelock.address,
+                fn_name: "queue",
+                args: (pool.address.clone(), function.clone(), args.clone(), DELAY).into_val(env),
+                sub_invokes: &[],
+            },
+        }]);
+        timelock.queue(&pool.address, &function, &args, &DELAY)
+    }
+
+    #[test]
+    fn test_queue_and_execute_after_delay() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        let (timelock, pool, proposer) = setup(&env);
+
+        let id = queue_set_ltv(&env, &timelock, &pool, &proposer, 6_000_000);
+        let operation = timelock.get_operation(&id).unwrap();
+        assert_eq!(operation.eta, 1_000 + DELAY);
+        assert_eq!(operation.state, OperationState::Queued);
+
+        // Too early
+        assert_eq!(timelock.try_execute(&id).err(), Some(Ok(TimelockError::NotReady)));
+
+        // Anyone can execute once ready; the pool accepts the timelock as admin
+        env.ledger().with_mut(|li| li.timestamp = 1_000 + DELAY);
+        timelock.execute(&id);
+        assert_eq!(pool.get_ltv_ratio(&symbol_short!("XLM")), 6_000_000);
+        assert_eq!(timelock.get_operation(&id).unwrap().state, OperationState::Executed);
+        assert_eq!(timelock.try_execute(&id).err(), Some(Ok(TimelockError::OperationNotQueued)));
+    }
+
+    #[test]
+    fn test_cancel_and_expiry() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        let (timelock, pool, proposer) = setup(&env);
+
+        let cancelled = queue_set_ltv(&env, &timelock, &pool, &proposer, 6_000_000);
+        let expired = queue_set_ltv(&env, &timelock, &pool, &proposer, 5_000_000);
+        env.mock_all_auths();
+        timelock.cancel(&cancelled);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000 + DELAY + GRACE_PERIOD + 1);
+        assert_eq!(timelock.try_execute(&cancelled).err(), Some(Ok(TimelockError::OperationNotQueued)));
+        assert_eq!(timelock.try_execute(&expired).err(), Some(Ok(TimelockError::Expired)));
+        assert_eq!(pool.get_ltv_ratio(&symbol_short!("XLM")), 7_500_000);
+
+        // Delays shorter than the minimum are rejected
+        let args: Vec<Val> = Vec::new(&env);
+        assert_eq!(
+            timelock.try_queue(&pool.address, &symbol_short!("unpause"), &args, &(DELAY - 1)),
+            Err(Ok(TimelockError::InvalidDelay))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Auth, InvalidAction)")]
+    fn test_queue_requires_proposer() {
+        let env = Env::default();
+        let (timelock, pool, _proposer) = setup(&env);
+
+        timelock.queue(&pool.address, &symbol_short!("unpause"), &Vec::new(&env), &DELAY);
+    }
+}
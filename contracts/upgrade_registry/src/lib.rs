@@ -0,0 +1,345 @@
+#![no_std]
+
+//! # Stellend Upgrade Registry Contract
+//!
+//! Keeps an immutable, append-only audit trail of every deployment of the
+//! protocol's contracts (pool, oracle, interest rate model, ...).
+//!
+//! ## How It Works
+//!
+//! - The registry is bound to the lending pool at initialization
+//! - Only the pool's current admin can record an upgrade
+//! - Each upgrade is stored as a numbered `UpgradeEntry` that is never modified
+//! - The latest `new_address` per contract type is the current deployment
+//!
+//! ## Contract Types
+//!
+//! Contract types are free-form symbols, by convention:
+//! - `pool` - Lending pool
+//! - `oracle` - Price oracle
+//! - `irm` - Interest rate model
+
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Symbol,
+    Vec,
+};
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the upgrade registry
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Lending pool whose admin is authorized to record upgrades
+    Pool,
+    /// Number of upgrade entries recorded
+    EntryCount,
+    /// Upgrade entry by sequential ID (starting at 0)
+    UpgradeEntry(u32),
+    /// Entry IDs recorded for a contract type, in chronological order
+    History(Symbol),
+    /// Current deployment address for a contract type
+    CurrentAddress(Symbol),
+}
+
+/// A single recorded upgrade
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UpgradeEntry {
+    pub contract_type: Symbol,
+    pub old_address: Address,
+    pub new_address: Address,
+    pub upgraded_at: u64,
+    pub upgraded_by: Address,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the upgrade registry
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RegistryError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// New address must differ from old address
+    SameAddress = 3,
+    /// Old address does not match the current deployment
+    OldAddressMismatch = 4,
+    /// No deployment recorded for the contract type
+    NoDeployment = 5,
+    /// No upgrade entry with the given ID
+    EntryNotFound = 6,
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+/// Stellend Upgrade Registry Contract
+///
+/// Tracks all past and current contract deployments of the protocol.
+#[contract]
+pub struct UpgradeRegistry;
+
+#[contractimpl]
+impl UpgradeRegistry {
+    // ========================================================================
+    // INITIALIZATION
+    // ========================================================================
+
+    /// Initialize the registry
+    ///
+    /// # Arguments
+    /// * `pool` - Lending pool contract; its admin is authorized to record upgrades
+    pub fn initialize(env: Env, pool: Address) -> Result<(), RegistryError> {
+        if env.storage().instance().has(&DataKey::Pool) {
+            return Err(RegistryError::AlreadyInitialized);
+        }
+
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().set(&DataKey::EntryCount, &0u32);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // RECORDING (Pool Admin Only)
+    // ========================================================================
+
+    /// Record an upgrade of a protocol contract
+    ///
+    /// Only callable by the lending pool's admin. If a deployment is already
+    /// registered for `contract_type`, `old_addr` must match it.
+    ///
+    /// # Arguments
+    /// * `contract_type` - Contract type (e.g., `pool`, `oracle`, `irm`)
+    /// * `old_addr` - Address being replaced
+    /// * `new_addr` - Address of the new deployment
+    ///
+    /// # Returns
+    /// ID of the recorded entry
+    ///
+    /// # Events
+    /// Emits `("upgrade", contract_type)` with `(old_addr, new_addr)`
+    pub fn record_upgrade(
+        env: Env,
+        contract_type: Symbol,
+        old_addr: Address,
+        new_addr: Address,
+    ) -> Result<u32, RegistryError> {
+        let admin = Self::pool_admin(&env)?;
+        admin.require_auth();
+
+        if old_addr == new_addr {
+            return Err(RegistryError::SameAddress);
+        }
+        if let Some(current) = Self::get_current_address_internal(&env, &contract_type) {
+            if current != old_addr {
+                return Err(RegistryError::OldAddressMismatch);
+            }
+        }
+
+        let entry_id: u32 = env.storage().instance().get(&DataKey::EntryCount).unwrap_or(0);
+        let entry = UpgradeEntry {
+            contract_type: contract_type.clone(),
+            old_address: old_addr.clone(),
+            new_address: new_addr.clone(),
+            upgraded_at: env.ledger().timestamp(),
+            upgraded_by: admin,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::UpgradeEntry(entry_id), &entry);
+
+        let mut history: Vec<u32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::History(contract_type.clone()))
+            .unwrap_or(Vec::new(&env));
+        history.push_back(entry_id);
+        env.storage()
+            .persistent()
+            .set(&DataKey::History(contract_type.clone()), &history);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::CurrentAddress(contract_type.clone()), &new_addr);
+        env.storage()
+            .instance()
+            .set(&DataKey::EntryCount, &(entry_id + 1));
+
+        env.events()
+            .publish((symbol_short!("upgrade"), contract_type), (old_addr, new_addr));
+
+        Ok(entry_id)
+    }
+
+    // ========================================================================
+    // QUERIES (Public)
+    // ========================================================================
+
+    /// Get all upgrades recorded for a contract type, oldest first
+    pub fn get_upgrade_history(env: Env, contract_type: Symbol) -> Vec<UpgradeEntry> {
+        let ids: Vec<u32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::History(contract_type))
+            .unwrap_or(Vec::new(&env));
+
+        let mut history = Vec::new(&env);
+        for id in ids.iter() {
+            let entry: UpgradeEntry = env
+                .storage()
+                .persistent()
+                .get(&DataKey::UpgradeEntry(id))
+                .unwrap();
+            history.push_back(entry);
+        }
+        history
+    }
+
+    /// Get the current deployment address for a contract type
+    ///
+    /// # Errors
+    /// `NoDeployment` if no upgrade has been recorded for `contract_type`
+    pub fn get_current_address(env: Env, contract_type: Symbol) -> Result<Address, RegistryError> {
+        Self::get_current_address_internal(&env, &contract_type).ok_or(RegistryError::NoDeployment)
+    }
+
+    /// Get a single upgrade entry by ID
+    pub fn get_upgrade_entry(env: Env, entry_id: u32) -> Result<UpgradeEntry, RegistryError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UpgradeEntry(entry_id))
+            .ok_or(RegistryError::EntryNotFound)
+    }
+
+    /// Get the total number of upgrades recorded
+    pub fn get_entry_count(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::EntryCount).unwrap_or(0)
+    }
+
+    /// Get the lending pool this registry is bound to
+    pub fn get_pool(env: Env) -> Result<Address, RegistryError> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Pool)
+            .ok_or(RegistryError::NotInitialized)
+    }
+
+    // ========================================================================
+    // INTERNAL HELPERS
+    // ========================================================================
+
+    /// Fetch the lending pool's current admin
+    fn pool_admin(env: &Env) -> Result<Address, RegistryError> {
+        let pool = Self::get_pool(env.clone())?;
+        Ok(env.invoke_contract(&pool, &Symbol::new(env, "get_admin"), Vec::new(env)))
+    }
+
+    fn get_current_address_internal(env: &Env, contract_type: &Symbol) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::CurrentAddress(contract_type.clone()))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        Env,
+    };
+    use apogee_testutils::{create_native_xlm, create_token};
+    use stellend_pool::{LendingPool, LendingPoolClient};
+
+    fn setup(env: &Env) -> (UpgradeRegistryClient<'_>, Address) {
+        let admin = Address::generate(env);
+        let pool_id = env.register_contract(None, LendingPool);
+        LendingPoolClient::new(env, &pool_id).initialize(
+            &admin,
+            &Address::generate(env),
+            &Address::generate(env),
+            &create_native_xlm(env, &admin).0.address,
+            &create_token(env, &admin).0.address,
+        );
+
+        let contract_id = env.register_contract(None, UpgradeRegistry);
+        let client = UpgradeRegistryClient::new(env, &contract_id);
+        client.initialize(&pool_id);
+        (client, admin)
+    }
+
+    #[test]
+    fn test_record_upgrades_in_order() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin) = setup(&env);
+
+        let oracle_v1 = Address::generate(&env);
+        let oracle_v2 = Address::generate(&env);
+        let oracle_v3 = Address::generate(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.record_upgrade(&symbol_short!("oracle"), &oracle_v1, &oracle_v2);
+        env.ledger().with_mut(|li| li.timestamp = 2_000);
+        client.record_upgrade(&symbol_short!("oracle"), &oracle_v2, &oracle_v3);
+
+        let history = client.get_upgrade_history(&symbol_short!("oracle"));
+        assert_eq!(history.len(), 2);
+
+        let first = history.get(0).unwrap();
+        assert_eq!(first.old_address, oracle_v1);
+        assert_eq!(first.new_address, oracle_v2);
+        assert_eq!(first.upgraded_at, 1_000);
+        assert_eq!(first.upgraded_by, admin);
+
+        let second = history.get(1).unwrap();
+        assert_eq!(second.old_address, oracle_v2);
+        assert_eq!(second.new_address, oracle_v3);
+        assert_eq!(second.upgraded_at, 2_000);
+
+        assert_eq!(client.get_current_address(&symbol_short!("oracle")), oracle_v3);
+        assert_eq!(client.get_entry_count(), 2);
+        assert_eq!(client.get_upgrade_history(&symbol_short!("pool")).len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #4)")]
+    fn test_record_upgrade_with_stale_old_address() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, _admin) = setup(&env);
+
+        let v1 = Address::generate(&env);
+        let v2 = Address::generate(&env);
+        client.record_upgrade(&symbol_short!("irm"), &v1, &v2);
+        client.record_upgrade(&symbol_short!("irm"), &v1, &Address::generate(&env));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_record_upgrade_requires_pool_admin() {
+        let env = Env::default();
+        let (client, _admin) = setup(&env);
+
+        client.record_upgrade(
+            &symbol_short!("pool"),
+            &Address::generate(&env),
+            &Address::generate(&env),
+        );
+    }
+}
@@ -0,0 +1,457 @@
+#![no_std]
+
+//! # Stellend Vault Adapter Contract
+//!
+//! Exposes the supply side of one pool reserve through the ERC-4626 vault
+//! interface (`deposit` / `mint` / `withdraw` / `redeem`,
+//! `convert_to_shares` / `convert_to_assets`), so yield aggregators can hold
+//! pool deposits without integrating the pool itself.
+//!
+//! ## How It Works
+//!
+//! - Deposits are supplied to the pool on the vault's behalf, paid straight
+//!   from the depositor's wallet; the pool shares minted to the vault are
+//!   credited to the receiver one for one
+//! - Vault shares are pool shares, so they grow with the pool's exchange
+//!   rate and convert at `get_exchange_rate_with_interest`
+//! - Withdrawals redeem the vault's pool shares and forward the underlying
+//!   the pool pays out to the receiver
+//!
+//! Assets are counted in the pool's units (7 decimals) whatever the token's
+//! own decimals; the pool converts at the transfer. `mint` and `withdraw`
+//! gross amounts up for the reserve's deposit and withdrawal fees.
+//! One vault is deployed per reserve.
+
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token,
+    Address, Env, Symbol,
+};
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding};
+
+use apogee_common::{ReserveConfig, BUMP_AMOUNT, EXCHANGE_RATE_SCALE, LIFETIME_THRESHOLD, SCALE};
+
+// ============================================================================
+// POOL INTERFACE
+// ============================================================================
+
+/// Lending pool functions used by the vault
+#[contractclient(name = "PoolClient")]
+pub trait Pool {
+    fn supply(
+        env: Env,
+        from: Address,
+        on_behalf_of: Address,
+        asset: Symbol,
+        amount: i128,
+        referral_code: Option<u32>,
+    ) -> i128;
+    fn withdraw(
+        env: Env,
+        user: Address,
+        asset: Symbol,
+        share_amount: i128,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> i128;
+    fn get_reserve(env: Env, asset: Symbol) -> ReserveConfig;
+    fn get_exchange_rate_with_interest(env: Env, asset: Symbol) -> i128;
+    fn get_deposit_fee(env: Env, asset: Symbol) -> i128;
+    fn get_withdraw_fee(env: Env, asset: Symbol) -> i128;
+}
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+/// Storage keys for the vault
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    /// Lending pool holding the deposits
+    Pool,
+    /// Reserve asset symbol in the pool
+    Asset,
+    /// Underlying token contract of the reserve
+    Token,
+    /// Total vault shares outstanding
+    TotalShares,
+    /// Vault shares held by an address
+    Shares(Address),
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Errors returned by the vault
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum VaultError {
+    /// Contract has already been initialized
+    AlreadyInitialized = 1,
+    /// Contract has not been initialized
+    NotInitialized = 2,
+    /// Amount must be positive
+    InvalidAmount = 3,
+    /// Owner does not hold enough vault shares
+    InsufficientShares = 4,
+    /// Arithmetic overflow or division by zero
+    MathOverflow = 5,
+}
+
+impl From<FixedPointError> for VaultError {
+    fn from(_: FixedPointError) -> Self {
+        VaultError::MathOverflow
+    }
+}
+
+// ============================================================================
+// CONTRACT
+// ============================================================================
+
+#[contract]
+pub struct Vault;
+
+#[contractimpl]
+impl Vault {
+    /// Initialize the vault over one pool reserve
+    ///
+    /// # Arguments
+    /// * `pool` - Lending pool holding the deposits
+    /// * `asset` - Reserve asset symbol in the pool (e.g., USDC)
+    pub fn initialize(env: Env, pool: Address, asset: Symbol) -> Result<(), VaultError> {
+        if env.storage().instance().has(&DataKey::Pool) {
+            return Err(VaultError::AlreadyInitialized);
+        }
+
+        let reserve = PoolClient::new(&env, &pool).get_reserve(&asset);
+        env.storage().instance().set(&DataKey::Pool, &pool);
+        env.storage().instance().set(&DataKey::Asset, &asset);
+        env.storage().instance().set(&DataKey::Token, &reserve.token);
+        env.storage().instance().extend_ttl(LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // DEPOSITS AND WITHDRAWALS
+    // ========================================================================
+
+    /// Deposit `assets` from `from`'s wallet and credit the shares to `receiver`
+    ///
+    /// # Returns
+    /// Vault shares minted
+    pub fn deposit(env: Env, from: Address, assets: i128, receiver: Address) -> Result<i128, VaultError> {
+        from.require_auth();
+
+        if assets <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::deposit_internal(&env, &from, assets, &receiver)
+    }
+
+    /// Mint `shares` to `receiver`, paying what they cost from `from`'s wallet
+    ///
+    /// # Returns
+    /// Assets paid, deposit fee included
+    pub fn mint(env: Env, from: Address, shares: i128, receiver: Address) -> Result<i128, VaultError> {
+        from.require_auth();
+
+        if shares <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        let (pool, asset) = Self::pool(&env)?;
+        let net = mul_div(shares, pool.get_exchange_rate_with_interest(&asset), EXCHANGE_RATE_SCALE, Rounding::Up)?;
+        let assets = Self::gross_up(net, pool.get_deposit_fee(&asset))?;
+
+        Self::deposit_internal(&env, &from, assets, &receiver)?;
+        Ok(assets)
+    }
+
+    /// Burn `owner`'s shares so `receiver` gets exactly `assets`
+    ///
+    /// # Returns
+    /// Vault shares burned
+    pub fn withdraw(env: Env, owner: Address, assets: i128, receiver: Address) -> Result<i128, VaultError> {
+        owner.require_auth();
+
+        if assets <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        let (pool, asset) = Self::pool(&env)?;
+        let gross = Self::gross_up(assets, pool.get_withdraw_fee(&asset))?;
+        let shares = mul_div(gross, EXCHANGE_RATE_SCALE, pool.get_exchange_rate_with_interest(&asset), Rounding::Up)?;
+
+        Self::redeem_internal(&env, &owner, shares, assets, &receiver)?;
+        Ok(shares)
+    }
+
+    /// Burn `shares` of `owner`'s and send what they are worth to `receiver`
+    ///
+    /// # Returns
+    /// Assets paid out, net of the withdrawal fee
+    pub fn redeem(env: Env, owner: Address, shares: i128, receiver: Address) -> Result<i128, VaultError> {
+        owner.require_auth();
+
+        if shares <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        Self::redeem_internal(&env, &owner, shares, 0, &receiver)
+    }
+
+    // ========================================================================
+    // VIEW FUNCTIONS
+    // ========================================================================
+
+    /// Shares `assets` converts to at the current exchange rate, before fees
+    pub fn convert_to_shares(env: Env, assets: i128) -> Result<i128, VaultError> {
+        let (pool, asset) = Self::pool(&env)?;
+        Ok(mul_div(assets, EXCHANGE_RATE_SCALE, pool.get_exchange_rate_with_interest(&asset), Rounding::Down)?)
+    }
+
+    /// Assets `shares` converts to at the current exchange rate, before fees
+    pub fn convert_to_assets(env: Env, shares: i128) -> Result<i128, VaultError> {
+        let (pool, asset) = Self::pool(&env)?;
+        Ok(mul_div(shares, pool.get_exchange_rate_with_interest(&asset), EXCHANGE_RATE_SCALE, Rounding::Down)?)
+    }
+
+    /// Assets all vault shares are worth, interest included
+    pub fn total_assets(env: Env) -> Result<i128, VaultError> {
+        Self::convert_to_assets(env.clone(), Self::total_supply(env))
+    }
+
+    /// Total vault shares outstanding
+    pub fn total_supply(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::TotalShares).unwrap_or(0)
+    }
+
+    /// Vault shares held by `id`
+    pub fn balance(env: Env, id: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::Shares(id)).unwrap_or(0)
+    }
+
+    /// Underlying token of the vault's reserve
+    pub fn asset(env: Env) -> Result<Address, VaultError> {
+        env.storage().instance().get(&DataKey::Token).ok_or(VaultError::NotInitialized)
+    }
+
+    /// Lending pool holding the deposits
+    pub fn get_pool(env: Env) -> Result<Address, VaultError> {
+        env.storage().instance().get(&DataKey::Pool).ok_or(VaultError::NotInitialized)
+    }
+
+    // ========================================================================
+    // INTERNAL FUNCTIONS
+    // ========================================================================
+
+    /// Internal: Supply `assets` paid by `from` and credit the pool shares
+    fn deposit_internal(env: &Env, from: &Address, assets: i128, receiver: &Address) -> Result<i128, VaultError> {
+        let (pool, asset) = Self::pool(env)?;
+        let shares = pool.supply(from, &env.current_contract_address(), &asset, &assets, &None);
+
+        env.storage().instance().set(&DataKey::TotalShares, &(Self::total_supply(env.clone()) + shares));
+        Self::set_shares(env, receiver, Self::balance(env.clone(), receiver.clone()) + shares);
+
+        env.events()
+            .publish((symbol_short!("deposit"), from.clone(), receiver.clone()), (assets, shares));
+
+        Ok(shares)
+    }
+
+    /// Internal: Redeem `shares` of `owner`'s from the pool and forward the
+    /// underlying to `receiver`
+    fn redeem_internal(
+        env: &Env,
+        owner: &Address,
+        shares: i128,
+        min_out: i128,
+        receiver: &Address,
+    ) -> Result<i128, VaultError> {
+        let balance = Self::balance(env.clone(), owner.clone());
+        if balance < shares {
+            return Err(VaultError::InsufficientShares);
+        }
+        env.storage().instance().set(&DataKey::TotalShares, &(Self::total_supply(env.clone()) - shares));
+        Self::set_shares(env, owner, balance - shares);
+
+        // The pool pays out in token units; forward exactly what arrived
+        let (pool, asset) = Self::pool(env)?;
+        let vault = env.current_contract_address();
+        let token = token::Client::new(env, &Self::asset(env.clone())?);
+        let before = token.balance(&vault);
+        let assets = pool.withdraw(&vault, &asset, &shares, &min_out, &None);
+        let received = token.balance(&vault) - before;
+        if received > 0 {
+            token.transfer(&vault, receiver, &received);
+        }
+
+        env.events()
+            .publish((symbol_short!("withdraw"), owner.clone(), receiver.clone()), (assets, shares));
+
+        Ok(assets)
+    }
+
+    /// Internal: Amount that leaves `net` after a fee of `fee` (scaled by SCALE)
+    fn gross_up(net: i128, fee: i128) -> Result<i128, VaultError> {
+        if fee <= 0 {
+            return Ok(net);
+        }
+        Ok(mul_div(net, SCALE, SCALE - fee, Rounding::Up)?)
+    }
+
+    /// Internal: Pool client and reserve asset
+    fn pool(env: &Env) -> Result<(PoolClient<'_>, Symbol), VaultError> {
+        let asset = env.storage().instance().get(&DataKey::Asset).ok_or(VaultError::NotInitialized)?;
+        Ok((PoolClient::new(env, &Self::get_pool(env.clone())?), asset))
+    }
+
+    /// Internal: Store a holder's shares and extend their TTL
+    fn set_shares(env: &Env, id: &Address, shares: i128) {
+        let key = DataKey::Shares(id.clone());
+        env.storage().persistent().set(&key, &shares);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_testutils::{create_token, fund};
+    use soroban_sdk::{symbol_short, testutils::Address as _};
+
+    /// Pool supplying and redeeming at a settable exchange rate, with an
+    /// optional deposit fee
+    #[contract]
+    struct MockPool;
+
+    #[contractimpl]
+    impl MockPool {
+        pub fn setup(env: Env, token: Address, exchange_rate: i128, deposit_fee: i128) {
+            env.storage().instance().set(&symbol_short!("token"), &token);
+            env.storage().instance().set(&symbol_short!("rate"), &exchange_rate);
+            env.storage().instance().set(&symbol_short!("fee"), &deposit_fee);
+        }
+
+        pub fn supply(
+            env: Env,
+            from: Address,
+            on_behalf_of: Address,
+            _asset: Symbol,
+            amount: i128,
+            _referral_code: Option<u32>,
+        ) -> i128 {
+            from.require_auth();
+            let token: Address = env.storage().instance().get(&symbol_short!("token")).unwrap();
+            token::Client::new(&env, &token).transfer(&from, &env.current_contract_address(), &amount);
+            let fee = amount * Self::get_deposit_fee(env.clone(), _asset.clone()) / SCALE;
+            let shares = (amount - fee) * EXCHANGE_RATE_SCALE / Self::get_exchange_rate_with_interest(env.clone(), _asset);
+            let held: i128 = env.storage().instance().get(&on_behalf_of).unwrap_or(0);
+            env.storage().instance().set(&on_behalf_of, &(held + shares));
+            shares
+        }
+
+        pub fn withdraw(
+            env: Env,
+            user: Address,
+            asset: Symbol,
+            share_amount: i128,
+            min_out: i128,
+            _deadline: Option<u64>,
+        ) -> i128 {
+            user.require_auth();
+            let held: i128 = env.storage().instance().get(&user).unwrap_or(0);
+            assert!(held >= share_amount);
+            env.storage().instance().set(&user, &(held - share_amount));
+            let amount = share_amount * Self::get_exchange_rate_with_interest(env.clone(), asset) / EXCHANGE_RATE_SCALE;
+            assert!(amount >= min_out);
+            let token: Address = env.storage().instance().get(&symbol_short!("token")).unwrap();
+            token::Client::new(&env, &token).transfer(&env.current_contract_address(), &user, &amount);
+            amount
+        }
+
+        pub fn get_reserve(env: Env, _asset: Symbol) -> ReserveConfig {
+            ReserveConfig {
+                token: env.storage().instance().get(&symbol_short!("token")).unwrap(),
+                decimals: 7,
+                ltv: 0,
+                liquidation_threshold: 0,
+                collateral_enabled: false,
+                borrow_enabled: false,
+                interest_rate_model: env.current_contract_address(),
+            }
+        }
+
+        pub fn get_exchange_rate_with_interest(env: Env, _asset: Symbol) -> i128 {
+            env.storage().instance().get(&symbol_short!("rate")).unwrap()
+        }
+
+        pub fn get_deposit_fee(env: Env, _asset: Symbol) -> i128 {
+            env.storage().instance().get(&symbol_short!("fee")).unwrap()
+        }
+
+        pub fn get_withdraw_fee(_env: Env, _asset: Symbol) -> i128 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_vault_shares_track_pool_exchange_rate() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        let (token, _) = create_token(&env, &admin);
+        fund(&env, &token.address, &[&alice, &bob], 10_000);
+
+        let pool_id = env.register_contract(None, MockPool);
+        let pool = MockPoolClient::new(&env, &pool_id);
+        pool.setup(&token.address, &EXCHANGE_RATE_SCALE, &0);
+        fund(&env, &token.address, &[&pool_id], 10_000);
+        let vault = VaultClient::new(&env, &env.register_contract(None, Vault));
+        vault.initialize(&pool_id, &symbol_short!("USDC"));
+        assert_eq!(vault.asset(), token.address);
+
+        assert_eq!(vault.deposit(&alice, &1_000, &alice), 1_000);
+        assert_eq!(vault.balance(&alice), 1_000);
+
+        // Interest lifts the pool's exchange rate, and with it every share
+        pool.setup(&token.address, &(EXCHANGE_RATE_SCALE * 3 / 2), &0);
+        assert_eq!(vault.convert_to_assets(&1_000), 1_500);
+        assert_eq!(vault.convert_to_shares(&1_500), 1_000);
+        assert_eq!(vault.total_assets(), 1_500);
+
+        // Bob mints 300 shares for 450
+        assert_eq!(vault.mint(&bob, &300, &bob), 450);
+        assert_eq!(token.balance(&bob), 9_550);
+        assert_eq!(vault.total_supply(), 1_300);
+
+        // Alice takes out exactly 150 for 100 shares, then redeems the rest
+        assert_eq!(vault.withdraw(&alice, &150, &alice), 100);
+        assert_eq!(vault.redeem(&alice, &900, &alice), 1_350);
+        assert_eq!(token.balance(&alice), 10_500);
+        assert_eq!(vault.balance(&alice), 0);
+        assert_eq!(token.balance(&vault.address), 0);
+        assert_eq!(vault.try_redeem(&alice, &1, &alice), Err(Ok(VaultError::InsufficientShares)));
+    }
+
+    #[test]
+    fn test_mint_covers_deposit_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let alice = Address::generate(&env);
+        let (token, _) = create_token(&env, &admin);
+        fund(&env, &token.address, &[&alice], 10_000);
+
+        let pool_id = env.register_contract(None, MockPool);
+        // 1% deposit fee
+        MockPoolClient::new(&env, &pool_id).setup(&token.address, &EXCHANGE_RATE_SCALE, &100_000);
+        let vault = VaultClient::new(&env, &env.register_contract(None, Vault));
+        vault.initialize(&pool_id, &symbol_short!("USDC"));
+
+        assert_eq!(vault.mint(&alice, &990, &alice), 1_000);
+        assert_eq!(vault.balance(&alice), 990);
+    }
+}
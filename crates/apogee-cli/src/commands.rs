@@ -0,0 +1,292 @@
+//! Command implementations
+
+use std::path::PathBuf;
+
+use apogee_sdk::{Invoker, OracleClient, PoolClient, RateModelClient, SdkError, Transport};
+use sha2::{Digest, Sha256};
+
+use crate::{format_amount, parse_amount, CliError, Command, Deployment};
+
+/// Oracle prices and pool USD values are scaled by 1e7
+const USD_DECIMALS: u32 = 7;
+
+/// Every contract reports a second `initialize` as error #1
+const ALREADY_INITIALIZED: u32 = 1;
+
+/// Contracts `deploy` creates, in dependency order
+const CONTRACTS: [(&str, &str); 3] = [
+    ("oracle", "stellend_price_oracle.wasm"),
+    ("interestRateModel", "stellend_interest_rate_model.wasm"),
+    ("pool", "stellend_pool.wasm"),
+];
+
+/// The network the commands run against
+pub struct Context<'a, T: Transport> {
+    pub invoker: Invoker<'a, T>,
+    pub deployment: Deployment,
+    /// `deploy` writes the new contract ids here
+    pub deployment_path: PathBuf,
+}
+
+impl<T: Transport> Context<'_, T> {
+    fn pool(&self) -> PoolClient<'_, T> {
+        PoolClient::new(&self.invoker, &self.deployment.contracts.pool)
+    }
+
+    fn oracle(&self) -> OracleClient<'_, T> {
+        OracleClient::new(&self.invoker, &self.deployment.contracts.oracle)
+    }
+
+    fn rate_model(&self) -> RateModelClient<'_, T> {
+        RateModelClient::new(
+            &self.invoker,
+            &self.deployment.contracts.interest_rate_model,
+        )
+    }
+
+    /// Parses an amount in the reserve's token units
+    fn amount(&self, asset: &str, amount: &str) -> Result<i128, CliError> {
+        parse_amount(amount, self.pool().get_reserve(asset)?.decimals)
+    }
+}
+
+/// Runs a command, printing its results to stdout
+pub fn run<T: Transport>(context: &mut Context<'_, T>, command: &Command) -> Result<(), CliError> {
+    match command {
+        Command::Deploy { wasm_dir } => deploy(context, wasm_dir),
+        Command::Initialize => initialize(context),
+        Command::SetPrice { asset, price } => {
+            let token = context.deployment.token(asset)?;
+            let price = parse_amount(price, USD_DECIMALS)?;
+            context.oracle().set_price(&token, price)?;
+            println!("{asset} = ${}", format_amount(price, USD_DECIMALS));
+            Ok(())
+        }
+        Command::Deposit {
+            asset,
+            amount,
+            collateral,
+        } => {
+            let pool = context.pool();
+            let decimals = pool.get_reserve(asset)?.decimals;
+            let amount = parse_amount(amount, decimals)?;
+            if *collateral {
+                let balance = pool.deposit_collateral(asset, amount)?;
+                println!("collateral: {} {asset}", format_amount(balance, decimals));
+            } else {
+                let shares = pool.supply(asset, amount)?;
+                println!(
+                    "supplied {} {asset} for {shares} shares",
+                    format_amount(amount, decimals)
+                );
+            }
+            Ok(())
+        }
+        Command::Borrow { asset, amount } => {
+            let pool = context.pool();
+            let decimals = pool.get_reserve(asset)?.decimals;
+            let debt = pool.borrow(asset, parse_amount(amount, decimals)?)?;
+            println!("debt: {} {asset}", format_amount(debt, decimals));
+            Ok(())
+        }
+        Command::Liquidate {
+            borrower,
+            repay_asset,
+            amount,
+            collateral_asset,
+        } => {
+            let repay_amount = context.amount(repay_asset, amount)?;
+            let pool = context.pool();
+            let seized =
+                pool.liquidate(borrower, repay_asset, repay_amount, collateral_asset, 0)?;
+            let decimals = pool.get_reserve(collateral_asset)?.decimals;
+            println!(
+                "seized {} {collateral_asset}",
+                format_amount(seized, decimals)
+            );
+            Ok(())
+        }
+        Command::Account { address } => account(context, address),
+        Command::Params { asset } => params(context, asset.as_deref()),
+    }
+}
+
+/// Uploads and instantiates each contract, then records the ids
+///
+/// Token ids already in the deployment file are kept; `initialize` needs
+/// them.
+fn deploy<T: Transport>(
+    context: &mut Context<'_, T>,
+    wasm_dir: &std::path::Path,
+) -> Result<(), CliError> {
+    let deployer = context.invoker.source().account_id();
+    for (name, file) in CONTRACTS {
+        let path = wasm_dir.join(file);
+        let wasm = std::fs::read(&path).map_err(|e| {
+            CliError::Config(format!(
+                "{}: {e} (build with `cargo build --target wasm32-unknown-unknown --release`)",
+                path.display()
+            ))
+        })?;
+        let hash = context.invoker.upload_wasm(&wasm)?;
+
+        let salt = Sha256::new()
+            .chain_update(&deployer)
+            .chain_update(name)
+            .chain_update(
+                chrono::Utc::now()
+                    .timestamp_nanos_opt()
+                    .unwrap_or(0)
+                    .to_be_bytes(),
+            )
+            .finalize()
+            .into();
+        let id = context.invoker.create_contract(hash, salt)?;
+        println!("{name}: {id}");
+
+        let contracts = &mut context.deployment.contracts;
+        match name {
+            "oracle" => contracts.oracle = id,
+            "interestRateModel" => contracts.interest_rate_model = id,
+            _ => contracts.pool = id,
+        }
+    }
+
+    context.deployment.timestamp =
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    context
+        .deployment
+        .accounts
+        .insert("deployer".to_string(), deployer);
+    context.deployment.save(&context.deployment_path)?;
+    println!("saved {}", context.deployment_path.display());
+    Ok(())
+}
+
+/// Initializes the oracle, rate model and pool with the signer as admin;
+/// contracts that are already initialized are skipped
+fn initialize<T: Transport>(context: &Context<'_, T>) -> Result<(), CliError> {
+    let xlm = context.deployment.token("xlm")?;
+    let usdc = context.deployment.token("usdc")?;
+    let contracts = &context.deployment.contracts;
+
+    report_init("oracle", context.oracle().initialize(&xlm, &usdc))?;
+    report_init(
+        "interest rate model",
+        context.rate_model().initialize_default(),
+    )?;
+    report_init(
+        "pool",
+        context.pool().initialize(
+            &contracts.oracle,
+            &contracts.interest_rate_model,
+            &xlm,
+            &usdc,
+        ),
+    )
+}
+
+fn report_init(name: &str, result: Result<(), SdkError>) -> Result<(), CliError> {
+    match result {
+        Ok(()) => println!("{name}: initialized"),
+        Err(SdkError::Contract(ALREADY_INITIALIZED)) => println!("{name}: already initialized"),
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+fn account<T: Transport>(context: &Context<'_, T>, address: &str) -> Result<(), CliError> {
+    let pool = context.pool();
+    let data = pool.get_account_data(address)?;
+    let usd = |v| format_amount(v, USD_DECIMALS);
+
+    println!("collateral    ${}", usd(data.total_collateral_usd));
+    println!("debt          ${}", usd(data.total_debt_usd));
+    println!("can borrow    ${}", usd(data.available_borrow_usd));
+    println!("ltv           {}%", percent(data.current_ltv));
+    println!("max ltv       {}%", percent(data.max_ltv));
+    println!("liq threshold {}%", percent(data.liquidation_threshold));
+    if data.total_debt_usd == 0 {
+        println!("health factor - (no debt)");
+    } else {
+        println!("health factor {}", usd(data.health_factor));
+    }
+
+    let collaterals = pool.get_user_collaterals(address)?;
+    for asset in pool.list_reserves()? {
+        let decimals = pool.get_reserve(&asset)?.decimals;
+        let collateral = collaterals.get(&asset).copied().unwrap_or(0);
+        let supplied = pool.get_user_deposit(address, &asset)?;
+        let debt = pool.get_user_debt(address, &asset)?;
+        if collateral != 0 || supplied != 0 || debt != 0 {
+            println!(
+                "{asset:<6} collateral {}  supplied {}  debt {}",
+                format_amount(collateral, decimals),
+                format_amount(supplied, decimals),
+                format_amount(debt, decimals)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn params<T: Transport>(context: &Context<'_, T>, asset: Option<&str>) -> Result<(), CliError> {
+    let pool = context.pool();
+    let assets = match asset {
+        Some(asset) => vec![asset.to_string()],
+        None => pool.list_reserves()?,
+    };
+
+    println!("close factor  {}%", percent(pool.get_close_factor()?));
+    let curve = context.rate_model().get_config()?;
+    println!(
+        "rate model    {}% -> {}% at {}% utilization -> {}%",
+        percent(curve.rate_min),
+        percent(curve.rate_opt),
+        percent(curve.optimal_utilization),
+        percent(curve.rate_max)
+    );
+
+    for asset in assets {
+        let reserve = pool.get_reserve(&asset)?;
+        let market = pool.get_market_info(&asset)?;
+        println!();
+        println!("{asset}  {}", reserve.token);
+        println!(
+            "  ltv {}%  liquidation threshold {}%  bonus {}%",
+            percent(reserve.ltv),
+            percent(reserve.liquidation_threshold),
+            percent(pool.get_liquidation_bonus(&asset)?)
+        );
+        println!(
+            "  collateral {}  borrowing {}",
+            enabled(reserve.collateral_enabled),
+            enabled(reserve.borrow_enabled)
+        );
+        println!(
+            "  supplied {}  borrowed {}  utilization {}%",
+            format_amount(market.total_supply, reserve.decimals),
+            format_amount(market.total_borrow, reserve.decimals),
+            percent(market.utilization_rate)
+        );
+        println!(
+            "  borrow apr {}%  supply apr {}%",
+            percent(market.borrow_rate),
+            percent(market.supply_rate)
+        );
+    }
+    Ok(())
+}
+
+/// SCALE (1e7) ratio as a percentage
+fn percent(ratio: i128) -> String {
+    format_amount(ratio * 100, USD_DECIMALS)
+}
+
+fn enabled(flag: bool) -> &'static str {
+    if flag {
+        "enabled"
+    } else {
+        "disabled"
+    }
+}
@@ -0,0 +1,445 @@
+//! # Stellend CLI
+//!
+//! Command-line replacement for the deployment and admin scripts in
+//! `scripts/`, built on `apogee-sdk`.
+//!
+//! ```text
+//! apogee-cli [--config PATH] [--network NAME] <command>
+//!
+//! deploy [--wasm-dir DIR]        upload and instantiate oracle, rate model, pool
+//! initialize                     initialize the deployed contracts
+//! set-price <ASSET> <PRICE>      set a USD price, e.g. `set-price XLM 0.12`
+//! deposit <ASSET> <AMOUNT> [--collateral]
+//! borrow <ASSET> <AMOUNT>
+//! liquidate <BORROWER> <REPAY_ASSET> <AMOUNT> <COLLATERAL_ASSET>
+//! account <ADDRESS>              positions and health of an account
+//! params [ASSET]                 reserve and risk parameters
+//! ```
+//!
+//! Networks are defined in a JSON config (see `apogee.example.json`): each
+//! names an RPC endpoint, passphrase and a deployment file in the
+//! `scripts/deployment.json` format holding the contract and token ids.
+//! The config is read from `--config`, `APOGEE_CONFIG` or `./apogee.json`.
+//! Commands that submit transactions sign with `APOGEE_SECRET` (fees
+//! optionally paid by `FEE_BUMP_SECRET`); `account` and `params` only
+//! simulate and need no key.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use apogee_sdk::SdkError;
+use serde::{Deserialize, Serialize};
+
+mod commands;
+
+pub use commands::{run, Context};
+
+pub const USAGE: &str = "\
+usage: apogee-cli [--config PATH] [--network NAME] <command>
+
+commands:
+  deploy [--wasm-dir DIR]
+  initialize
+  set-price <ASSET> <PRICE>
+  deposit <ASSET> <AMOUNT> [--collateral]
+  borrow <ASSET> <AMOUNT>
+  liquidate <BORROWER> <REPAY_ASSET> <AMOUNT> <COLLATERAL_ASSET>
+  account <ADDRESS>
+  params [ASSET]
+
+environment: APOGEE_CONFIG, APOGEE_SECRET, FEE_BUMP_SECRET";
+
+/// Where `deploy` looks for contract builds by default
+pub const DEFAULT_WASM_DIR: &str = "contracts/target/wasm32-unknown-unknown/release";
+
+/// Parsed command line
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Args {
+    pub config: Option<PathBuf>,
+    pub network: Option<String>,
+    pub command: Command,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    Deploy {
+        wasm_dir: PathBuf,
+    },
+    Initialize,
+    SetPrice {
+        asset: String,
+        price: String,
+    },
+    Deposit {
+        asset: String,
+        amount: String,
+        collateral: bool,
+    },
+    Borrow {
+        asset: String,
+        amount: String,
+    },
+    Liquidate {
+        borrower: String,
+        repay_asset: String,
+        amount: String,
+        collateral_asset: String,
+    },
+    Account {
+        address: String,
+    },
+    Params {
+        asset: Option<String>,
+    },
+}
+
+impl Command {
+    /// Whether the command submits transactions and so needs `APOGEE_SECRET`
+    pub fn signs(&self) -> bool {
+        !matches!(self, Command::Account { .. } | Command::Params { .. })
+    }
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Args, CliError> {
+    let mut config = None;
+    let mut network = None;
+    let mut wasm_dir = None;
+    let mut collateral = false;
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("{flag} needs a value")))
+        };
+        match arg.as_str() {
+            "--config" => config = Some(PathBuf::from(value("--config")?)),
+            "--network" => network = Some(value("--network")?),
+            "--wasm-dir" => wasm_dir = Some(PathBuf::from(value("--wasm-dir")?)),
+            "--collateral" => collateral = true,
+            "-h" | "--help" => return Err(CliError::Usage(String::new())),
+            flag if flag.starts_with("--") => {
+                return Err(CliError::Usage(format!("unknown option {flag}")))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let Some((name, rest)) = positional.split_first() else {
+        return Err(CliError::Usage(String::new()));
+    };
+    let arity = |n: usize| {
+        if rest.len() == n {
+            Ok(())
+        } else {
+            Err(CliError::Usage(format!(
+                "{name} takes {n} argument(s), got {}",
+                rest.len()
+            )))
+        }
+    };
+    let command = match name.as_str() {
+        "deploy" => {
+            arity(0)?;
+            Command::Deploy {
+                wasm_dir: wasm_dir.take().unwrap_or_else(|| DEFAULT_WASM_DIR.into()),
+            }
+        }
+        "initialize" => {
+            arity(0)?;
+            Command::Initialize
+        }
+        "set-price" => {
+            arity(2)?;
+            Command::SetPrice {
+                asset: rest[0].clone(),
+                price: rest[1].clone(),
+            }
+        }
+        "deposit" => {
+            arity(2)?;
+            Command::Deposit {
+                asset: rest[0].clone(),
+                amount: rest[1].clone(),
+                collateral: std::mem::take(&mut collateral),
+            }
+        }
+        "borrow" => {
+            arity(2)?;
+            Command::Borrow {
+                asset: rest[0].clone(),
+                amount: rest[1].clone(),
+            }
+        }
+        "liquidate" => {
+            arity(4)?;
+            Command::Liquidate {
+                borrower: rest[0].clone(),
+                repay_asset: rest[1].clone(),
+                amount: rest[2].clone(),
+                collateral_asset: rest[3].clone(),
+            }
+        }
+        "account" => {
+            arity(1)?;
+            Command::Account {
+                address: rest[0].clone(),
+            }
+        }
+        "params" => {
+            if rest.len() > 1 {
+                arity(1)?;
+            }
+            Command::Params {
+                asset: rest.first().cloned(),
+            }
+        }
+        other => return Err(CliError::Usage(format!("unknown command {other}"))),
+    };
+    if wasm_dir.is_some() || collateral {
+        return Err(CliError::Usage(format!("option not valid for {name}")));
+    }
+
+    Ok(Args {
+        config,
+        network,
+        command,
+    })
+}
+
+/// Network definitions
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Config {
+    pub default_network: String,
+    pub networks: BTreeMap<String, NetworkConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct NetworkConfig {
+    /// `http://` Soroban RPC endpoint
+    pub rpc_url: String,
+    pub network_passphrase: String,
+    /// Deployment file, relative to the config file
+    pub deployment: PathBuf,
+}
+
+impl Config {
+    pub fn from_json(json: &str) -> Result<Self, CliError> {
+        serde_json::from_str(json).map_err(|e| CliError::Config(e.to_string()))
+    }
+
+    /// The named network, or the default one
+    pub fn network(&self, name: Option<&str>) -> Result<&NetworkConfig, CliError> {
+        let name = name.unwrap_or(&self.default_network);
+        self.networks
+            .get(name)
+            .ok_or_else(|| CliError::Config(format!("network `{name}` is not configured")))
+    }
+}
+
+/// Contract and token ids, in the `scripts/deployment.json` format
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Deployment {
+    pub network: String,
+    pub timestamp: String,
+    pub contracts: Contracts,
+    /// Token contracts by lowercase symbol (`xlm`, `usdc`), plus issuers
+    pub tokens: BTreeMap<String, String>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contracts {
+    pub pool: String,
+    pub oracle: String,
+    pub interest_rate_model: String,
+}
+
+impl Deployment {
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| CliError::Config(format!("{}: {e}", path.display())))?;
+        serde_json::from_str(&json)
+            .map_err(|e| CliError::Config(format!("{}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CliError> {
+        let json = serde_json::to_string_pretty(self).expect("deployment serializes");
+        std::fs::write(path, json + "\n")
+            .map_err(|e| CliError::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// Token contract for an asset symbol; a `C...` id is passed through
+    pub fn token(&self, asset: &str) -> Result<String, CliError> {
+        if asset.len() == 56 && asset.starts_with('C') {
+            return Ok(asset.to_string());
+        }
+        self.tokens
+            .get(&asset.to_lowercase())
+            .cloned()
+            .ok_or_else(|| CliError::Config(format!("no token for {asset} in the deployment")))
+    }
+}
+
+/// Parses a decimal amount into base units with `decimals` places
+///
+/// Rejects negative values and more precision than the token has.
+pub fn parse_amount(text: &str, decimals: u32) -> Result<i128, CliError> {
+    let invalid = || CliError::Usage(format!("invalid amount `{text}`"));
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+        || fraction.len() > decimals as usize
+    {
+        return Err(invalid());
+    }
+    let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    match digits.trim_start_matches('0') {
+        "" => Ok(0),
+        significant => significant.parse().map_err(|_| invalid()),
+    }
+}
+
+/// Formats base units with `decimals` places, trimming trailing zeros
+pub fn format_amount(value: i128, decimals: u32) -> String {
+    let scale = 10i128.pow(decimals);
+    let sign = if value < 0 { "-" } else { "" };
+    let (whole, fraction) = (
+        value.unsigned_abs() / scale as u128,
+        value.unsigned_abs() % scale as u128,
+    );
+    let fraction = format!("{fraction:0width$}", width = decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => format!("{sign}{whole}"),
+        fraction => format!("{sign}{whole}.{fraction}"),
+    }
+}
+
+/// Errors returned by the CLI
+#[derive(Debug)]
+pub enum CliError {
+    /// Bad command line; an empty message just prints the usage
+    Usage(String),
+    /// Missing or invalid config, deployment file or environment
+    Config(String),
+    /// A contract call failed
+    Sdk(SdkError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(e) if e.is_empty() => write!(f, "{USAGE}"),
+            CliError::Usage(e) => write!(f, "{e}\n\n{USAGE}"),
+            CliError::Config(e) => write!(f, "{e}"),
+            CliError::Sdk(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<SdkError> for CliError {
+    fn from(e: SdkError) -> Self {
+        CliError::Sdk(e)
+    }
+}
+
+impl From<apogee_sdk::RpcError> for CliError {
+    fn from(e: apogee_sdk::RpcError) -> Self {
+        CliError::Sdk(e.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, CliError> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse("--network mainnet deposit XLM 100.5 --collateral").unwrap();
+        assert_eq!(args.network.as_deref(), Some("mainnet"));
+        assert_eq!(
+            args.command,
+            Command::Deposit {
+                asset: "XLM".to_string(),
+                amount: "100.5".to_string(),
+                collateral: true
+            }
+        );
+        assert_eq!(
+            parse("deploy").unwrap().command,
+            Command::Deploy {
+                wasm_dir: DEFAULT_WASM_DIR.into()
+            }
+        );
+        assert_eq!(
+            parse("params").unwrap().command,
+            Command::Params { asset: None }
+        );
+        assert!(!parse("account GABC").unwrap().command.signs());
+
+        assert!(matches!(parse("borrow USDC"), Err(CliError::Usage(_))));
+        assert!(matches!(
+            parse("borrow USDC 1 --collateral"),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(parse("swap XLM USDC"), Err(CliError::Usage(_))));
+        assert!(matches!(parse("--network"), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(parse_amount("100.5", 7).unwrap(), 1_005_000_000);
+        assert_eq!(parse_amount("0.0000001", 7).unwrap(), 1);
+        assert_eq!(parse_amount(".5", 7).unwrap(), 5_000_000);
+        assert_eq!(parse_amount("0", 7).unwrap(), 0);
+        assert!(parse_amount("0.00000001", 7).is_err());
+        assert!(parse_amount("-1", 7).is_err());
+        assert!(parse_amount("1e3", 7).is_err());
+        assert!(parse_amount(".", 7).is_err());
+
+        assert_eq!(format_amount(1_005_000_000, 7), "100.5");
+        assert_eq!(format_amount(-1, 7), "-0.0000001");
+        assert_eq!(format_amount(30_000_000, 7), "3");
+    }
+
+    #[test]
+    fn test_example_config_and_deployment() {
+        let config = Config::from_json(include_str!("../apogee.example.json")).unwrap();
+        assert_eq!(
+            config.network(None).unwrap().network_passphrase,
+            apogee_sdk::TESTNET_PASSPHRASE
+        );
+        assert_eq!(
+            config.network(Some("mainnet")).unwrap().network_passphrase,
+            apogee_sdk::PUBLIC_PASSPHRASE
+        );
+        assert!(config.network(Some("futurenet")).is_err());
+
+        let json = include_str!("../../../scripts/deployment.json");
+        let deployment: Deployment = serde_json::from_str(json).unwrap();
+        assert_eq!(deployment.token("USDC").unwrap(), deployment.tokens["usdc"]);
+        assert_eq!(
+            deployment.token(&deployment.contracts.pool).unwrap(),
+            deployment.contracts.pool
+        );
+        assert!(deployment.token("BTC").is_err());
+
+        let round_trip: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&deployment).unwrap()).unwrap();
+        assert_eq!(
+            round_trip,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+    }
+}
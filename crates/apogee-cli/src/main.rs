@@ -0,0 +1,68 @@
+//! `apogee-cli` — see the crate docs for commands, config and environment
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use apogee_cli::{parse_args, run, CliError, Config, Context, Deployment};
+use apogee_sdk::{Invoker, RpcClient, Signer};
+
+fn main() -> ExitCode {
+    match cli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cli() -> Result<(), CliError> {
+    let args = parse_args(std::env::args().skip(1))?;
+
+    let config_path = args
+        .config
+        .or_else(|| std::env::var_os("APOGEE_CONFIG").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("apogee.json"));
+    let config = Config::from_json(
+        &std::fs::read_to_string(&config_path)
+            .map_err(|e| CliError::Config(format!("{}: {e}", config_path.display())))?,
+    )?;
+    let network = config.network(args.network.as_deref())?;
+    let deployment_path = config_path
+        .parent()
+        .unwrap_or(".".as_ref())
+        .join(&network.deployment);
+    let deployment = match Deployment::load(&deployment_path) {
+        Ok(deployment) => deployment,
+        // `deploy` creates the file
+        Err(_) if matches!(args.command, apogee_cli::Command::Deploy { .. }) => Deployment {
+            network: args
+                .network
+                .clone()
+                .unwrap_or(config.default_network.clone()),
+            ..Default::default()
+        },
+        Err(e) => return Err(e),
+    };
+
+    let signer = match std::env::var("APOGEE_SECRET") {
+        Ok(secret) => Signer::from_secret(&secret)?,
+        Err(_) if args.command.signs() => {
+            return Err(CliError::Config("APOGEE_SECRET is not set".to_string()))
+        }
+        Err(_) => Signer::read_only(),
+    };
+
+    let rpc = RpcClient::new(&network.rpc_url)?;
+    let mut invoker = Invoker::new(&rpc, &network.network_passphrase, signer);
+    if let Ok(secret) = std::env::var("FEE_BUMP_SECRET") {
+        invoker = invoker.with_fee_payer(Signer::from_secret(&secret)?);
+    }
+
+    let mut context = Context {
+        invoker,
+        deployment,
+        deployment_path,
+    };
+    run(&mut context, &args.command)
+}
@@ -0,0 +1,255 @@
+#![no_std]
+
+//! # Stellend Common
+//!
+//! Scaling constants and contract types shared by the pool, price oracle,
+//! interest rate model and the periphery contracts.
+//!
+//! ## Scaling conventions
+//!
+//! ```text
+//! SCALE               1e7     percentages, rates, USD values and prices
+//! PRICE_SCALE         1e7     oracle prices ($1.00 = 10_000_000)
+//! EXCHANGE_RATE_SCALE 1e9     sToken exchange rate and borrow index
+//! BPS_SCALE           1e4     basis points, only at user-facing edges
+//! ```
+//!
+//! Every stored ratio is scaled by [`SCALE`]. Basis points are accepted by a
+//! few entrypoints (e.g. oracle chaos haircuts) and are converted with
+//! [`bps_to_scale`] before they touch any other value.
+//!
+//! Error enums stay in each contract: a `contracterror` code is part of that
+//! contract's interface, and sharing one enum would renumber them.
+
+use soroban_sdk::{contractclient, contracttype, Address, Bytes, Env, Symbol};
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding, SCALE_7, SCALE_9};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Scaling factor for percentages and rates (1e7 = 10_000_000)
+/// 100% = 10_000_000, 75% = 7_500_000, 1% = 100_000
+pub const SCALE: i128 = SCALE_7;
+
+/// Scaling factor for prices (1e7 = 10_000_000)
+/// - $1.00 = 10_000_000
+/// - $0.01 = 100_000
+pub const PRICE_SCALE: i128 = SCALE_7;
+
+/// Scaling factor for the sToken exchange rate and borrow index (1e9)
+pub const EXCHANGE_RATE_SCALE: i128 = SCALE_9;
+
+/// Basis points in 100%
+pub const BPS_SCALE: i128 = 10_000;
+
+/// Seconds per year (365.25 days)
+pub const SECONDS_PER_YEAR: i128 = 31_557_600;
+
+/// Storage TTL management (in ledgers, ~5s each)
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+/// TTL that touched entries are extended to
+pub const BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+/// Entries are only extended once their TTL drops below this
+pub const LIFETIME_THRESHOLD: u32 = BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Decimals of native XLM (1 XLM = 10^7 stroops)
+pub const NATIVE_DECIMALS: u32 = 7;
+
+/// XDR of `Asset::Native`: the `ASSET_TYPE_NATIVE` discriminant alone
+pub const NATIVE_ASSET_XDR: [u8; 4] = [0; 4];
+
+/// Address of the native XLM Stellar asset contract on the current network
+///
+/// The address is derived from the network passphrase, so it differs between
+/// testnet and mainnet but never needs configuring.
+pub fn native_xlm(env: &Env) -> Address {
+    env.deployer()
+        .with_stellar_asset(Bytes::from_array(env, &NATIVE_ASSET_XDR))
+        .deployed_address()
+}
+
+/// Converts basis points to a SCALE ratio (1 bps = 1_000)
+pub const fn bps_to_scale(bps: i128) -> i128 {
+    bps * (SCALE / BPS_SCALE)
+}
+
+/// Utilization of a reserve: borrowed / supplied, scaled by SCALE
+///
+/// Both totals are in token units. Rounds down, and an empty reserve has
+/// zero utilization. The pool quotes rates and accrues interest at this
+/// value, so every caller must compute it the same way.
+pub fn utilization_rate(total_borrow: i128, total_supply: i128) -> Result<i128, FixedPointError> {
+    if total_supply <= 0 {
+        return Ok(0);
+    }
+    mul_div(total_borrow, SCALE, total_supply, Rounding::Down)
+}
+
+/// Amount of one asset worth `amount` of another: amount * price_from / price_to
+///
+/// Both prices must share a scale and both amounts are in the same units.
+/// Rounds down. The oracle's `convert` and the pool's seizure math go
+/// through this so cross-asset amounts always agree.
+pub fn convert_amount(amount: i128, price_from: i128, price_to: i128) -> Result<i128, FixedPointError> {
+    mul_div(amount, price_from, price_to, Rounding::Down)
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Operational roles split off from the admin key
+///
+/// Each role has a single holder. A role nobody was granted falls back to
+/// the admin, so a fresh deployment behaves like a single-key setup. Every
+/// contract checks only the roles it needs: the pool ignores `OracleFeeder`,
+/// the oracle checks `OracleFeeder` and `RiskManager`, and the rate model
+/// checks `RiskManager`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    /// Upgrade key; grants and revokes the other roles
+    Admin,
+    /// Updates risk parameters (LTV, thresholds, caps, fees, rate curves)
+    RiskManager,
+    /// Pauses user actions (the guardian)
+    Pauser,
+    /// Pushes prices to the oracle
+    OracleFeeder,
+    /// Manages the treasury and the deposit match program
+    TreasuryManager,
+}
+
+/// Side of a reserve tracked by the emissions controller
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RewardSide {
+    Supply,
+    Borrow,
+}
+
+/// Configuration of a listed reserve asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReserveConfig {
+    /// Token contract for the asset
+    pub token: Address,
+    /// Token decimals; the pool reads them from the token when listing and
+    /// reports what it read
+    pub decimals: u32,
+    /// LTV ratio (scaled by SCALE)
+    pub ltv: i128,
+    /// Liquidation threshold (scaled by SCALE)
+    pub liquidation_threshold: i128,
+    /// Whether the asset can be deposited as collateral
+    pub collateral_enabled: bool,
+    /// Whether the asset can be borrowed
+    pub borrow_enabled: bool,
+    /// Interest rate model contract for the asset
+    pub interest_rate_model: Address,
+}
+
+/// Valuation of a user's account at live oracle prices
+///
+/// Shared by borrow, withdraw_collateral, liquidation and the position views
+/// so that every check values collateral and debt the same way.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AccountData {
+    /// Total collateral value in USD
+    pub collateral_value_usd: i128,
+    /// Total debt value in USD, including accrued interest
+    pub debt_value_usd: i128,
+    /// Maximum debt allowed by each collateral's (volatility-adjusted) LTV, in USD
+    pub borrow_capacity_usd: i128,
+    /// sum(collateral_usd * liquidation_threshold), in USD
+    pub threshold_collateral_usd: i128,
+    /// sum(collateral_usd * liquidation_threshold) / debt_usd (scaled by SCALE)
+    pub health_factor: i128,
+}
+
+/// Payload of the pool's `liquidate` events
+///
+/// Carries everything needed to audit a liquidation from the event alone:
+/// what was repaid and seized, how the bonus was split, the oracle prices
+/// the seizure was valued at, and where it left the borrower.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationEvent {
+    pub liquidator: Address,
+    pub borrower: Address,
+    pub repay_asset: Symbol,
+    pub collateral_asset: Symbol,
+    /// Debt repaid by the liquidator
+    pub repay_amount: i128,
+    /// Collateral transferred to the liquidator
+    pub collateral_seized: i128,
+    /// Collateral transferred to the treasury as the protocol's share of the bonus
+    pub protocol_fee: i128,
+    /// Liquidation bonus applied (scaled by SCALE)
+    pub bonus: i128,
+    /// Collateral paid out as bonus, before the protocol's share is taken
+    pub bonus_amount: i128,
+    /// Borrower's remaining debt in the repaid asset
+    pub new_debt: i128,
+    /// Borrower's remaining collateral in the seized asset
+    pub new_collateral: i128,
+    /// Borrower's health factor after the liquidation (scaled by SCALE)
+    pub health_factor_after: i128,
+    /// Oracle price of the repaid asset used (scaled by SCALE)
+    pub repay_price: i128,
+    /// Oracle price of the collateral asset used (scaled by SCALE)
+    pub collateral_price: i128,
+}
+
+/// Interface of an AMM integration the pool swaps through
+///
+/// The caller transfers `amount_in` of `token_in` to the adapter first; the
+/// adapter swaps all of it and sends the output to `to`. Adapters hold no
+/// funds between calls.
+#[contractclient(name = "SwapAdapterClient")]
+pub trait SwapAdapter {
+    /// Swap `amount_in` of `token_in` for at least `min_out` of `token_out`
+    ///
+    /// # Returns
+    /// Amount of `token_out` sent to `to`
+    fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bps_to_scale() {
+        assert_eq!(bps_to_scale(BPS_SCALE), SCALE);
+        assert_eq!(bps_to_scale(1), 1_000);
+        assert_eq!(bps_to_scale(2_500), 2_500_000);
+    }
+
+    #[test]
+    fn test_utilization_rate() {
+        assert_eq!(utilization_rate(0, 0), Ok(0));
+        assert_eq!(utilization_rate(200_000_000, 1_000_000_000), Ok(2_000_000));
+        // 1/3 rounds down
+        assert_eq!(utilization_rate(1, 3), Ok(3_333_333));
+        assert_eq!(utilization_rate(i128::MAX, 1), Err(FixedPointError::Overflow));
+    }
+
+    #[test]
+    fn test_convert_amount() {
+        // 100 USDC at $1.00 buys 400 XLM at $0.25
+        assert_eq!(convert_amount(1_000_000_000, 10_000_000, 2_500_000), Ok(4_000_000_000));
+        // 1 unit of a $0.30 asset in a $1.00 asset rounds down
+        assert_eq!(convert_amount(1, 3_000_000, 10_000_000), Ok(0));
+        assert_eq!(convert_amount(1, 1, 0), Err(FixedPointError::DivisionByZero));
+    }
+}
@@ -0,0 +1,127 @@
+//! Quote aggregation and the update schedule
+
+use apogee_common::BPS_SCALE;
+
+use crate::FeedError;
+
+/// Why a price is pushed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PushReason {
+    /// The oracle has no price for the asset yet
+    Initial,
+    /// The on-chain price is older than the heartbeat
+    Heartbeat,
+    /// The aggregate moved at least the deviation threshold
+    Deviation,
+}
+
+fn median(sorted: &[i128]) -> i128 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        sorted[mid]
+    } else {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    }
+}
+
+/// Distance between two prices in basis points of `reference`
+fn deviation_bps(price: i128, reference: i128) -> i128 {
+    (price - reference).abs().saturating_mul(BPS_SCALE) / reference
+}
+
+/// Median of the quotes after dropping outliers
+///
+/// A quote is an outlier when it is more than `max_spread_bps` away from the
+/// median of all quotes. At least `min_sources` quotes must survive.
+pub fn aggregate(
+    asset: &str,
+    quotes: &[i128],
+    max_spread_bps: u32,
+    min_sources: usize,
+) -> Result<i128, FeedError> {
+    let not_enough = |got| FeedError::NotEnoughQuotes {
+        asset: asset.to_string(),
+        got,
+        need: min_sources.max(1),
+    };
+
+    let mut sorted: Vec<i128> = quotes.iter().copied().filter(|&q| q > 0).collect();
+    if sorted.is_empty() {
+        return Err(not_enough(0));
+    }
+    sorted.sort_unstable();
+
+    let center = median(&sorted);
+    sorted.retain(|&q| deviation_bps(q, center) <= i128::from(max_spread_bps));
+    if sorted.len() < min_sources.max(1) {
+        return Err(not_enough(sorted.len()));
+    }
+    Ok(median(&sorted))
+}
+
+/// Whether to push `price` given the oracle's current price and its last update time
+pub fn should_push(
+    price: i128,
+    onchain_price: i128,
+    last_update: u64,
+    now: u64,
+    heartbeat_secs: u64,
+    deviation_threshold_bps: u32,
+) -> Option<PushReason> {
+    if onchain_price <= 0 || last_update == 0 {
+        Some(PushReason::Initial)
+    } else if now.saturating_sub(last_update) >= heartbeat_secs {
+        Some(PushReason::Heartbeat)
+    } else if deviation_bps(price, onchain_price) >= i128::from(deviation_threshold_bps) {
+        Some(PushReason::Deviation)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_drops_outliers() {
+        // 0.15 is 36% off the median and is dropped; median of the rest
+        let quotes = [1_100_000, 1_090_000, 1_500_000, 1_095_000];
+        assert_eq!(aggregate("XLM", &quotes, 200, 2), Ok(1_095_000));
+
+        // Two sources disagreeing by 40%: neither can be trusted
+        assert_eq!(
+            aggregate("XLM", &[1_000_000, 1_400_000], 200, 2),
+            Err(FeedError::NotEnoughQuotes {
+                asset: "XLM".to_string(),
+                got: 0,
+                need: 2
+            })
+        );
+        assert!(aggregate("XLM", &[], 200, 1).is_err());
+        assert_eq!(aggregate("USDC", &[10_000_000], 200, 1), Ok(10_000_000));
+    }
+
+    #[test]
+    fn test_push_schedule() {
+        let hour = 3600;
+        assert_eq!(
+            should_push(1_000_000, 0, 0, 1_000, hour, 50),
+            Some(PushReason::Initial)
+        );
+        // Within heartbeat, 0.3% move
+        assert_eq!(
+            should_push(1_003_000, 1_000_000, 1_000, 1_600, hour, 50),
+            None
+        );
+        // 0.5% move
+        assert_eq!(
+            should_push(995_000, 1_000_000, 1_000, 1_600, hour, 50),
+            Some(PushReason::Deviation)
+        );
+        assert_eq!(
+            should_push(1_000_000, 1_000_000, 1_000, 1_000 + hour, hour, 50),
+            Some(PushReason::Heartbeat)
+        );
+    }
+}
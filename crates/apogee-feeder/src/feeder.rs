@@ -0,0 +1,177 @@
+//! One feeding round per tick: fetch -> aggregate -> compare -> push
+
+use std::collections::HashMap;
+
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, ContractCall, Invoker, RpcError, Transport};
+
+use crate::aggregate::{aggregate, should_push, PushReason};
+use crate::sources::{ContractReader, PriceSource};
+use crate::{unix_now, Config, FeedError};
+
+struct FeedAsset {
+    symbol: String,
+    token: String,
+    sources: Vec<Box<dyn PriceSource>>,
+}
+
+/// Result of one tick for one asset
+#[derive(Debug)]
+pub struct AssetReport {
+    pub symbol: String,
+    /// Sources that failed this tick; the others were aggregated
+    pub failed_sources: Vec<FeedError>,
+    /// Aggregated price and why it was pushed, if it was
+    pub outcome: Result<(i128, Option<PushReason>), FeedError>,
+}
+
+/// Price feeder for one oracle
+///
+/// Pushes with `set_price` while the oracle takes direct updates, and with
+/// `submit_price` once it runs with registered feeders. In feeder mode the
+/// heartbeat is measured from this feeder's own last submission, since other
+/// feeders keep the aggregate fresh while ours may age out of the quorum.
+pub struct Feeder<'a, T: Transport> {
+    invoker: Invoker<'a, T>,
+    config: Config,
+    assets: Vec<FeedAsset>,
+    submitted: HashMap<String, u64>,
+}
+
+impl<'a, T: Transport> Feeder<'a, T> {
+    pub fn new(invoker: Invoker<'a, T>, config: Config) -> Result<Self, FeedError> {
+        let assets = config
+            .assets
+            .iter()
+            .map(|asset| {
+                Ok(FeedAsset {
+                    symbol: asset.symbol.clone(),
+                    token: asset.token.clone(),
+                    sources: asset
+                        .sources
+                        .iter()
+                        .map(|s| s.build())
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_, FeedError>>()?;
+        Ok(Feeder {
+            invoker,
+            config,
+            assets,
+            submitted: HashMap::new(),
+        })
+    }
+
+    fn oracle_call(&self, function: &str, args: Vec<ScVal>) -> ContractCall {
+        ContractCall::new(&self.config.oracle_id, function, args)
+    }
+
+    pub fn tick(&mut self) -> Result<Vec<AssetReport>, RpcError> {
+        let me = self.invoker.source().account_id();
+        let feeders = self
+            .invoker
+            .simulate(&self.oracle_call("get_feeders", vec![]))?;
+        let feeders = scval::to_vec(&feeders)?
+            .iter()
+            .map(scval::to_address)
+            .collect::<Result<Vec<_>, _>>()?;
+        let feeder_mode = !feeders.is_empty();
+        if feeder_mode && !feeders.contains(&me) {
+            return Err(RpcError::Simulation(format!(
+                "{me} is not a registered oracle feeder"
+            )));
+        }
+
+        let mut reports = Vec::with_capacity(self.assets.len());
+        for index in 0..self.assets.len() {
+            reports.push(self.feed(index, feeder_mode, &me));
+        }
+        Ok(reports)
+    }
+
+    fn feed(
+        &mut self,
+        index: usize,
+        feeder_mode: bool,
+        me: &str,
+    ) -> AssetReport {
+        let asset = &self.assets[index];
+        let mut quotes = Vec::new();
+        let mut failed = Vec::new();
+        for source in &asset.sources {
+            match source.fetch(&self.invoker as &dyn ContractReader) {
+                Ok(quote) => quotes.push(quote),
+                Err(e) => failed.push(e),
+            }
+        }
+
+        let symbol = asset.symbol.clone();
+        let token = asset.token.clone();
+        let outcome = aggregate(
+            &symbol,
+            &quotes,
+            self.config.max_spread_bps,
+            self.config.min_sources,
+        )
+        .and_then(|price| {
+            let reason = self.publish(&symbol, &token, price, feeder_mode, me)?;
+            Ok((price, reason))
+        });
+        AssetReport {
+            symbol,
+            failed_sources: failed,
+            outcome,
+        }
+    }
+
+    /// Pushes `price` if the schedule calls for it
+    fn publish(
+        &mut self,
+        symbol: &str,
+        token: &str,
+        price: i128,
+        feeder_mode: bool,
+        me: &str,
+    ) -> Result<Option<PushReason>, FeedError> {
+        let token = scval::address(token)?;
+
+        // The stored price, not get_price, which applies any chaos haircut
+        let round = self
+            .invoker
+            .simulate(&self.oracle_call("latest_round", vec![token.clone()]))?;
+        let (onchain_price, mut last_update) = match round {
+            ScVal::Void => (0, 0),
+            round => (
+                scval::to_i128(scval::field(&round, "price")?)?,
+                scval::to_u64(scval::field(&round, "timestamp")?)?,
+            ),
+        };
+        if feeder_mode {
+            last_update = self.submitted.get(symbol).copied().unwrap_or(0);
+        }
+
+        let now = unix_now();
+        let reason = should_push(
+            price,
+            onchain_price,
+            last_update,
+            now,
+            self.config.heartbeat_secs,
+            self.config.deviation_bps,
+        );
+        if reason.is_some() {
+            let call = if feeder_mode {
+                self.oracle_call(
+                    "submit_price",
+                    vec![scval::address(me)?, token, scval::i128(price)],
+                )
+            } else {
+                self.oracle_call("set_price", vec![token, scval::i128(price)])
+            };
+            self.invoker.invoke(&call)?;
+            self.submitted.insert(symbol.to_string(), now);
+        }
+        Ok(reason)
+    }
+}
@@ -0,0 +1,193 @@
+//! # Stellend Feeder
+//!
+//! Price feeder daemon for the price oracle, replacing the one-shot
+//! `scripts/update_price.ts` keeper.
+//!
+//! ```text
+//! every interval, per asset:
+//!   fetch      each configured source (JSON HTTP API, Reflector, fixed)
+//!   aggregate  drop quotes more than max_spread_bps from the median,
+//!              require min_sources, take the median of the rest
+//!   compare    against the oracle's latest round
+//!   push       set_price (or submit_price in multi-feeder mode) when the
+//!              heartbeat elapsed or the price moved deviation_bps
+//! ```
+//!
+//! Sources are configured in a JSON file (see `feeder.example.json`); the
+//! signing key comes from `FEEDER_SECRET` and an optional fee payer from
+//! `FEE_BUMP_SECRET`. HTTP sources must be plain `http://` URLs, so public
+//! HTTPS exchange APIs need a local TLS-terminating proxy.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use apogee_rpc::RpcError;
+use serde::Deserialize;
+
+mod aggregate;
+mod feeder;
+mod sources;
+
+pub use aggregate::{aggregate, should_push, PushReason};
+pub use feeder::{AssetReport, Feeder};
+pub use sources::{parse_decimal, ContractReader, PriceSource, SourceConfig};
+
+/// Feeder settings
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Config {
+    /// `http://` Soroban RPC endpoint
+    pub rpc_url: String,
+    #[serde(default = "default_passphrase")]
+    pub network_passphrase: String,
+    /// Price oracle contract `C...`
+    pub oracle_id: String,
+    /// Seconds between ticks
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Push at least this often, even if the price did not move
+    #[serde(default = "default_heartbeat_secs")]
+    pub heartbeat_secs: u64,
+    /// Push when the price moved at least this much (bps)
+    #[serde(default = "default_deviation_bps")]
+    pub deviation_bps: u32,
+    /// Quotes further than this from the median are dropped (bps)
+    #[serde(default = "default_max_spread_bps")]
+    pub max_spread_bps: u32,
+    /// Quotes that must survive outlier filtering
+    #[serde(default = "default_min_sources")]
+    pub min_sources: usize,
+    pub assets: Vec<AssetConfig>,
+}
+
+/// One fed asset
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct AssetConfig {
+    /// Symbol, for logs
+    pub symbol: String,
+    /// Token contract `C...` the oracle keys the price by
+    pub token: String,
+    pub sources: Vec<SourceConfig>,
+}
+
+fn default_passphrase() -> String {
+    apogee_rpc::TESTNET_PASSPHRASE.to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_heartbeat_secs() -> u64 {
+    1800
+}
+
+fn default_deviation_bps() -> u32 {
+    50
+}
+
+fn default_max_spread_bps() -> u32 {
+    200
+}
+
+fn default_min_sources() -> usize {
+    1
+}
+
+impl Config {
+    pub fn from_json(json: &str) -> Result<Self, FeedError> {
+        let config: Config =
+            serde_json::from_str(json).map_err(|e| FeedError::Config(e.to_string()))?;
+        if let Some(asset) = config
+            .assets
+            .iter()
+            .find(|a| a.sources.len() < config.min_sources)
+        {
+            return Err(FeedError::Config(format!(
+                "{} has {} sources but min_sources is {}",
+                asset.symbol,
+                asset.sources.len(),
+                config.min_sources
+            )));
+        }
+        Ok(config)
+    }
+}
+
+/// Errors returned by the feeder
+#[derive(Debug)]
+pub enum FeedError {
+    /// Invalid configuration
+    Config(String),
+    /// A price source failed
+    Source { source: String, reason: String },
+    /// Too few quotes survived outlier filtering
+    NotEnoughQuotes {
+        asset: String,
+        got: usize,
+        need: usize,
+    },
+    /// Reading or updating the oracle failed
+    Rpc(RpcError),
+}
+
+impl fmt::Display for FeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedError::Config(e) => write!(f, "invalid config: {e}"),
+            FeedError::Source { source, reason } => write!(f, "source {source}: {reason}"),
+            FeedError::NotEnoughQuotes { asset, got, need } => {
+                write!(f, "{asset}: {got} usable quotes, need {need}")
+            }
+            FeedError::Rpc(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FeedError {}
+
+impl From<RpcError> for FeedError {
+    fn from(e: RpcError) -> Self {
+        FeedError::Rpc(e)
+    }
+}
+
+// RpcError carries an io::Error and so is not comparable; compare by message
+impl PartialEq for FeedError {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example_config_parses() {
+        let config = Config::from_json(include_str!("../feeder.example.json")).unwrap();
+        assert_eq!(config.network_passphrase, apogee_rpc::TESTNET_PASSPHRASE);
+        assert_eq!(config.assets.len(), 2);
+        assert!(matches!(
+            config.assets[1].sources[0],
+            SourceConfig::Fixed { .. }
+        ));
+        for asset in &config.assets {
+            for source in &asset.sources {
+                source.build().unwrap();
+            }
+        }
+
+        let too_few = r#"{"rpc_url": "http://localhost:8000", "oracle_id": "C", "min_sources": 2,
+            "assets": [{"symbol": "USDC", "token": "C", "sources": [{"type": "fixed", "price": "1"}]}]}"#;
+        assert!(matches!(
+            Config::from_json(too_few),
+            Err(FeedError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+//! `apogee-feeder <config.json>` — see the crate docs for the config and environment
+
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use apogee_feeder::{Config, Feeder};
+use apogee_rpc::{Invoker, RpcClient, Signer};
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args()
+        .nth(1)
+        .filter(|a| a != "--help" && a != "-h")
+    else {
+        eprintln!("usage: apogee-feeder <config.json>  (FEEDER_SECRET, optional FEE_BUMP_SECRET)");
+        return ExitCode::FAILURE;
+    };
+
+    let setup = || -> Result<_, Box<dyn std::error::Error>> {
+        let config = Config::from_json(&std::fs::read_to_string(&path)?)?;
+        let rpc = RpcClient::new(&config.rpc_url)?;
+        let feeder = Signer::from_secret(
+            &std::env::var("FEEDER_SECRET").map_err(|_| "FEEDER_SECRET is not set")?,
+        )?;
+        let fee_payer = std::env::var("FEE_BUMP_SECRET")
+            .ok()
+            .map(|s| Signer::from_secret(&s))
+            .transpose()?;
+        Ok((config, rpc, feeder, fee_payer))
+    };
+    let (config, rpc, feeder, fee_payer) = match setup() {
+        Ok(parts) => parts,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!(
+        "feeder {} updating oracle {}",
+        feeder.account_id(),
+        config.oracle_id
+    );
+    let mut invoker = Invoker::new(&rpc, &config.network_passphrase, feeder);
+    if let Some(fee_payer) = fee_payer {
+        invoker = invoker.with_fee_payer(fee_payer);
+    }
+
+    let interval = Duration::from_secs(config.interval_secs);
+    let mut feeder = match Feeder::new(invoker, config) {
+        Ok(feeder) => feeder,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    loop {
+        match feeder.tick() {
+            Ok(reports) => {
+                for report in reports {
+                    for failure in &report.failed_sources {
+                        eprintln!("{}: {failure}", report.symbol);
+                    }
+                    match report.outcome {
+                        Ok((price, Some(reason))) => {
+                            eprintln!("{}: pushed {price} ({reason:?})", report.symbol)
+                        }
+                        Ok((price, None)) => eprintln!("{}: {price} unchanged", report.symbol),
+                        Err(e) => eprintln!("{}: {e}", report.symbol),
+                    }
+                }
+            }
+            Err(e) => eprintln!("tick failed: {e}"),
+        }
+        thread::sleep(interval);
+    }
+}
@@ -0,0 +1,327 @@
+//! Price sources
+
+use std::time::Duration;
+
+use apogee_common::PRICE_SCALE;
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, ContractCall, HttpTransport, Invoker, RpcError, Transport};
+use serde::Deserialize;
+use stellend_fixed_point::{mul_div, Rounding};
+
+use crate::FeedError;
+
+/// Decimals of [`PRICE_SCALE`]
+const PRICE_DECIMALS: u32 = 7;
+
+/// Read-only contract access, for on-chain sources
+pub trait ContractReader {
+    fn read(&self, call: &ContractCall) -> Result<ScVal, RpcError>;
+}
+
+impl<T: Transport> ContractReader for Invoker<'_, T> {
+    fn read(&self, call: &ContractCall) -> Result<ScVal, RpcError> {
+        self.simulate(call)
+    }
+}
+
+/// A USD price quote for one asset
+pub trait PriceSource {
+    fn name(&self) -> &str;
+
+    /// Current price, scaled by PRICE_SCALE
+    fn fetch(&self, chain: &dyn ContractReader) -> Result<i128, FeedError>;
+}
+
+/// Source settings as written in the config file
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceConfig {
+    /// JSON API; `pointer` is an RFC 6901 pointer to the price
+    /// (a number or decimal string), e.g. `/stellar/usd`
+    HttpJson {
+        name: String,
+        url: String,
+        pointer: String,
+    },
+    /// SEP-40 feed such as Reflector, queried with `lastprice(Other(asset))`
+    Reflector {
+        contract: String,
+        asset: String,
+        #[serde(default = "default_max_age_secs")]
+        max_age_secs: u64,
+    },
+    /// Constant price, e.g. a stablecoin peg
+    Fixed { price: String },
+}
+
+fn default_max_age_secs() -> u64 {
+    600
+}
+
+impl SourceConfig {
+    pub fn build(&self) -> Result<Box<dyn PriceSource>, FeedError> {
+        Ok(match self {
+            SourceConfig::HttpJson { name, url, pointer } => Box::new(HttpJsonSource {
+                name: name.clone(),
+                transport: HttpTransport::new(url)
+                    .map_err(|e| FeedError::Config(format!("source `{name}`: {e}")))?
+                    .with_timeout(Duration::from_secs(10)),
+                pointer: pointer.clone(),
+            }),
+            SourceConfig::Reflector {
+                contract,
+                asset,
+                max_age_secs,
+            } => Box::new(ReflectorSource {
+                name: format!("reflector:{asset}"),
+                contract: contract.clone(),
+                asset: asset.clone(),
+                max_age_secs: *max_age_secs,
+            }),
+            SourceConfig::Fixed { price } => Box::new(FixedSource {
+                price: parse_decimal(price)
+                    .filter(|&p| p > 0)
+                    .ok_or_else(|| FeedError::Config(format!("invalid fixed price `{price}`")))?,
+            }),
+        })
+    }
+}
+
+/// Price read from a JSON HTTP API
+pub struct HttpJsonSource {
+    name: String,
+    transport: HttpTransport,
+    pointer: String,
+}
+
+impl PriceSource for HttpJsonSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn fetch(&self, _chain: &dyn ContractReader) -> Result<i128, FeedError> {
+        let body = self
+            .transport
+            .get()
+            .map_err(|e| self.error(e.to_string()))?;
+        price_at(&body, &self.pointer).map_err(|reason| self.error(reason))
+    }
+}
+
+impl HttpJsonSource {
+    fn error(&self, reason: String) -> FeedError {
+        FeedError::Source {
+            source: self.name.clone(),
+            reason,
+        }
+    }
+}
+
+/// Price read from a Reflector (SEP-40) price feed contract
+pub struct ReflectorSource {
+    name: String,
+    contract: String,
+    asset: String,
+    max_age_secs: u64,
+}
+
+impl PriceSource for ReflectorSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn fetch(&self, chain: &dyn ContractReader) -> Result<i128, FeedError> {
+        let error = |reason: String| FeedError::Source {
+            source: self.name.clone(),
+            reason,
+        };
+        let read = |function: &str, args: Vec<ScVal>| {
+            chain
+                .read(&ContractCall::new(&self.contract, function, args))
+                .map_err(|e| error(e.to_string()))
+        };
+
+        let decimals =
+            scval::to_u32(&read("decimals", vec![])?).map_err(|e| error(e.to_string()))?;
+        // `Asset::Other(Symbol)` is encoded as [variant, value]
+        let asset = ScVal::Vec(Some(
+            vec![scval::symbol("Other")?, scval::symbol(&self.asset)?]
+                .try_into()
+                .map_err(RpcError::from)?,
+        ));
+        let data = match read("lastprice", vec![asset])? {
+            ScVal::Void => return Err(error("no price".to_string())),
+            data => data,
+        };
+
+        let decode = || -> Result<(i128, u64), RpcError> {
+            Ok((
+                scval::to_i128(scval::field(&data, "price")?)?,
+                scval::to_u64(scval::field(&data, "timestamp")?)?,
+            ))
+        };
+        let (price, timestamp) = decode().map_err(|e| error(e.to_string()))?;
+        if crate::unix_now().saturating_sub(timestamp) > self.max_age_secs {
+            return Err(error(format!(
+                "price from {timestamp} is older than {}s",
+                self.max_age_secs
+            )));
+        }
+        rescale(price, decimals)
+            .ok_or_else(|| error(format!("cannot rescale {price} from {decimals} decimals")))
+    }
+}
+
+/// Constant price
+pub struct FixedSource {
+    price: i128,
+}
+
+impl PriceSource for FixedSource {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    fn fetch(&self, _chain: &dyn ContractReader) -> Result<i128, FeedError> {
+        Ok(self.price)
+    }
+}
+
+/// Extracts the price at `pointer` from a JSON body
+fn price_at(body: &str, pointer: &str) -> Result<i128, String> {
+    let json: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let text = match json.pointer(pointer) {
+        Some(serde_json::Value::Number(n)) => n.to_string(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        _ => return Err(format!("no price at `{pointer}`")),
+    };
+    parse_decimal(&text)
+        .filter(|&p| p > 0)
+        .ok_or_else(|| format!("invalid price `{text}`"))
+}
+
+/// Parses a decimal (`0.1234`, `12`, `1.5e-3`) into PRICE_SCALE units,
+/// truncating digits beyond the seventh decimal
+pub fn parse_decimal(text: &str) -> Option<i128> {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((m, e)) => (m, e.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    // digits * 10^(exponent - fraction digits + 7)
+    let digits = format!("{whole}{fraction}");
+    let digits: i128 = match digits.trim_start_matches('0') {
+        "" => 0,
+        significant => significant.parse().ok()?,
+    };
+    let shift = exponent - fraction.len() as i32 + PRICE_DECIMALS as i32;
+    if shift >= 0 {
+        digits.checked_mul(10i128.checked_pow(shift as u32)?)
+    } else {
+        Some(
+            10i128
+                .checked_pow(shift.unsigned_abs())
+                .map_or(0, |d| digits / d),
+        )
+    }
+}
+
+/// Converts a price with `decimals` decimals to PRICE_SCALE
+fn rescale(price: i128, decimals: u32) -> Option<i128> {
+    let unit = 10i128.checked_pow(decimals)?;
+    mul_div(price, PRICE_SCALE, unit, Rounding::Down).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_rpc::xdr::{ScMap, ScMapEntry};
+    use std::cell::RefCell;
+
+    /// Answers `decimals` and `lastprice` like a Reflector feed
+    struct Feed {
+        price: i128,
+        timestamp: u64,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl ContractReader for Feed {
+        fn read(&self, call: &ContractCall) -> Result<ScVal, RpcError> {
+            self.calls.borrow_mut().push(call.function.clone());
+            Ok(match call.function.as_str() {
+                "decimals" => scval::u32(14),
+                _ => {
+                    let entry = |k: &str, v: ScVal| ScMapEntry {
+                        key: scval::symbol(k).unwrap(),
+                        val: v,
+                    };
+                    ScVal::Map(Some(ScMap(
+                        vec![
+                            entry("price", scval::i128(self.price)),
+                            entry("timestamp", scval::u64(self.timestamp)),
+                        ]
+                        .try_into()
+                        .unwrap(),
+                    )))
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("1"), Some(PRICE_SCALE));
+        assert_eq!(parse_decimal("0.1234"), Some(1_234_000));
+        assert_eq!(parse_decimal("0.123456789"), Some(1_234_567));
+        assert_eq!(parse_decimal("1.5e-3"), Some(15_000));
+        assert_eq!(parse_decimal("2E2"), Some(200 * PRICE_SCALE));
+        assert_eq!(parse_decimal(".5"), Some(5_000_000));
+        assert_eq!(parse_decimal("-1"), None);
+        assert_eq!(parse_decimal("abc"), None);
+        assert_eq!(parse_decimal(""), None);
+    }
+
+    #[test]
+    fn test_price_at_pointer() {
+        let body = r#"{"stellar":{"usd":0.1087},"usd-coin":{"usd":"0.9998"}}"#;
+        assert_eq!(price_at(body, "/stellar/usd"), Ok(1_087_000));
+        assert_eq!(price_at(body, "/usd-coin/usd"), Ok(9_998_000));
+        assert!(price_at(body, "/bitcoin/usd").is_err());
+    }
+
+    #[test]
+    fn test_reflector_source_rescales_and_rejects_stale() {
+        let source = ReflectorSource {
+            name: "reflector:XLM".to_string(),
+            contract: "CREFLECTOR".to_string(),
+            asset: "XLM".to_string(),
+            max_age_secs: 600,
+        };
+
+        // $0.1087 at 14 decimals
+        let fresh = Feed {
+            price: 10_870_000_000_000,
+            timestamp: crate::unix_now(),
+            calls: RefCell::new(vec![]),
+        };
+        assert_eq!(source.fetch(&fresh).unwrap(), 1_087_000);
+        assert_eq!(*fresh.calls.borrow(), ["decimals", "lastprice"]);
+
+        let stale = Feed {
+            price: 10_870_000_000_000,
+            timestamp: crate::unix_now() - 601,
+            calls: RefCell::new(vec![]),
+        };
+        assert!(matches!(
+            source.fetch(&stale),
+            Err(FeedError::Source { .. })
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+//! Read-only HTTP API over the indexed state
+//!
+//! ```text
+//! GET /health                    last indexed ledger and record count
+//! GET /positions                 every known account's balances
+//! GET /positions/<address>       one account, with its event history
+//! GET /tvl                       current TVL by asset and in USD
+//! GET /tvl/history?limit=N       TVL after each ledger with activity
+//! GET /events?type=T&limit=N     latest records, optionally of one type
+//! ```
+//!
+//! Amounts are strings in token base units; USD values are scaled by 1e7.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::store::{Position, State, Store};
+
+/// Entries returned when a request sets no `limit`
+const DEFAULT_LIMIT: usize = 100;
+
+/// Serves requests until the listener fails, one thread per connection
+pub fn serve(listener: TcpListener, store: Arc<Mutex<Store>>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let store = Arc::clone(&store);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &store) {
+                eprintln!("api: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, store: &Mutex<Store>) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+
+    let (status, body) = if method == "GET" {
+        let store = store.lock().unwrap_or_else(|e| e.into_inner());
+        route(&store.state, target)
+    } else {
+        (405, json!({ "error": "only GET is supported" }))
+    };
+
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Bad Request",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\n\
+         Access-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Answers a GET for `target` (path and query)
+pub fn route(state: &State, target: &str) -> (u16, Value) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query: BTreeMap<&str, &str> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    let limit = match query.get("limit").map(|l| l.parse::<usize>()) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => return (400, json!({ "error": "invalid limit" })),
+    };
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["health"] => (
+            200,
+            json!({ "ledger": state.last_ledger(), "records": state.records.len() }),
+        ),
+        ["positions"] => (
+            200,
+            state
+                .positions
+                .iter()
+                .map(|(address, p)| position_json(address, p))
+                .collect(),
+        ),
+        ["positions", address] => match state.positions.get(*address) {
+            Some(position) => {
+                let mut body = position_json(address, position);
+                body["history"] = latest(
+                    state
+                        .records
+                        .iter()
+                        .filter(|r| r.kind.users().contains(address)),
+                    limit,
+                );
+                (200, body)
+            }
+            None => (404, json!({ "error": "no events for this account" })),
+        },
+        ["tvl"] => {
+            let (assets, usd) = state.tvl();
+            let assets: BTreeMap<&String, Value> = state
+                .totals
+                .iter()
+                .map(|(asset, t)| {
+                    (
+                        asset,
+                        json!({
+                            "tvl": assets[asset].to_string(),
+                            "supplied": t.supplied.to_string(),
+                            "collateral": t.collateral.to_string(),
+                            "borrowed": t.borrowed.to_string(),
+                        }),
+                    )
+                })
+                .collect();
+            (
+                200,
+                json!({
+                    "ledger": state.last_ledger(),
+                    "assets": assets,
+                    "usd": usd.map(|v| v.to_string()),
+                }),
+            )
+        }
+        ["tvl", "history"] => {
+            let start = state.tvl_history.len().saturating_sub(limit);
+            let points: Vec<Value> = state.tvl_history[start..]
+                .iter()
+                .map(|p| {
+                    json!({
+                        "ledger": p.ledger,
+                        "closed_at": p.closed_at,
+                        "assets": amounts(&p.assets),
+                        "usd": p.usd.map(|v| v.to_string()),
+                    })
+                })
+                .collect();
+            (200, Value::Array(points))
+        }
+        ["events"] => {
+            let kind = query.get("type").copied();
+            (
+                200,
+                latest(
+                    state
+                        .records
+                        .iter()
+                        .filter(|r| kind.is_none_or(|k| r.kind.name() == k)),
+                    limit,
+                ),
+            )
+        }
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
+
+/// The last `limit` records, newest first
+fn latest<'a, I>(records: I, limit: usize) -> Value
+where
+    I: DoubleEndedIterator<Item = &'a crate::records::Record>,
+{
+    records
+        .rev()
+        .take(limit)
+        .map(|r| serde_json::to_value(r).expect("records serialize"))
+        .collect()
+}
+
+fn position_json(address: &str, position: &Position) -> Value {
+    json!({
+        "address": address,
+        "shares": amounts(&position.shares),
+        "collateral": amounts(&position.collateral),
+        "debt": amounts(&position.debt),
+        "updated_ledger": position.updated_ledger,
+    })
+}
+
+fn amounts(map: &BTreeMap<String, i128>) -> Value {
+    map.iter()
+        .map(|(asset, amount)| (asset.clone(), Value::String(amount.to_string())))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::records::test::{collateral_deposit, token, user};
+    use crate::records::{decode, Record, RecordKind};
+    use crate::store::test::reserves;
+
+    #[test]
+    fn test_routes() {
+        let mut state = State::new(reserves());
+        state.apply(
+            decode(&collateral_deposit("0001", 20_000_000, 20_000_000))
+                .unwrap()
+                .unwrap(),
+        );
+
+        let (status, body) = route(&state, &format!("/positions/{}", user()));
+        assert_eq!(status, 200);
+        assert_eq!(body["collateral"]["XLM"], "20000000");
+        assert_eq!(body["history"][0]["type"], "collateral_deposit");
+
+        // 2 XLM, unpriced, then at $0.12
+        assert_eq!(route(&state, "/tvl").1["usd"], Value::Null);
+        state.apply(Record {
+            id: "0002".to_string(),
+            ledger: 101,
+            closed_at: String::new(),
+            tx_hash: String::new(),
+            kind: RecordKind::PriceUpdate {
+                token: token(),
+                price: 1_200_000,
+            },
+        });
+        let (_, tvl) = route(&state, "/tvl");
+        assert_eq!(tvl["assets"]["XLM"]["tvl"], "20000000");
+        assert_eq!(tvl["usd"], "2400000");
+
+        assert_eq!(
+            route(&state, "/events?type=price_update")
+                .1
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(route(&state, "/events?limit=0").1, json!([]));
+        assert_eq!(route(&state, "/tvl/history").1.as_array().unwrap().len(), 1);
+        assert_eq!(route(&state, "/events?limit=x").0, 400);
+        assert_eq!(route(&state, "/positions/GNOBODY").0, 404);
+        assert_eq!(route(&state, "/nope").0, 404);
+    }
+}
@@ -0,0 +1,84 @@
+//! Event polling: getEvents -> decode -> store
+
+use std::sync::{Arc, Mutex};
+
+use apogee_rpc::{EventFilter, RpcClient, RpcError, Transport};
+
+use crate::records::decode;
+use crate::store::Store;
+
+/// Events requested per `getEvents` page
+const EVENTS_PAGE_LIMIT: u32 = 200;
+
+/// Follows the pool and oracle event streams into a [`Store`]
+pub struct Indexer<'a, T: Transport> {
+    rpc: &'a RpcClient<T>,
+    filter: EventFilter,
+    start_ledger: Option<u32>,
+    store: Arc<Mutex<Store>>,
+}
+
+impl<'a, T: Transport> Indexer<'a, T> {
+    pub fn new(
+        rpc: &'a RpcClient<T>,
+        contract_ids: Vec<String>,
+        start_ledger: Option<u32>,
+        store: Arc<Mutex<Store>>,
+    ) -> Self {
+        Indexer {
+            rpc,
+            filter: EventFilter {
+                contract_ids,
+                topics: vec![],
+            },
+            start_ledger,
+            store,
+        }
+    }
+
+    /// Reads all new events; returns the number of records stored
+    ///
+    /// Without a saved cursor or `START_LEDGER` indexing starts at the
+    /// current tip.
+    pub fn poll(&mut self) -> Result<usize, RpcError> {
+        let mut cursor = self.lock().cursor().map(str::to_string);
+        if cursor.is_none() && self.start_ledger.is_none() {
+            self.start_ledger = Some(self.rpc.get_latest_ledger()?.sequence);
+        }
+
+        let mut stored = 0;
+        loop {
+            let page = self.rpc.get_events(
+                self.start_ledger,
+                cursor.as_deref(),
+                std::slice::from_ref(&self.filter),
+                EVENTS_PAGE_LIMIT,
+            )?;
+
+            let mut store = self.lock();
+            for event in &page.events {
+                match decode(event) {
+                    Ok(Some(record)) => {
+                        if store.append(record).map_err(RpcError::Io)? {
+                            stored += 1;
+                        }
+                    }
+                    Ok(None) => {}
+                    // Keep indexing past an event this version cannot read
+                    Err(e) => eprintln!("skipping event {}: {e}", event.id),
+                }
+            }
+            if let Some(next) = page.cursor {
+                store.set_cursor(&next).map_err(RpcError::Io)?;
+                cursor = Some(next);
+            }
+            if page.events.len() < EVENTS_PAGE_LIMIT as usize {
+                return Ok(stored);
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,102 @@
+//! # Stellend Indexer
+//!
+//! Indexes pool and oracle events and serves positions and TVL history
+//! over a small JSON API.
+//!
+//! ```text
+//! every poll:
+//!   getEvents(pool, oracle)   supply/withdraw/coll_dep/coll_wth/borrow/
+//!                             repay/liquidate/set_price
+//!   decode                    -> Record (records.rs)
+//!   append                    DATA_DIR/records.jsonl, then DATA_DIR/cursor
+//!   apply                     positions, reserve totals, prices, TVL history
+//! ```
+//!
+//! Records are stored as JSON lines and replayed into memory on startup.
+//! The log is the database: back it up, or delete the data directory to
+//! re-index from `START_LEDGER`. Reserve totals are net principal flows, so
+//! TVL excludes interest accrued since the last event. The API is
+//! described in [`api`].
+
+use std::fmt;
+use std::time::Duration;
+
+pub mod api;
+mod indexer;
+mod records;
+mod store;
+
+pub use indexer::Indexer;
+pub use records::{decode, Record, RecordKind};
+pub use store::{Position, ReserveInfo, ReserveTotals, State, Store, TvlPoint};
+
+/// Indexer settings, read from the environment
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// `RPC_URL`: `http://` Soroban RPC endpoint
+    pub rpc_url: String,
+    /// `NETWORK_PASSPHRASE` (default: testnet)
+    pub network_passphrase: String,
+    /// `POOL_ID`: pool contract `C...`
+    pub pool_id: String,
+    /// `ORACLE_ID`: price oracle contract `C...`
+    pub oracle_id: String,
+    /// `DATA_DIR` (default `indexer-data`)
+    pub data_dir: String,
+    /// `LISTEN_ADDR` for the API (default `127.0.0.1:3030`)
+    pub listen_addr: String,
+    /// `POLL_INTERVAL_SECS` (default 5)
+    pub poll_interval: Duration,
+    /// `START_LEDGER`: first ledger to index when there is no saved cursor
+    /// (default: latest)
+    pub start_ledger: Option<u32>,
+}
+
+/// Invalid or missing setting
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError(pub String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let required =
+            |key: &str| lookup(key).ok_or_else(|| ConfigError(format!("{key} is not set")));
+        fn parsed<V: std::str::FromStr>(
+            key: &str,
+            value: Option<String>,
+        ) -> Result<Option<V>, ConfigError> {
+            value
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| ConfigError(format!("invalid {key} `{v}`")))
+                })
+                .transpose()
+        }
+
+        Ok(Config {
+            rpc_url: required("RPC_URL")?,
+            network_passphrase: lookup("NETWORK_PASSPHRASE")
+                .unwrap_or_else(|| apogee_rpc::TESTNET_PASSPHRASE.to_string()),
+            pool_id: required("POOL_ID")?,
+            oracle_id: required("ORACLE_ID")?,
+            data_dir: lookup("DATA_DIR").unwrap_or_else(|| "indexer-data".to_string()),
+            listen_addr: lookup("LISTEN_ADDR").unwrap_or_else(|| "127.0.0.1:3030".to_string()),
+            poll_interval: Duration::from_secs(
+                parsed("POLL_INTERVAL_SECS", lookup("POLL_INTERVAL_SECS"))?.unwrap_or(5),
+            ),
+            start_ledger: parsed("START_LEDGER", lookup("START_LEDGER"))?,
+        })
+    }
+}
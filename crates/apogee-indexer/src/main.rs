@@ -0,0 +1,83 @@
+//! `apogee-indexer` — configured through environment variables, see [`Config`]
+
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use apogee_indexer::{api, Config, Indexer, ReserveInfo, State, Store};
+use apogee_rpc::{Invoker, RpcClient, Signer};
+use apogee_sdk::PoolClient;
+
+fn main() -> ExitCode {
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rpc = match RpcClient::new(&config.rpc_url) {
+        Ok(rpc) => rpc,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let setup = || -> Result<_, Box<dyn std::error::Error>> {
+        // Token ids and decimals for USD valuation
+        let invoker = Invoker::new(&rpc, &config.network_passphrase, Signer::read_only());
+        let pool = PoolClient::new(&invoker, &config.pool_id);
+        let mut reserves = BTreeMap::new();
+        for asset in pool.list_reserves()? {
+            let reserve = pool.get_reserve(&asset)?;
+            reserves.insert(
+                asset,
+                ReserveInfo {
+                    token: reserve.token,
+                    decimals: reserve.decimals,
+                },
+            );
+        }
+        let store = Store::open(Path::new(&config.data_dir), State::new(reserves))?;
+        let listener = TcpListener::bind(&config.listen_addr)?;
+        Ok((Arc::new(Mutex::new(store)), listener))
+    };
+    let (store, listener) = match setup() {
+        Ok(parts) => parts,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!(
+        "indexing pool {} and oracle {}, serving on {}",
+        config.pool_id, config.oracle_id, config.listen_addr
+    );
+    let api_store = Arc::clone(&store);
+    thread::spawn(move || {
+        if let Err(e) = api::serve(listener, api_store) {
+            eprintln!("api stopped: {e}");
+        }
+    });
+
+    let mut indexer = Indexer::new(
+        &rpc,
+        vec![config.pool_id.clone(), config.oracle_id.clone()],
+        config.start_ledger,
+        store,
+    );
+    loop {
+        match indexer.poll() {
+            Ok(0) => {}
+            Ok(stored) => eprintln!("stored {stored} records"),
+            Err(e) => eprintln!("poll failed: {e}"),
+        }
+        thread::sleep(config.poll_interval);
+    }
+}
@@ -0,0 +1,350 @@
+//! Decoding pool and oracle events into stored records
+
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, Event, RpcError};
+use serde::{Deserialize, Serialize};
+
+/// One indexed event
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Record {
+    /// RPC event id; ids sort in chain order
+    pub id: String,
+    pub ledger: u32,
+    pub closed_at: String,
+    pub tx_hash: String,
+    #[serde(flatten)]
+    pub kind: RecordKind,
+}
+
+/// Decoded event payloads; amounts are token base units, serialized as
+/// strings so JSON consumers do not lose precision
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordKind {
+    Supply {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        /// sToken balance after the action
+        #[serde(with = "amount")]
+        shares: i128,
+    },
+    Withdraw {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        #[serde(with = "amount")]
+        shares: i128,
+    },
+    CollateralDeposit {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        #[serde(with = "amount")]
+        balance: i128,
+    },
+    CollateralWithdraw {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        #[serde(with = "amount")]
+        balance: i128,
+    },
+    Borrow {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        /// Debt after the action, including accrued interest
+        #[serde(with = "amount")]
+        debt: i128,
+    },
+    Repay {
+        user: String,
+        asset: String,
+        #[serde(with = "amount")]
+        amount: i128,
+        #[serde(with = "amount")]
+        debt: i128,
+    },
+    Liquidation {
+        liquidator: String,
+        borrower: String,
+        repay_asset: String,
+        collateral_asset: String,
+        #[serde(with = "amount")]
+        repay_amount: i128,
+        /// Collateral taken from the borrower, including the protocol fee
+        #[serde(with = "amount")]
+        collateral_seized: i128,
+        /// Borrower's debt in `repay_asset` afterwards
+        #[serde(with = "amount")]
+        debt: i128,
+    },
+    PriceUpdate {
+        /// Token contract `C...`
+        token: String,
+        /// USD, scaled by 1e7
+        #[serde(with = "amount")]
+        price: i128,
+    },
+}
+
+impl RecordKind {
+    /// Accounts the record concerns
+    pub fn users(&self) -> Vec<&str> {
+        match self {
+            RecordKind::Supply { user, .. }
+            | RecordKind::Withdraw { user, .. }
+            | RecordKind::CollateralDeposit { user, .. }
+            | RecordKind::CollateralWithdraw { user, .. }
+            | RecordKind::Borrow { user, .. }
+            | RecordKind::Repay { user, .. } => vec![user],
+            RecordKind::Liquidation {
+                liquidator,
+                borrower,
+                ..
+            } => vec![liquidator, borrower],
+            RecordKind::PriceUpdate { .. } => vec![],
+        }
+    }
+
+    /// `type` tag, as used in the JSON encoding
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecordKind::Supply { .. } => "supply",
+            RecordKind::Withdraw { .. } => "withdraw",
+            RecordKind::CollateralDeposit { .. } => "collateral_deposit",
+            RecordKind::CollateralWithdraw { .. } => "collateral_withdraw",
+            RecordKind::Borrow { .. } => "borrow",
+            RecordKind::Repay { .. } => "repay",
+            RecordKind::Liquidation { .. } => "liquidation",
+            RecordKind::PriceUpdate { .. } => "price_update",
+        }
+    }
+}
+
+/// Decodes an event from the pool or oracle; other events (admin, config,
+/// flash loans, ...) are not indexed and yield `None`
+pub fn decode(event: &Event) -> Result<Option<Record>, RpcError> {
+    let Some(name) = event.topics.first().and_then(|t| scval::to_symbol(t).ok()) else {
+        return Ok(None);
+    };
+    let value = &event.value;
+    let text = |name: &str| -> Result<String, RpcError> {
+        let field = scval::field(value, name)?;
+        match field {
+            ScVal::Symbol(_) => scval::to_symbol(field),
+            _ => scval::to_address(field),
+        }
+    };
+    let int = |name: &str| scval::to_i128(scval::field(value, name)?);
+
+    let kind = match name.as_str() {
+        "supply" | "withdraw" => {
+            let (user, asset, amount, shares) = (
+                text("user")?,
+                text("asset")?,
+                int("amount")?,
+                int("new_balance")?,
+            );
+            if name == "supply" {
+                RecordKind::Supply {
+                    user,
+                    asset,
+                    amount,
+                    shares,
+                }
+            } else {
+                RecordKind::Withdraw {
+                    user,
+                    asset,
+                    amount,
+                    shares,
+                }
+            }
+        }
+        "coll_dep" | "coll_wth" => {
+            let (user, asset, amount, balance) = (
+                text("user")?,
+                text("asset")?,
+                int("amount")?,
+                int("new_balance")?,
+            );
+            if name == "coll_dep" {
+                RecordKind::CollateralDeposit {
+                    user,
+                    asset,
+                    amount,
+                    balance,
+                }
+            } else {
+                RecordKind::CollateralWithdraw {
+                    user,
+                    asset,
+                    amount,
+                    balance,
+                }
+            }
+        }
+        "borrow" | "repay" => {
+            let (user, asset, amount, debt) = (
+                text("user")?,
+                text("asset")?,
+                int("amount")?,
+                int("new_debt")?,
+            );
+            if name == "borrow" {
+                RecordKind::Borrow {
+                    user,
+                    asset,
+                    amount,
+                    debt,
+                }
+            } else {
+                RecordKind::Repay {
+                    user,
+                    asset,
+                    amount,
+                    debt,
+                }
+            }
+        }
+        "liquidate" => RecordKind::Liquidation {
+            liquidator: text("liquidator")?,
+            borrower: text("borrower")?,
+            repay_asset: text("repay_asset")?,
+            collateral_asset: text("collateral_asset")?,
+            repay_amount: int("repay_amount")?,
+            collateral_seized: int("collateral_seized")? + int("protocol_fee")?,
+            debt: int("new_debt")?,
+        },
+        // `(set_price, token) -> price` or `-> (price, expires_at)`
+        "set_price" => RecordKind::PriceUpdate {
+            token: scval::to_address(event.topics.get(1).unwrap_or(&ScVal::Void))?,
+            price: match value {
+                ScVal::Vec(_) => scval::to_i128(
+                    scval::to_vec(value)?
+                        .first()
+                        .ok_or_else(|| RpcError::Xdr("empty set_price event".to_string()))?,
+                )?,
+                _ => scval::to_i128(value)?,
+            },
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Record {
+        id: event.id.clone(),
+        ledger: event.ledger,
+        closed_at: event.ledger_closed_at.clone(),
+        tx_hash: event.tx_hash.clone(),
+        kind,
+    }))
+}
+
+/// `i128` as a decimal string
+mod amount {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use apogee_rpc::xdr::{ScMap, ScMapEntry};
+
+    pub(crate) fn user() -> String {
+        stellar_strkey::ed25519::PublicKey([1; 32]).to_string()
+    }
+
+    pub(crate) fn token() -> String {
+        stellar_strkey::Contract([7; 32]).to_string()
+    }
+
+    pub(crate) fn event(id: &str, topics: Vec<ScVal>, fields: Vec<(&str, ScVal)>) -> Event {
+        let entries: Vec<ScMapEntry> = fields
+            .into_iter()
+            .map(|(k, val)| ScMapEntry {
+                key: scval::symbol(k).unwrap(),
+                val,
+            })
+            .collect();
+        Event {
+            id: id.to_string(),
+            ledger: 100,
+            ledger_closed_at: "2025-11-30T01:17:59Z".to_string(),
+            contract_id: token(),
+            tx_hash: "ab".to_string(),
+            topics,
+            value: ScVal::Map(Some(ScMap(entries.try_into().unwrap()))),
+        }
+    }
+
+    pub(crate) fn collateral_deposit(id: &str, amount: i128, balance: i128) -> Event {
+        event(
+            id,
+            vec![scval::symbol("coll_dep").unwrap()],
+            vec![
+                ("amount", scval::i128(amount)),
+                ("asset", scval::symbol("XLM").unwrap()),
+                ("new_balance", scval::i128(balance)),
+                ("user", scval::address(&user()).unwrap()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_decode_events() {
+        let record = decode(&collateral_deposit("1", 50, 150)).unwrap().unwrap();
+        assert_eq!(
+            record.kind,
+            RecordKind::CollateralDeposit {
+                user: user(),
+                asset: "XLM".to_string(),
+                amount: 50,
+                balance: 150
+            }
+        );
+        assert_eq!(record.closed_at, "2025-11-30T01:17:59Z");
+
+        // Amounts round-trip through JSON as strings
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["type"], "collateral_deposit");
+        assert_eq!(json["amount"], "50");
+        assert_eq!(serde_json::from_value::<Record>(json).unwrap(), record);
+
+        let mut price = event(
+            "2",
+            vec![
+                scval::symbol("set_price").unwrap(),
+                scval::address(&token()).unwrap(),
+            ],
+            vec![],
+        );
+        price.value = scval::i128(1_200_000);
+        assert_eq!(
+            decode(&price).unwrap().unwrap().kind,
+            RecordKind::PriceUpdate {
+                token: token(),
+                price: 1_200_000
+            }
+        );
+
+        let admin = event("3", vec![scval::symbol("set_ltv").unwrap()], vec![]);
+        assert_eq!(decode(&admin).unwrap(), None);
+    }
+}
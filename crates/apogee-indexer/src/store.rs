@@ -0,0 +1,342 @@
+//! Append-only record log and the state derived from it
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::records::{Record, RecordKind};
+
+const LOG_FILE: &str = "records.jsonl";
+const CURSOR_FILE: &str = "cursor";
+
+/// Listed reserve, for USD valuation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReserveInfo {
+    /// Token contract `C...`, the key of its oracle price
+    pub token: String,
+    pub decimals: u32,
+}
+
+/// An account's balances as of its latest event
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    /// sToken balances by asset
+    pub shares: BTreeMap<String, i128>,
+    pub collateral: BTreeMap<String, i128>,
+    /// Debt including interest accrued up to the last borrow/repay/liquidation
+    pub debt: BTreeMap<String, i128>,
+    pub updated_ledger: u32,
+}
+
+/// Net principal flows into a reserve; accrued interest is not included
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ReserveTotals {
+    pub supplied: i128,
+    pub collateral: i128,
+    pub borrowed: i128,
+}
+
+/// Total value locked after a ledger with pool activity
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TvlPoint {
+    pub ledger: u32,
+    pub closed_at: String,
+    /// Supplied plus collateral, by asset
+    pub assets: BTreeMap<String, i128>,
+    /// USD value scaled by 1e7; `None` until every held asset has a price
+    pub usd: Option<i128>,
+}
+
+/// Everything the API serves, rebuilt from the log on startup
+#[derive(Debug, Default)]
+pub struct State {
+    pub reserves: BTreeMap<String, ReserveInfo>,
+    pub records: Vec<Record>,
+    pub positions: BTreeMap<String, Position>,
+    pub totals: BTreeMap<String, ReserveTotals>,
+    /// Latest oracle price by token
+    pub prices: BTreeMap<String, i128>,
+    pub tvl_history: Vec<TvlPoint>,
+}
+
+impl State {
+    pub fn new(reserves: BTreeMap<String, ReserveInfo>) -> Self {
+        State {
+            reserves,
+            ..Default::default()
+        }
+    }
+
+    pub fn last_ledger(&self) -> u32 {
+        self.records.last().map_or(0, |r| r.ledger)
+    }
+
+    pub fn apply(&mut self, record: Record) {
+        let ledger = record.ledger;
+        let mut flows = true;
+        match &record.kind {
+            RecordKind::Supply {
+                user,
+                asset,
+                amount,
+                shares,
+            }
+            | RecordKind::Withdraw {
+                user,
+                asset,
+                amount,
+                shares,
+            } => {
+                let supply = matches!(record.kind, RecordKind::Supply { .. });
+                self.position(user, ledger)
+                    .shares
+                    .insert(asset.clone(), *shares);
+                self.totals.entry(asset.clone()).or_default().supplied +=
+                    if supply { *amount } else { -amount };
+            }
+            RecordKind::CollateralDeposit {
+                user,
+                asset,
+                amount,
+                balance,
+            }
+            | RecordKind::CollateralWithdraw {
+                user,
+                asset,
+                amount,
+                balance,
+            } => {
+                let deposit = matches!(record.kind, RecordKind::CollateralDeposit { .. });
+                self.position(user, ledger)
+                    .collateral
+                    .insert(asset.clone(), *balance);
+                self.totals.entry(asset.clone()).or_default().collateral +=
+                    if deposit { *amount } else { -amount };
+            }
+            RecordKind::Borrow {
+                user,
+                asset,
+                amount,
+                debt,
+            }
+            | RecordKind::Repay {
+                user,
+                asset,
+                amount,
+                debt,
+            } => {
+                let borrow = matches!(record.kind, RecordKind::Borrow { .. });
+                self.position(user, ledger)
+                    .debt
+                    .insert(asset.clone(), *debt);
+                self.totals.entry(asset.clone()).or_default().borrowed +=
+                    if borrow { *amount } else { -amount };
+            }
+            RecordKind::Liquidation {
+                borrower,
+                repay_asset,
+                collateral_asset,
+                repay_amount,
+                collateral_seized,
+                debt,
+                ..
+            } => {
+                let position = self.position(borrower, ledger);
+                position.debt.insert(repay_asset.clone(), *debt);
+                if let Some(balance) = position.collateral.get_mut(collateral_asset) {
+                    *balance -= collateral_seized;
+                }
+                self.totals.entry(repay_asset.clone()).or_default().borrowed -= repay_amount;
+                self.totals
+                    .entry(collateral_asset.clone())
+                    .or_default()
+                    .collateral -= collateral_seized;
+            }
+            RecordKind::PriceUpdate { token, price } => {
+                self.prices.insert(token.clone(), *price);
+                flows = false;
+            }
+        }
+        if flows {
+            self.record_tvl(ledger, &record.closed_at);
+        }
+        self.records.push(record);
+    }
+
+    fn position(&mut self, user: &str, ledger: u32) -> &mut Position {
+        let position = self.positions.entry(user.to_string()).or_default();
+        position.updated_ledger = ledger;
+        position
+    }
+
+    /// Current TVL by asset, and in USD if every held asset is priced
+    pub fn tvl(&self) -> (BTreeMap<String, i128>, Option<i128>) {
+        let assets: BTreeMap<String, i128> = self
+            .totals
+            .iter()
+            .map(|(asset, t)| (asset.clone(), t.supplied + t.collateral))
+            .collect();
+        let usd = assets.iter().try_fold(0i128, |sum, (asset, amount)| {
+            if *amount == 0 {
+                return Some(sum);
+            }
+            let reserve = self.reserves.get(asset)?;
+            let price = self.prices.get(&reserve.token)?;
+            Some(sum + amount * price / 10i128.pow(reserve.decimals))
+        });
+        (assets, usd)
+    }
+
+    /// One point per ledger: a later event in the same ledger replaces it
+    fn record_tvl(&mut self, ledger: u32, closed_at: &str) {
+        let (assets, usd) = self.tvl();
+        let point = TvlPoint {
+            ledger,
+            closed_at: closed_at.to_string(),
+            assets,
+            usd,
+        };
+        match self.tvl_history.last_mut() {
+            Some(last) if last.ledger == ledger => *last = point,
+            _ => self.tvl_history.push(point),
+        }
+    }
+}
+
+/// Records persisted as JSON lines in a data directory, with the RPC
+/// event cursor next to them
+pub struct Store {
+    dir: PathBuf,
+    log: File,
+    cursor: Option<String>,
+    pub state: State,
+}
+
+impl Store {
+    /// Opens or creates the store, replaying the log into `state`
+    pub fn open(dir: &Path, mut state: State) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let log_path = dir.join(LOG_FILE);
+        if log_path.exists() {
+            let lines: Vec<String> = BufReader::new(File::open(&log_path)?)
+                .lines()
+                .collect::<Result<_, _>>()?;
+            for (i, line) in lines.iter().enumerate() {
+                match serde_json::from_str::<Record>(line) {
+                    Ok(record) => state.apply(record),
+                    // A crash mid-append leaves a torn last line; the
+                    // event is read again after the saved cursor
+                    Err(_) if i + 1 == lines.len() => {}
+                    Err(e) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{}:{}: {e}", log_path.display(), i + 1),
+                        ))
+                    }
+                }
+            }
+        }
+        let cursor = match fs::read_to_string(dir.join(CURSOR_FILE)) {
+            Ok(cursor) => Some(cursor.trim().to_string()).filter(|c| !c.is_empty()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        Ok(Store {
+            dir: dir.to_path_buf(),
+            log: OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)?,
+            cursor,
+            state,
+        })
+    }
+
+    /// Persists and applies a record; records at or before the last stored
+    /// one are skipped, so re-reading events after a restart is harmless
+    pub fn append(&mut self, record: Record) -> io::Result<bool> {
+        if self
+            .state
+            .records
+            .last()
+            .is_some_and(|last| record.id <= last.id)
+        {
+            return Ok(false);
+        }
+        let line = serde_json::to_string(&record).map_err(io::Error::other)?;
+        writeln!(self.log, "{line}")?;
+        self.log.flush()?;
+        self.state.apply(record);
+        Ok(true)
+    }
+
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// Saves the RPC cursor; written after the records it covers
+    pub fn set_cursor(&mut self, cursor: &str) -> io::Result<()> {
+        let tmp = self.dir.join(format!("{CURSOR_FILE}.tmp"));
+        fs::write(&tmp, cursor)?;
+        fs::rename(tmp, self.dir.join(CURSOR_FILE))?;
+        self.cursor = Some(cursor.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::records::decode;
+    use crate::records::test::{collateral_deposit, token, user};
+
+    pub(crate) fn reserves() -> BTreeMap<String, ReserveInfo> {
+        BTreeMap::from([(
+            "XLM".to_string(),
+            ReserveInfo {
+                token: token(),
+                decimals: 7,
+            },
+        )])
+    }
+
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("apogee-indexer-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_store_replays_and_skips_duplicates() {
+        let dir = temp_dir("store");
+        let record = |event| decode(&event).unwrap().unwrap();
+
+        let mut store = Store::open(&dir, State::new(reserves())).unwrap();
+        assert!(store
+            .append(record(collateral_deposit("0001", 100, 100)))
+            .unwrap());
+        assert!(store
+            .append(record(collateral_deposit("0002", 50, 150)))
+            .unwrap());
+        assert!(!store
+            .append(record(collateral_deposit("0002", 50, 150)))
+            .unwrap());
+        store.set_cursor("0002").unwrap();
+        assert_eq!(
+            store.state.tvl(),
+            (BTreeMap::from([("XLM".to_string(), 150)]), None)
+        );
+        drop(store);
+
+        let store = Store::open(&dir, State::new(reserves())).unwrap();
+        assert_eq!(store.cursor(), Some("0002"));
+        assert_eq!(store.state.records.len(), 2);
+        assert_eq!(store.state.positions[&user()].collateral["XLM"], 150);
+        // Both events are in ledger 100: one history point
+        assert_eq!(store.state.tvl_history.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
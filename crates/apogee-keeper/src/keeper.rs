@@ -0,0 +1,269 @@
+//! Poll loop: events -> position refresh -> pricing -> liquidation
+
+use std::collections::BTreeMap;
+
+use apogee_common::SCALE;
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, ContractCall, EventFilter, Invoker, RpcError, Transport};
+
+use crate::positions::{
+    health_factor, plan_liquidation, Liquidation, Position, PositionCache, Reserve,
+};
+use crate::Config;
+
+/// Events requested per `getEvents` page
+const EVENTS_PAGE_LIMIT: u32 = 100;
+
+/// Borrowers requested per `get_borrowers` page
+const BORROWERS_PAGE_LIMIT: u32 = 100;
+
+/// Outcome of one poll
+#[derive(Debug, Default)]
+pub struct PollReport {
+    /// Position events read
+    pub events: usize,
+    /// Positions re-read from the pool
+    pub refreshed: usize,
+    /// Cached positions with debt
+    pub positions: usize,
+    /// Positions whose local health factor is below 1
+    pub unhealthy: usize,
+    /// Submitted liquidations with the collateral seized, or the error
+    pub liquidations: Vec<(Liquidation, Result<i128, RpcError>)>,
+}
+
+/// Liquidation keeper for one pool
+///
+/// Reserves and the close factor are re-read every
+/// `full_refresh_polls` polls, together with every cached position so that
+/// accrued interest is picked up; in between, only users named in new pool
+/// events are re-read.
+pub struct Keeper<'a, T: Transport> {
+    invoker: Invoker<'a, T>,
+    config: Config,
+    cache: PositionCache,
+    reserves: BTreeMap<String, Reserve>,
+    close_factor: i128,
+    cursor: Option<String>,
+    polls: u32,
+}
+
+impl<'a, T: Transport> Keeper<'a, T> {
+    pub fn new(invoker: Invoker<'a, T>, config: Config) -> Self {
+        Keeper {
+            invoker,
+            config,
+            cache: PositionCache::default(),
+            reserves: BTreeMap::new(),
+            close_factor: 0,
+            cursor: None,
+            polls: 0,
+        }
+    }
+
+    pub fn poll(&mut self) -> Result<PollReport, RpcError> {
+        let mut report = PollReport::default();
+
+        if self.reserves.is_empty()
+            || self
+                .polls
+                .is_multiple_of(self.config.full_refresh_polls.max(1))
+        {
+            self.load_reserves()?;
+            self.cache.mark_all_dirty();
+        }
+        if self.polls == 0 {
+            self.load_borrowers()?;
+        }
+        self.polls = self.polls.wrapping_add(1);
+
+        report.events = self.read_events()?;
+
+        for user in self.cache.take_dirty() {
+            let position = self.read_position(&user)?;
+            self.cache.update(user, position);
+            report.refreshed += 1;
+        }
+        report.positions = self.cache.len();
+
+        let prices = self.read_prices()?;
+        let candidates: Vec<Liquidation> = self
+            .cache
+            .iter()
+            .filter(|(_, position)| {
+                health_factor(position, &self.reserves, &prices).is_some_and(|hf| hf < SCALE)
+            })
+            .filter_map(|(user, position)| {
+                plan_liquidation(user, position, &prices, self.close_factor)
+            })
+            .collect();
+        report.unhealthy = candidates.len();
+
+        for liquidation in candidates {
+            // The pool may value collateral at a TWAP; only its own health factor counts
+            if self.on_chain_health_factor(&liquidation.borrower)? >= SCALE {
+                continue;
+            }
+            let result = self.liquidate(&liquidation);
+            // Re-read the borrower next poll whether or not the liquidation landed
+            self.cache.mark_dirty(&liquidation.borrower);
+            report.liquidations.push((liquidation, result));
+        }
+
+        Ok(report)
+    }
+
+    fn pool_call(&self, function: &str, args: Vec<ScVal>) -> ContractCall {
+        ContractCall::new(&self.config.pool_id, function, args)
+    }
+
+    fn load_reserves(&mut self) -> Result<(), RpcError> {
+        let assets = self
+            .invoker
+            .simulate(&self.pool_call("list_reserves", vec![]))?;
+
+        let mut reserves = BTreeMap::new();
+        for asset in scval::to_vec(&assets)? {
+            let config = self
+                .invoker
+                .simulate(&self.pool_call("get_reserve", vec![asset.clone()]))?;
+            reserves.insert(
+                scval::to_symbol(asset)?,
+                Reserve {
+                    token: scval::to_address(scval::field(&config, "token")?)?,
+                    liquidation_threshold: scval::to_i128(scval::field(
+                        &config,
+                        "liquidation_threshold",
+                    )?)?,
+                },
+            );
+        }
+        self.reserves = reserves;
+        self.close_factor = scval::to_i128(
+            &self
+                .invoker
+                .simulate(&self.pool_call("get_close_factor", vec![]))?,
+        )?;
+        Ok(())
+    }
+
+    /// Marks every address in the pool's borrower registry dirty, so a fresh
+    /// keeper sees positions opened before its event window
+    fn load_borrowers(&mut self) -> Result<(), RpcError> {
+        let mut offset = 0;
+        loop {
+            let page = self.invoker.simulate(&self.pool_call(
+                "get_borrowers",
+                vec![scval::u32(offset), scval::u32(BORROWERS_PAGE_LIMIT)],
+            ))?;
+            let borrowers = scval::to_vec(&page)?;
+            for borrower in borrowers {
+                self.cache.mark_dirty(&scval::to_address(borrower)?);
+            }
+            if borrowers.len() < BORROWERS_PAGE_LIMIT as usize {
+                return Ok(());
+            }
+            offset += BORROWERS_PAGE_LIMIT;
+        }
+    }
+
+    /// Reads new pool events and marks the users they touch dirty
+    fn read_events(&mut self) -> Result<usize, RpcError> {
+        let rpc = self.invoker.rpc();
+        let filter = EventFilter {
+            contract_ids: vec![self.config.pool_id.clone()],
+            topics: vec![],
+        };
+
+        if self.cursor.is_none() && self.config.start_ledger.is_none() {
+            // Nothing to backfill from: follow from the current tip
+            self.config.start_ledger = Some(rpc.get_latest_ledger()?.sequence);
+        }
+
+        let mut touched = 0;
+        loop {
+            let page = rpc.get_events(
+                self.config.start_ledger,
+                self.cursor.as_deref(),
+                std::slice::from_ref(&filter),
+                EVENTS_PAGE_LIMIT,
+            )?;
+            for event in &page.events {
+                if self.cache.apply_event(event).is_some() {
+                    touched += 1;
+                }
+            }
+            if page.cursor.is_some() {
+                self.cursor = page.cursor;
+            }
+            if page.events.len() < EVENTS_PAGE_LIMIT as usize {
+                return Ok(touched);
+            }
+        }
+    }
+
+    fn read_position(&self, user: &str) -> Result<Position, RpcError> {
+        let user = scval::address(user)?;
+        let mut position = Position::default();
+
+        let collaterals = self
+            .invoker
+            .simulate(&self.pool_call("get_user_collaterals", vec![user.clone()]))?;
+        for (asset, amount) in scval::to_map(&collaterals)? {
+            position
+                .collateral
+                .insert(scval::to_symbol(asset)?, scval::to_i128(amount)?);
+        }
+
+        for asset in self.reserves.keys() {
+            let call = self.pool_call(
+                "get_user_debt_total",
+                vec![user.clone(), scval::symbol(asset)?],
+            );
+            let debt = scval::to_i128(&self.invoker.simulate(&call)?)?;
+            if debt > 0 {
+                position.debt.insert(asset.clone(), debt);
+            }
+        }
+        Ok(position)
+    }
+
+    fn read_prices(&self) -> Result<BTreeMap<String, i128>, RpcError> {
+        let mut prices = BTreeMap::new();
+        for (asset, reserve) in &self.reserves {
+            let call = ContractCall::new(
+                &self.config.oracle_id,
+                "get_price",
+                vec![scval::address(&reserve.token)?],
+            );
+            prices.insert(
+                asset.clone(),
+                scval::to_i128(&self.invoker.simulate(&call)?)?,
+            );
+        }
+        Ok(prices)
+    }
+
+    fn on_chain_health_factor(&self, borrower: &str) -> Result<i128, RpcError> {
+        let call = self.pool_call("get_health_factor", vec![scval::address(borrower)?]);
+        scval::to_i128(&self.invoker.simulate(&call)?)
+    }
+
+    fn liquidate(&self, liquidation: &Liquidation) -> Result<i128, RpcError> {
+        let call = self.pool_call(
+            "liquidate",
+            vec![
+                scval::address(&self.invoker.source().account_id())?,
+                scval::address(&liquidation.borrower)?,
+                scval::symbol(&liquidation.repay_asset)?,
+                scval::i128(liquidation.repay_amount),
+                scval::symbol(&liquidation.collateral_asset)?,
+                scval::i128(0),
+                ScVal::Void,
+            ],
+        );
+        // invoke simulates first, so a liquidation that would fail costs no fee
+        let seized = self.invoker.invoke(&call)?;
+        seized.as_ref().map(scval::to_i128).unwrap_or(Ok(0))
+    }
+}
@@ -0,0 +1,136 @@
+//! # Stellend Keeper
+//!
+//! Off-chain liquidation bot for the lending pool.
+//!
+//! ```text
+//! first poll:
+//!   get_borrowers            pool's registry of addresses with debt
+//! every poll:
+//!   getEvents(pool)          borrow/repay/coll_dep/coll_wth/... -> user dirty
+//!   simulate pool views      re-read dirty users' collateral and debt
+//!   simulate oracle get_price
+//!   local health factor      sum(collateral * price * threshold) / debt
+//!   HF < 1                   confirm with get_health_factor, then submit
+//!                            liquidate (optionally fee-bumped)
+//! ```
+//!
+//! Existing borrowers come from the pool's borrower registry, so the keeper
+//! can start from the current ledger; later positions are discovered from
+//! pool events. `START_LEDGER` still lets it replay older events, e.g. for
+//! positions untouched since before the registry. The keeper account
+//! repays debt from its own balance and must hold the borrowed assets.
+
+use std::fmt;
+use std::time::Duration;
+
+mod keeper;
+mod positions;
+
+pub use keeper::{Keeper, PollReport};
+pub use positions::{
+    event_user, health_factor, plan_liquidation, Liquidation, Position, PositionCache, Reserve,
+};
+
+/// Keeper settings, read from the environment
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// `RPC_URL`: `http://` Soroban RPC endpoint
+    pub rpc_url: String,
+    /// `NETWORK_PASSPHRASE` (default: testnet)
+    pub network_passphrase: String,
+    /// `POOL_ID`: pool contract `C...`
+    pub pool_id: String,
+    /// `ORACLE_ID`: price oracle contract `C...`
+    pub oracle_id: String,
+    /// `KEEPER_SECRET`: `S...` seed of the liquidating account
+    pub keeper_secret: String,
+    /// `FEE_BUMP_SECRET`: `S...` seed of an account paying fees through fee bumps
+    pub fee_bump_secret: Option<String>,
+    /// `POLL_INTERVAL_SECS` (default 10)
+    pub poll_interval: Duration,
+    /// `START_LEDGER`: first ledger to read events from (default: latest)
+    pub start_ledger: Option<u32>,
+    /// `FULL_REFRESH_POLLS`: polls between full position refreshes (default 30)
+    pub full_refresh_polls: u32,
+}
+
+/// Invalid or missing setting
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError(pub String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the config from a variable lookup
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let required =
+            |key: &str| lookup(key).ok_or_else(|| ConfigError(format!("{key} is not set")));
+        fn parsed<V: std::str::FromStr>(
+            key: &str,
+            value: Option<String>,
+        ) -> Result<Option<V>, ConfigError> {
+            value
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| ConfigError(format!("invalid {key} `{v}`")))
+                })
+                .transpose()
+        }
+
+        Ok(Config {
+            rpc_url: required("RPC_URL")?,
+            network_passphrase: lookup("NETWORK_PASSPHRASE")
+                .unwrap_or_else(|| apogee_rpc::TESTNET_PASSPHRASE.to_string()),
+            pool_id: required("POOL_ID")?,
+            oracle_id: required("ORACLE_ID")?,
+            keeper_secret: required("KEEPER_SECRET")?,
+            fee_bump_secret: lookup("FEE_BUMP_SECRET"),
+            poll_interval: Duration::from_secs(
+                parsed("POLL_INTERVAL_SECS", lookup("POLL_INTERVAL_SECS"))?.unwrap_or(10),
+            ),
+            start_ledger: parsed("START_LEDGER", lookup("START_LEDGER"))?,
+            full_refresh_polls: parsed("FULL_REFRESH_POLLS", lookup("FULL_REFRESH_POLLS"))?
+                .unwrap_or(30),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_config_from_lookup() {
+        let mut vars = HashMap::from([
+            ("RPC_URL", "http://localhost:8000/soroban/rpc"),
+            ("POOL_ID", "CPOOL"),
+            ("ORACLE_ID", "CORACLE"),
+            ("KEEPER_SECRET", "SKEEPER"),
+            ("START_LEDGER", "1200"),
+        ]);
+        let config = Config::from_lookup(|k| vars.get(k).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.network_passphrase, apogee_rpc::TESTNET_PASSPHRASE);
+        assert_eq!(config.poll_interval, Duration::from_secs(10));
+        assert_eq!(config.start_ledger, Some(1200));
+        assert_eq!(config.fee_bump_secret, None);
+
+        vars.insert("POLL_INTERVAL_SECS", "soon");
+        assert!(Config::from_lookup(|k| vars.get(k).map(|v| v.to_string())).is_err());
+        vars.remove("POOL_ID");
+        assert_eq!(
+            Config::from_lookup(|k| vars.get(k).map(|v| v.to_string())),
+            Err(ConfigError("POOL_ID is not set".to_string()))
+        );
+    }
+}
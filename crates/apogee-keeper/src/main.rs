@@ -0,0 +1,75 @@
+//! `apogee-keeper` — configured through environment variables, see [`Config`]
+
+use std::process::ExitCode;
+use std::thread;
+
+use apogee_keeper::{Config, Keeper};
+use apogee_rpc::{Invoker, RpcClient, Signer};
+
+fn main() -> ExitCode {
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let setup = || -> Result<_, apogee_rpc::RpcError> {
+        let rpc = RpcClient::new(&config.rpc_url)?;
+        let keeper = Signer::from_secret(&config.keeper_secret)?;
+        let fee_payer = config
+            .fee_bump_secret
+            .as_deref()
+            .map(Signer::from_secret)
+            .transpose()?;
+        Ok((rpc, keeper, fee_payer))
+    };
+    let (rpc, keeper, fee_payer) = match setup() {
+        Ok(parts) => parts,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    eprintln!(
+        "keeper {} watching pool {}",
+        keeper.account_id(),
+        config.pool_id
+    );
+    let mut invoker = Invoker::new(&rpc, &config.network_passphrase, keeper);
+    if let Some(fee_payer) = fee_payer {
+        eprintln!("fees paid by {} through fee bumps", fee_payer.account_id());
+        invoker = invoker.with_fee_payer(fee_payer);
+    }
+
+    let interval = config.poll_interval;
+    let mut keeper = Keeper::new(invoker, config);
+    loop {
+        match keeper.poll() {
+            Ok(report) => {
+                eprintln!(
+                    "events={} refreshed={} positions={} unhealthy={}",
+                    report.events, report.refreshed, report.positions, report.unhealthy
+                );
+                for (liquidation, result) in report.liquidations {
+                    match result {
+                        Ok(seized) => eprintln!(
+                            "liquidated {}: repaid {} {}, seized {} {}",
+                            liquidation.borrower,
+                            liquidation.repay_amount,
+                            liquidation.repay_asset,
+                            seized,
+                            liquidation.collateral_asset
+                        ),
+                        Err(e) => eprintln!("liquidation of {} failed: {e}", liquidation.borrower),
+                    }
+                }
+            }
+            // RPC hiccups are retried on the next poll
+            Err(e) => eprintln!("poll failed: {e}"),
+        }
+        thread::sleep(interval);
+    }
+}
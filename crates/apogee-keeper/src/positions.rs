@@ -0,0 +1,343 @@
+//! Local position cache and health factor math
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use apogee_common::SCALE;
+use apogee_rpc::{scval, Event};
+use stellend_fixed_point::{mul_div, Rounding};
+
+/// Pool events that change a user's collateral or debt, and the topic
+/// position holding that user
+const POSITION_EVENTS: [(&str, usize); 8] = [
+    ("borrow", 1),
+    ("repay", 1),
+    ("coll_dep", 1),
+    ("coll_wth", 1),
+    ("swap_rate", 1),
+    ("rebalance", 1),
+    ("liquidate", 2),
+    ("bad_debt", 1),
+];
+
+/// The parts of a reserve the keeper needs
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reserve {
+    /// `C...` token contract, the oracle's price key
+    pub token: String,
+    /// Scaled by SCALE
+    pub liquidation_threshold: i128,
+}
+
+/// A user's balances per asset symbol, in token units
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    pub collateral: BTreeMap<String, i128>,
+    pub debt: BTreeMap<String, i128>,
+}
+
+impl Position {
+    pub fn has_debt(&self) -> bool {
+        self.debt.values().any(|&d| d > 0)
+    }
+}
+
+/// A liquidation to submit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Liquidation {
+    pub borrower: String,
+    pub repay_asset: String,
+    pub repay_amount: i128,
+    pub collateral_asset: String,
+}
+
+/// Positions by `G...`/`C...` user address
+///
+/// Users named in position events are marked dirty and re-read from the
+/// pool on the next refresh; everyone else is re-valued at current prices
+/// from their cached balances.
+#[derive(Debug, Default)]
+pub struct PositionCache {
+    positions: BTreeMap<String, Position>,
+    dirty: BTreeSet<String>,
+}
+
+impl PositionCache {
+    /// Marks the user touched by a pool event dirty; returns that user
+    pub fn apply_event(&mut self, event: &Event) -> Option<String> {
+        let user = event_user(event)?;
+        self.dirty.insert(user.clone());
+        Some(user)
+    }
+
+    pub fn mark_dirty(&mut self, user: &str) {
+        self.dirty.insert(user.to_string());
+    }
+
+    /// Marks every known user dirty, so accrued interest is picked up
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.extend(self.positions.keys().cloned());
+    }
+
+    pub fn take_dirty(&mut self) -> BTreeSet<String> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Stores a refreshed position; users without debt are dropped
+    pub fn update(&mut self, user: String, position: Position) {
+        if position.has_debt() {
+            self.positions.insert(user, position);
+        } else {
+            self.positions.remove(&user);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Position)> {
+        self.positions.iter()
+    }
+}
+
+/// User affected by a pool event, if it is a position event
+pub fn event_user(event: &Event) -> Option<String> {
+    let name = scval::to_symbol(event.topics.first()?).ok()?;
+    let (_, index) = POSITION_EVENTS
+        .iter()
+        .find(|(event_name, _)| *event_name == name)?;
+    scval::to_address(event.topics.get(*index)?).ok()
+}
+
+fn value(amount: i128, asset: &str, prices: &BTreeMap<String, i128>) -> Option<i128> {
+    let price = *prices.get(asset).filter(|&&p| p > 0)?;
+    mul_div(amount, price, SCALE, Rounding::Down).ok()
+}
+
+/// Health factor at the given prices (by asset symbol), scaled by SCALE
+///
+/// Mirrors the pool: sum(collateral value * liquidation threshold) / debt
+/// value, with 999 for a position without debt. Returns None when a price
+/// or reserve is missing. Collateral is valued at spot here while the pool
+/// may use a TWAP, so candidates are confirmed on chain before liquidating.
+pub fn health_factor(
+    position: &Position,
+    reserves: &BTreeMap<String, Reserve>,
+    prices: &BTreeMap<String, i128>,
+) -> Option<i128> {
+    let mut threshold_collateral = 0i128;
+    for (asset, &amount) in position.collateral.iter().filter(|(_, &a)| a > 0) {
+        let reserve = reserves.get(asset)?;
+        let collateral = value(amount, asset, prices)?;
+        threshold_collateral = threshold_collateral.checked_add(
+            mul_div(
+                collateral,
+                reserve.liquidation_threshold,
+                SCALE,
+                Rounding::Down,
+            )
+            .ok()?,
+        )?;
+    }
+
+    let mut debt = 0i128;
+    for (asset, &amount) in position.debt.iter().filter(|(_, &a)| a > 0) {
+        debt = debt.checked_add(value(amount, asset, prices)?)?;
+    }
+
+    if debt == 0 {
+        return Some(999 * SCALE);
+    }
+    mul_div(threshold_collateral, SCALE, debt, Rounding::Down).ok()
+}
+
+/// Liquidation of an unhealthy position: repays the largest debt (by value)
+/// up to the close factor and seizes the largest collateral
+pub fn plan_liquidation(
+    borrower: &str,
+    position: &Position,
+    prices: &BTreeMap<String, i128>,
+    close_factor: i128,
+) -> Option<Liquidation> {
+    fn largest<'b>(
+        balances: &'b BTreeMap<String, i128>,
+        prices: &BTreeMap<String, i128>,
+    ) -> Option<(i128, &'b String, i128)> {
+        balances
+            .iter()
+            .filter_map(|(asset, &amount)| Some((value(amount, asset, prices)?, asset, amount)))
+            .filter(|(usd, _, _)| *usd > 0)
+            .max_by_key(|(usd, _, _)| *usd)
+    }
+    let (_, repay_asset, debt) = largest(&position.debt, prices)?;
+    let (_, collateral_asset, _) = largest(&position.collateral, prices)?;
+
+    let repay_amount = mul_div(debt, close_factor, SCALE, Rounding::Down).ok()?;
+    (repay_amount > 0).then(|| Liquidation {
+        borrower: borrower.to_string(),
+        repay_asset: repay_asset.clone(),
+        repay_amount,
+        collateral_asset: collateral_asset.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_rpc::xdr::ScVal;
+
+    const USDC_PRICE: i128 = SCALE;
+    const XLM_PRICE: i128 = 1_000_000; // $0.10
+
+    fn reserves() -> BTreeMap<String, Reserve> {
+        [("XLM", 8_000_000), ("USDC", 8_500_000)]
+            .into_iter()
+            .map(|(asset, liquidation_threshold)| {
+                (
+                    asset.to_string(),
+                    Reserve {
+                        token: format!("C{asset}"),
+                        liquidation_threshold,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn prices(xlm: i128) -> BTreeMap<String, i128> {
+        BTreeMap::from([("XLM".to_string(), xlm), ("USDC".to_string(), USDC_PRICE)])
+    }
+
+    /// 10,000 XLM collateral, 700 USDC debt
+    fn position() -> Position {
+        Position {
+            collateral: BTreeMap::from([("XLM".to_string(), 10_000 * SCALE)]),
+            debt: BTreeMap::from([("USDC".to_string(), 700 * SCALE)]),
+        }
+    }
+
+    fn event(topics: Vec<ScVal>) -> Event {
+        Event {
+            id: "1".to_string(),
+            ledger: 1,
+            ledger_closed_at: String::new(),
+            contract_id: "CPOOL".to_string(),
+            tx_hash: String::new(),
+            topics,
+            value: ScVal::Void,
+        }
+    }
+
+    #[test]
+    fn test_health_factor_matches_pool_formula() {
+        // $1,000 * 0.8 / $700
+        assert_eq!(
+            health_factor(&position(), &reserves(), &prices(XLM_PRICE)),
+            Some(11_428_571)
+        );
+        // XLM at $0.08: $800 * 0.8 / $700 < 1
+        assert_eq!(
+            health_factor(&position(), &reserves(), &prices(800_000)),
+            Some(9_142_857)
+        );
+
+        let no_debt = Position {
+            debt: BTreeMap::new(),
+            ..position()
+        };
+        assert_eq!(
+            health_factor(&no_debt, &reserves(), &prices(XLM_PRICE)),
+            Some(999 * SCALE)
+        );
+
+        let mut missing_price = prices(XLM_PRICE);
+        missing_price.remove("XLM");
+        assert_eq!(
+            health_factor(&position(), &reserves(), &missing_price),
+            None
+        );
+    }
+
+    #[test]
+    fn test_plan_liquidation_uses_close_factor() {
+        let mut position = position();
+        position.collateral.insert("USDC".to_string(), 10 * SCALE);
+
+        let plan = plan_liquidation("GBORROWER", &position, &prices(800_000), 5_000_000).unwrap();
+        assert_eq!(plan.repay_asset, "USDC");
+        assert_eq!(plan.repay_amount, 350 * SCALE);
+        // $800 of XLM beats $10 of USDC
+        assert_eq!(plan.collateral_asset, "XLM");
+
+        let no_debt = Position {
+            debt: BTreeMap::new(),
+            ..position
+        };
+        assert_eq!(
+            plan_liquidation("GBORROWER", &no_debt, &prices(800_000), 5_000_000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_position_events_mark_users_dirty() {
+        let user = stellar_account(1);
+        let liquidator = stellar_account(2);
+        let asset = scval::symbol("USDC").unwrap();
+        let mut cache = PositionCache::default();
+
+        let borrow = event(vec![
+            scval::symbol("borrow").unwrap(),
+            scval::address(&user).unwrap(),
+            asset.clone(),
+        ]);
+        assert_eq!(cache.apply_event(&borrow), Some(user.clone()));
+
+        // The borrower is the third topic of a liquidation
+        let borrower = stellar_account(3);
+        let liquidate = event(vec![
+            scval::symbol("liquidate").unwrap(),
+            scval::address(&liquidator).unwrap(),
+            scval::address(&borrower).unwrap(),
+        ]);
+        assert_eq!(cache.apply_event(&liquidate), Some(borrower.clone()));
+
+        // Deposits into the lending side do not change a borrow position
+        let supply = event(vec![
+            scval::symbol("supply").unwrap(),
+            scval::address(&user).unwrap(),
+            asset,
+        ]);
+        assert_eq!(cache.apply_event(&supply), None);
+
+        assert_eq!(cache.take_dirty(), BTreeSet::from([user, borrower]));
+        assert!(cache.take_dirty().is_empty());
+    }
+
+    #[test]
+    fn test_cache_drops_repaid_positions() {
+        let mut cache = PositionCache::default();
+        cache.update("GUSER".to_string(), position());
+        assert_eq!(cache.len(), 1);
+
+        cache.mark_all_dirty();
+        assert_eq!(cache.take_dirty(), BTreeSet::from(["GUSER".to_string()]));
+
+        cache.update(
+            "GUSER".to_string(),
+            Position {
+                debt: BTreeMap::new(),
+                ..position()
+            },
+        );
+        assert!(cache.is_empty());
+    }
+
+    fn stellar_account(seed: u8) -> String {
+        stellar_strkey::ed25519::PublicKey([seed; 32]).to_string()
+    }
+}
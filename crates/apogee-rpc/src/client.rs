@@ -0,0 +1,462 @@
+//! Soroban RPC methods
+
+use std::cell::Cell;
+use std::thread;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::http::{HttpTransport, Transport};
+use crate::xdr::{
+    AccountEntry, AccountId, LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, PublicKey,
+    ReadXdr, ScVal, SorobanAuthorizationEntry, SorobanTransactionData, TransactionEnvelope,
+    TransactionMeta, Uint256, WriteXdr,
+};
+use crate::RpcError;
+
+/// JSON-RPC client for a Soroban RPC endpoint
+pub struct RpcClient<T = HttpTransport> {
+    transport: T,
+    next_id: Cell<u64>,
+}
+
+/// `getLatestLedger` result
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestLedger {
+    pub id: String,
+    pub protocol_version: u32,
+    pub sequence: u32,
+}
+
+/// `getEvents` filter on one contract, optionally by topic segments (`*` matches any)
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventFilter {
+    pub contract_ids: Vec<String>,
+    /// Base64 XDR `ScVal` per topic position, or `*`
+    pub topics: Vec<Vec<String>>,
+}
+
+/// A contract event as returned by `getEvents`, with topics and value decoded
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub id: String,
+    pub ledger: u32,
+    /// RFC 3339 close time of `ledger`
+    pub ledger_closed_at: String,
+    pub contract_id: String,
+    pub tx_hash: String,
+    pub topics: Vec<ScVal>,
+    pub value: ScVal,
+}
+
+/// One page of `getEvents` results
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventsPage {
+    pub events: Vec<Event>,
+    pub latest_ledger: u32,
+    /// Cursor to pass to the next request to continue after this page
+    pub cursor: Option<String>,
+}
+
+/// Decoded `simulateTransaction` result
+#[derive(Clone, Debug, PartialEq)]
+pub struct Simulation {
+    pub transaction_data: SorobanTransactionData,
+    pub min_resource_fee: i64,
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    /// Return value of the invoked function
+    pub result: Option<ScVal>,
+    pub latest_ledger: u32,
+}
+
+/// `sendTransaction` result
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SendResult {
+    /// PENDING, DUPLICATE, TRY_AGAIN_LATER or ERROR
+    pub status: String,
+    pub hash: String,
+    #[serde(default)]
+    pub error_result_xdr: Option<String>,
+}
+
+/// Final or pending status of a submitted transaction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransactionStatus {
+    Success,
+    NotFound,
+    Failed,
+}
+
+/// `getTransaction` result
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionResult {
+    pub status: TransactionStatus,
+    pub ledger: Option<u32>,
+    /// Return value of the invoked function, for successful Soroban transactions
+    pub return_value: Option<ScVal>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<R> {
+    result: Option<R>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEvent {
+    id: String,
+    ledger: u32,
+    #[serde(default)]
+    ledger_closed_at: String,
+    contract_id: String,
+    #[serde(default)]
+    tx_hash: String,
+    topic: Vec<String>,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEventsPage {
+    events: Vec<RawEvent>,
+    latest_ledger: u32,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSimulation {
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    transaction_data: String,
+    #[serde(default)]
+    min_resource_fee: String,
+    #[serde(default)]
+    results: Vec<RawSimulationResult>,
+    latest_ledger: u32,
+}
+
+#[derive(Deserialize)]
+struct RawSimulationResult {
+    #[serde(default)]
+    auth: Vec<String>,
+    xdr: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawTransaction {
+    status: String,
+    #[serde(default)]
+    ledger: Option<u32>,
+    #[serde(default)]
+    result_meta_xdr: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawLedgerEntries {
+    entries: Option<Vec<RawLedgerEntry>>,
+}
+
+#[derive(Deserialize)]
+struct RawLedgerEntry {
+    xdr: String,
+}
+
+impl RpcClient<HttpTransport> {
+    /// Client for an `http://` RPC endpoint
+    pub fn new(url: &str) -> Result<Self, RpcError> {
+        Ok(Self::with_transport(HttpTransport::new(url)?))
+    }
+}
+
+impl<T: Transport> RpcClient<T> {
+    pub fn with_transport(transport: T) -> Self {
+        RpcClient {
+            transport,
+            next_id: Cell::new(1),
+        }
+    }
+
+    fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> Result<R, RpcError> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let body = self.transport.post(&request.to_string())?;
+        let response: RpcResponse<R> = serde_json::from_str(&body)?;
+        if let Some(e) = response.error {
+            return Err(RpcError::Rpc {
+                code: e.code,
+                message: e.message,
+            });
+        }
+        response
+            .result
+            .ok_or_else(|| RpcError::Json(format!("`{method}` returned neither result nor error")))
+    }
+
+    pub fn get_latest_ledger(&self) -> Result<LatestLedger, RpcError> {
+        self.call("getLatestLedger", Value::Null)
+    }
+
+    /// Loads an account entry (for its sequence number)
+    pub fn get_account(&self, account_id: &str) -> Result<AccountEntry, RpcError> {
+        let key = stellar_strkey::ed25519::PublicKey::from_string(account_id)
+            .map_err(|_| RpcError::InvalidKey(account_id.to_string()))?;
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key.0))),
+        });
+
+        let raw: RawLedgerEntries = self.call(
+            "getLedgerEntries",
+            json!({ "keys": [key.to_xdr_base64(Limits::none())?] }),
+        )?;
+        let entry = raw
+            .entries
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .ok_or_else(|| RpcError::Rpc {
+                code: 0,
+                message: format!("account {account_id} not found"),
+            })?;
+        match LedgerEntryData::from_xdr_base64(entry.xdr, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account),
+            _ => Err(RpcError::Xdr("expected an account entry".to_string())),
+        }
+    }
+
+    /// Fetches contract events, from `start_ledger` or after `cursor`
+    pub fn get_events(
+        &self,
+        start_ledger: Option<u32>,
+        cursor: Option<&str>,
+        filters: &[EventFilter],
+        limit: u32,
+    ) -> Result<EventsPage, RpcError> {
+        let filters: Vec<Value> = filters
+            .iter()
+            .map(|f| {
+                let mut filter = json!({ "type": "contract", "contractIds": f.contract_ids });
+                if !f.topics.is_empty() {
+                    filter["topics"] = json!(f.topics);
+                }
+                filter
+            })
+            .collect();
+        let mut params = json!({ "filters": filters, "pagination": { "limit": limit } });
+        match cursor {
+            Some(cursor) => params["pagination"]["cursor"] = json!(cursor),
+            None => params["startLedger"] = json!(start_ledger.unwrap_or(0)),
+        }
+
+        let raw: RawEventsPage = self.call("getEvents", params)?;
+        let events = raw
+            .events
+            .into_iter()
+            .map(|e| {
+                Ok(Event {
+                    topics: e
+                        .topic
+                        .iter()
+                        .map(|t| ScVal::from_xdr_base64(t, Limits::none()))
+                        .collect::<Result<_, _>>()?,
+                    value: ScVal::from_xdr_base64(&e.value, Limits::none())?,
+                    id: e.id,
+                    ledger: e.ledger,
+                    ledger_closed_at: e.ledger_closed_at,
+                    contract_id: e.contract_id,
+                    tx_hash: e.tx_hash,
+                })
+            })
+            .collect::<Result<Vec<_>, RpcError>>()?;
+        // Older RPC versions have no top-level cursor; the last event id continues the stream
+        let cursor = raw.cursor.or_else(|| events.last().map(|e| e.id.clone()));
+
+        Ok(EventsPage {
+            events,
+            latest_ledger: raw.latest_ledger,
+            cursor,
+        })
+    }
+
+    pub fn simulate_transaction(&self, tx: &TransactionEnvelope) -> Result<Simulation, RpcError> {
+        let raw: RawSimulation = self.call(
+            "simulateTransaction",
+            json!({ "transaction": tx.to_xdr_base64(Limits::none())? }),
+        )?;
+        if let Some(error) = raw.error {
+            return Err(RpcError::Simulation(error));
+        }
+
+        let first = raw.results.into_iter().next();
+        Ok(Simulation {
+            transaction_data: SorobanTransactionData::from_xdr_base64(
+                &raw.transaction_data,
+                Limits::none(),
+            )?,
+            min_resource_fee: raw.min_resource_fee.parse().map_err(|_| {
+                RpcError::Json(format!("bad minResourceFee `{}`", raw.min_resource_fee))
+            })?,
+            auth: match &first {
+                Some(result) => result
+                    .auth
+                    .iter()
+                    .map(|a| SorobanAuthorizationEntry::from_xdr_base64(a, Limits::none()))
+                    .collect::<Result<_, _>>()?,
+                None => Vec::new(),
+            },
+            result: first
+                .map(|r| ScVal::from_xdr_base64(r.xdr, Limits::none()))
+                .transpose()?,
+            latest_ledger: raw.latest_ledger,
+        })
+    }
+
+    pub fn send_transaction(&self, tx: &TransactionEnvelope) -> Result<SendResult, RpcError> {
+        self.call(
+            "sendTransaction",
+            json!({ "transaction": tx.to_xdr_base64(Limits::none())? }),
+        )
+    }
+
+    pub fn get_transaction(&self, hash: &str) -> Result<TransactionResult, RpcError> {
+        let raw: RawTransaction = self.call("getTransaction", json!({ "hash": hash }))?;
+        let status = match raw.status.as_str() {
+            "SUCCESS" => TransactionStatus::Success,
+            "NOT_FOUND" => TransactionStatus::NotFound,
+            _ => TransactionStatus::Failed,
+        };
+        let return_value = match (status, raw.result_meta_xdr) {
+            (TransactionStatus::Success, Some(meta)) => {
+                match TransactionMeta::from_xdr_base64(meta, Limits::none())? {
+                    TransactionMeta::V3(v3) => v3.soroban_meta.map(|m| m.return_value),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        Ok(TransactionResult {
+            status,
+            ledger: raw.ledger,
+            return_value,
+        })
+    }
+
+    /// Polls `getTransaction` until the transaction leaves NOT_FOUND
+    pub fn wait_for_transaction(
+        &self,
+        hash: &str,
+        attempts: u32,
+        interval: Duration,
+    ) -> Result<TransactionResult, RpcError> {
+        for _ in 0..attempts {
+            let result = self.get_transaction(hash)?;
+            match result.status {
+                TransactionStatus::Success => return Ok(result),
+                TransactionStatus::Failed => {
+                    return Err(RpcError::Transaction(format!("{hash} failed on chain")))
+                }
+                TransactionStatus::NotFound => thread::sleep(interval),
+            }
+        }
+        Err(RpcError::Timeout(hash.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Replays canned responses and records the requests
+    pub(crate) struct Canned {
+        pub responses: RefCell<Vec<String>>,
+        pub requests: RefCell<Vec<Value>>,
+    }
+
+    impl Transport for Canned {
+        fn post(&self, body: &str) -> Result<String, RpcError> {
+            self.requests
+                .borrow_mut()
+                .push(serde_json::from_str(body).unwrap());
+            Ok(self.responses.borrow_mut().remove(0))
+        }
+    }
+
+    fn client(responses: &[Value]) -> RpcClient<Canned> {
+        RpcClient::with_transport(Canned {
+            responses: RefCell::new(responses.iter().map(|r| r.to_string()).collect()),
+            requests: RefCell::new(Vec::new()),
+        })
+    }
+
+    #[test]
+    fn test_events_are_decoded_and_paged() {
+        let topic = ScVal::Symbol("borrow".try_into().unwrap())
+            .to_xdr_base64(Limits::none())
+            .unwrap();
+        let value = crate::scval::i128(5).to_xdr_base64(Limits::none()).unwrap();
+        let rpc = client(&[json!({ "jsonrpc": "2.0", "id": 1, "result": {
+            "latestLedger": 120,
+            "events": [{
+                "type": "contract", "ledger": 110, "contractId": "CPOOL", "id": "0000472446406656-0000000001",
+                "pagingToken": "0000472446406656-0000000001", "topic": [topic], "value": value,
+                "inSuccessfulContractCall": true, "txHash": "ab"
+            }]
+        }})]);
+
+        let filter = EventFilter {
+            contract_ids: vec!["CPOOL".to_string()],
+            topics: vec![],
+        };
+        let page = rpc.get_events(Some(100), None, &[filter], 50).unwrap();
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(
+            page.events[0].topics[0],
+            ScVal::Symbol("borrow".try_into().unwrap())
+        );
+        assert_eq!(page.events[0].value, crate::scval::i128(5));
+        assert_eq!(page.cursor.as_deref(), Some("0000472446406656-0000000001"));
+
+        let request = &rpc.transport.requests.borrow()[0];
+        assert_eq!(request["method"], "getEvents");
+        assert_eq!(request["params"]["startLedger"], 100);
+        assert_eq!(request["params"]["filters"][0]["contractIds"][0], "CPOOL");
+    }
+
+    #[test]
+    fn test_rpc_and_simulation_errors_surface() {
+        let rpc = client(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32600, "message": "bad request" } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "result": { "error": "HostError: Error(Contract, #3)", "latestLedger": 7 } }),
+        ]);
+        assert!(matches!(
+            rpc.get_latest_ledger(),
+            Err(RpcError::Rpc { code: -32600, .. })
+        ));
+
+        let tx = TransactionEnvelope::Tx(crate::xdr::TransactionV1Envelope {
+            tx: crate::tx::test::empty_transaction(),
+            signatures: Default::default(),
+        });
+        assert!(
+            matches!(rpc.simulate_transaction(&tx), Err(RpcError::Simulation(e)) if e.contains("#3"))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! JSON-RPC transport
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::RpcError;
+
+/// Sends a JSON-RPC request body and returns the response body
+pub trait Transport {
+    fn post(&self, body: &str) -> Result<String, RpcError>;
+}
+
+/// Plain HTTP/1.1 transport, one connection per request
+#[derive(Clone, Debug)]
+pub struct HttpTransport {
+    host: String,
+    port: u16,
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    /// Parses an `http://host[:port][/path]` URL
+    pub fn new(url: &str) -> Result<Self, RpcError> {
+        let invalid = || RpcError::InvalidUrl(url.to_string());
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(HttpTransport {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Sets the connect/read/write timeout (default 30s)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// GETs the URL and returns the response body
+    ///
+    /// Lets the off-chain services read plain-HTTP price APIs with the same
+    /// client they use for RPC.
+    pub fn get(&self) -> Result<String, RpcError> {
+        self.request("GET", None)
+    }
+
+    fn request(&self, method: &str, body: Option<&str>) -> Result<String, RpcError> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| RpcError::InvalidUrl(self.host.clone()))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        write!(
+            stream,
+            "{method} {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            self.path, self.host
+        )?;
+        match body {
+            Some(body) => write!(
+                stream,
+                "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )?,
+            None => write!(stream, "\r\n")?,
+        }
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        parse_response(&raw)
+    }
+}
+
+impl Transport for HttpTransport {
+    fn post(&self, body: &str) -> Result<String, RpcError> {
+        self.request("POST", Some(body))
+    }
+}
+
+/// Extracts the body of an HTTP/1.1 response, decoding chunked transfer encoding
+pub(crate) fn parse_response(raw: &[u8]) -> Result<String, RpcError> {
+    let malformed = |what: &str| RpcError::Json(format!("malformed HTTP response: {what}"));
+
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| malformed("no header end"))?;
+    let head = std::str::from_utf8(&raw[..split]).map_err(|_| malformed("non-UTF-8 headers"))?;
+    let mut body = &raw[split + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| malformed("bad status line"))?;
+
+    let mut chunked = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked") {
+            chunked = true;
+        } else if name.eq_ignore_ascii_case("content-length") {
+            let len: usize = value.parse().map_err(|_| malformed("bad content-length"))?;
+            body = body.get(..len).ok_or_else(|| malformed("truncated body"))?;
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body).ok_or_else(|| malformed("bad chunk"))?
+    } else {
+        body.to_vec()
+    };
+    let body = String::from_utf8(body).map_err(|_| malformed("non-UTF-8 body"))?;
+
+    if !(200..300).contains(&status) {
+        return Err(RpcError::Http(status, body));
+    }
+    Ok(body)
+}
+
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_field = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_field.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let t = HttpTransport::new("http://localhost:8000/soroban/rpc").unwrap();
+        assert_eq!(
+            (t.host.as_str(), t.port, t.path.as_str()),
+            ("localhost", 8000, "/soroban/rpc")
+        );
+
+        let t = HttpTransport::new("http://rpc.internal").unwrap();
+        assert_eq!(
+            (t.host.as_str(), t.port, t.path.as_str()),
+            ("rpc.internal", 80, "/")
+        );
+
+        assert!(HttpTransport::new("https://soroban-testnet.stellar.org").is_err());
+        assert!(HttpTransport::new("http://:8000").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(parse_response(raw).unwrap(), "{}");
+
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        assert_eq!(parse_response(raw).unwrap(), "{\"a\":1}");
+
+        let raw = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\n\r\nbusy";
+        assert!(matches!(parse_response(raw), Err(RpcError::Http(503, body)) if body == "busy"));
+    }
+}
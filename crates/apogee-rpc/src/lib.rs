@@ -0,0 +1,104 @@
+//! # Stellend RPC
+//!
+//! Minimal Soroban RPC client and transaction builder shared by the
+//! off-chain services (keeper, price feeder, indexer, CLI).
+//!
+//! ```text
+//! Transport      POSTs JSON-RPC bodies (HttpTransport: plain HTTP/1.1)
+//! RpcClient      getLatestLedger, getLedgerEntries, getEvents,
+//!                simulateTransaction, sendTransaction, getTransaction
+//! Signer         ed25519 key from an `S...` secret seed
+//! Invoker        simulate -> assemble -> sign -> (fee bump) -> send -> wait
+//! scval          ScVal builders and readers for contract arguments/results
+//! ```
+//!
+//! [`HttpTransport`] speaks plain HTTP only. Point it at a local RPC node,
+//! or put a TLS-terminating proxy in front of a public endpoint; other
+//! transports can be plugged in through the [`Transport`] trait.
+
+use std::fmt;
+
+mod client;
+mod http;
+pub mod scval;
+mod tx;
+
+pub use client::{
+    Event, EventFilter, EventsPage, LatestLedger, RpcClient, SendResult, Simulation,
+    TransactionResult, TransactionStatus,
+};
+pub use http::{HttpTransport, Transport};
+pub use tx::{network_id, transaction_hash, ContractCall, Invoker, Signer};
+
+/// Re-exported XDR definitions (`stellar-xdr`, current protocol)
+pub use stellar_xdr::curr as xdr;
+
+/// Public network passphrase
+pub const PUBLIC_PASSPHRASE: &str = "Public Global Stellar Network ; September 2015";
+/// Testnet passphrase
+pub const TESTNET_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+
+/// Errors returned by the RPC client and transaction builder
+#[derive(Debug)]
+pub enum RpcError {
+    /// URL is not an `http://host[:port][/path]` URL
+    InvalidUrl(String),
+    /// Network failure
+    Io(std::io::Error),
+    /// Non-2xx HTTP status
+    Http(u16, String),
+    /// Response body is not the expected JSON
+    Json(String),
+    /// JSON-RPC error object
+    Rpc { code: i64, message: String },
+    /// XDR encoding or decoding failed
+    Xdr(String),
+    /// Key or address strkey could not be parsed
+    InvalidKey(String),
+    /// Simulation reported an error (the call would fail on chain)
+    Simulation(String),
+    /// The transaction was rejected or failed on chain
+    Transaction(String),
+    /// The transaction did not reach a final status in time
+    Timeout(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::InvalidUrl(url) => write!(
+                f,
+                "invalid RPC url `{url}` (expected http://host[:port][/path])"
+            ),
+            RpcError::Io(e) => write!(f, "network error: {e}"),
+            RpcError::Http(status, body) => write!(f, "HTTP {status}: {body}"),
+            RpcError::Json(e) => write!(f, "malformed response: {e}"),
+            RpcError::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+            RpcError::Xdr(e) => write!(f, "XDR error: {e}"),
+            RpcError::InvalidKey(key) => write!(f, "invalid key or address `{key}`"),
+            RpcError::Simulation(e) => write!(f, "simulation failed: {e}"),
+            RpcError::Transaction(e) => write!(f, "transaction failed: {e}"),
+            RpcError::Timeout(hash) => write!(f, "transaction {hash} not confirmed in time"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<std::io::Error> for RpcError {
+    fn from(e: std::io::Error) -> Self {
+        RpcError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for RpcError {
+    fn from(e: serde_json::Error) -> Self {
+        RpcError::Json(e.to_string())
+    }
+}
+
+impl From<xdr::Error> for RpcError {
+    fn from(e: xdr::Error) -> Self {
+        RpcError::Xdr(e.to_string())
+    }
+}
@@ -0,0 +1,161 @@
+//! `ScVal` builders for contract arguments and readers for results and events
+
+use crate::xdr::{
+    AccountId, Hash, Int128Parts, PublicKey, ScAddress, ScMap, ScSymbol, ScVal, Uint256,
+};
+use crate::RpcError;
+
+/// Address argument from a `G...` account or `C...` contract strkey
+pub fn address(strkey: &str) -> Result<ScVal, RpcError> {
+    let address = match stellar_strkey::Strkey::from_string(strkey) {
+        Ok(stellar_strkey::Strkey::PublicKeyEd25519(key)) => {
+            ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(key.0))))
+        }
+        Ok(stellar_strkey::Strkey::Contract(contract)) => ScAddress::Contract(Hash(contract.0)),
+        _ => return Err(RpcError::InvalidKey(strkey.to_string())),
+    };
+    Ok(ScVal::Address(address))
+}
+
+pub fn symbol(s: &str) -> Result<ScVal, RpcError> {
+    let symbol =
+        ScSymbol::try_from(s).map_err(|_| RpcError::Xdr(format!("invalid symbol `{s}`")))?;
+    Ok(ScVal::Symbol(symbol))
+}
+
+pub fn i128(value: i128) -> ScVal {
+    ScVal::I128(Int128Parts {
+        hi: (value >> 64) as i64,
+        lo: value as u64,
+    })
+}
+
+pub fn u32(value: u32) -> ScVal {
+    ScVal::U32(value)
+}
+
+pub fn u64(value: u64) -> ScVal {
+    ScVal::U64(value)
+}
+
+pub fn bool(value: bool) -> ScVal {
+    ScVal::Bool(value)
+}
+
+fn unexpected(expected: &str, value: &ScVal) -> RpcError {
+    RpcError::Xdr(format!("expected {expected}, got {value:?}"))
+}
+
+pub fn to_i128(value: &ScVal) -> Result<i128, RpcError> {
+    match value {
+        ScVal::I128(Int128Parts { hi, lo }) => Ok(((*hi as i128) << 64) | *lo as i128),
+        _ => Err(unexpected("i128", value)),
+    }
+}
+
+pub fn to_u32(value: &ScVal) -> Result<u32, RpcError> {
+    match value {
+        ScVal::U32(v) => Ok(*v),
+        _ => Err(unexpected("u32", value)),
+    }
+}
+
+pub fn to_u64(value: &ScVal) -> Result<u64, RpcError> {
+    match value {
+        ScVal::U64(v) => Ok(*v),
+        _ => Err(unexpected("u64", value)),
+    }
+}
+
+pub fn to_bool(value: &ScVal) -> Result<bool, RpcError> {
+    match value {
+        ScVal::Bool(v) => Ok(*v),
+        _ => Err(unexpected("bool", value)),
+    }
+}
+
+pub fn to_symbol(value: &ScVal) -> Result<String, RpcError> {
+    match value {
+        ScVal::Symbol(s) => Ok(s.0.to_utf8_string_lossy()),
+        _ => Err(unexpected("symbol", value)),
+    }
+}
+
+/// `G...` or `C...` strkey of an address value
+pub fn to_address(value: &ScVal) -> Result<String, RpcError> {
+    match value {
+        ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(
+            Uint256(key),
+        )))) => Ok(stellar_strkey::ed25519::PublicKey(*key).to_string()),
+        ScVal::Address(ScAddress::Contract(Hash(id))) => {
+            Ok(stellar_strkey::Contract(*id).to_string())
+        }
+        _ => Err(unexpected("address", value)),
+    }
+}
+
+pub fn to_vec(value: &ScVal) -> Result<&[ScVal], RpcError> {
+    match value {
+        ScVal::Vec(Some(items)) => Ok(items.as_slice()),
+        _ => Err(unexpected("vec", value)),
+    }
+}
+
+/// Key/value pairs of a map (contract `Map` and `contracttype` structs alike)
+pub fn to_map(value: &ScVal) -> Result<Vec<(&ScVal, &ScVal)>, RpcError> {
+    match value {
+        ScVal::Map(Some(ScMap(entries))) => Ok(entries.iter().map(|e| (&e.key, &e.val)).collect()),
+        _ => Err(unexpected("map", value)),
+    }
+}
+
+/// Field of a `contracttype` struct, which is encoded as a symbol-keyed map
+pub fn field<'a>(value: &'a ScVal, name: &str) -> Result<&'a ScVal, RpcError> {
+    to_map(value)?
+        .into_iter()
+        .find(|(key, _)| matches!(key, ScVal::Symbol(s) if s.0.as_slice() == name.as_bytes()))
+        .map(|(_, val)| val)
+        .ok_or_else(|| RpcError::Xdr(format!("missing field `{name}`")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xdr::ScMapEntry;
+
+    #[test]
+    fn test_round_trips() {
+        let account = stellar_strkey::ed25519::PublicKey([3; 32]).to_string();
+        let contract = stellar_strkey::Contract([4; 32]).to_string();
+        assert_eq!(to_address(&address(&account).unwrap()).unwrap(), account);
+        assert_eq!(to_address(&address(&contract).unwrap()).unwrap(), contract);
+        assert!(address("GNOTAKEY").is_err());
+
+        for v in [0, 1, -1, i128::MAX, i128::MIN, 12_345_678_901_234_567_890] {
+            assert_eq!(to_i128(&i128(v)).unwrap(), v);
+        }
+        assert_eq!(to_symbol(&symbol("borrow").unwrap()).unwrap(), "borrow");
+        assert!(to_i128(&u32(1)).is_err());
+    }
+
+    #[test]
+    fn test_struct_field() {
+        let entry = |k: &str, v: ScVal| ScMapEntry {
+            key: symbol(k).unwrap(),
+            val: v,
+        };
+        let config = ScVal::Map(Some(ScMap(
+            vec![
+                entry("ltv", u32(7_500_000)),
+                entry("liquidation_threshold", u32(8_000_000)),
+            ]
+            .try_into()
+            .unwrap(),
+        )));
+        assert_eq!(
+            to_u32(field(&config, "liquidation_threshold").unwrap()).unwrap(),
+            8_000_000
+        );
+        assert!(field(&config, "missing").is_err());
+    }
+}
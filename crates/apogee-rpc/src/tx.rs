@@ -0,0 +1,387 @@
+//! Transaction building, signing and submission
+
+use std::time::Duration;
+
+use ed25519_dalek::{Signer as _, SigningKey};
+use sha2::{Digest, Sha256};
+
+use crate::client::RpcClient;
+use crate::http::Transport;
+use crate::scval;
+use crate::xdr::{
+    AccountId, ContractExecutable, ContractIdPreimage, ContractIdPreimageFromAddress,
+    CreateContractArgs, DecoratedSignature, FeeBumpTransaction, FeeBumpTransactionEnvelope,
+    FeeBumpTransactionExt, FeeBumpTransactionInnerTx, Hash, HostFunction, InvokeContractArgs,
+    InvokeHostFunctionOp, Limits, Memo, MuxedAccount, Operation, OperationBody, Preconditions,
+    PublicKey, ScAddress, ScVal, SequenceNumber, Signature, SignatureHint, Transaction,
+    TransactionEnvelope, TransactionExt, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope, Uint256, WriteXdr,
+};
+use crate::RpcError;
+
+/// Inclusion fee per operation, in stroops
+const BASE_FEE: u32 = 100;
+
+/// `getTransaction` polls before giving up on a submitted transaction
+const CONFIRM_ATTEMPTS: u32 = 30;
+const CONFIRM_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Network id: SHA-256 of the network passphrase
+pub fn network_id(passphrase: &str) -> Hash {
+    Hash(Sha256::digest(passphrase.as_bytes()).into())
+}
+
+/// Hash signed by the source account of `tx`
+pub fn transaction_hash(tx: &Transaction, network: &Hash) -> Result<[u8; 32], RpcError> {
+    payload_hash(
+        TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+        network,
+    )
+}
+
+fn payload_hash(
+    tagged: TransactionSignaturePayloadTaggedTransaction,
+    network: &Hash,
+) -> Result<[u8; 32], RpcError> {
+    let payload = TransactionSignaturePayload {
+        network_id: network.clone(),
+        tagged_transaction: tagged,
+    };
+    Ok(Sha256::digest(payload.to_xdr(Limits::none())?).into())
+}
+
+/// ed25519 account key
+pub struct Signer {
+    key: SigningKey,
+}
+
+impl Signer {
+    /// Parses an `S...` secret seed
+    pub fn from_secret(secret: &str) -> Result<Self, RpcError> {
+        let seed = stellar_strkey::ed25519::PrivateKey::from_string(secret)
+            .map_err(|_| RpcError::InvalidKey("secret seed".to_string()))?;
+        Ok(Signer {
+            key: SigningKey::from_bytes(&seed.0),
+        })
+    }
+
+    /// Fixed, publicly known key for invokers that only simulate
+    ///
+    /// Simulation needs a source account but no signature, so read-only
+    /// tools use this instead of asking for a secret. Never fund it.
+    pub fn read_only() -> Self {
+        Signer {
+            key: SigningKey::from_bytes(&Sha256::digest(b"apogee read-only").into()),
+        }
+    }
+
+    /// `G...` account id
+    pub fn account_id(&self) -> String {
+        stellar_strkey::ed25519::PublicKey(self.key.verifying_key().to_bytes()).to_string()
+    }
+
+    pub fn muxed_account(&self) -> MuxedAccount {
+        MuxedAccount::Ed25519(Uint256(self.key.verifying_key().to_bytes()))
+    }
+
+    pub fn sc_address(&self) -> ScAddress {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            self.key.verifying_key().to_bytes(),
+        ))))
+    }
+
+    /// Signs a transaction hash
+    pub fn sign_hash(&self, hash: &[u8; 32]) -> DecoratedSignature {
+        let public = self.key.verifying_key().to_bytes();
+        let signature = self.key.sign(hash).to_bytes();
+        DecoratedSignature {
+            hint: SignatureHint([public[28], public[29], public[30], public[31]]),
+            signature: Signature(
+                signature
+                    .to_vec()
+                    .try_into()
+                    .expect("ed25519 signatures are 64 bytes"),
+            ),
+        }
+    }
+}
+
+/// A contract function call
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractCall {
+    /// `C...` contract id
+    pub contract: String,
+    pub function: String,
+    pub args: Vec<ScVal>,
+}
+
+impl ContractCall {
+    pub fn new(contract: &str, function: &str, args: Vec<ScVal>) -> Self {
+        ContractCall {
+            contract: contract.to_string(),
+            function: function.to_string(),
+            args,
+        }
+    }
+
+    fn to_host_function(&self) -> Result<HostFunction, RpcError> {
+        let ScVal::Address(contract_address) = scval::address(&self.contract)? else {
+            return Err(RpcError::InvalidKey(self.contract.clone()));
+        };
+        Ok(HostFunction::InvokeContract(InvokeContractArgs {
+            contract_address,
+            function_name: self.function.as_str().try_into().map_err(|_| {
+                RpcError::Xdr(format!("function name `{}` too long", self.function))
+            })?,
+            args: self.args.clone().try_into()?,
+        }))
+    }
+}
+
+fn to_operation(host_function: HostFunction) -> Operation {
+    Operation {
+        source_account: None,
+        body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function,
+            auth: Default::default(),
+        }),
+    }
+}
+
+/// Signs and submits contract calls from one account
+///
+/// Every call is simulated first; the simulation supplies the footprint,
+/// resource fee and authorization entries, and a failing simulation stops
+/// the call before anything is submitted. With a fee payer set, the signed
+/// transaction is wrapped in a fee bump paid by that account.
+pub struct Invoker<'a, T: Transport> {
+    rpc: &'a RpcClient<T>,
+    network: Hash,
+    source: Signer,
+    fee_payer: Option<Signer>,
+}
+
+impl<'a, T: Transport> Invoker<'a, T> {
+    pub fn new(rpc: &'a RpcClient<T>, passphrase: &str, source: Signer) -> Self {
+        Invoker {
+            rpc,
+            network: network_id(passphrase),
+            source,
+            fee_payer: None,
+        }
+    }
+
+    /// Pays fees from another account through a fee bump
+    pub fn with_fee_payer(mut self, fee_payer: Signer) -> Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    pub fn rpc(&self) -> &'a RpcClient<T> {
+        self.rpc
+    }
+
+    pub fn source(&self) -> &Signer {
+        &self.source
+    }
+
+    /// Simulates a call without submitting it and returns its result
+    pub fn simulate(&self, call: &ContractCall) -> Result<ScVal, RpcError> {
+        let tx = self.build(call.to_host_function()?, 0)?;
+        let simulation = self.rpc.simulate_transaction(&tx.into())?;
+        simulation
+            .result
+            .ok_or_else(|| RpcError::Simulation("no result".to_string()))
+    }
+
+    /// Submits a call and waits for it to succeed; returns the function's result
+    pub fn invoke(&self, call: &ContractCall) -> Result<Option<ScVal>, RpcError> {
+        self.invoke_host_function(call.to_host_function()?)
+    }
+
+    /// Uploads contract code; returns its hash (uploading known code is a no-op)
+    pub fn upload_wasm(&self, wasm: &[u8]) -> Result<Hash, RpcError> {
+        match self.invoke_host_function(HostFunction::UploadContractWasm(wasm.try_into()?))? {
+            Some(ScVal::Bytes(hash)) => {
+                Ok(Hash(hash.as_slice().try_into().map_err(|_| {
+                    RpcError::Xdr("wasm hash is not 32 bytes".to_string())
+                })?))
+            }
+            other => Err(RpcError::Xdr(format!(
+                "expected a wasm hash, got {other:?}"
+            ))),
+        }
+    }
+
+    /// Instantiates uploaded code; returns the new contract's `C...` id
+    ///
+    /// The id is derived from the source account and `salt`, so reusing a
+    /// salt fails instead of creating a second instance.
+    pub fn create_contract(&self, wasm_hash: Hash, salt: [u8; 32]) -> Result<String, RpcError> {
+        let function = HostFunction::CreateContract(CreateContractArgs {
+            contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                address: self.source.sc_address(),
+                salt: Uint256(salt),
+            }),
+            executable: ContractExecutable::Wasm(wasm_hash),
+        });
+        match self.invoke_host_function(function)? {
+            Some(address) => scval::to_address(&address),
+            None => Err(RpcError::Xdr("no contract address returned".to_string())),
+        }
+    }
+
+    /// Submits any host function the same way as [`Invoker::invoke`]
+    pub fn invoke_host_function(&self, function: HostFunction) -> Result<Option<ScVal>, RpcError> {
+        let account = self.rpc.get_account(&self.source.account_id())?;
+        let mut tx = self.build(function, account.seq_num.0 + 1)?;
+
+        let simulation = self.rpc.simulate_transaction(&tx.clone().into())?;
+        tx.fee = tx
+            .fee
+            .checked_add(u32::try_from(simulation.min_resource_fee).unwrap_or(u32::MAX))
+            .ok_or_else(|| RpcError::Simulation("resource fee overflows u32".to_string()))?;
+        tx.ext = TransactionExt::V1(simulation.transaction_data);
+        let mut operations = tx.operations.to_vec();
+        if let OperationBody::InvokeHostFunction(op) = &mut operations[0].body {
+            op.auth = simulation.auth.try_into()?;
+        }
+        tx.operations = operations.try_into()?;
+
+        let signature = self
+            .source
+            .sign_hash(&transaction_hash(&tx, &self.network)?);
+        let mut envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: vec![signature].try_into()?,
+        });
+        if let Some(fee_payer) = &self.fee_payer {
+            envelope = self.fee_bump(envelope, fee_payer)?;
+        }
+
+        let sent = self.rpc.send_transaction(&envelope)?;
+        if sent.status != "PENDING" && sent.status != "DUPLICATE" {
+            return Err(RpcError::Transaction(format!(
+                "{} rejected with {}{}",
+                sent.hash,
+                sent.status,
+                sent.error_result_xdr
+                    .map(|r| format!(": {r}"))
+                    .unwrap_or_default()
+            )));
+        }
+        let result =
+            self.rpc
+                .wait_for_transaction(&sent.hash, CONFIRM_ATTEMPTS, CONFIRM_INTERVAL)?;
+        Ok(result.return_value)
+    }
+
+    fn build(&self, function: HostFunction, sequence: i64) -> Result<Transaction, RpcError> {
+        Ok(Transaction {
+            source_account: self.source.muxed_account(),
+            fee: BASE_FEE,
+            seq_num: SequenceNumber(sequence),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: vec![to_operation(function)].try_into()?,
+            ext: TransactionExt::V0,
+        })
+    }
+
+    /// Wraps a signed transaction in a fee bump paid by `fee_payer`
+    ///
+    /// The outer fee covers the inner fee plus the inclusion fee for the
+    /// extra fee-bump "operation".
+    fn fee_bump(
+        &self,
+        inner: TransactionEnvelope,
+        fee_payer: &Signer,
+    ) -> Result<TransactionEnvelope, RpcError> {
+        let TransactionEnvelope::Tx(inner) = inner else {
+            return Err(RpcError::Transaction(
+                "only v1 transactions can be fee-bumped".to_string(),
+            ));
+        };
+        let fee_bump = FeeBumpTransaction {
+            fee_source: fee_payer.muxed_account(),
+            fee: i64::from(inner.tx.fee) + 2 * i64::from(BASE_FEE),
+            inner_tx: FeeBumpTransactionInnerTx::Tx(inner),
+            ext: FeeBumpTransactionExt::V0,
+        };
+        let hash = payload_hash(
+            TransactionSignaturePayloadTaggedTransaction::TxFeeBump(fee_bump.clone()),
+            &self.network,
+        )?;
+        Ok(TransactionEnvelope::TxFeeBump(FeeBumpTransactionEnvelope {
+            tx: fee_bump,
+            signatures: vec![fee_payer.sign_hash(&hash)].try_into()?,
+        }))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use ed25519_dalek::{Verifier, VerifyingKey};
+
+    const SECRET: &str = "SBFGFF27Y64ZUGFAIG5AMJGQODZZKV2YQKAVUUN4HNE24XZXD2OEUVUP";
+
+    pub(crate) fn empty_transaction() -> Transaction {
+        Transaction {
+            source_account: MuxedAccount::Ed25519(Uint256([0; 32])),
+            fee: BASE_FEE,
+            seq_num: SequenceNumber(1),
+            cond: Preconditions::None,
+            memo: Memo::None,
+            operations: Default::default(),
+            ext: TransactionExt::V0,
+        }
+    }
+
+    #[test]
+    fn test_network_id_is_passphrase_hash() {
+        // Well-known testnet network id
+        assert_eq!(
+            network_id(crate::TESTNET_PASSPHRASE).0,
+            [
+                0xce, 0xe0, 0x30, 0x2d, 0x59, 0x84, 0x4d, 0x32, 0xbd, 0xca, 0x91, 0x5c, 0x82, 0x03,
+                0xdd, 0x44, 0xb3, 0x3f, 0xbb, 0x7e, 0xdc, 0x19, 0x05, 0x1e, 0xa3, 0x7a, 0xbe, 0xdf,
+                0x28, 0xec, 0xd4, 0x72
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signature_verifies_against_account() {
+        let signer = Signer::from_secret(SECRET).unwrap();
+        let account =
+            stellar_strkey::ed25519::PublicKey::from_string(&signer.account_id()).unwrap();
+
+        let hash =
+            transaction_hash(&empty_transaction(), &network_id(crate::TESTNET_PASSPHRASE)).unwrap();
+        let decorated = signer.sign_hash(&hash);
+        assert_eq!(decorated.hint.0, account.0[28..]);
+
+        let signature =
+            ed25519_dalek::Signature::from_slice(decorated.signature.0.as_slice()).unwrap();
+        assert!(VerifyingKey::from_bytes(&account.0)
+            .unwrap()
+            .verify(&hash, &signature)
+            .is_ok());
+        assert!(Signer::from_secret("SNOTAKEY").is_err());
+    }
+
+    #[test]
+    fn test_contract_call_builds_invoke_operation() {
+        let contract = stellar_strkey::Contract([7; 32]).to_string();
+        let call = ContractCall::new(&contract, "get_health_factor", vec![scval::u32(1)]);
+        let HostFunction::InvokeContract(args) = call.to_host_function().unwrap() else {
+            panic!("expected a contract invocation");
+        };
+        assert_eq!(
+            args.function_name.0.to_utf8_string_lossy(),
+            "get_health_factor"
+        );
+        assert_eq!(args.args.len(), 1);
+    }
+}
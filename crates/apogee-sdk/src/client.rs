@@ -0,0 +1,332 @@
+//! Typed pool, oracle and rate model clients
+
+use std::collections::BTreeMap;
+
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, ContractCall, Invoker, Transport};
+
+use crate::types::{
+    optional, FromScVal, MarketInfo, PriceRound, ProtocolStats, RateModelConfig, ReserveConfig,
+    ReserveData, UserAccountData,
+};
+use crate::SdkError;
+
+/// Reads simulate the call; writes are signed by the invoker's account
+struct Contract<'a, T: Transport> {
+    invoker: &'a Invoker<'a, T>,
+    id: String,
+}
+
+impl<'a, T: Transport> Contract<'a, T> {
+    fn new(invoker: &'a Invoker<'a, T>, id: &str) -> Self {
+        Contract {
+            invoker,
+            id: id.to_string(),
+        }
+    }
+
+    fn read<R: FromScVal>(&self, function: &str, args: Vec<ScVal>) -> Result<R, SdkError> {
+        let call = ContractCall::new(&self.id, function, args);
+        let value = self.invoker.simulate(&call).map_err(SdkError::from_rpc)?;
+        Ok(R::from_scval(&value)?)
+    }
+
+    fn write<R: FromScVal>(&self, function: &str, args: Vec<ScVal>) -> Result<R, SdkError> {
+        let call = ContractCall::new(&self.id, function, args);
+        let value = self.invoker.invoke(&call).map_err(SdkError::from_rpc)?;
+        Ok(R::from_scval(&value.unwrap_or(ScVal::Void))?)
+    }
+
+    fn me(&self) -> Result<ScVal, SdkError> {
+        Ok(scval::address(&self.invoker.source().account_id())?)
+    }
+}
+
+/// Lending pool client
+///
+/// Amounts are in the asset's token units; writes act for the invoker's
+/// own account.
+pub struct PoolClient<'a, T: Transport> {
+    contract: Contract<'a, T>,
+}
+
+impl<'a, T: Transport> PoolClient<'a, T> {
+    pub fn new(invoker: &'a Invoker<'a, T>, pool_id: &str) -> Self {
+        PoolClient {
+            contract: Contract::new(invoker, pool_id),
+        }
+    }
+
+    /// One-time setup with the invoker as admin; lists the XLM and USDC reserves
+    pub fn initialize(
+        &self,
+        oracle: &str,
+        rate_model: &str,
+        xlm_token: &str,
+        usdc_token: &str,
+    ) -> Result<(), SdkError> {
+        self.contract.write(
+            "initialize",
+            vec![
+                self.contract.me()?,
+                scval::address(oracle)?,
+                scval::address(rate_model)?,
+                scval::address(xlm_token)?,
+                scval::address(usdc_token)?,
+            ],
+        )
+    }
+
+    /// Supplies liquidity; returns the shares minted
+    pub fn supply(&self, asset: &str, amount: i128) -> Result<i128, SdkError> {
+        let me = self.contract.me()?;
+        self.contract.write(
+            "supply",
+            vec![
+                me.clone(),
+                me,
+                scval::symbol(asset)?,
+                scval::i128(amount),
+                optional(None),
+            ],
+        )
+    }
+
+    /// Redeems supply shares for at least `min_out` underlying; returns the
+    /// underlying withdrawn
+    pub fn withdraw(&self, asset: &str, shares: i128, min_out: i128) -> Result<i128, SdkError> {
+        self.contract.write(
+            "withdraw",
+            vec![
+                self.contract.me()?,
+                scval::symbol(asset)?,
+                scval::i128(shares),
+                scval::i128(min_out),
+                optional(None),
+            ],
+        )
+    }
+
+    /// Deposits collateral; returns the new collateral balance
+    pub fn deposit_collateral(&self, asset: &str, amount: i128) -> Result<i128, SdkError> {
+        self.contract.write(
+            "deposit_collateral",
+            vec![
+                self.contract.me()?,
+                scval::symbol(asset)?,
+                scval::i128(amount),
+            ],
+        )
+    }
+
+    /// Withdraws collateral; returns the new collateral balance
+    pub fn withdraw_collateral(&self, asset: &str, amount: i128) -> Result<i128, SdkError> {
+        self.contract.write(
+            "withdraw_collateral",
+            vec![
+                self.contract.me()?,
+                scval::symbol(asset)?,
+                scval::i128(amount),
+            ],
+        )
+    }
+
+    /// Borrows at the variable rate; returns the new debt
+    pub fn borrow(&self, asset: &str, amount: i128) -> Result<i128, SdkError> {
+        self.contract.write(
+            "borrow",
+            vec![
+                self.contract.me()?,
+                scval::symbol(asset)?,
+                scval::i128(amount),
+                optional(None),
+            ],
+        )
+    }
+
+    /// Repays the invoker's own debt; returns the amount repaid
+    pub fn repay(&self, asset: &str, amount: i128) -> Result<i128, SdkError> {
+        let me = self.contract.me()?;
+        self.contract.write(
+            "repay",
+            vec![me.clone(), me, scval::symbol(asset)?, scval::i128(amount)],
+        )
+    }
+
+    /// Liquidates an unhealthy borrower, seizing at least `min_seized`;
+    /// returns the collateral seized
+    pub fn liquidate(
+        &self,
+        borrower: &str,
+        repay_asset: &str,
+        repay_amount: i128,
+        collateral_asset: &str,
+        min_seized: i128,
+    ) -> Result<i128, SdkError> {
+        self.contract.write(
+            "liquidate",
+            vec![
+                self.contract.me()?,
+                scval::address(borrower)?,
+                scval::symbol(repay_asset)?,
+                scval::i128(repay_amount),
+                scval::symbol(collateral_asset)?,
+                scval::i128(min_seized),
+                optional(None),
+            ],
+        )
+    }
+
+    pub fn get_account_data(&self, user: &str) -> Result<UserAccountData, SdkError> {
+        self.contract
+            .read("get_user_account_data", vec![scval::address(user)?])
+    }
+
+    /// Scaled by SCALE (1e7); below 1e7 is liquidatable
+    pub fn get_health_factor(&self, user: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_health_factor", vec![scval::address(user)?])
+    }
+
+    /// Collateral balances by asset symbol
+    pub fn get_user_collaterals(&self, user: &str) -> Result<BTreeMap<String, i128>, SdkError> {
+        self.contract
+            .read("get_user_collaterals", vec![scval::address(user)?])
+    }
+
+    /// Debt with accrued interest, in both rate modes
+    pub fn get_user_debt(&self, user: &str, asset: &str) -> Result<i128, SdkError> {
+        self.contract.read(
+            "get_user_debt_total",
+            vec![scval::address(user)?, scval::symbol(asset)?],
+        )
+    }
+
+    /// Underlying value of the user's supply shares
+    pub fn get_user_deposit(&self, user: &str, asset: &str) -> Result<i128, SdkError> {
+        self.contract.read(
+            "get_user_deposit",
+            vec![scval::address(user)?, scval::symbol(asset)?],
+        )
+    }
+
+    pub fn list_reserves(&self) -> Result<Vec<String>, SdkError> {
+        self.contract.read("list_reserves", vec![])
+    }
+
+    pub fn get_reserve(&self, asset: &str) -> Result<ReserveConfig, SdkError> {
+        self.contract
+            .read("get_reserve", vec![scval::symbol(asset)?])
+    }
+
+    pub fn get_market_info(&self, asset: &str) -> Result<MarketInfo, SdkError> {
+        self.contract
+            .read("get_market_info", vec![scval::symbol(asset)?])
+    }
+
+    /// Totals, APYs, index and caps of a market in one simulation
+    pub fn get_reserve_data(&self, asset: &str) -> Result<ReserveData, SdkError> {
+        self.contract
+            .read("get_reserve_data", vec![scval::symbol(asset)?])
+    }
+
+    /// Pool-wide TVL, debt and treasury in USD, with interest accrued
+    pub fn get_protocol_stats(&self) -> Result<ProtocolStats, SdkError> {
+        self.contract.read("get_protocol_stats", vec![])
+    }
+
+    /// Share of a debt one liquidation may repay, scaled by SCALE (1e7)
+    pub fn get_close_factor(&self) -> Result<i128, SdkError> {
+        self.contract.read("get_close_factor", vec![])
+    }
+
+    /// Bonus on seized collateral, scaled by SCALE (1e7)
+    pub fn get_liquidation_bonus(&self, asset: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_liquidation_bonus", vec![scval::symbol(asset)?])
+    }
+
+    /// Fee taken from supplies, scaled by SCALE (1e7)
+    pub fn get_deposit_fee(&self, asset: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_deposit_fee", vec![scval::symbol(asset)?])
+    }
+
+    /// Fee taken from withdrawals, scaled by SCALE (1e7)
+    pub fn get_withdraw_fee(&self, asset: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_withdraw_fee", vec![scval::symbol(asset)?])
+    }
+}
+
+/// Price oracle client
+pub struct OracleClient<'a, T: Transport> {
+    contract: Contract<'a, T>,
+}
+
+impl<'a, T: Transport> OracleClient<'a, T> {
+    pub fn new(invoker: &'a Invoker<'a, T>, oracle_id: &str) -> Self {
+        OracleClient {
+            contract: Contract::new(invoker, oracle_id),
+        }
+    }
+
+    /// One-time setup with the invoker as admin and price keeper
+    pub fn initialize(&self, xlm_token: &str, usdc_token: &str) -> Result<(), SdkError> {
+        self.contract.write(
+            "initialize",
+            vec![
+                self.contract.me()?,
+                scval::address(xlm_token)?,
+                scval::address(usdc_token)?,
+            ],
+        )
+    }
+
+    /// USD price of a token contract, scaled by 1e7 (0 if unset)
+    pub fn get_price(&self, token: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_price", vec![scval::address(token)?])
+    }
+
+    pub fn get_price_by_symbol(&self, symbol: &str) -> Result<i128, SdkError> {
+        self.contract
+            .read("get_price_by_symbol", vec![scval::symbol(symbol)?])
+    }
+
+    pub fn latest_round(&self, token: &str) -> Result<Option<PriceRound>, SdkError> {
+        self.contract
+            .read("latest_round", vec![scval::address(token)?])
+    }
+
+    /// Sets a price; the invoker must hold the oracle feeder role
+    pub fn set_price(&self, token: &str, price: i128) -> Result<(), SdkError> {
+        self.contract.write(
+            "set_price",
+            vec![scval::address(token)?, scval::i128(price)],
+        )
+    }
+}
+
+/// Interest rate model client
+pub struct RateModelClient<'a, T: Transport> {
+    contract: Contract<'a, T>,
+}
+
+impl<'a, T: Transport> RateModelClient<'a, T> {
+    pub fn new(invoker: &'a Invoker<'a, T>, rate_model_id: &str) -> Self {
+        RateModelClient {
+            contract: Contract::new(invoker, rate_model_id),
+        }
+    }
+
+    /// One-time setup with the default curve and the invoker as admin
+    pub fn initialize_default(&self) -> Result<(), SdkError> {
+        self.contract
+            .write("initialize_default", vec![self.contract.me()?])
+    }
+
+    pub fn get_config(&self) -> Result<RateModelConfig, SdkError> {
+        self.contract.read("get_config", vec![])
+    }
+}
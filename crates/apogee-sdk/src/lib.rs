@@ -0,0 +1,326 @@
+//! # Stellend SDK
+//!
+//! Typed Rust clients for the lending pool, price oracle and interest rate
+//! model, built on
+//! `apogee-rpc` for transaction building, signing and submission.
+//!
+//! ```no_run
+//! use apogee_sdk::{Invoker, PoolClient, RpcClient, Signer, TESTNET_PASSPHRASE};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let rpc = RpcClient::new("http://localhost:8000/soroban/rpc")?;
+//! let signer = Signer::from_secret("S...")?;
+//! let invoker = Invoker::new(&rpc, TESTNET_PASSPHRASE, signer);
+//!
+//! let pool = PoolClient::new(&invoker, "C...");
+//! pool.deposit_collateral("XLM", 1_000_0000000)?;
+//! pool.borrow("USDC", 50_0000000)?;
+//! let account = pool.get_account_data(&invoker.source().account_id())?;
+//! println!("health factor {}", account.health_factor);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Result types mirror the contracts' `#[contracttype]` structs field for
+//! field; the spec test checks every wrapped function against the specs
+//! the contracts export, so signature drift fails the build.
+
+use std::fmt;
+
+mod client;
+mod types;
+
+pub use apogee_rpc::{
+    scval, xdr, ContractCall, HttpTransport, Invoker, RpcClient, RpcError, Signer, Transport,
+    PUBLIC_PASSPHRASE, TESTNET_PASSPHRASE,
+};
+pub use client::{OracleClient, PoolClient, RateModelClient};
+pub use types::{
+    FromScVal, MarketInfo, PriceRound, ProtocolStats, RateModelConfig, ReserveConfig, ReserveData,
+    UserAccountData,
+};
+
+/// Errors returned by the clients
+#[derive(Debug)]
+pub enum SdkError {
+    /// The contract returned its error code `n` (e.g. `PoolError` discriminant)
+    Contract(u32),
+    /// Transport, encoding or submission failure
+    Rpc(RpcError),
+}
+
+/// `PoolError` code for a call submitted after its deadline
+pub const POOL_DEADLINE_EXPIRED: u32 = 51;
+/// `PoolError` code for an output below the caller's minimum
+pub const POOL_SLIPPAGE_EXCEEDED: u32 = 52;
+
+impl SdkError {
+    /// Whether the pool rejected the call because its deadline had passed
+    pub fn is_deadline_expired(&self) -> bool {
+        matches!(self, SdkError::Contract(POOL_DEADLINE_EXPIRED))
+    }
+
+    /// Whether the pool rejected the call because its output fell short of
+    /// the caller's minimum
+    pub fn is_slippage_exceeded(&self) -> bool {
+        matches!(self, SdkError::Contract(POOL_SLIPPAGE_EXCEEDED))
+    }
+
+    /// Recognizes `Error(Contract, #n)` in simulation failures
+    pub(crate) fn from_rpc(e: RpcError) -> Self {
+        match &e {
+            RpcError::Simulation(message) => {
+                contract_error_code(message).map_or(SdkError::Rpc(e), SdkError::Contract)
+            }
+            _ => SdkError::Rpc(e),
+        }
+    }
+}
+
+fn contract_error_code(message: &str) -> Option<u32> {
+    let start = message.find("Error(Contract, #")? + "Error(Contract, #".len();
+    let digits: String = message[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdkError::Contract(code) => write!(f, "contract error #{code}"),
+            SdkError::Rpc(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
+
+impl From<RpcError> for SdkError {
+    fn from(e: RpcError) -> Self {
+        SdkError::from_rpc(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_rpc::xdr::{Limits, ReadXdr, ScSpecEntry, ScSpecFunctionV0};
+    use stellend_interest_rate_model::InterestRateModel;
+    use stellend_pool::{LendingPool, PoolError};
+    use stellend_price_oracle::PriceOracle;
+
+    fn spec(xdr: &[u8]) -> ScSpecFunctionV0 {
+        match ScSpecEntry::from_xdr(xdr, Limits::none()).unwrap() {
+            ScSpecEntry::FunctionV0(function) => function,
+            other => panic!("not a function spec: {other:?}"),
+        }
+    }
+
+    fn inputs(function: &ScSpecFunctionV0) -> Vec<String> {
+        function
+            .inputs
+            .iter()
+            .map(|i| i.name.to_utf8_string_lossy())
+            .collect()
+    }
+
+    #[test]
+    fn test_clients_match_contract_specs() {
+        // (function, spec, argument names the client passes, in order)
+        let pool = [
+            (
+                "initialize",
+                spec(&LendingPool::spec_xdr_initialize()),
+                vec![
+                    "admin",
+                    "price_oracle",
+                    "interest_rate_model",
+                    "xlm_token",
+                    "usdc_token",
+                ],
+            ),
+            (
+                "supply",
+                spec(&LendingPool::spec_xdr_supply()),
+                vec!["from", "on_behalf_of", "asset", "amount", "referral_code"],
+            ),
+            (
+                "withdraw",
+                spec(&LendingPool::spec_xdr_withdraw()),
+                vec!["user", "asset", "share_amount", "min_out", "deadline"],
+            ),
+            (
+                "deposit_collateral",
+                spec(&LendingPool::spec_xdr_deposit_collateral()),
+                vec!["user", "asset", "amount"],
+            ),
+            (
+                "withdraw_collateral",
+                spec(&LendingPool::spec_xdr_withdraw_collateral()),
+                vec!["user", "asset", "amount"],
+            ),
+            (
+                "borrow",
+                spec(&LendingPool::spec_xdr_borrow()),
+                vec!["user", "asset", "amount", "referral_code"],
+            ),
+            (
+                "repay",
+                spec(&LendingPool::spec_xdr_repay()),
+                vec!["from", "on_behalf_of", "asset", "amount"],
+            ),
+            (
+                "liquidate",
+                spec(&LendingPool::spec_xdr_liquidate()),
+                vec![
+                    "liquidator",
+                    "borrower",
+                    "repay_asset",
+                    "repay_amount",
+                    "collateral_asset",
+                    "min_seized",
+                    "deadline",
+                ],
+            ),
+            (
+                "get_user_account_data",
+                spec(&LendingPool::spec_xdr_get_user_account_data()),
+                vec!["user"],
+            ),
+            (
+                "get_health_factor",
+                spec(&LendingPool::spec_xdr_get_health_factor()),
+                vec!["user"],
+            ),
+            (
+                "get_user_collaterals",
+                spec(&LendingPool::spec_xdr_get_user_collaterals()),
+                vec!["user"],
+            ),
+            (
+                "get_user_debt_total",
+                spec(&LendingPool::spec_xdr_get_user_debt_total()),
+                vec!["user", "asset"],
+            ),
+            (
+                "get_user_deposit",
+                spec(&LendingPool::spec_xdr_get_user_deposit()),
+                vec!["user", "asset"],
+            ),
+            (
+                "list_reserves",
+                spec(&LendingPool::spec_xdr_list_reserves()),
+                vec![],
+            ),
+            (
+                "get_reserve",
+                spec(&LendingPool::spec_xdr_get_reserve()),
+                vec!["asset"],
+            ),
+            (
+                "get_market_info",
+                spec(&LendingPool::spec_xdr_get_market_info()),
+                vec!["asset"],
+            ),
+            (
+                "get_reserve_data",
+                spec(&LendingPool::spec_xdr_get_reserve_data()),
+                vec!["asset"],
+            ),
+            (
+                "get_protocol_stats",
+                spec(&LendingPool::spec_xdr_get_protocol_stats()),
+                vec![],
+            ),
+            (
+                "get_close_factor",
+                spec(&LendingPool::spec_xdr_get_close_factor()),
+                vec![],
+            ),
+            (
+                "get_liquidation_bonus",
+                spec(&LendingPool::spec_xdr_get_liquidation_bonus()),
+                vec!["asset"],
+            ),
+            (
+                "get_deposit_fee",
+                spec(&LendingPool::spec_xdr_get_deposit_fee()),
+                vec!["asset"],
+            ),
+            (
+                "get_withdraw_fee",
+                spec(&LendingPool::spec_xdr_get_withdraw_fee()),
+                vec!["asset"],
+            ),
+        ];
+        let oracle = [
+            (
+                "initialize",
+                spec(&PriceOracle::spec_xdr_initialize()),
+                vec!["admin", "xlm_token", "usdc_token"],
+            ),
+            (
+                "get_price",
+                spec(&PriceOracle::spec_xdr_get_price()),
+                vec!["asset"],
+            ),
+            (
+                "get_price_by_symbol",
+                spec(&PriceOracle::spec_xdr_get_price_by_symbol()),
+                vec!["symbol"],
+            ),
+            (
+                "latest_round",
+                spec(&PriceOracle::spec_xdr_latest_round()),
+                vec!["asset"],
+            ),
+            (
+                "set_price",
+                spec(&PriceOracle::spec_xdr_set_price()),
+                vec!["asset", "price"],
+            ),
+        ];
+        let rate_model = [
+            (
+                "initialize_default",
+                spec(&InterestRateModel::spec_xdr_initialize_default()),
+                vec!["admin"],
+            ),
+            (
+                "get_config",
+                spec(&InterestRateModel::spec_xdr_get_config()),
+                vec![],
+            ),
+        ];
+
+        for (name, function, args) in pool.iter().chain(&oracle).chain(&rate_model) {
+            assert_eq!(function.name.0.to_utf8_string_lossy(), *name);
+            assert_eq!(inputs(function), *args, "{name} arguments changed");
+        }
+    }
+
+    #[test]
+    fn test_contract_error_code_is_parsed() {
+        let e = SdkError::from_rpc(RpcError::Simulation(
+            "HostError: Error(Contract, #12)\n\nEvent log (newest first): ...".to_string(),
+        ));
+        assert!(matches!(e, SdkError::Contract(12)));
+        assert!(matches!(
+            SdkError::from_rpc(RpcError::Simulation(
+                "HostError: Error(Budget, ExceededLimit)".to_string()
+            )),
+            SdkError::Rpc(_)
+        ));
+    }
+
+    #[test]
+    fn test_pool_error_codes_match_contract() {
+        assert_eq!(POOL_DEADLINE_EXPIRED, PoolError::DeadlineExpired as u32);
+        assert_eq!(POOL_SLIPPAGE_EXCEEDED, PoolError::SlippageExceeded as u32);
+        assert!(SdkError::Contract(51).is_deadline_expired());
+        assert!(SdkError::Contract(52).is_slippage_exceeded());
+        assert!(!SdkError::Contract(30).is_deadline_expired());
+    }
+}
@@ -0,0 +1,251 @@
+//! Typed contract values and their `ScVal` conversions
+
+use std::collections::BTreeMap;
+
+use apogee_rpc::xdr::ScVal;
+use apogee_rpc::{scval, RpcError};
+
+/// Decodes a contract return value
+pub trait FromScVal: Sized {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError>;
+}
+
+impl FromScVal for i128 {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_i128(value)
+    }
+}
+
+impl FromScVal for u32 {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_u32(value)
+    }
+}
+
+impl FromScVal for u64 {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_u64(value)
+    }
+}
+
+impl FromScVal for bool {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_bool(value)
+    }
+}
+
+impl FromScVal for () {
+    fn from_scval(_value: &ScVal) -> Result<Self, RpcError> {
+        Ok(())
+    }
+}
+
+/// Addresses are `G...`/`C...` strkeys and symbols plain strings; a `String`
+/// accepts either
+impl FromScVal for String {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        match value {
+            ScVal::Symbol(_) => scval::to_symbol(value),
+            _ => scval::to_address(value),
+        }
+    }
+}
+
+impl<V: FromScVal> FromScVal for Option<V> {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        match value {
+            ScVal::Void => Ok(None),
+            value => V::from_scval(value).map(Some),
+        }
+    }
+}
+
+impl<V: FromScVal> FromScVal for Vec<V> {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_vec(value)?.iter().map(V::from_scval).collect()
+    }
+}
+
+impl<V: FromScVal> FromScVal for BTreeMap<String, V> {
+    fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+        scval::to_map(value)?
+            .into_iter()
+            .map(|(k, v)| Ok((String::from_scval(k)?, V::from_scval(v)?)))
+            .collect()
+    }
+}
+
+/// `Option<T>` arguments: `None` is encoded as void
+pub(crate) fn optional(value: Option<ScVal>) -> ScVal {
+    value.unwrap_or(ScVal::Void)
+}
+
+/// Declares a struct mirroring a `#[contracttype]` struct, decoded from its
+/// symbol-keyed map encoding
+macro_rules! contract_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($(#[$field_meta:meta])* pub $field:ident: $ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $ty,)*
+        }
+
+        impl FromScVal for $name {
+            fn from_scval(value: &ScVal) -> Result<Self, RpcError> {
+                Ok($name {
+                    $($field: FromScVal::from_scval(scval::field(value, stringify!($field))?)?,)*
+                })
+            }
+        }
+    };
+}
+
+contract_struct! {
+    /// Pool `ReserveConfig`
+    pub struct ReserveConfig {
+        /// Token contract `C...`
+        pub token: String,
+        pub decimals: u32,
+        /// Scaled by SCALE (1e7)
+        pub ltv: i128,
+        /// Scaled by SCALE (1e7)
+        pub liquidation_threshold: i128,
+        pub collateral_enabled: bool,
+        pub borrow_enabled: bool,
+        /// Rate model contract `C...`
+        pub interest_rate_model: String,
+    }
+}
+
+contract_struct! {
+    /// Pool `UserAccountData`; USD values and ratios scaled by SCALE (1e7)
+    pub struct UserAccountData {
+        pub total_collateral_usd: i128,
+        pub total_debt_usd: i128,
+        pub available_borrow_usd: i128,
+        pub current_ltv: i128,
+        pub max_ltv: i128,
+        pub liquidation_threshold: i128,
+        pub health_factor: i128,
+    }
+}
+
+contract_struct! {
+    /// Pool `MarketInfo`; rates and ratios scaled by SCALE (1e7)
+    pub struct MarketInfo {
+        pub total_supply: i128,
+        pub total_borrow: i128,
+        pub total_shares: i128,
+        /// Scaled by 1e9
+        pub exchange_rate: i128,
+        pub utilization_rate: i128,
+        pub borrow_rate: i128,
+        pub supply_rate: i128,
+        pub ltv_ratio: i128,
+    }
+}
+
+contract_struct! {
+    /// Pool `ReserveData`, with interest accrued to the simulated ledger
+    pub struct ReserveData {
+        pub total_supply: i128,
+        pub total_borrow: i128,
+        pub available_liquidity: i128,
+        /// Scaled by SCALE (1e7)
+        pub utilization_rate: i128,
+        /// Scaled by SCALE (1e7)
+        pub borrow_apy: i128,
+        /// Scaled by SCALE (1e7)
+        pub supply_apy: i128,
+        /// Scaled by 1e27
+        pub borrow_index: i128,
+        pub last_accrual_time: u64,
+        /// Token units, 0 = no cap
+        pub supply_cap: i128,
+        /// Token units, 0 = no cap
+        pub borrow_cap: i128,
+    }
+}
+
+contract_struct! {
+    /// Pool `ProtocolStats`; USD values scaled by SCALE (1e7)
+    pub struct ProtocolStats {
+        pub tvl_usd: i128,
+        pub total_supply_usd: i128,
+        pub total_debt_usd: i128,
+        pub treasury_usd: i128,
+        pub active_reserves: u32,
+    }
+}
+
+contract_struct! {
+    /// Interest rate model `RateModelConfig`; all scaled by SCALE (1e7)
+    pub struct RateModelConfig {
+        pub rate_min: i128,
+        pub rate_opt: i128,
+        pub rate_max: i128,
+        pub optimal_utilization: i128,
+    }
+}
+
+contract_struct! {
+    /// Oracle `PriceRound`
+    pub struct PriceRound {
+        pub round_id: u64,
+        /// USD, scaled by 1e7
+        pub price: i128,
+        pub timestamp: u64,
+        pub ledger: u32,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apogee_rpc::xdr::{ScMap, ScMapEntry};
+
+    fn map(entries: Vec<(&str, ScVal)>) -> ScVal {
+        let entries: Vec<ScMapEntry> = entries
+            .into_iter()
+            .map(|(k, val)| ScMapEntry {
+                key: scval::symbol(k).unwrap(),
+                val,
+            })
+            .collect();
+        ScVal::Map(Some(ScMap(entries.try_into().unwrap())))
+    }
+
+    #[test]
+    fn test_decode_contract_struct() {
+        let round = map(vec![
+            ("ledger", scval::u32(12)),
+            ("price", scval::i128(1_087_000)),
+            ("round_id", scval::u64(3)),
+            ("timestamp", scval::u64(1_700_000_000)),
+        ]);
+        assert_eq!(
+            PriceRound::from_scval(&round).unwrap(),
+            PriceRound {
+                round_id: 3,
+                price: 1_087_000,
+                timestamp: 1_700_000_000,
+                ledger: 12
+            }
+        );
+        assert_eq!(
+            Option::<PriceRound>::from_scval(&ScVal::Void).unwrap(),
+            None
+        );
+        assert!(PriceRound::from_scval(&scval::i128(1)).is_err());
+
+        let collaterals = map(vec![("XLM", scval::i128(5)), ("USDC", scval::i128(7))]);
+        let collaterals = BTreeMap::<String, i128>::from_scval(&collaterals).unwrap();
+        assert_eq!(collaterals["XLM"], 5);
+        assert_eq!(collaterals["USDC"], 7);
+    }
+}
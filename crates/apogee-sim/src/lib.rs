@@ -0,0 +1,254 @@
+//! # Stellend Simulation
+//!
+//! Runs parameterized market scenarios against the real pool, oracle and
+//! rate model contracts in the Soroban test environment and reports one CSV
+//! row per step, so risk parameters can be tuned with data instead of
+//! intuition.
+//!
+//! ```text
+//! utilization_ramp   borrows ramp utilization up to `max_utilization`;
+//!                    reports rates, indexes and reserves per step
+//! price_crash        XLM falls `crash_pct` per step while a keeper
+//!                    liquidates every `keeper_latency + 1` steps
+//! keeper_latency     reruns price_crash for latencies 0..=keeper_latency
+//!                    and reports the outcome of each run
+//! ```
+//!
+//! All values use the contracts' own scaling: ratios and USD values by 1e7,
+//! token amounts in 7-decimal units, indexes by 1e9.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use apogee_common::SCALE;
+
+mod scenarios;
+mod world;
+
+pub use scenarios::{keeper_latency, price_crash, utilization_ramp, CrashSummary};
+pub use world::World;
+
+/// Names accepted by [`run`]
+pub const SCENARIOS: [&str; 3] = ["utilization_ramp", "price_crash", "keeper_latency"];
+
+/// Errors returned by the simulator
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SimError {
+    /// Scenario name not in [`SCENARIOS`]
+    UnknownScenario(String),
+    /// Parameter name not known to [`Params::set`]
+    UnknownParam(String),
+    /// Parameter value could not be parsed
+    InvalidValue(String, String),
+    /// A contract rejected the scenario setup (e.g. LTV above threshold)
+    Setup(String),
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::UnknownScenario(name) => {
+                write!(f, "unknown scenario `{name}` (expected one of {})", SCENARIOS.join(", "))
+            }
+            SimError::UnknownParam(key) => write!(f, "unknown parameter `{key}`"),
+            SimError::InvalidValue(key, value) => write!(f, "invalid value `{value}` for `{key}`"),
+            SimError::Setup(reason) => write!(f, "scenario setup failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/// Scenario parameters
+///
+/// Ratios are scaled by SCALE (1e7). On the command line they can also be
+/// given as percentages, e.g. `ltv=70%` or `crash_pct=2.5%`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Params {
+    /// XLM loan-to-value ratio
+    pub ltv: i128,
+    /// XLM liquidation threshold
+    pub liquidation_threshold: i128,
+    /// XLM liquidation bonus
+    pub liquidation_bonus: i128,
+    /// Rate model floor
+    pub rate_min: i128,
+    /// Rate model rate at the optimal utilization
+    pub rate_opt: i128,
+    /// Rate model rate at 100% utilization
+    pub rate_max: i128,
+    /// Rate model kink
+    pub optimal_utilization: i128,
+    /// Number of steps to simulate
+    pub steps: u32,
+    /// Ledger time between steps, in seconds
+    pub step_secs: u64,
+    /// Utilization reached at the last step of `utilization_ramp`
+    pub max_utilization: i128,
+    /// Relative XLM price drop per step in `price_crash`
+    pub crash_pct: i128,
+    /// Steps the keeper waits between liquidation passes
+    pub keeper_latency: u32,
+    /// Number of borrowers in `price_crash`, spread from 50% to 100% of their borrow limit
+    pub borrowers: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            ltv: 7_500_000,
+            liquidation_threshold: 8_000_000,
+            liquidation_bonus: 500_000,
+            rate_min: 0,
+            rate_opt: 400_000,
+            rate_max: 10_000_000,
+            optimal_utilization: 8_000_000,
+            steps: 20,
+            step_secs: 24 * 60 * 60,
+            max_utilization: 9_500_000,
+            crash_pct: 500_000,
+            keeper_latency: 0,
+            borrowers: 10,
+        }
+    }
+}
+
+impl Params {
+    /// Sets one parameter from a `key=value` command-line pair
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SimError> {
+        let invalid = || SimError::InvalidValue(key.to_string(), value.to_string());
+        match key {
+            "ltv" => self.ltv = parse_ratio(value).ok_or_else(invalid)?,
+            "liquidation_threshold" => self.liquidation_threshold = parse_ratio(value).ok_or_else(invalid)?,
+            "liquidation_bonus" => self.liquidation_bonus = parse_ratio(value).ok_or_else(invalid)?,
+            "rate_min" => self.rate_min = parse_ratio(value).ok_or_else(invalid)?,
+            "rate_opt" => self.rate_opt = parse_ratio(value).ok_or_else(invalid)?,
+            "rate_max" => self.rate_max = parse_ratio(value).ok_or_else(invalid)?,
+            "optimal_utilization" => self.optimal_utilization = parse_ratio(value).ok_or_else(invalid)?,
+            "steps" => self.steps = value.parse().map_err(|_| invalid())?,
+            "step_secs" => self.step_secs = value.parse().map_err(|_| invalid())?,
+            "max_utilization" => self.max_utilization = parse_ratio(value).ok_or_else(invalid)?,
+            "crash_pct" => self.crash_pct = parse_ratio(value).ok_or_else(invalid)?,
+            "keeper_latency" => self.keeper_latency = value.parse().map_err(|_| invalid())?,
+            "borrowers" => self.borrowers = value.parse().map_err(|_| invalid())?,
+            _ => return Err(SimError::UnknownParam(key.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Parses a ratio given either scaled by SCALE (`7500000`) or as a percentage (`75%`, `2.5%`)
+pub fn parse_ratio(value: &str) -> Option<i128> {
+    let Some(percent) = value.strip_suffix('%') else {
+        return value.parse().ok();
+    };
+
+    // 1% = SCALE / 100 = 100_000, so up to 5 fractional digits are exact
+    let (whole, fraction) = percent.split_once('.').unwrap_or((percent, ""));
+    if fraction.len() > 5 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let whole: i128 = whole.parse().ok()?;
+    let fraction: i128 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<5}").parse().ok()?
+    };
+    Some(whole * (SCALE / 100) + fraction)
+}
+
+/// Scenario output: named integer columns, one row per step
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Table {
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<i128>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Table { columns: columns.to_vec(), rows: Vec::new() }
+    }
+
+    pub fn push(&mut self, row: Vec<i128>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    /// Values of one column, in row order
+    pub fn column(&self, name: &str) -> Option<Vec<i128>> {
+        let index = self.columns.iter().position(|c| *c == name)?;
+        Some(self.rows.iter().map(|row| row[index]).collect())
+    }
+
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", self.columns.join(","))?;
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(i128::to_string).collect();
+            writeln!(out, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a scenario by name
+pub fn run(scenario: &str, params: &Params) -> Result<Table, SimError> {
+    match scenario {
+        "utilization_ramp" => utilization_ramp(params),
+        "price_crash" => price_crash(params).map(|(table, _)| table),
+        "keeper_latency" => keeper_latency(params),
+        _ => Err(SimError::UnknownScenario(scenario.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_ratio() {
+        assert_eq!(parse_ratio("7500000"), Some(7_500_000));
+        assert_eq!(parse_ratio("75%"), Some(7_500_000));
+        assert_eq!(parse_ratio("2.5%"), Some(250_000));
+        assert_eq!(parse_ratio("0.00001%"), Some(1));
+        assert_eq!(parse_ratio("0.000001%"), None);
+        assert_eq!(parse_ratio("abc%"), None);
+    }
+
+    #[test]
+    fn test_utilization_ramp_rates_follow_utilization() {
+        let params = Params { steps: 5, ..Params::default() };
+        let table = run("utilization_ramp", &params).unwrap();
+
+        let utilization = table.column("utilization").unwrap();
+        let borrow_rate = table.column("borrow_rate").unwrap();
+        let borrow_index = table.column("borrow_index").unwrap();
+        assert_eq!(table.rows.len(), 5);
+        assert!(utilization.windows(2).all(|w| w[1] > w[0]));
+        assert!(borrow_rate.windows(2).all(|w| w[1] > w[0]));
+        assert!(borrow_index.windows(2).all(|w| w[1] > w[0]));
+        assert!(*utilization.last().unwrap() >= 9_400_000);
+
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("step,utilization,borrow_rate,"));
+        assert_eq!(csv.lines().count(), 6);
+    }
+
+    #[test]
+    fn test_slower_keeper_leaves_more_shortfall() {
+        let params = Params { steps: 8, crash_pct: 1_000_000, borrowers: 4, ..Params::default() };
+        let (_, prompt) = price_crash(&params).unwrap();
+        let (_, late) = price_crash(&Params { keeper_latency: 3, ..params.clone() }).unwrap();
+
+        assert!(prompt.liquidations > 0);
+        assert!(late.shortfall_usd >= prompt.shortfall_usd);
+    }
+
+    #[test]
+    fn test_invalid_setup_is_reported() {
+        let params = Params { ltv: 9_000_000, liquidation_threshold: 8_000_000, ..Params::default() };
+        assert!(matches!(run("price_crash", &params), Err(SimError::Setup(_))));
+        assert_eq!(run("moon", &params), Err(SimError::UnknownScenario("moon".to_string())));
+    }
+}
@@ -0,0 +1,67 @@
+//! `apogee-sim <scenario> [key=value ...] [--out <file>]`
+//!
+//! Runs a scenario and writes its CSV to stdout or `--out`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use apogee_sim::{run, Params, SCENARIOS};
+
+fn usage() -> String {
+    format!(
+        "usage: apogee-sim <scenario> [key=value ...] [--out <file>]\n\
+         scenarios: {}\n\
+         parameters: ltv, liquidation_threshold, liquidation_bonus, rate_min, rate_opt,\n\
+         \x20           rate_max, optimal_utilization, max_utilization, crash_pct (1e7 or `75%`),\n\
+         \x20           steps, step_secs, keeper_latency, borrowers",
+        SCENARIOS.join(", ")
+    )
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(scenario) = args.next().filter(|a| a != "--help" && a != "-h") else {
+        eprintln!("{}", usage());
+        return ExitCode::FAILURE;
+    };
+
+    let mut params = Params::default();
+    let mut out_path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--out" {
+            out_path = args.next();
+            continue;
+        }
+        let Some((key, value)) = arg.split_once('=') else {
+            eprintln!("expected key=value, got `{arg}`\n{}", usage());
+            return ExitCode::FAILURE;
+        };
+        if let Err(e) = params.set(key, value) {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let table = match run(&scenario, &params) {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let written = match out_path {
+        Some(path) => File::create(&path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            table.write_csv(&mut out)?;
+            out.flush()
+        }),
+        None => table.write_csv(&mut io::stdout().lock()),
+    };
+    if let Err(e) = written {
+        eprintln!("failed to write CSV: {e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,212 @@
+//! Scenario implementations
+
+use apogee_common::SCALE;
+use soroban_sdk::Address;
+
+use crate::world::{World, INITIAL_XLM_PRICE, UNIT, USDC, XLM};
+use crate::{Params, SimError, Table};
+
+/// Collateral every `price_crash` borrower deposits (10,000 XLM)
+const BORROWER_COLLATERAL: i128 = 10_000 * UNIT;
+
+/// Liquidations the keeper attempts per borrower in one pass
+const MAX_LIQUIDATIONS_PER_PASS: u32 = 10;
+
+/// Outcome of one `price_crash` run
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CrashSummary {
+    pub liquidations: i128,
+    /// USDC repaid by the keeper
+    pub debt_repaid: i128,
+    /// XLM seized by the keeper
+    pub collateral_seized: i128,
+    /// Positions with a health factor below 1 at the end
+    pub unhealthy_positions: i128,
+    /// Debt not covered by collateral at the end, in USD
+    pub shortfall_usd: i128,
+}
+
+/// Borrowers push utilization from 0 to `max_utilization` in equal steps
+///
+/// Columns: step, utilization, borrow_rate, supply_rate, borrow_index,
+/// exchange_rate, total_reserves
+pub fn utilization_ramp(params: &Params) -> Result<Table, SimError> {
+    let world = World::new(params)?;
+    let pool = &world.pool;
+    let mut table = Table::new(&[
+        "step",
+        "utilization",
+        "borrow_rate",
+        "supply_rate",
+        "borrow_index",
+        "exchange_rate",
+        "total_reserves",
+    ]);
+
+    let supplier = world.user(0, 1_000_000 * UNIT);
+    pool.supply(&supplier, &supplier, &USDC, &(1_000_000 * UNIT), &None);
+
+    // Enough collateral that the borrow limit never binds
+    let borrower = world.user(100_000_000 * UNIT, 0);
+    pool.deposit_collateral(&borrower, &XLM, &(100_000_000 * UNIT));
+
+    for step in 1..=params.steps {
+        let target = params.max_utilization * step as i128 / params.steps as i128;
+        let market = pool.get_market_info(&USDC);
+        let shortfall = market.total_supply * target / SCALE - market.total_borrow;
+        if shortfall > 0 {
+            pool.borrow(&borrower, &USDC, &shortfall, &None);
+        }
+
+        world.advance(params.step_secs);
+
+        let market = pool.get_market_info(&USDC);
+        table.push(vec![
+            step as i128,
+            market.utilization_rate,
+            market.borrow_rate,
+            market.supply_rate,
+            pool.get_borrow_index(&USDC),
+            market.exchange_rate,
+            pool.get_total_reserves(&USDC),
+        ]);
+    }
+
+    Ok(table)
+}
+
+/// XLM falls by `crash_pct` every step while a keeper liquidates unhealthy positions
+///
+/// Borrowers each deposit 10,000 XLM and borrow between 50% and 100% of
+/// their limit. The keeper runs every `keeper_latency + 1` steps and
+/// liquidates each unhealthy position until it is healthy again or has no
+/// collateral left.
+///
+/// Columns: step, xlm_price, liquidations, debt_repaid, collateral_seized,
+/// unhealthy_positions, shortfall_usd, total_borrow
+pub fn price_crash(params: &Params) -> Result<(Table, CrashSummary), SimError> {
+    let world = World::new(params)?;
+    let pool = &world.pool;
+    let mut table = Table::new(&[
+        "step",
+        "xlm_price",
+        "liquidations",
+        "debt_repaid",
+        "collateral_seized",
+        "unhealthy_positions",
+        "shortfall_usd",
+        "total_borrow",
+    ]);
+
+    let supplier = world.user(0, 10_000_000 * UNIT);
+    pool.supply(&supplier, &supplier, &USDC, &(10_000_000 * UNIT), &None);
+
+    // Borrow limit in USDC for one position at the starting price
+    let limit = BORROWER_COLLATERAL * INITIAL_XLM_PRICE / SCALE * params.ltv / SCALE;
+    let borrowers: Vec<Address> = (0..params.borrowers)
+        .map(|i| {
+            let borrower = world.user(BORROWER_COLLATERAL, 0);
+            pool.deposit_collateral(&borrower, &XLM, &BORROWER_COLLATERAL);
+            let fraction = SCALE / 2 + SCALE / 2 * i as i128 / params.borrowers as i128;
+            pool.borrow(&borrower, &USDC, &(limit * fraction / SCALE), &None);
+            borrower
+        })
+        .collect();
+
+    let keeper = world.user(0, 100_000_000 * UNIT);
+    let mut summary = CrashSummary::default();
+    let mut price = INITIAL_XLM_PRICE;
+
+    for step in 1..=params.steps {
+        price = (price * (SCALE - params.crash_pct) / SCALE).max(1);
+        world.set_xlm_price(price);
+        world.advance(params.step_secs);
+
+        if step % (params.keeper_latency + 1) == 0 {
+            for borrower in &borrowers {
+                liquidate_until_healthy(&world, &keeper, borrower, &mut summary);
+            }
+        }
+
+        let (unhealthy, shortfall) = position_health(&world, &borrowers);
+        summary.unhealthy_positions = unhealthy;
+        summary.shortfall_usd = shortfall;
+        table.push(vec![
+            step as i128,
+            price,
+            summary.liquidations,
+            summary.debt_repaid,
+            summary.collateral_seized,
+            unhealthy,
+            shortfall,
+            pool.get_market_info(&USDC).total_borrow,
+        ]);
+    }
+
+    Ok((table, summary))
+}
+
+/// Reruns `price_crash` for every latency from 0 to `keeper_latency`
+///
+/// Columns: keeper_latency, liquidations, debt_repaid, collateral_seized,
+/// unhealthy_positions, shortfall_usd
+pub fn keeper_latency(params: &Params) -> Result<Table, SimError> {
+    let mut table = Table::new(&[
+        "keeper_latency",
+        "liquidations",
+        "debt_repaid",
+        "collateral_seized",
+        "unhealthy_positions",
+        "shortfall_usd",
+    ]);
+
+    for latency in 0..=params.keeper_latency {
+        let run = Params { keeper_latency: latency, ..params.clone() };
+        let (_, summary) = price_crash(&run)?;
+        table.push(vec![
+            latency as i128,
+            summary.liquidations,
+            summary.debt_repaid,
+            summary.collateral_seized,
+            summary.unhealthy_positions,
+            summary.shortfall_usd,
+        ]);
+    }
+
+    Ok(table)
+}
+
+fn liquidate_until_healthy(world: &World, keeper: &Address, borrower: &Address, summary: &mut CrashSummary) {
+    let pool = &world.pool;
+    let close_factor = pool.get_close_factor();
+
+    for _ in 0..MAX_LIQUIDATIONS_PER_PASS {
+        if pool.get_health_factor(borrower) >= SCALE || pool.get_user_collateral(borrower, &XLM) == 0 {
+            return;
+        }
+        let debt_before = pool.get_user_debt_total(borrower, &USDC);
+        let repay = debt_before * close_factor / SCALE;
+        match pool.try_liquidate(keeper, borrower, &USDC, &repay, &XLM, &0, &None) {
+            Ok(Ok(seized)) => {
+                summary.liquidations += 1;
+                summary.debt_repaid += debt_before - pool.get_user_debt_total(borrower, &USDC);
+                summary.collateral_seized += seized;
+            }
+            _ => return,
+        }
+    }
+}
+
+/// Number of unhealthy positions and the debt their collateral no longer covers
+fn position_health(world: &World, borrowers: &[Address]) -> (i128, i128) {
+    let mut unhealthy = 0;
+    let mut shortfall = 0;
+    for borrower in borrowers {
+        let account = world.pool.get_user_account_data(borrower);
+        if account.total_debt_usd > 0 && account.health_factor < SCALE {
+            unhealthy += 1;
+        }
+        shortfall += (account.total_debt_usd - account.total_collateral_usd).max(0);
+    }
+    (unhealthy, shortfall)
+}
@@ -0,0 +1,102 @@
+//! Deployment of the contracts a scenario runs against
+
+use apogee_testutils::{advance_time, create_native_xlm, create_token, fund};
+use soroban_sdk::{symbol_short, testutils::Address as _, Address, Env, Symbol};
+use stellend_interest_rate_model::{InterestRateModel, InterestRateModelClient};
+use stellend_pool::{LendingPool, LendingPoolClient};
+use stellend_price_oracle::{PriceOracle, PriceOracleClient};
+
+use crate::{Params, SimError};
+
+pub const XLM: Symbol = symbol_short!("XLM");
+pub const USDC: Symbol = symbol_short!("USDC");
+
+/// One unit of a 7-decimal token
+pub const UNIT: i128 = 10_000_000;
+
+/// XLM price every scenario starts from ($0.30)
+pub const INITIAL_XLM_PRICE: i128 = 3_000_000;
+
+/// A pool wired to a real oracle and rate model, with SAC tokens for XLM and USDC
+pub struct World {
+    pub env: Env,
+    pub pool: LendingPoolClient<'static>,
+    pub oracle: PriceOracleClient<'static>,
+    pub xlm: Address,
+    pub usdc: Address,
+    poker: Address,
+}
+
+impl World {
+    /// Deploys and configures the contracts for `params`
+    pub fn new(params: &Params) -> Result<Self, SimError> {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Scenarios run hundreds of calls; resource limits are not what is being measured
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let (xlm, _) = create_native_xlm(&env, &admin);
+        let (usdc, _) = create_token(&env, &admin);
+
+        let oracle = PriceOracleClient::new(&env, &env.register_contract(None, PriceOracle));
+        oracle.initialize(&admin, &xlm.address, &usdc.address);
+        oracle.set_price(&xlm.address, &INITIAL_XLM_PRICE);
+
+        let irm = InterestRateModelClient::new(&env, &env.register_contract(None, InterestRateModel));
+        irm.try_initialize(
+            &admin,
+            &params.rate_min,
+            &params.rate_opt,
+            &params.rate_max,
+            &params.optimal_utilization,
+        )
+        .map_err(|e| SimError::Setup(format!("rate model rejected the curve: {e:?}")))?
+        .map_err(|e| SimError::Setup(format!("rate model rejected the curve: {e:?}")))?;
+
+        let pool = LendingPoolClient::new(&env, &env.register_contract(None, LendingPool));
+        pool.initialize(&admin, &oracle.address, &irm.address, &xlm.address, &usdc.address);
+
+        // Move the threshold first when it rises and the LTV first when it
+        // falls, so the LTV never passes the threshold in between
+        let risk_setup = if params.liquidation_threshold >= pool.get_liquidation_threshold(&XLM) {
+            pool.try_set_liquidation_threshold(&XLM, &params.liquidation_threshold)
+                .and_then(|_| pool.try_set_ltv_ratio(&XLM, &params.ltv))
+        } else {
+            pool.try_set_ltv_ratio(&XLM, &params.ltv)
+                .and_then(|_| pool.try_set_liquidation_threshold(&XLM, &params.liquidation_threshold))
+        }
+        .and_then(|_| pool.try_set_liquidation_bonus(&XLM, &params.liquidation_bonus));
+        if let Err(e) = risk_setup {
+            return Err(SimError::Setup(format!("pool rejected the risk parameters: {e:?}")));
+        }
+
+        let poker = Address::generate(&env);
+        fund(&env, &usdc.address, &[&poker], 1_000_000 * UNIT);
+
+        Ok(World { env, pool, oracle, xlm: xlm.address, usdc: usdc.address, poker })
+    }
+
+    /// Creates a user holding the given XLM and USDC amounts
+    pub fn user(&self, xlm: i128, usdc: i128) -> Address {
+        let user = Address::generate(&self.env);
+        if xlm > 0 {
+            fund(&self.env, &self.xlm, &[&user], xlm);
+        }
+        if usdc > 0 {
+            fund(&self.env, &self.usdc, &[&user], usdc);
+        }
+        user
+    }
+
+    pub fn set_xlm_price(&self, price: i128) {
+        self.oracle.set_price(&self.xlm, &price);
+    }
+
+    /// Moves the clock forward and brings the USDC market's indexes up to date
+    pub fn advance(&self, secs: u64) {
+        advance_time(&self.env, secs);
+        // Any state-changing call accrues interest first; a 1 USDC supply is the cheapest
+        self.pool.supply(&self.poker, &self.poker, &USDC, &UNIT, &None);
+    }
+}
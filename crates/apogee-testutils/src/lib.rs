@@ -0,0 +1,193 @@
+#![no_std]
+
+//! # Stellend Test Utilities
+//!
+//! Fixtures shared by the contract test suites, so each one does not have
+//! to redeploy tokens, stand up an oracle and move the ledger by hand.
+//!
+//! ```text
+//! create_token        Stellar asset contract + admin client
+//! create_native_xlm   the network's native XLM contract, mintable by a test admin
+//! fund                mint a token to several users
+//! MockOracle          oracle whose prices the test sets directly
+//! MockSwapAdapter     swap adapter trading at rates the test sets
+//! advance_time        move the ledger clock forward
+//! advance_ledgers     move the ledger sequence forward (TTL tests)
+//! ```
+//!
+//! Only meant as a `dev-dependency`.
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype,
+    testutils::Ledger,
+    token::{StellarAssetClient, TokenClient},
+    vec, Address, Bytes, Env, Symbol,
+};
+
+/// Scale of [`MockSwapAdapter`] rates (1e7, like prices)
+const RATE_SCALE: i128 = 10_000_000;
+
+/// Registers a Stellar asset contract administered by `admin`
+///
+/// Returns the SEP-41 client and the admin client used for minting.
+pub fn create_token<'a>(env: &Env, admin: &Address) -> (TokenClient<'a>, StellarAssetClient<'a>) {
+    let contract_address = env.register_stellar_asset_contract_v2(admin.clone());
+    (
+        TokenClient::new(env, &contract_address.address()),
+        StellarAssetClient::new(env, &contract_address.address()),
+    )
+}
+
+/// Deploys the native XLM Stellar asset contract at its network address
+///
+/// The native contract has no admin, so `admin` is written into its instance
+/// storage under the key the asset contract reads; minting then works as with
+/// [`create_token`]. Lets tests exercise code that only accepts the real
+/// native asset contract.
+pub fn create_native_xlm<'a>(env: &Env, admin: &Address) -> (TokenClient<'a>, StellarAssetClient<'a>) {
+    let address = env
+        .deployer()
+        .with_stellar_asset(Bytes::from_array(env, &apogee_common::NATIVE_ASSET_XDR))
+        .deploy();
+    env.as_contract(&address, || {
+        env.storage().instance().set(&vec![env, Symbol::new(env, "Admin")], admin);
+    });
+    (TokenClient::new(env, &address), StellarAssetClient::new(env, &address))
+}
+
+/// Mints `amount` of `token` to every user
+///
+/// Requires auths to be mocked.
+pub fn fund(env: &Env, token: &Address, users: &[&Address], amount: i128) {
+    let minter = StellarAssetClient::new(env, token);
+    for user in users {
+        minter.mint(user, &amount);
+    }
+}
+
+/// Moves the ledger timestamp forward by `seconds`
+pub fn advance_time(env: &Env, seconds: u64) {
+    env.ledger().with_mut(|li| li.timestamp += seconds);
+}
+
+/// Moves the ledger sequence forward by `ledgers`, leaving the clock alone
+pub fn advance_ledgers(env: &Env, ledgers: u32) {
+    env.ledger().with_mut(|li| li.sequence_number += ledgers);
+}
+
+/// Registers a [`MockOracle`]
+pub fn create_mock_oracle<'a>(env: &Env) -> MockOracleClient<'a> {
+    MockOracleClient::new(env, &env.register_contract(None, MockOracle))
+}
+
+// ============================================================================
+// MOCK ORACLE
+// ============================================================================
+
+#[derive(Clone)]
+#[contracttype]
+enum MockOracleKey {
+    Price(Address),
+    Variance(Address),
+    PriceReads,
+    Depegged(Address),
+}
+
+/// Price oracle whose prices are set directly by the test
+///
+/// Implements the read side of the price oracle the pool calls (`get_price`,
+/// `get_twap`, `get_price_variance`, `is_depegged`) without staleness, deviation or feeder
+/// checks. The TWAP is the spot price and unset prices read as 0. Counts
+/// `get_price` calls so tests can check how often a caller reads prices.
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    /// Set the price of an asset (scaled by 1e7)
+    pub fn set_price(env: Env, asset: Address, price: i128) {
+        env.storage().instance().set(&MockOracleKey::Price(asset), &price);
+    }
+
+    /// Set the price variance reported for an asset
+    pub fn set_price_variance(env: Env, asset: Address, variance: i128) {
+        env.storage().instance().set(&MockOracleKey::Variance(asset), &variance);
+    }
+
+    pub fn get_price(env: Env, asset: Address) -> i128 {
+        let reads = Self::price_reads(env.clone());
+        env.storage().instance().set(&MockOracleKey::PriceReads, &(reads + 1));
+        env.storage().instance().get(&MockOracleKey::Price(asset)).unwrap_or(0)
+    }
+
+    pub fn get_twap(env: Env, asset: Address, _window_secs: u64) -> i128 {
+        env.storage().instance().get(&MockOracleKey::Price(asset)).unwrap_or(0)
+    }
+
+    /// Number of `get_price` calls so far
+    pub fn price_reads(env: Env) -> u32 {
+        env.storage().instance().get(&MockOracleKey::PriceReads).unwrap_or(0)
+    }
+
+    pub fn get_price_variance(env: Env, asset: Address) -> i128 {
+        env.storage().instance().get(&MockOracleKey::Variance(asset)).unwrap_or(0)
+    }
+
+    /// Flag an asset as depegged or back in peg
+    pub fn set_depegged(env: Env, asset: Address, depegged: bool) {
+        env.storage().instance().set(&MockOracleKey::Depegged(asset), &depegged);
+    }
+
+    pub fn is_depegged(env: Env, asset: Address) -> bool {
+        env.storage().instance().get(&MockOracleKey::Depegged(asset)).unwrap_or(false)
+    }
+}
+
+// ============================================================================
+// MOCK SWAP ADAPTER
+// ============================================================================
+
+/// Registers a [`MockSwapAdapter`]
+pub fn create_mock_swap_adapter<'a>(env: &Env) -> MockSwapAdapterClient<'a> {
+    MockSwapAdapterClient::new(env, &env.register_contract(None, MockSwapAdapter))
+}
+
+#[derive(Clone)]
+#[contracttype]
+enum MockSwapKey {
+    Rate(Address, Address),
+}
+
+/// Swap adapter trading at fixed rates out of its own balance
+///
+/// Implements the pool's `SwapAdapter` interface. Fund it with the output
+/// tokens; inputs stay in the adapter.
+#[contract]
+pub struct MockSwapAdapter;
+
+#[contractimpl]
+impl MockSwapAdapter {
+    /// Set the `token_out` paid per `token_in` (scaled by 1e7)
+    pub fn set_rate(env: Env, token_in: Address, token_out: Address, rate: i128) {
+        env.storage().instance().set(&MockSwapKey::Rate(token_in, token_out), &rate);
+    }
+
+    pub fn swap_exact_in(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_out: i128,
+        to: Address,
+    ) -> i128 {
+        let rate: i128 = env
+            .storage()
+            .instance()
+            .get(&MockSwapKey::Rate(token_in, token_out.clone()))
+            .unwrap_or(0);
+        let amount_out = amount_in * rate / RATE_SCALE;
+        assert!(amount_out >= min_out, "insufficient output");
+        TokenClient::new(&env, &token_out).transfer(&env.current_contract_address(), &to, &amount_out);
+        amount_out
+    }
+}
@@ -0,0 +1,266 @@
+//! # Stellend Wasm
+//!
+//! Browser bindings for client-side previews: account totals, the health
+//! factor after a borrow or withdrawal, and rates and APYs along the rate
+//! model's curve. The math is the contracts' own: the same
+//! `stellend-fixed-point` helpers with the same rounding, so the numbers a
+//! user sees before signing are the ones the pool computes.
+//!
+//! ```text
+//! wasm-pack build crates/apogee-wasm --target web
+//! ```
+//!
+//! ```js
+//! import init, { Account, RateModel } from "./pkg/apogee_wasm.js";
+//!
+//! await init();
+//! const account = new Account();
+//! account.add_collateral("XLM", "10000000000", "1000000", "7500000", "8000000", true);
+//! account.add_debt("USDC", "400000000", "10000000");
+//! account.preview_borrow("USDC", "100000000", "10000000").health_factor; // "16000000"
+//! RateModel.default_curve().borrow_apy("9000000");
+//! ```
+//!
+//! Amounts, prices and rates cross the boundary as decimal strings, since
+//! JavaScript numbers cannot hold an `i128`. Scales follow the contracts:
+//! token units for amounts, 1e7 for prices, USD values, ratios and rates.
+
+use wasm_bindgen::prelude::*;
+
+mod math;
+
+pub use math::{
+    account_data, compound_annual_rate, utilization, AccountData, Collateral, Debt, RateCurve,
+    NO_DEBT_HEALTH_FACTOR, SECONDS_PER_YEAR,
+};
+
+use stellend_fixed_point::FixedPointError;
+
+fn parse(name: &str, value: &str) -> Result<i128, JsError> {
+    value
+        .parse()
+        .map_err(|_| JsError::new(&format!("{name}: not an integer: {value:?}")))
+}
+
+fn math_error(e: FixedPointError) -> JsError {
+    JsError::new(match e {
+        FixedPointError::Overflow => "arithmetic overflow",
+        FixedPointError::DivisionByZero => "division by zero",
+    })
+}
+
+/// A user's collateral and debt, as read from the pool
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Account {
+    collateral: Vec<Collateral>,
+    debt: Vec<Debt>,
+}
+
+#[wasm_bindgen]
+impl Account {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Account {
+        Account::default()
+    }
+
+    /// Adds collateral in `asset`; `ltv` is the volatility-adjusted one
+    /// (`get_volatility_adjusted_cf`) and `enabled` whether the reserve
+    /// still counts towards borrow capacity
+    pub fn add_collateral(
+        &mut self,
+        asset: &str,
+        amount: &str,
+        price: &str,
+        ltv: &str,
+        liquidation_threshold: &str,
+        enabled: bool,
+    ) -> Result<(), JsError> {
+        self.collateral.push(Collateral {
+            asset: asset.to_string(),
+            amount: parse("amount", amount)?,
+            price: parse("price", price)?,
+            ltv: parse("ltv", ltv)?,
+            liquidation_threshold: parse("liquidation_threshold", liquidation_threshold)?,
+            enabled,
+        });
+        Ok(())
+    }
+
+    /// Adds debt in `asset`, including accrued interest
+    pub fn add_debt(&mut self, asset: &str, amount: &str, price: &str) -> Result<(), JsError> {
+        self.debt.push(Debt {
+            asset: asset.to_string(),
+            amount: parse("amount", amount)?,
+            price: parse("price", price)?,
+        });
+        Ok(())
+    }
+
+    /// Current totals and health factor
+    pub fn data(&self) -> Result<AccountPreview, JsError> {
+        self.preview(&self.collateral, &self.debt)
+    }
+
+    /// Totals after borrowing `amount` of `asset` at `price`
+    pub fn preview_borrow(
+        &self,
+        asset: &str,
+        amount: &str,
+        price: &str,
+    ) -> Result<AccountPreview, JsError> {
+        let mut debt = self.debt.clone();
+        debt.push(Debt {
+            asset: asset.to_string(),
+            amount: parse("amount", amount)?,
+            price: parse("price", price)?,
+        });
+        self.preview(&self.collateral, &debt)
+    }
+
+    /// Totals after withdrawing `amount` of `asset` collateral
+    pub fn preview_withdraw(&self, asset: &str, amount: &str) -> Result<AccountPreview, JsError> {
+        let mut amount = parse("amount", amount)?;
+        let mut collateral = self.collateral.clone();
+        for c in collateral.iter_mut().filter(|c| c.asset == asset) {
+            let taken = amount.min(c.amount);
+            c.amount -= taken;
+            amount -= taken;
+        }
+        if amount > 0 {
+            return Err(JsError::new(&format!(
+                "withdrawal exceeds {asset} collateral"
+            )));
+        }
+        self.preview(&collateral, &self.debt)
+    }
+
+    fn preview(&self, collateral: &[Collateral], debt: &[Debt]) -> Result<AccountPreview, JsError> {
+        account_data(collateral, debt)
+            .map(AccountPreview)
+            .map_err(math_error)
+    }
+}
+
+/// Account totals; USD values and the health factor are scaled by 1e7
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct AccountPreview(AccountData);
+
+#[wasm_bindgen]
+impl AccountPreview {
+    #[wasm_bindgen(getter)]
+    pub fn collateral_value_usd(&self) -> String {
+        self.0.collateral_value_usd.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn debt_value_usd(&self) -> String {
+        self.0.debt_value_usd.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn borrow_capacity_usd(&self) -> String {
+        self.0.borrow_capacity_usd.to_string()
+    }
+
+    /// Borrow capacity not yet used, in USD
+    #[wasm_bindgen(getter)]
+    pub fn available_borrow_usd(&self) -> String {
+        (self.0.borrow_capacity_usd - self.0.debt_value_usd)
+            .max(0)
+            .to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn health_factor(&self) -> String {
+        self.0.health_factor.to_string()
+    }
+
+    /// Whether the pool would allow liquidating the account
+    #[wasm_bindgen(getter)]
+    pub fn liquidatable(&self) -> bool {
+        self.0.health_factor < stellend_fixed_point::SCALE_7
+    }
+}
+
+/// A rate model curve; see `InterestRateModel::get_config`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RateModel(RateCurve);
+
+#[wasm_bindgen]
+impl RateModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        rate_min: &str,
+        rate_opt: &str,
+        rate_max: &str,
+        optimal_utilization: &str,
+    ) -> Result<RateModel, JsError> {
+        Ok(RateModel(RateCurve {
+            rate_min: parse("rate_min", rate_min)?,
+            rate_opt: parse("rate_opt", rate_opt)?,
+            rate_max: parse("rate_max", rate_max)?,
+            optimal_utilization: parse("optimal_utilization", optimal_utilization)?,
+        }))
+    }
+
+    /// The curve `initialize_default` sets up
+    pub fn default_curve() -> RateModel {
+        RateModel(RateCurve::default())
+    }
+
+    pub fn borrow_rate(&self, utilization: &str) -> Result<String, JsError> {
+        let u = parse("utilization", utilization)?;
+        self.0
+            .borrow_rate(u)
+            .map(|r| r.to_string())
+            .map_err(math_error)
+    }
+
+    pub fn supply_rate(&self, utilization: &str) -> Result<String, JsError> {
+        let u = parse("utilization", utilization)?;
+        self.0
+            .supply_rate(u)
+            .map(|r| r.to_string())
+            .map_err(math_error)
+    }
+
+    pub fn borrow_apy(&self, utilization: &str) -> Result<String, JsError> {
+        let u = parse("utilization", utilization)?;
+        self.0
+            .borrow_rate(u)
+            .and_then(compound_annual_rate)
+            .map(|r| r.to_string())
+            .map_err(math_error)
+    }
+
+    pub fn supply_apy(&self, utilization: &str) -> Result<String, JsError> {
+        let u = parse("utilization", utilization)?;
+        self.0
+            .supply_rate(u)
+            .and_then(compound_annual_rate)
+            .map(|r| r.to_string())
+            .map_err(math_error)
+    }
+}
+
+/// Utilization of a reserve from its totals, scaled by 1e7
+#[wasm_bindgen(js_name = utilization)]
+pub fn utilization_js(total_borrow: &str, total_supply: &str) -> Result<String, JsError> {
+    utilization(
+        parse("total_borrow", total_borrow)?,
+        parse("total_supply", total_supply)?,
+    )
+    .map(|u| u.to_string())
+    .map_err(math_error)
+}
+
+/// APY of an annual rate compounded every second, both scaled by 1e7
+#[wasm_bindgen]
+pub fn apy(annual_rate: &str) -> Result<String, JsError> {
+    compound_annual_rate(parse("annual_rate", annual_rate)?)
+        .map(|r| r.to_string())
+        .map_err(math_error)
+}
@@ -0,0 +1,313 @@
+//! The pool's account valuation and the rate model's curve, without `Env`
+//!
+//! Each function follows the contract code it names step for step, with the
+//! same `mul_div` calls and rounding, so a preview matches the on-chain
+//! result to the unit.
+
+use stellend_fixed_point::{mul_div, FixedPointError, Rounding, RAY, SCALE_7 as SCALE};
+
+/// Seconds per year used for accrual; `apogee_common::SECONDS_PER_YEAR`
+pub const SECONDS_PER_YEAR: i128 = 31_557_600;
+
+/// Health factor the pool reports for an account without debt
+pub const NO_DEBT_HEALTH_FACTOR: i128 = 999 * SCALE;
+
+/// Utilization thresholds of the multi-kink curve (scaled by SCALE)
+const U_85: i128 = 8_500_000;
+const U_90: i128 = 9_000_000;
+const U_95: i128 = 9_500_000;
+const U_99: i128 = 9_900_000;
+
+/// Collateral held in one reserve
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Collateral {
+    pub asset: String,
+    /// Token units
+    pub amount: i128,
+    /// Collateral price (the TWAP when the pool has a TWAP window), scaled by 1e7
+    pub price: i128,
+    /// Volatility-adjusted LTV, scaled by SCALE
+    pub ltv: i128,
+    /// Scaled by SCALE
+    pub liquidation_threshold: i128,
+    /// Disabled reserves back the health factor but add no borrow capacity
+    pub enabled: bool,
+}
+
+/// Debt owed in one reserve
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Debt {
+    pub asset: String,
+    /// Token units, including accrued interest
+    pub amount: i128,
+    /// Spot price, scaled by 1e7
+    pub price: i128,
+}
+
+/// `apogee_common::AccountData`: USD values scaled by 1e7
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AccountData {
+    pub collateral_value_usd: i128,
+    pub debt_value_usd: i128,
+    pub borrow_capacity_usd: i128,
+    pub threshold_collateral_usd: i128,
+    pub health_factor: i128,
+}
+
+/// `LendingPool::account_data`
+pub fn account_data(
+    collateral: &[Collateral],
+    debt: &[Debt],
+) -> Result<AccountData, FixedPointError> {
+    let mut data = AccountData::default();
+
+    for c in collateral.iter().filter(|c| c.amount > 0) {
+        let value = mul_div(c.amount, c.price, SCALE, Rounding::Down)?;
+        data.collateral_value_usd += value;
+        if c.enabled {
+            data.borrow_capacity_usd += mul_div(value, c.ltv, SCALE, Rounding::Down)?;
+        }
+        data.threshold_collateral_usd +=
+            mul_div(value, c.liquidation_threshold, SCALE, Rounding::Down)?;
+    }
+    for d in debt.iter().filter(|d| d.amount > 0) {
+        data.debt_value_usd += mul_div(d.amount, d.price, SCALE, Rounding::Down)?;
+    }
+
+    data.health_factor = if data.debt_value_usd == 0 {
+        NO_DEBT_HEALTH_FACTOR
+    } else {
+        mul_div(
+            data.threshold_collateral_usd,
+            SCALE,
+            data.debt_value_usd,
+            Rounding::Down,
+        )?
+    };
+    Ok(data)
+}
+
+/// `apogee_common::utilization_rate`, as the pool computes it; scaled by SCALE
+pub fn utilization(total_borrow: i128, total_supply: i128) -> Result<i128, FixedPointError> {
+    if total_supply == 0 {
+        return Ok(0);
+    }
+    mul_div(total_borrow, SCALE, total_supply, Rounding::Down)
+}
+
+/// `RateModelConfig`: rates and utilization scaled by SCALE
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateCurve {
+    pub rate_min: i128,
+    pub rate_opt: i128,
+    pub rate_max: i128,
+    pub optimal_utilization: i128,
+}
+
+impl Default for RateCurve {
+    /// `InterestRateModel::initialize_default`
+    fn default() -> Self {
+        RateCurve {
+            rate_min: 0,
+            rate_opt: 400_000,
+            rate_max: 10_000_000,
+            optimal_utilization: 8_000_000,
+        }
+    }
+}
+
+impl RateCurve {
+    /// `InterestRateModel::get_borrow_rate`: annualized, scaled by SCALE
+    pub fn borrow_rate(&self, utilization: i128) -> Result<i128, FixedPointError> {
+        let (rate_opt, u_optimal) = (self.rate_opt, self.optimal_utilization);
+        let delta_r = self.rate_max - rate_opt;
+
+        // Each zone adds its share of ΔR (in 1/1000ths) on top of the zones
+        // before it
+        let raw_rate = if utilization <= u_optimal {
+            mul_div(rate_opt, utilization, u_optimal, Rounding::Down)?
+        } else if utilization <= U_85 {
+            let penalty = mul_div(
+                delta_r * 50,
+                utilization - u_optimal,
+                (U_85 - u_optimal) * 1000,
+                Rounding::Down,
+            )?;
+            rate_opt + penalty
+        } else if utilization <= U_90 {
+            rate_opt
+                + (delta_r * 50) / 1000
+                + mul_div(
+                    delta_r * 100,
+                    utilization - U_85,
+                    (U_90 - U_85) * 1000,
+                    Rounding::Down,
+                )?
+        } else if utilization <= U_95 {
+            rate_opt
+                + (delta_r * 150) / 1000
+                + mul_div(
+                    delta_r * 150,
+                    utilization - U_90,
+                    (U_95 - U_90) * 1000,
+                    Rounding::Down,
+                )?
+        } else if utilization <= U_99 {
+            rate_opt
+                + (delta_r * 300) / 1000
+                + mul_div(
+                    delta_r * 200,
+                    utilization - U_95,
+                    (U_99 - U_95) * 1000,
+                    Rounding::Down,
+                )?
+        } else {
+            let range = SCALE - U_99;
+            let progress = if utilization >= SCALE {
+                range
+            } else {
+                utilization - U_99
+            };
+            rate_opt
+                + (delta_r * 500) / 1000
+                + mul_div(delta_r * 500, progress, range * 1000, Rounding::Down)?
+        };
+
+        Ok(raw_rate.max(self.rate_min))
+    }
+
+    /// `InterestRateModel::get_supply_rate`: 10% of interest goes to reserves
+    pub fn supply_rate(&self, utilization: i128) -> Result<i128, FixedPointError> {
+        self.supply_rate_with_reserve(utilization, 1_000_000)
+    }
+
+    /// `InterestRateModel::get_supply_rate_with_reserve`; `reserve_factor`
+    /// scaled by SCALE
+    pub fn supply_rate_with_reserve(
+        &self,
+        utilization: i128,
+        reserve_factor: i128,
+    ) -> Result<i128, FixedPointError> {
+        let borrow_rate = self.borrow_rate(utilization)?;
+        mul_div(
+            borrow_rate * utilization,
+            SCALE - reserve_factor,
+            SCALE * SCALE,
+            Rounding::Down,
+        )
+    }
+}
+
+/// The rate model's `compound_annual_rate`: APY of a simple annual rate
+/// compounded every second, both scaled by SCALE
+pub fn compound_annual_rate(annual_rate: i128) -> Result<i128, FixedPointError> {
+    let rate_per_second = mul_div(annual_rate, RAY, SCALE * SECONDS_PER_YEAR, Rounding::Down)?;
+
+    let mut base = RAY + rate_per_second;
+    let mut exponent = SECONDS_PER_YEAR;
+    let mut result = RAY;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_div(result, base, RAY, Rounding::Down)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul_div(base, base, RAY, Rounding::Down)?;
+        }
+    }
+
+    mul_div(result - RAY, SCALE, RAY, Rounding::Down)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::{Address, Env};
+    use stellend_interest_rate_model::{InterestRateModel, InterestRateModelClient};
+
+    #[test]
+    fn test_rates_match_rate_model_contract() {
+        assert_eq!(SECONDS_PER_YEAR, apogee_common::SECONDS_PER_YEAR);
+
+        let env = Env::default();
+        let model =
+            InterestRateModelClient::new(&env, &env.register_contract(None, InterestRateModel));
+        model.initialize(
+            &Address::generate(&env),
+            &100_000,
+            &400_000,
+            &10_000_000,
+            &8_000_000,
+        );
+        let curve = RateCurve {
+            rate_min: 100_000,
+            ..RateCurve::default()
+        };
+
+        for u in [
+            0, 1_234_567, 8_000_000, 8_400_000, 8_700_000, 9_300_000, 9_700_000, 9_950_000, SCALE,
+        ] {
+            assert_eq!(
+                curve.borrow_rate(u).unwrap(),
+                model.get_borrow_rate(&u),
+                "u = {u}"
+            );
+            assert_eq!(
+                curve.supply_rate(u).unwrap(),
+                model.get_supply_rate(&u),
+                "u = {u}"
+            );
+            assert_eq!(
+                curve.supply_rate_with_reserve(u, 2_000_000).unwrap(),
+                model.get_supply_rate_with_reserve(&u, &2_000_000),
+                "u = {u}"
+            );
+            assert_eq!(
+                compound_annual_rate(curve.borrow_rate(u).unwrap()).unwrap(),
+                model.get_borrow_apy(&u),
+                "u = {u}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_data() {
+        // 1000 XLM at $0.10 (LTV 75%, LT 80%) and 50 USDC at $1 (disabled,
+        // LT 90%) against 40 USDC of debt
+        let collateral = [
+            Collateral {
+                asset: "XLM".to_string(),
+                amount: 10_000_000_000,
+                price: 1_000_000,
+                ltv: 7_500_000,
+                liquidation_threshold: 8_000_000,
+                enabled: true,
+            },
+            Collateral {
+                asset: "USDC".to_string(),
+                amount: 500_000_000,
+                price: 10_000_000,
+                ltv: 8_000_000,
+                liquidation_threshold: 9_000_000,
+                enabled: false,
+            },
+        ];
+        let debt = [Debt {
+            asset: "USDC".to_string(),
+            amount: 400_000_000,
+            price: 10_000_000,
+        }];
+
+        let data = account_data(&collateral, &debt).unwrap();
+        assert_eq!(data.collateral_value_usd, 1_500_000_000);
+        assert_eq!(data.borrow_capacity_usd, 750_000_000);
+        assert_eq!(data.threshold_collateral_usd, 1_250_000_000);
+        // 125 / 40
+        assert_eq!(data.health_factor, 31_250_000);
+        assert_eq!(
+            account_data(&collateral, &[]).unwrap().health_factor,
+            NO_DEBT_HEALTH_FACTOR
+        );
+    }
+}
@@ -226,7 +226,7 @@ class SorobanContractService {
     try {
       const result = await sorobanServer.simulateTransaction(
         await this.buildReadTransaction(
-          this.oracleContract.call("get_price", nativeToScVal(asset, { type: "symbol" }))
+          this.oracleContract.call("get_price_by_symbol", nativeToScVal(asset, { type: "symbol" }))
         )
       )
 
@@ -268,7 +268,7 @@ class SorobanContractService {
       const txXdr = await this.buildTransaction(
         publicKey,
         this.oracleContract.call(
-          "set_price",
+          "set_price_by_symbol",
           nativeToScVal(asset, { type: "symbol" }),
           nativeToScVal(scaledPrice, { type: "i128" })
         )
@@ -601,8 +601,10 @@ class SorobanContractService {
     const operation = this.poolContract.call(
       "supply",
       new Address(userAddress).toScVal(),
+      new Address(userAddress).toScVal(),
       nativeToScVal(asset, { type: "symbol" }),
-      nativeToScVal(toContractAmount(amount), { type: "i128" })
+      nativeToScVal(toContractAmount(amount), { type: "i128" }),
+      xdr.ScVal.scvVoid() // no referral code
     )
 
     return this.buildTransaction(userAddress, operation)
@@ -616,7 +618,9 @@ class SorobanContractService {
       "withdraw",
       new Address(userAddress).toScVal(),
       nativeToScVal(asset, { type: "symbol" }),
-      nativeToScVal(toContractAmount(amount), { type: "i128" })
+      nativeToScVal(toContractAmount(amount), { type: "i128" }),
+      nativeToScVal(0, { type: "i128" }), // no minimum output
+      xdr.ScVal.scvVoid() // no deadline
     )
 
     return this.buildTransaction(userAddress, operation)
@@ -678,7 +682,29 @@ class SorobanContractService {
       "borrow",
       new Address(userAddress).toScVal(),
       nativeToScVal(asset, { type: "symbol" }),
-      nativeToScVal(toContractAmount(amount), { type: "i128" })
+      nativeToScVal(toContractAmount(amount), { type: "i128" }),
+      xdr.ScVal.scvVoid() // no referral code
+    )
+
+    return this.buildTransaction(userAddress, operation)
+  }
+
+  // Deposit XLM collateral and borrow USDC against it in one multicall
+  async buildDepositAndBorrowTx(userAddress: string, collateral: number, borrow: number): Promise<string> {
+    if (!this.poolContract) throw new Error("Pool contract not configured")
+
+    // Operation enum variants encode as [variant, ...fields]
+    const op = (variant: string, asset: string, amount: number) =>
+      xdr.ScVal.scvVec([
+        nativeToScVal(variant, { type: "symbol" }),
+        nativeToScVal(asset, { type: "symbol" }),
+        nativeToScVal(toContractAmount(amount), { type: "i128" }),
+      ])
+
+    const operation = this.poolContract.call(
+      "multicall",
+      new Address(userAddress).toScVal(),
+      xdr.ScVal.scvVec([op("DepositCollateral", "XLM", collateral), op("Borrow", "USDC", borrow)])
     )
 
     return this.buildTransaction(userAddress, operation)
@@ -691,6 +717,7 @@ class SorobanContractService {
     const operation = this.poolContract.call(
       "repay",
       new Address(userAddress).toScVal(),
+      new Address(userAddress).toScVal(),
       nativeToScVal(asset, { type: "symbol" }),
       nativeToScVal(toContractAmount(amount), { type: "i128" })
     )
@@ -861,6 +888,19 @@ export const apogeeContractAPI = {
     return true
   },
 
+  depositAndBorrow: async (
+    userAddress: string,
+    collateral: number,
+    amount: number,
+    signTx: (xdr: string) => Promise<string>
+  ): Promise<boolean> => {
+    await ensurePoolInitialized()
+    const txXdr = await sorobanService.buildDepositAndBorrowTx(userAddress, collateral, amount)
+    const signedXdr = await signTx(txXdr)
+    await sorobanService.submitTransaction(signedXdr)
+    return true
+  },
+
   repay: async (
     userAddress: string,
     amount: number,
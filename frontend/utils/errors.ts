@@ -35,6 +35,32 @@ const CONTRACT_ERRORS: Record<string, string> = {
   "timeout": "Transaction timed out. Please check the explorer and try again.",
 }
 
+// Pool contract error codes (PoolError in contracts/pool)
+const POOL_ERROR_CODES: Record<number, string> = {
+  1: "Contract is already initialized",
+  2: "Contract is not initialized",
+  3: "Amount must be greater than zero",
+  4: "Amount is too small",
+  5: "This asset is not supported by the pool",
+  6: "This asset cannot be used as collateral",
+  7: "This asset cannot be borrowed",
+  8: "Insufficient supplied balance",
+  9: "Insufficient liquidity in the pool. Try a smaller amount.",
+  10: "Not enough collateral to complete this action",
+  11: "This action would make your position unsafe (Health Factor < 1.0).",
+  12: "Borrow limit exceeded. Deposit more collateral or borrow less.",
+  13: "No outstanding debt to repay",
+  14: "Borrower has no collateral in this asset",
+  15: "This position is healthy and cannot be liquidated.",
+  16: "Protocol-wide borrow limit reached. Try again later.",
+  17: "Price not available for this asset",
+  30: "This action is temporarily paused. Please try again later.",
+  33: "Supply cap reached for this asset. Try a smaller amount.",
+  34: "Borrow cap reached for this asset. Try a smaller amount.",
+  51: "Transaction expired before it was executed. Please try again.",
+  52: "Price moved past your minimum amount. Try again or lower the minimum.",
+}
+
 // Error patterns for regex matching
 const ERROR_PATTERNS: { pattern: RegExp; message: string }[] = [
   { 
@@ -100,6 +126,12 @@ export function parseContractError(error: unknown): string {
   
   const lowerMessage = errorMessage.toLowerCase()
   
+  // Check for typed contract error codes, e.g. "Error(Contract, #12)"
+  const codeMatch = errorMessage.match(/Error\(Contract, #(\d+)\)/)
+  if (codeMatch && POOL_ERROR_CODES[Number(codeMatch[1])]) {
+    return POOL_ERROR_CODES[Number(codeMatch[1])]
+  }
+  
   // Check for exact matches in known errors
   for (const [key, message] of Object.entries(CONTRACT_ERRORS)) {
     if (lowerMessage.includes(key.toLowerCase())) {
@@ -255,6 +255,8 @@ async function initializeOracle(
   server: SorobanRpc.Server,
   oracleContractId: string,
   adminKeypair: StellarSdk.Keypair,
+  xlmTokenId: string,
+  usdcTokenId: string,
   networkPassphrase: string
 ): Promise<void> {
   console.log("   Initializing Price Oracle...");
@@ -266,7 +268,9 @@ async function initializeOracle(
 
   const operation = contract.call(
     "initialize",
-    StellarSdk.nativeToScVal(publicKey, { type: "address" })
+    StellarSdk.nativeToScVal(publicKey, { type: "address" }),
+    StellarSdk.nativeToScVal(xlmTokenId, { type: "address" }), // xlm_token
+    StellarSdk.nativeToScVal(usdcTokenId, { type: "address" }) // usdc_token
   );
 
   let transaction = new StellarSdk.TransactionBuilder(sourceAccount, {
@@ -320,7 +324,7 @@ async function setOraclePrice(
   const contract = new StellarSdk.Contract(oracleContractId);
 
   const operation = contract.call(
-    "set_price",
+    "set_price_by_symbol",
     StellarSdk.nativeToScVal(asset, { type: "symbol" }),
     StellarSdk.nativeToScVal(price, { type: "i128" })
   );
@@ -366,8 +370,12 @@ async function initializeInterestRateModel(
 
   const contract = new StellarSdk.Contract(modelContractId);
 
-  // Use initialize_default for the default parameters
-  const operation = contract.call("initialize_default");
+  // Use initialize_default for the default parameters; the deployer is the
+  // admin allowed to tune the rate curve later
+  const operation = contract.call(
+    "initialize_default",
+    StellarSdk.nativeToScVal(publicKey, { type: "address" })
+  );
 
   let transaction = new StellarSdk.TransactionBuilder(sourceAccount, {
     fee: "100000",
@@ -661,6 +669,8 @@ async function main() {
     config.server,
     oracleContractId,
     config.keypair,
+    xlmContractId,
+    usdcContractId,
     config.network.networkPassphrase
   );
 
@@ -71,8 +71,10 @@ async function supplyToPool(
   const operation = contract.call(
     "supply",
     StellarSdk.nativeToScVal(publicKey, { type: "address" }),
+    StellarSdk.nativeToScVal(publicKey, { type: "address" }),
     StellarSdk.nativeToScVal(asset, { type: "symbol" }),
-    StellarSdk.nativeToScVal(amount, { type: "i128" })
+    StellarSdk.nativeToScVal(amount, { type: "i128" }),
+    StellarSdk.xdr.ScVal.scvVoid() // no referral code
   );
 
   // Build transaction
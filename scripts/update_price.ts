@@ -9,6 +9,11 @@
  * - Supports NORMAL mode (real prices) and CRASH mode (50% drop)
  * - Proper Soroban transaction building and submission
  * - Fallback to mock prices for testing without API
+ *
+ * For continuous feeding with multiple sources, outlier filtering and a
+ * heartbeat/deviation schedule, run the `apogee-feeder` daemon
+ * (crates/apogee-feeder) instead; this script is kept for one-off updates
+ * and the crash demo.
  * 
  * ## Usage
  *
@@ -203,17 +208,29 @@ async function updatePriceOnChain(
   // Create contract instance
   const contract = new StellarSdk.Contract(oracleContractId);
 
-  // Build transaction to set XLM price
+  // Build one transaction updating every price together
   const xlmPriceScaled = priceToScaled(prices.xlm);
+  const usdcPriceScaled = priceToScaled(prices.usdc);
   console.log(`\n📝 Building transaction...`);
   console.log(`   Contract: ${oracleContractId.substring(0, 8)}...`);
   console.log(`   XLM Price: ${xlmPriceScaled} (scaled)`);
+  console.log(`   USDC Price: ${usdcPriceScaled} (scaled)`);
 
-  // Build the call operation
+  // Build the call operation: set_prices(Vec<(Symbol, i128)>)
+  const entries: [string, bigint][] = [
+    ["XLM", xlmPriceScaled],
+    ["USDC", usdcPriceScaled],
+  ];
   const operation = contract.call(
-    "set_price",
-    StellarSdk.nativeToScVal("XLM", { type: "symbol" }),
-    StellarSdk.nativeToScVal(xlmPriceScaled, { type: "i128" })
+    "set_prices",
+    StellarSdk.xdr.ScVal.scvVec(
+      entries.map(([symbol, price]) =>
+        StellarSdk.xdr.ScVal.scvVec([
+          StellarSdk.nativeToScVal(symbol, { type: "symbol" }),
+          StellarSdk.nativeToScVal(price, { type: "i128" }),
+        ])
+      )
+    )
   );
   
   // Build transaction